@@ -0,0 +1,518 @@
+//! Macros declaring a `Junction` and its channels.
+//!
+//! The `#[junction]` attribute turns a module of functions into a
+//! `Junction` with a Join Pattern per function. Every function of the module
+//! whose parameters are typed `Send<T>`, `Recv<R>` or `Bidir<T, R>` becomes
+//! the function body of a Join Pattern on the channels named by its
+//! parameters. Parameters of the same name in different functions refer to
+//! the same channel. Within the function, the parameters of `Send<T>` and
+//! `Bidir<T, R>` channels are the messages of type `T`, while parameters of
+//! `Recv<R>` channels are dropped. The return value is the reply on the
+//! `Recv<R>` or `Bidir<T, R>` channel, of which a Join Pattern has at most
+//! one. An additional parameter of type `&Channels` gives access to all
+//! channels, e.g. to send messages from within the function body.
+//!
+//! The module gets two generated structs: `Channels`, holding a public
+//! field per channel, and a struct named after the module in upper camel
+//! case, owning the `Junction` and dereferencing to its `Channels`. Its
+//! `new` function creates the `Junction` along with all channels and Join
+//! Patterns.
+//!
+//! `#[derive(JunctionMessage)]` on an enum generates a struct named after
+//! the enum with a `Channels` suffix, holding a send channel per variant.
+//!
+//! The generated code refers to the `rusty_junctions` crate, which
+//! re-exports both macros in `rusty_junctions::attr`, see there for
+//! examples.
+
+use std::collections::HashMap;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, DeriveInput, Error, FnArg, GenericArgument, Ident, Item,
+    ItemFn, ItemMod, Pat, PathArguments, Result, ReturnType, Type,
+};
+
+mod message;
+
+/// Declare a `Junction` with its channels and Join Patterns as a module.
+///
+/// See the crate documentation for how the module is expanded.
+#[proc_macro_attribute]
+pub fn junction(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return Error::new(
+            Span::call_site(),
+            "`#[junction]` does not take any arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let module = parse_macro_input!(item as ItemMod);
+    expand(module)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Create a send channel per variant of an enum.
+///
+/// See the crate documentation for what is generated.
+#[proc_macro_derive(JunctionMessage)]
+pub fn derive_junction_message(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    message::expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Kind of a channel along with the types of its messages and replies.
+#[derive(Clone)]
+enum Kind {
+    Send(Box<Type>),
+    Recv(Box<Type>),
+    Bidir(Box<Type>, Box<Type>),
+}
+
+impl Kind {
+    /// Return the type of the channel handle of this kind.
+    fn channel_type(&self) -> TokenStream2 {
+        match self {
+            Kind::Send(t) => quote!(::rusty_junctions::channels::SendChannel<#t>),
+            Kind::Recv(r) => quote!(::rusty_junctions::channels::RecvChannel<#r>),
+            Kind::Bidir(t, r) => quote!(::rusty_junctions::channels::BidirChannel<#t, #r>),
+        }
+    }
+
+    /// Return the expression creating a channel of this kind on `junction`.
+    fn create(&self) -> TokenStream2 {
+        match self {
+            Kind::Send(t) => quote!(junction.send_channel::<#t>()),
+            Kind::Recv(r) => quote!(junction.recv_channel::<#r>()),
+            Kind::Bidir(t, r) => quote!(junction.bidir_channel::<#t, #r>()),
+        }
+    }
+
+    /// Return `true` if both kinds describe the same channel type.
+    fn same_as(&self, other: &Kind) -> bool {
+        let tokens = |kind: &Kind| kind.channel_type().to_string();
+        tokens(self) == tokens(other)
+    }
+}
+
+/// Parameter of a function making up a Join Pattern.
+enum Param {
+    Channel(Ident, Kind),
+    Channels,
+}
+
+/// Join Pattern declared by a function of the module.
+struct Pattern {
+    function: Ident,
+    params: Vec<Param>,
+}
+
+/// Channels of the module, in the order of their first appearance.
+#[derive(Default)]
+struct ChannelTable {
+    names: Vec<Ident>,
+    kinds: HashMap<Ident, Kind>,
+}
+
+impl ChannelTable {
+    /// Add the given channel, unless it has been added already with the
+    /// same kind.
+    fn add(&mut self, name: &Ident, kind: &Kind) -> Result<()> {
+        match self.kinds.get(name) {
+            Some(existing) if existing.same_as(kind) => Ok(()),
+            Some(_) => Err(Error::new(
+                name.span(),
+                format!("channel `{name}` is declared with different types"),
+            )),
+            None => {
+                self.names.push(name.clone());
+                self.kinds.insert(name.clone(), kind.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Expand the annotated module.
+fn expand(mut module: ItemMod) -> Result<TokenStream2> {
+    let Some((_, items)) = module.content.as_mut() else {
+        return Err(Error::new(
+            module.span(),
+            "`#[junction]` requires a module with a body",
+        ));
+    };
+
+    let mut channels = ChannelTable::default();
+    let mut patterns = Vec::new();
+    for item in items.iter_mut() {
+        if let Item::Fn(function) = item {
+            if let Some(pattern) = parse_pattern(function)? {
+                for param in &pattern.params {
+                    if let Param::Channel(name, kind) = param {
+                        channels.add(name, kind)?;
+                    }
+                }
+                patterns.push(pattern);
+            }
+        }
+    }
+
+    let struct_name = format_ident!(
+        "{}",
+        upper_camel_case(&module.ident.to_string()),
+        span = module.ident.span()
+    );
+    let struct_doc = format!(
+        "`Junction` declared by the `{}` module, along with its channels and Join Patterns.",
+        module.ident
+    );
+
+    let names = &channels.names;
+    let channel_types = names.iter().map(|name| channels.kinds[name].channel_type());
+    let creations = names.iter().map(|name| channels.kinds[name].create());
+    let declarations = patterns.iter().map(declare).collect::<Vec<_>>();
+
+    items.push(syn::parse_quote! {
+        /// Channels of the `Junction`, see the fields for each channel.
+        #[derive(Clone)]
+        pub struct Channels {
+            #(pub #names: #channel_types,)*
+        }
+    });
+    items.push(syn::parse_quote! {
+        #[doc = #struct_doc]
+        pub struct #struct_name {
+            junction: ::rusty_junctions::Junction,
+            channels: Channels,
+        }
+    });
+    items.push(syn::parse_quote! {
+        impl #struct_name {
+            /// Create the `Junction` along with all of its channels and
+            /// Join Patterns.
+            pub fn new() -> #struct_name {
+                let junction = ::rusty_junctions::Junction::new();
+                let channels = Channels {
+                    #(#names: #creations,)*
+                };
+
+                #(#declarations)*
+
+                #struct_name { junction, channels }
+            }
+
+            /// Return the `Junction`, e.g. to declare further Join Patterns.
+            pub fn junction(&self) -> &::rusty_junctions::Junction {
+                &self.junction
+            }
+        }
+    });
+    items.push(syn::parse_quote! {
+        impl ::std::default::Default for #struct_name {
+            fn default() -> #struct_name {
+                #struct_name::new()
+            }
+        }
+    });
+    items.push(syn::parse_quote! {
+        impl ::std::ops::Deref for #struct_name {
+            type Target = Channels;
+
+            fn deref(&self) -> &Channels {
+                &self.channels
+            }
+        }
+    });
+
+    Ok(quote!(#module))
+}
+
+/// Parse the given function as a Join Pattern, rewriting the types of its
+/// channel parameters to those of their messages.
+///
+/// Return `None` for functions without channel parameters, which are left
+/// untouched.
+fn parse_pattern(function: &mut ItemFn) -> Result<Option<Pattern>> {
+    let mut params = Vec::new();
+    for input in &function.sig.inputs {
+        let FnArg::Typed(typed) = input else {
+            params.push(None);
+            continue;
+        };
+
+        let param = if is_channels_ref(&typed.ty) {
+            Some(Param::Channels)
+        } else {
+            match (parse_kind(&typed.ty)?, &*typed.pat) {
+                (Some(kind), Pat::Ident(name)) => Some(Param::Channel(name.ident.clone(), kind)),
+                (Some(_), pat) => {
+                    return Err(Error::new(
+                        pat.span(),
+                        "channel parameters have to be plain identifiers",
+                    ))
+                }
+                (None, _) => None,
+            }
+        };
+        params.push(param);
+    }
+
+    if !params
+        .iter()
+        .any(|param| matches!(param, Some(Param::Channel(..))))
+    {
+        return Ok(None);
+    }
+    for (input, param) in function.sig.inputs.iter().zip(&params) {
+        if param.is_none() {
+            return Err(Error::new(
+                input.span(),
+                "parameters of Join Pattern functions have to be of type `Send<T>`, `Recv<R>`, `Bidir<T, R>` or `&Channels`",
+            ));
+        }
+    }
+    let params: Vec<Param> = params.into_iter().flatten().collect();
+
+    let reply_channels = params
+        .iter()
+        .filter(|param| matches!(param, Param::Channel(_, Kind::Recv(_) | Kind::Bidir(..))))
+        .count();
+    if reply_channels > 1 {
+        return Err(Error::new(
+            function.sig.span(),
+            "a Join Pattern has at most one `Recv` or `Bidir` channel",
+        ));
+    }
+    if reply_channels == 0 && !matches!(function.sig.output, ReturnType::Default) {
+        return Err(Error::new(
+            function.sig.output.span(),
+            "only Join Patterns with a `Recv` or `Bidir` channel return a value",
+        ));
+    }
+    if params
+        .iter()
+        .filter(|param| matches!(param, Param::Channels))
+        .count()
+        > 1
+    {
+        return Err(Error::new(
+            function.sig.span(),
+            "`&Channels` can only be taken once",
+        ));
+    }
+
+    // Replace the channel types with those of their messages, dropping
+    // parameters of `Recv` channels, which carry no message.
+    let inputs = std::mem::take(&mut function.sig.inputs);
+    for (mut input, param) in inputs.into_iter().zip(&params) {
+        match param {
+            Param::Channel(_, Kind::Recv(_)) => continue,
+            Param::Channel(_, Kind::Send(t) | Kind::Bidir(t, _)) => {
+                if let FnArg::Typed(typed) = &mut input {
+                    typed.ty = t.clone();
+                }
+            }
+            Param::Channels => {}
+        }
+        function.sig.inputs.push(input);
+    }
+
+    Ok(Some(Pattern {
+        function: function.sig.ident.clone(),
+        params,
+    }))
+}
+
+/// Return the kind of channel named by the given parameter type, if any.
+fn parse_kind(ty: &Type) -> Result<Option<Kind>> {
+    let Type::Path(path) = ty else {
+        return Ok(None);
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return Ok(None);
+    };
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return Ok(None);
+    };
+    let types: Vec<Box<Type>> = arguments
+        .args
+        .iter()
+        .filter_map(|argument| match argument {
+            GenericArgument::Type(ty) => Some(Box::new(ty.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let kind = match (segment.ident.to_string().as_str(), &types[..]) {
+        ("Send", [t]) => Kind::Send(t.clone()),
+        ("Recv", [r]) => Kind::Recv(r.clone()),
+        ("Bidir", [t, r]) => Kind::Bidir(t.clone(), r.clone()),
+        ("Send" | "Recv" | "Bidir", _) => {
+            return Err(Error::new(
+                ty.span(),
+                "expected `Send<T>`, `Recv<R>` or `Bidir<T, R>`",
+            ))
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(kind))
+}
+
+/// Return `true` if the given parameter type is `&Channels`.
+fn is_channels_ref(ty: &Type) -> bool {
+    let Type::Reference(reference) = ty else {
+        return false;
+    };
+    matches!(&*reference.elem, Type::Path(path) if path.path.is_ident("Channels"))
+}
+
+/// Return the statement declaring the given Join Pattern on `junction`,
+/// calling its function with the messages in the order of its parameters.
+fn declare(pattern: &Pattern) -> TokenStream2 {
+    let function = &pattern.function;
+    let sends: Vec<&Ident> = pattern
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            Param::Channel(name, Kind::Send(_)) => Some(name),
+            _ => None,
+        })
+        .collect();
+    let reply = pattern.params.iter().find_map(|param| match param {
+        Param::Channel(name, kind @ (Kind::Recv(_) | Kind::Bidir(..))) => Some((name, kind)),
+        _ => None,
+    });
+
+    let mut chain = match (sends.split_first(), reply) {
+        (Some((first, rest)), _) => {
+            let mut chain = quote!(junction.when(&channels.#first));
+            for name in rest {
+                chain = quote!(#chain.and(&channels.#name));
+            }
+            chain
+        }
+        (None, Some((name, Kind::Recv(_)))) => quote!(junction.when_recv(&channels.#name)),
+        (None, Some((name, _))) => quote!(junction.when_bidir(&channels.#name)),
+        (None, None) => unreachable!("Join Pattern without channels"),
+    };
+    if !sends.is_empty() {
+        match reply {
+            Some((name, Kind::Recv(_))) => chain = quote!(#chain.and_recv(&channels.#name)),
+            Some((name, _)) => chain = quote!(#chain.and_bidir(&channels.#name)),
+            None => {}
+        }
+    }
+
+    let mut closure_params: Vec<&Ident> = sends.clone();
+    if let Some((name, Kind::Bidir(..))) = reply {
+        closure_params.push(name);
+    }
+
+    let arguments = pattern.params.iter().filter_map(|param| match param {
+        Param::Channel(_, Kind::Recv(_)) => None,
+        Param::Channel(name, _) => Some(quote!(#name)),
+        Param::Channels => Some(quote!(&pattern_channels)),
+    });
+    let takes_channels = pattern
+        .params
+        .iter()
+        .any(|param| matches!(param, Param::Channels));
+    let capture = takes_channels.then(|| quote!(let pattern_channels = channels.clone();));
+
+    quote! {
+        {
+            #capture
+            #chain.then_do(move |#(#closure_params),*| self::#function(#(#arguments),*));
+        }
+    }
+}
+
+/// Convert the given snake case name to upper camel case.
+fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_to_upper_camel_case() {
+        assert_eq!(upper_camel_case("cell"), "Cell");
+        assert_eq!(upper_camel_case("storage_cell"), "StorageCell");
+        assert_eq!(upper_camel_case("_rate__limiter"), "RateLimiter");
+    }
+
+    #[test]
+    fn rewrite_channel_parameters() {
+        let mut function: ItemFn = syn::parse_quote! {
+            fn get(channels: &Channels, get: Recv<u32>, value: Send<u32>) -> u32 {
+                value
+            }
+        };
+        let pattern = parse_pattern(&mut function).unwrap().unwrap();
+
+        assert_eq!(pattern.params.len(), 3);
+        let expected: ItemFn = syn::parse_quote! {
+            fn get(channels: &Channels, value: u32) -> u32 {
+                value
+            }
+        };
+        assert_eq!(quote!(#function).to_string(), quote!(#expected).to_string());
+    }
+
+    #[test]
+    fn leave_helper_functions() {
+        let mut function: ItemFn = syn::parse_quote! {
+            fn double(n: u32) -> u32 {
+                n * 2
+            }
+        };
+
+        assert!(parse_pattern(&mut function).unwrap().is_none());
+    }
+
+    #[test]
+    fn reject_two_reply_channels() {
+        let mut function: ItemFn = syn::parse_quote! {
+            fn both(a: Recv<u32>, b: Bidir<u32, u32>) -> u32 {
+                0
+            }
+        };
+
+        assert!(parse_pattern(&mut function).is_err());
+    }
+
+    #[test]
+    fn reject_conflicting_channel_types() {
+        let mut table = ChannelTable::default();
+        let name = format_ident!("value");
+
+        table
+            .add(&name, &Kind::Send(syn::parse_quote!(u32)))
+            .unwrap();
+        table
+            .add(&name, &Kind::Send(syn::parse_quote!(u32)))
+            .unwrap();
+        assert!(table
+            .add(&name, &Kind::Send(syn::parse_quote!(i64)))
+            .is_err());
+    }
+}
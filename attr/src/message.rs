@@ -0,0 +1,171 @@
+//! Expansion of `#[derive(JunctionMessage)]`.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, Data, DeriveInput, Error, Fields, Result};
+
+/// Expand the derive on the given enum.
+pub(crate) fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new(
+            input.ident.span(),
+            "`JunctionMessage` can only be derived for enums",
+        ));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "`JunctionMessage` cannot be derived for generic enums",
+        ));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let channels_name = format_ident!("{name}Channels");
+    let channels_doc = format!("Send channels of the variants of `{name}`, one per variant.");
+
+    let mut fields = Vec::new();
+    let mut payloads = Vec::new();
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let variant_name = &variant.ident;
+        let field = field_name(&snake_case(&variant_name.to_string()), variant_name.span());
+
+        let (payload, arm) = match &variant.fields {
+            Fields::Unit => (
+                quote!(()),
+                quote!(#name::#variant_name => self.#field.send(())),
+            ),
+            Fields::Unnamed(unnamed) => {
+                let types: Vec<_> = unnamed.unnamed.iter().map(|field| &field.ty).collect();
+                let bindings: Vec<_> = (0..types.len())
+                    .map(|i| format_ident!("field_{i}"))
+                    .collect();
+                let payload = match &types[..] {
+                    [ty] => quote!(#ty),
+                    _ => quote!((#(#types),*)),
+                };
+                let value = match &bindings[..] {
+                    [binding] => quote!(#binding),
+                    _ => quote!((#(#bindings),*)),
+                };
+                (
+                    payload,
+                    quote!(#name::#variant_name(#(#bindings),*) => self.#field.send(#value)),
+                )
+            }
+            Fields::Named(named) => {
+                let types = named.named.iter().map(|field| &field.ty);
+                let bindings: Vec<_> = named.named.iter().map(|field| &field.ident).collect();
+                (
+                    quote!((#(#types,)*)),
+                    quote!(#name::#variant_name { #(#bindings),* } => self.#field.send((#(#bindings,)*))),
+                )
+            }
+        };
+
+        let field_doc = format!("Channel of `{name}::{variant_name}`.");
+        fields.push(quote! {
+            #[doc = #field_doc]
+            pub #field: ::rusty_junctions::channels::SendChannel<#payload>
+        });
+        payloads.push((field, payload));
+        arms.push(arm);
+    }
+    let creations = payloads
+        .iter()
+        .map(|(field, payload)| quote!(#field: junction.send_channel::<#payload>()));
+
+    let send = if arms.is_empty() {
+        quote!(match message {})
+    } else {
+        quote!(match message { #(#arms,)* })
+    };
+
+    Ok(quote! {
+        #[doc = #channels_doc]
+        #[derive(Clone)]
+        #vis struct #channels_name {
+            #(#fields,)*
+        }
+
+        impl #channels_name {
+            /// Create a channel per variant on the given `Junction`.
+            #vis fn new(junction: &::rusty_junctions::Junction) -> #channels_name {
+                #channels_name {
+                    #(#creations,)*
+                }
+            }
+
+            /// Send the payload of the given message on the channel of its
+            /// variant.
+            #vis fn send(
+                &self,
+                message: #name,
+            ) -> ::std::result::Result<
+                ::rusty_junctions::channels::MessageReceipt,
+                ::std::sync::mpsc::SendError<::rusty_junctions::attr::Packet>,
+            > {
+                #send
+            }
+        }
+
+        impl #name {
+            /// Create a channel per variant on the given `Junction`.
+            #vis fn channels(junction: &::rusty_junctions::Junction) -> #channels_name {
+                #channels_name::new(junction)
+            }
+        }
+    })
+}
+
+/// Return the field name of the given snake case name, which is a raw
+/// identifier for keywords, e.g. `r#move` for `Move`.
+fn field_name(name: &str, span: Span) -> Ident {
+    match syn::parse_str::<Ident>(name) {
+        Ok(_) => Ident::new(name, span),
+        Err(_) => Ident::new_raw(name, span),
+    }
+}
+
+/// Convert the given upper camel case name to snake case.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_to_snake_case() {
+        assert_eq!(snake_case("Put"), "put");
+        assert_eq!(snake_case("SetLimit"), "set_limit");
+    }
+
+    #[test]
+    fn escape_keywords() {
+        assert_eq!(field_name("move", Span::call_site()).to_string(), "r#move");
+        assert_eq!(field_name("put", Span::call_site()).to_string(), "put");
+    }
+
+    #[test]
+    fn reject_structs() {
+        let input: DeriveInput = syn::parse_quote! {
+            struct Put(u32);
+        };
+
+        assert!(expand(input).is_err());
+    }
+}
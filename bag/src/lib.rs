@@ -3,6 +3,7 @@
 //! Provides a new collection struct that can hold an arbitrary number
 //! of values for a given key and allows to retrieve them in FIFO order.
 
+use std::cmp::Reverse;
 use std::collections::{HashMap, VecDeque};
 use std::hash::Hash;
 
@@ -52,6 +53,60 @@ where
         self.items.get_mut(key)?.pop_front()
     }
 
+    /// Retrieve the last value available for the given key, if possible.
+    ///
+    /// Retrieve `Some` of the most recently added value for the given key
+    /// if there is at least one available, otherwise return `None`.
+    pub fn retrieve_last(&mut self, key: &K) -> Option<V> {
+        self.items.get_mut(key)?.pop_back()
+    }
+
+    /// Return a reference to the first value available for the given key,
+    /// if possible, without removing it.
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.items.get(key)?.front()
+    }
+
+    /// Retrieve the first of the values with the greatest priority for the
+    /// given key, if possible.
+    ///
+    /// Retrieve `Some` of the least recently added value among those for
+    /// which `priority` is greatest if there is at least one available,
+    /// otherwise return `None`.
+    pub fn retrieve_max_by_key<P, F>(&mut self, key: &K, priority: F) -> Option<V>
+    where
+        P: Ord,
+        F: Fn(&V) -> P,
+    {
+        let queue = self.items.get_mut(key)?;
+        let (position, _) = queue
+            .iter()
+            .enumerate()
+            .max_by_key(|(position, item)| (priority(item), Reverse(*position)))?;
+
+        queue.remove(position)
+    }
+
+    /// Retrieve the last of the values with the greatest priority for the
+    /// given key, if possible.
+    ///
+    /// Retrieve `Some` of the most recently added value among those for
+    /// which `priority` is greatest if there is at least one available,
+    /// otherwise return `None`.
+    pub fn retrieve_last_max_by_key<P, F>(&mut self, key: &K, priority: F) -> Option<V>
+    where
+        P: Ord,
+        F: Fn(&V) -> P,
+    {
+        let queue = self.items.get_mut(key)?;
+        let (position, _) = queue
+            .iter()
+            .enumerate()
+            .max_by_key(|(position, item)| (priority(item), *position))?;
+
+        queue.remove(position)
+    }
+
     /// Return true if there are values for the given key.
     pub fn contains_items(&self, key: &K) -> bool {
         self.items.get(key).map_or(false, |q| !q.is_empty())
@@ -61,6 +116,11 @@ where
     pub fn count_items(&self, key: &K) -> usize {
         self.items.get(key).map_or(0, |q| q.len())
     }
+
+    /// Remove and return all values for the given key in FIFO order.
+    pub fn take_all(&mut self, key: &K) -> VecDeque<V> {
+        self.items.remove(key).unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
@@ -267,4 +327,121 @@ mod tests {
         // Then:
         assert_eq!(0, bag.count_items(&217));
     }
+
+    #[test]
+    fn test_retrieving_last() {
+        // Given:
+        let mut bag: Bag<usize, char> = Bag::new();
+
+        // When:
+        bag.add(217, 'O');
+        bag.add(217, 'v');
+
+        let first = bag.retrieve_last(&217);
+        let second = bag.retrieve_last(&217);
+        let third = bag.retrieve_last(&217);
+
+        // Then:
+        assert_eq!('v', first.unwrap());
+        assert_eq!('O', second.unwrap());
+        assert!(third.is_none());
+    }
+
+    #[test]
+    fn test_take_all_fifo_order() {
+        // Given:
+        let mut bag: Bag<usize, char> = Bag::new();
+
+        // When:
+        bag.add(217, 'O');
+        bag.add(217, 'v');
+        bag.add(237, 'e');
+
+        let taken = bag.take_all(&217);
+
+        // Then:
+        assert_eq!(vec!['O', 'v'], Vec::from(taken));
+        assert!(!bag.contains_items(&217));
+        assert!(bag.contains_items(&237));
+    }
+
+    #[test]
+    fn test_take_all_with_unknown_key() {
+        // Given:
+        let mut bag: Bag<usize, char> = Bag::new();
+
+        // When:
+        let taken = bag.take_all(&42);
+
+        // Then:
+        assert!(taken.is_empty());
+    }
+
+    #[test]
+    fn test_retrieve_max_by_key_first_of_greatest() {
+        // Given:
+        let mut bag: Bag<usize, (u8, char)> = Bag::new();
+
+        // When:
+        bag.add(217, (0, 'O'));
+        bag.add(217, (1, 'v'));
+        bag.add(217, (1, 'e'));
+
+        let first = bag.retrieve_max_by_key(&217, |&(priority, _)| priority);
+        let second = bag.retrieve_max_by_key(&217, |&(priority, _)| priority);
+        let third = bag.retrieve_max_by_key(&217, |&(priority, _)| priority);
+
+        // Then:
+        assert_eq!('v', first.unwrap().1);
+        assert_eq!('e', second.unwrap().1);
+        assert_eq!('O', third.unwrap().1);
+        assert!(!bag.contains_items(&217));
+    }
+
+    #[test]
+    fn test_retrieve_last_max_by_key_last_of_greatest() {
+        // Given:
+        let mut bag: Bag<usize, (u8, char)> = Bag::new();
+
+        // When:
+        bag.add(217, (1, 'O'));
+        bag.add(217, (1, 'v'));
+        bag.add(217, (0, 'e'));
+
+        let first = bag.retrieve_last_max_by_key(&217, |&(priority, _)| priority);
+        let second = bag.retrieve_last_max_by_key(&217, |&(priority, _)| priority);
+
+        // Then:
+        assert_eq!('v', first.unwrap().1);
+        assert_eq!('O', second.unwrap().1);
+    }
+
+    #[test]
+    fn test_retrieve_max_by_key_with_unknown_key() {
+        // Given:
+        let mut bag: Bag<usize, char> = Bag::new();
+
+        // When:
+        let actual = bag.retrieve_max_by_key(&42, |_| 0);
+
+        // Then:
+        assert!(actual.is_none());
+    }
+
+    #[test]
+    fn test_peek_first_without_removing() {
+        // Given:
+        let mut bag: Bag<usize, char> = Bag::new();
+
+        // When:
+        bag.add(217, 'O');
+        bag.add(217, 'v');
+
+        let peeked = bag.peek(&217).copied();
+
+        // Then:
+        assert_eq!(Some('O'), peeked);
+        assert_eq!(2, bag.count_items(&217));
+        assert!(bag.peek(&42).is_none());
+    }
 }
@@ -0,0 +1,54 @@
+//! Throughput of the control thread for bursts of messages.
+//!
+//! A burst of messages is sent on a channel whose Join Pattern never fires,
+//! followed by a single call that only returns once the control thread has
+//! worked through the entire burst. Comparing batch sizes shows the effect
+//! of storing all queued messages before matching them.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rusty_junctions::Junction;
+
+const BURST: u64 = 1000;
+
+fn burst(c: &mut Criterion) {
+    let mut group = c.benchmark_group("burst");
+    group.throughput(Throughput::Elements(BURST));
+
+    for max_batch in [1, 64] {
+        group.bench_with_input(
+            BenchmarkId::new("max batch", max_batch),
+            &max_batch,
+            |b, &max_batch| {
+                // A fresh `Junction` for every burst, so unmatched messages do
+                // not pile up across iterations.
+                b.iter_batched(
+                    || {
+                        let j = Junction::with_max_batch(max_batch);
+                        let work = j.send_channel::<u64>();
+                        let never = j.send_channel::<()>();
+                        let sync = j.recv_channel::<()>();
+
+                        j.when(&work).and(&never).then_do(|_, _| {});
+                        j.when_recv(&sync).then_do(|| ());
+
+                        (j, work, sync)
+                    },
+                    |(j, work, sync)| {
+                        for n in 0..BURST {
+                            work.send(n).unwrap();
+                        }
+                        sync.recv().unwrap();
+
+                        // Returned to be dropped outside of the measurement.
+                        (j, work, sync)
+                    },
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, burst);
+criterion_main!(benches);
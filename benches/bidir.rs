@@ -0,0 +1,50 @@
+//! Round-trip latency of synchronous calls through a `Junction`.
+//!
+//! Every call sends a message on a `RecvChannel` or `BidirChannel` and blocks
+//! until the reply of the fired Join Pattern has arrived, so these benchmarks
+//! cover the complete path through the controller and back.
+use criterion::{criterion_group, criterion_main, Criterion};
+use rusty_junctions::Junction;
+
+fn recv_round_trip(c: &mut Criterion) {
+    let j = Junction::new();
+    let get = j.recv_channel::<u64>();
+    j.when_recv(&get).then_do(|| 1729);
+
+    c.bench_function("recv round trip", |b| b.iter(|| get.recv().unwrap()));
+}
+
+fn bidir_round_trip(c: &mut Criterion) {
+    let j = Junction::new();
+    let echo = j.bidir_channel::<u64, u64>();
+    j.when_bidir(&echo).then_do(|n| n);
+
+    c.bench_function("bidir round trip", |b| {
+        b.iter(|| echo.send_recv(1729).unwrap())
+    });
+}
+
+fn binary_bidir_round_trip(c: &mut Criterion) {
+    let j = Junction::new();
+    let state = j.send_channel::<u64>();
+    let swap = j.bidir_channel::<u64, u64>();
+
+    let state_clone = state.clone();
+    j.when(&state).and_bidir(&swap).then_do(move |old, new| {
+        state_clone.send(new).unwrap();
+        old
+    });
+    state.send(0).unwrap();
+
+    c.bench_function("binary bidir round trip", |b| {
+        b.iter(|| swap.send_recv(1729).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    recv_round_trip,
+    bidir_round_trip,
+    binary_bidir_round_trip
+);
+criterion_main!(benches);
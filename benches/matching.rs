@@ -0,0 +1,65 @@
+//! Throughput of matching and firing Join Patterns over several channels.
+//!
+//! Each round sends a message on every channel of a Join Pattern, which
+//! fires once the last of them has arrived and replies to the caller.
+//! Comparing Join Patterns of different sizes shows the cost of checking
+//! whether a Join Pattern can fire and of collecting its messages.
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rusty_junctions::Junction;
+
+const ROUNDS: u64 = 100;
+
+fn matching(c: &mut Criterion) {
+    let mut group = c.benchmark_group("matching");
+    group.throughput(Throughput::Elements(ROUNDS));
+
+    group.bench_function("2 channels", |b| {
+        let j = Junction::new();
+        let a = j.send_channel::<u64>();
+        let sum = j.recv_channel::<u64>();
+        j.when(&a).and_recv(&sum).then_do(|a| a);
+
+        b.iter(|| {
+            for n in 0..ROUNDS {
+                a.send(n).unwrap();
+                sum.recv().unwrap();
+            }
+        })
+    });
+
+    group.bench_function("3 channels", |b| {
+        let j = Junction::new();
+        let x = j.send_channel::<u64>();
+        let y = j.send_channel::<u64>();
+        let sum = j.recv_channel::<u64>();
+        j.when(&x).and(&y).and_recv(&sum).then_do(|x, y| x + y);
+
+        b.iter(|| {
+            for n in 0..ROUNDS {
+                x.send(n).unwrap();
+                y.send(n).unwrap();
+                sum.recv().unwrap();
+            }
+        })
+    });
+
+    group.bench_function("repeated channel", |b| {
+        let j = Junction::new();
+        let a = j.send_channel::<u64>();
+        let sum = j.recv_channel::<u64>();
+        j.when(&a).and(&a).and_recv(&sum).then_do(|x, y| x + y);
+
+        b.iter(|| {
+            for n in 0..ROUNDS {
+                a.send(n).unwrap();
+                a.send(n).unwrap();
+                sum.recv().unwrap();
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, matching);
+criterion_main!(benches);
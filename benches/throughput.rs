@@ -0,0 +1,76 @@
+//! Throughput of the message path through the control thread.
+//!
+//! Function bodies are run inline on the control thread, so that the cost
+//! of spawning a thread per fired Join Pattern does not drown out the cost
+//! of queueing, storing and matching messages. Every iteration waits for
+//! the `Junction` to become idle, i.e. for all messages to be handled.
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rusty_junctions::Junction;
+
+const MESSAGES: u64 = 1000;
+
+fn send(c: &mut Criterion) {
+    let mut group = c.benchmark_group("send");
+    group.throughput(Throughput::Elements(MESSAGES));
+
+    let j = Junction::new();
+    let tick = j.send_channel::<u64>();
+    let total = Arc::new(AtomicU64::new(0));
+    let counter = total.clone();
+    j.when(&tick).then_do_inline(move |n| {
+        counter.fetch_add(n, Ordering::Relaxed);
+    });
+
+    group.bench_function("fired inline", |b| {
+        b.iter(|| {
+            for n in 0..MESSAGES {
+                tick.send(n).unwrap();
+            }
+            j.wait_idle();
+        })
+    });
+
+    group.finish();
+}
+
+fn many_patterns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("many patterns");
+    group.throughput(Throughput::Elements(MESSAGES));
+
+    for patterns in [1, 10, 100] {
+        group.bench_with_input(
+            BenchmarkId::new("sharing a channel", patterns),
+            &patterns,
+            |b, &patterns| {
+                // Every Join Pattern shares `shared`, so each message on it
+                // is matched against all of them, while only the last one
+                // ever fires.
+                let j = Junction::new();
+                let shared = j.send_channel::<u64>();
+                let own: Vec<_> = (0..patterns).map(|_| j.send_channel::<()>()).collect();
+                for channel in &own {
+                    j.when(&shared).and(channel).then_do_inline(|_, _| {});
+                }
+                let last = own.last().unwrap();
+
+                b.iter(|| {
+                    for n in 0..MESSAGES {
+                        shared.send(n).unwrap();
+                        last.send(()).unwrap();
+                    }
+                    j.wait_idle();
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, send, many_patterns);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+/*
+ * C API of rusty-junctions, see `ffi/src/lib.rs` for the documentation of
+ * every function.
+ */
+
+#ifndef RUSTY_JUNCTIONS_H
+#define RUSTY_JUNCTIONS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RJ_OK 0
+#define RJ_ERROR (-1)
+
+typedef struct rj_junction rj_junction;
+typedef struct rj_channel rj_channel;
+
+typedef struct rj_buffer {
+    const uint8_t *data;
+    size_t len;
+} rj_buffer;
+
+typedef void (*rj_body)(void *user_data, const rj_buffer *messages, size_t count);
+
+rj_junction *rj_junction_new(void);
+void rj_junction_free(rj_junction *junction);
+
+rj_channel *rj_channel_new(const rj_junction *junction);
+void rj_channel_free(rj_channel *channel);
+
+int32_t rj_send(const rj_channel *channel, const uint8_t *data, size_t len);
+
+int32_t rj_when(const rj_junction *junction,
+                const rj_channel *const *channels,
+                size_t count,
+                rj_body body,
+                void *user_data);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUSTY_JUNCTIONS_H */
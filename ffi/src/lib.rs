@@ -0,0 +1,334 @@
+//! C API for `rusty-junctions`, so that non-Rust components of a mixed
+//! codebase can send messages to, and declare simple Join Patterns on, a
+//! `Junction` owned by Rust.
+//!
+//! `Junction`s and channels are handed out as opaque pointers, which have
+//! to be released through `rj_junction_free` and `rj_channel_free`. Messages
+//! are byte buffers, copied when sent, so that C code is free to reuse them
+//! right away. Function bodies of Join Patterns are function pointers,
+//! called with a user data pointer and the messages that fired the pattern.
+//!
+//! Only channels carrying messages without reply are supported, as there is
+//! no way for a C function body to hand back a value of a Rust type.
+//!
+//! The matching header is `include/rusty_junctions.h`.
+
+use std::{
+    ffi::c_void,
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use rusty_junctions::{channels::SendChannel, Junction};
+
+/// Return code of a successful call.
+pub const RJ_OK: i32 = 0;
+
+/// Return code of a failed call.
+pub const RJ_ERROR: i32 = -1;
+
+/// Opaque handle of a `Junction`.
+pub struct RjJunction(Junction);
+
+/// Opaque handle of a channel carrying byte buffers.
+pub struct RjChannel(SendChannel<Vec<u8>>);
+
+/// Message passed to the function body of a Join Pattern.
+///
+/// `data` points to `len` bytes, which are only valid for the duration of
+/// the call of the function body.
+#[repr(C)]
+pub struct RjBuffer {
+    pub data: *const u8,
+    pub len: usize,
+}
+
+/// Function body of a Join Pattern, called with the user data given to
+/// `rj_when` and one message per channel of the pattern, in the order of
+/// the channels.
+pub type RjBody = extern "C" fn(user_data: *mut c_void, messages: *const RjBuffer, count: usize);
+
+/// User data handed to every call of a function body.
+#[derive(Clone, Copy)]
+struct UserData(*mut c_void);
+
+// SAFETY: Callers of `rj_when` guarantee that the user data can be used from
+// any thread, as function bodies run on threads of their own.
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+/// Function body of a Join Pattern declared through `rj_when`.
+#[derive(Clone, Copy)]
+struct Body {
+    body: RjBody,
+    user_data: UserData,
+}
+
+impl Body {
+    /// Call the C function body with the given messages.
+    fn call(&self, messages: &[&[u8]]) {
+        let buffers: Vec<RjBuffer> = messages
+            .iter()
+            .map(|message| RjBuffer {
+                data: message.as_ptr(),
+                len: message.len(),
+            })
+            .collect();
+
+        (self.body)(self.user_data.0, buffers.as_ptr(), buffers.len());
+    }
+}
+
+/// Create a new `Junction`, to be released through `rj_junction_free`.
+#[no_mangle]
+pub extern "C" fn rj_junction_new() -> *mut RjJunction {
+    Box::into_raw(Box::new(RjJunction(Junction::new())))
+}
+
+/// Release a `Junction`, waiting for all function bodies of its Join
+/// Patterns to finish.
+///
+/// Channels of the `Junction` stay valid until released, but sending on
+/// them fails.
+///
+/// # Safety
+///
+/// `junction` has to be null or have been returned by `rj_junction_new`,
+/// and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rj_junction_free(junction: *mut RjJunction) {
+    if !junction.is_null() {
+        // SAFETY: Guaranteed by the caller.
+        drop(unsafe { Box::from_raw(junction) });
+    }
+}
+
+/// Create a new channel on the given `Junction`, to be released through
+/// `rj_channel_free`.
+///
+/// Return null if `junction` is null.
+///
+/// # Safety
+///
+/// `junction` has to be null or a live handle returned by
+/// `rj_junction_new`.
+#[no_mangle]
+pub unsafe extern "C" fn rj_channel_new(junction: *const RjJunction) -> *mut RjChannel {
+    // SAFETY: Guaranteed by the caller.
+    match unsafe { junction.as_ref() } {
+        Some(RjJunction(junction)) => Box::into_raw(Box::new(RjChannel(junction.send_channel()))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Release a channel.
+///
+/// # Safety
+///
+/// `channel` has to be null or have been returned by `rj_channel_new`, and
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rj_channel_free(channel: *mut RjChannel) {
+    if !channel.is_null() {
+        // SAFETY: Guaranteed by the caller.
+        drop(unsafe { Box::from_raw(channel) });
+    }
+}
+
+/// Send a copy of the `len` bytes at `data` on the given channel.
+///
+/// Return `RJ_ERROR` if `channel` is null, or if the `Junction` of the
+/// channel has been released.
+///
+/// # Safety
+///
+/// `channel` has to be null or a live handle returned by `rj_channel_new`,
+/// and `data` has to point to `len` readable bytes, unless `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn rj_send(channel: *const RjChannel, data: *const u8, len: usize) -> i32 {
+    // SAFETY: Guaranteed by the caller.
+    let Some(RjChannel(channel)) = (unsafe { channel.as_ref() }) else {
+        return RJ_ERROR;
+    };
+    let message = if len == 0 {
+        Vec::new()
+    } else {
+        // SAFETY: Guaranteed by the caller.
+        unsafe { slice::from_raw_parts(data, len) }.to_vec()
+    };
+
+    match channel.send(message) {
+        Ok(_) => RJ_OK,
+        Err(_) => RJ_ERROR,
+    }
+}
+
+/// Declare a Join Pattern on the `count` channels at `channels`, calling
+/// `body` with `user_data` and one message per channel whenever it fires.
+///
+/// Function bodies run on threads of their own, possibly several at once.
+///
+/// Return `RJ_ERROR` if any pointer is null, if `count` is not between 1
+/// and 3, or if any channel does not belong to the given `Junction`.
+///
+/// # Safety
+///
+/// `junction` and every channel have to be null or live handles, and
+/// `channels` has to point to `count` channels. `user_data` has to be safe
+/// to use from any thread until the `Junction` has been released.
+#[no_mangle]
+pub unsafe extern "C" fn rj_when(
+    junction: *const RjJunction,
+    channels: *const *const RjChannel,
+    count: usize,
+    body: Option<RjBody>,
+    user_data: *mut c_void,
+) -> i32 {
+    // SAFETY: Guaranteed by the caller.
+    let Some(RjJunction(junction)) = (unsafe { junction.as_ref() }) else {
+        return RJ_ERROR;
+    };
+    let Some(body) = body else {
+        return RJ_ERROR;
+    };
+    if channels.is_null() || !(1..=3).contains(&count) {
+        return RJ_ERROR;
+    }
+
+    // SAFETY: Guaranteed by the caller.
+    let channels = unsafe { slice::from_raw_parts(channels, count) };
+    let Some(channels) = channels
+        .iter()
+        // SAFETY: Guaranteed by the caller.
+        .map(|channel| unsafe { channel.as_ref() }.map(|RjChannel(channel)| channel))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return RJ_ERROR;
+    };
+
+    let body = Body {
+        body,
+        user_data: UserData(user_data),
+    };
+
+    // Declaring a Join Pattern on a channel of another `Junction` panics,
+    // which must not unwind into C code.
+    let declared = panic::catch_unwind(AssertUnwindSafe(|| match channels[..] {
+        [a] => junction.when(a).then_do(move |a| body.call(&[&a])),
+        [a, b] => junction
+            .when(a)
+            .and(b)
+            .then_do(move |a, b| body.call(&[&a, &b])),
+        [a, b, c] => junction
+            .when(a)
+            .and(b)
+            .and(c)
+            .then_do(move |a, b, c| body.call(&[&a, &b, &c])),
+        _ => unreachable!(),
+    }));
+
+    match declared {
+        Ok(()) => RJ_OK,
+        Err(_) => RJ_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{self, Sender};
+
+    /// Function body forwarding the concatenated messages to the `Sender`
+    /// given as user data.
+    extern "C" fn forward(user_data: *mut c_void, messages: *const RjBuffer, count: usize) {
+        let sender = unsafe { &*(user_data as *const Sender<Vec<u8>>) };
+        let messages = unsafe { slice::from_raw_parts(messages, count) };
+
+        let joined = messages
+            .iter()
+            .flat_map(|message| unsafe { slice::from_raw_parts(message.data, message.len) })
+            .copied()
+            .collect();
+        sender.send(joined).unwrap();
+    }
+
+    #[test]
+    fn fire_binary_pattern() {
+        let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+        let sender = Box::into_raw(Box::new(sender));
+
+        unsafe {
+            let junction = rj_junction_new();
+            let a = rj_channel_new(junction);
+            let b = rj_channel_new(junction);
+
+            let channels = [a as *const RjChannel, b as *const RjChannel];
+            let declared = rj_when(junction, channels.as_ptr(), 2, Some(forward), sender.cast());
+            assert_eq!(declared, RJ_OK);
+
+            assert_eq!(rj_send(a, b"ab".as_ptr(), 2), RJ_OK);
+            assert_eq!(rj_send(b, b"c".as_ptr(), 1), RJ_OK);
+            assert_eq!(receiver.recv().unwrap(), b"abc");
+
+            rj_channel_free(a);
+            rj_channel_free(b);
+            rj_junction_free(junction);
+            drop(Box::from_raw(sender));
+        }
+    }
+
+    #[test]
+    fn send_after_free_fails() {
+        unsafe {
+            let junction = rj_junction_new();
+            let channel = rj_channel_new(junction);
+            rj_junction_free(junction);
+
+            assert_eq!(rj_send(channel, ptr::null(), 0), RJ_ERROR);
+            rj_channel_free(channel);
+        }
+    }
+
+    #[test]
+    fn reject_foreign_channel() {
+        unsafe {
+            let junction = rj_junction_new();
+            let other = rj_junction_new();
+            let channel = rj_channel_new(other) as *const RjChannel;
+
+            let declared = rj_when(junction, &channel, 1, Some(forward), ptr::null_mut());
+            assert_eq!(declared, RJ_ERROR);
+
+            rj_channel_free(channel.cast_mut());
+            rj_junction_free(other);
+            rj_junction_free(junction);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_arguments() {
+        unsafe {
+            let junction = rj_junction_new();
+
+            assert!(rj_channel_new(ptr::null()).is_null());
+            assert_eq!(rj_send(ptr::null(), ptr::null(), 0), RJ_ERROR);
+            assert_eq!(
+                rj_when(junction, ptr::null(), 1, Some(forward), ptr::null_mut()),
+                RJ_ERROR
+            );
+
+            let channel = rj_channel_new(junction) as *const RjChannel;
+            assert_eq!(
+                rj_when(junction, &channel, 0, Some(forward), ptr::null_mut()),
+                RJ_ERROR
+            );
+            assert_eq!(
+                rj_when(junction, &channel, 1, None, ptr::null_mut()),
+                RJ_ERROR
+            );
+
+            rj_channel_free(channel.cast_mut());
+            rj_junction_free(junction);
+        }
+    }
+}
@@ -74,6 +74,14 @@ where
     pub fn peek_all(&self, key: &K) -> Option<&LinkedList<V>> {
         self.look_up_table.get(key)
     }
+
+    /// Remove the given key and return all of its values.
+    ///
+    /// Return the values, in order of insertion, if the key is available in
+    /// the collection. Otherwise, return `None`.
+    pub fn remove(&mut self, key: &K) -> Option<LinkedList<V>> {
+        self.look_up_table.remove(key)
+    }
 }
 
 #[cfg(test)]
@@ -189,4 +197,20 @@ mod tests {
         // Then:
         assert_matching!([65], *actual.unwrap());
     }
+
+    #[test]
+    fn test_remove() {
+        // Given:
+        let mut index: InvertedIndex<char, i32> = InvertedIndex::new();
+
+        // When:
+        index.insert_multiple('A', vec![65, 66, 67]);
+        index.insert_single('B', 66);
+        let actual = index.remove(&'A');
+
+        // Then:
+        assert_matching!([65, 66, 67], actual.unwrap());
+        assert!(index.peek_all(&'A').is_none());
+        assert!(index.peek_all(&'B').is_some());
+    }
 }
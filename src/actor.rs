@@ -0,0 +1,108 @@
+//! Actor-style mailboxes built on top of Join Patterns.
+//!
+//! An actor owns some state and processes the messages sent to its mailbox
+//! one at a time, in the order they arrived unless the `Junction` retrieves
+//! messages by `MessageOrdering::Lifo`. Internally, the state is carried by
+//! a message on a private channel, which is joined with the mailbox channel
+//! in a single Join Pattern. As the state message is only resent once the
+//! handler has returned, no two messages are ever processed concurrently.
+//!
+//! ```
+//! use rusty_junctions::Junction;
+//! use std::sync::mpsc::channel;
+//!
+//! let j = Junction::new();
+//! let (tx, rx) = channel();
+//!
+//! let counter = j.actor(0, move |count: &mut i32, n: i32| {
+//!     *count += n;
+//!     tx.send(*count).unwrap();
+//! });
+//!
+//! counter.send(1).unwrap();
+//! counter.send(2).unwrap();
+//!
+//! assert_eq!(rx.recv().unwrap(), 1);
+//! assert_eq!(rx.recv().unwrap(), 3);
+//! ```
+
+use std::{
+    any::Any,
+    sync::{mpsc::SendError, Arc},
+};
+
+use crate::{
+    channels::{MessageReceipt, SendChannel},
+    then_do::ThenDoSpawned,
+    types::Packet,
+    Junction,
+};
+
+/// Typed handle to the mailbox of an actor.
+///
+/// Addresses can be cloned freely, all clones refer to the same mailbox.
+pub struct Address<T> {
+    mailbox: SendChannel<T>,
+}
+
+impl<T: Any + Send> Address<T> {
+    /// Send a message to the mailbox of the actor.
+    ///
+    /// Does not block, the message is processed once all messages sent
+    /// to the actor before it have been processed, unless the `Junction`
+    /// retrieves messages by `MessageOrdering::Lifo`.
+    pub fn send(&self, msg: T) -> Result<MessageReceipt, SendError<Packet>> {
+        self.mailbox.send(msg)
+    }
+}
+
+impl<T> Clone for Address<T> {
+    fn clone(&self) -> Address<T> {
+        Address {
+            mailbox: self.mailbox.clone(),
+        }
+    }
+}
+
+impl Junction {
+    /// Create a new actor on this `Junction` and return its `Address`.
+    ///
+    /// Every message sent to the returned `Address` is passed to `handler`
+    /// alongside a mutable reference to the actor's `state`. Messages are
+    /// never handled concurrently, and handled in the order given by the
+    /// `MessageOrdering` of the `Junction`, i.e. in the order they were
+    /// received by default and the most recent first with
+    /// `MessageOrdering::Lifo`.
+    ///
+    /// If `handler` panics, the state of the actor is lost and no further
+    /// messages will be processed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initial state could not be sent to the control thread.
+    pub fn actor<S, T, F>(&self, state: S, handler: F) -> Address<T>
+    where
+        S: Any + Send,
+        T: Any + Send,
+        F: Fn(&mut S, T) + Send + Sync + 'static,
+    {
+        let mailbox = self.send_channel::<T>();
+        let state_channel = self.send_channel::<S>();
+
+        let handler = Arc::new(handler);
+        let state_channel_clone = state_channel.clone();
+        self.when(&state_channel)
+            .and(&mailbox)
+            .then_do_spawned(move |mut state, msg| {
+                handler(&mut state, msg);
+
+                // The Junction might have shut down in the meantime, in which
+                // case there is nobody left to pass the state on to.
+                let _ = state_channel_clone.send(state);
+            });
+
+        state_channel.send(state).unwrap();
+
+        Address { mailbox }
+    }
+}
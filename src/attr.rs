@@ -0,0 +1,116 @@
+//! Declaring a `Junction` along with its channels and Join Patterns as an
+//! annotated module, and channels for a protocol modelled as an enum.
+//!
+//! # Modules
+//!
+//! Every function of a module annotated with `#[junction]` whose parameters
+//! are channels becomes the function body of a Join Pattern, with
+//! parameters of the same name referring to the same channel:
+//!
+//! - A `Send<T>` parameter receives the message of type `T`.
+//! - A `Recv<R>` parameter is dropped from the function, which returns the
+//!   reply of type `R` instead.
+//! - A `Bidir<T, R>` parameter receives the message of type `T`, while the
+//!   function returns the reply of type `R`.
+//! - A `&Channels` parameter gives access to all channels of the `Junction`,
+//!   e.g. to send messages from within the function body.
+//!
+//! Each function takes at most one `Recv` or `Bidir` channel. Functions
+//! without channel parameters are left as they are.
+//!
+//! The module gains a `Channels` struct with a public field per channel, and
+//! a struct named after the module in upper camel case, which owns the
+//! `Junction` and dereferences to its `Channels`. Its `new` function creates
+//! the `Junction` along with all channels and Join Patterns:
+//!
+//! ```
+//! use rusty_junctions::attr::junction;
+//!
+//! #[junction]
+//! mod storage_cell {
+//!     fn put(channels: &Channels, put: Send<i32>, value: Send<i32>) {
+//!         let _ = value;
+//!         channels.value.send(put).unwrap();
+//!     }
+//!
+//!     fn get(channels: &Channels, get: Recv<i32>, value: Send<i32>) -> i32 {
+//!         channels.value.send(value).unwrap();
+//!         value
+//!     }
+//!
+//!     fn swap(channels: &Channels, swap: Bidir<i32, i32>, value: Send<i32>) -> i32 {
+//!         channels.value.send(swap).unwrap();
+//!         value
+//!     }
+//! }
+//!
+//! let cell = storage_cell::StorageCell::new();
+//! cell.value.send(1).unwrap();
+//!
+//! cell.put.send(2).unwrap();
+//! assert_eq!(cell.swap.send_recv(3).unwrap(), 2);
+//! assert_eq!(cell.get.recv().unwrap(), 3);
+//! ```
+
+//!
+//! # Message enums
+//!
+//! Join Patterns match on channels rather than on the variants of a
+//! message, so an enum deriving `JunctionMessage` gets a send channel per
+//! variant, in a struct named after the enum with a `Channels` suffix. Its
+//! fields are named after the variants in snake case, as raw identifiers
+//! for keywords. Channels of unit variants carry `()`, those of variants
+//! with a single field the field, and those of any other variants a tuple
+//! of their fields in order. Its `send` method sends the payload of a
+//! message on the channel of its variant:
+//!
+//! ```
+//! use rusty_junctions::{attr::JunctionMessage, Junction};
+//! use std::sync::mpsc;
+//!
+//! #[derive(JunctionMessage)]
+//! enum Session {
+//!     Login(String),
+//!     Logout { user: String, minutes: u32 },
+//!     Ping,
+//! }
+//!
+//! let j = Junction::new();
+//! let session = Session::channels(&j);
+//! let (log, entries) = mpsc::channel();
+//!
+//! let login_log = log.clone();
+//! j.when(&session.login)
+//!     .then_do(move |user| login_log.send(format!("{user} logged in")).unwrap());
+//! let logout_log = log.clone();
+//! j.when(&session.logout).then_do(move |(user, minutes)| {
+//!     logout_log
+//!         .send(format!("{user} logged out after {minutes} minutes"))
+//!         .unwrap()
+//! });
+//! j.when(&session.ping)
+//!     .then_do(move |()| log.send("ping".to_string()).unwrap());
+//!
+//! session.send(Session::Login("ada".to_string())).unwrap();
+//! session.send(Session::Ping).unwrap();
+//! session
+//!     .send(Session::Logout {
+//!         user: "ada".to_string(),
+//!         minutes: 5,
+//!     })
+//!     .unwrap();
+//!
+//! // Function bodies run concurrently, so their entries may arrive in any order.
+//! let mut entries: Vec<String> = entries.iter().take(3).collect();
+//! entries.sort();
+//! assert_eq!(
+//!     entries,
+//!     ["ada logged in", "ada logged out after 5 minutes", "ping"]
+//! );
+//! ```
+
+pub use rusty_junctions_attr::{junction, JunctionMessage};
+
+// Named by the `send` method generated by `JunctionMessage`.
+#[doc(hidden)]
+pub use crate::types::Packet;
@@ -0,0 +1,171 @@
+//! Bridges from other kinds of channels and readers into the channels of a
+//! `Junction`.
+//!
+//! Each bridge spawns a thread that receives from the external channel or
+//! reader and sends everything it receives on a new `SendChannel`, until
+//! either the external source is exhausted or the `Junction` has shut down.
+//! This allows code built around other channels to be moved over to Join
+//! Patterns bit by bit.
+
+use std::{
+    any::Any,
+    io::{self, BufRead, BufReader},
+    sync::mpsc::Receiver,
+    thread,
+};
+
+use crate::{channels::SendChannel, Junction};
+
+impl Junction {
+    /// Create a new `SendChannel` on this `Junction` that receives every
+    /// message sent through the given `Receiver`'s `Sender`s.
+    ///
+    /// ```
+    /// use std::sync::mpsc::channel;
+    /// use rusty_junctions::Junction;
+    ///
+    /// let (sender, receiver) = channel::<u32>();
+    ///
+    /// let j = Junction::new();
+    /// let value = j.feed_from(receiver);
+    /// let get = j.recv_channel::<u32>();
+    /// j.when(&value).and_recv(&get).then_do(|v| v);
+    ///
+    /// sender.send(42).unwrap();
+    /// assert_eq!(get.recv().unwrap(), 42);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new channel
+    /// ID from the control thread, or if the thread forwarding the messages
+    /// could not be spawned.
+    pub fn feed_from<T: Any + Send>(&self, receiver: Receiver<T>) -> SendChannel<T> {
+        self.bridge(move |channel| {
+            while let Ok(value) = receiver.recv() {
+                if channel.send(value).is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Create a new `SendChannel` on this `Junction` that receives every
+    /// message sent through the given Tokio `Receiver`'s `Sender`s.
+    ///
+    /// The messages are received in a thread of their own, so no Tokio
+    /// runtime is required.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new channel
+    /// ID from the control thread, or if the thread forwarding the messages
+    /// could not be spawned.
+    #[cfg(feature = "tokio")]
+    pub fn feed_from_tokio<T: Any + Send>(
+        &self,
+        mut receiver: tokio::sync::mpsc::Receiver<T>,
+    ) -> SendChannel<T> {
+        self.bridge(move |channel| {
+            while let Some(value) = receiver.blocking_recv() {
+                if channel.send(value).is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Create a new `SendChannel` on this `Junction` that receives every
+    /// message sent through the given Tokio `UnboundedReceiver`'s
+    /// `UnboundedSender`s, see `Junction::feed_from_tokio`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new channel
+    /// ID from the control thread, or if the thread forwarding the messages
+    /// could not be spawned.
+    #[cfg(feature = "tokio")]
+    pub fn feed_from_tokio_unbounded<T: Any + Send>(
+        &self,
+        mut receiver: tokio::sync::mpsc::UnboundedReceiver<T>,
+    ) -> SendChannel<T> {
+        self.bridge(move |channel| {
+            while let Some(value) = receiver.blocking_recv() {
+                if channel.send(value).is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Create a new `SendChannel` on this `Junction` that receives every line
+    /// read from the given reader, without its line ending.
+    ///
+    /// Reading stops at the end of the input or at the first error, which is
+    /// logged.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let line = j.lines_from(Cursor::new("first\nsecond\n"));
+    /// let next = j.recv_channel::<String>();
+    /// j.when(&line).and_recv(&next).then_do(|line| line);
+    ///
+    /// let mut lines = vec![next.recv().unwrap(), next.recv().unwrap()];
+    /// lines.sort();
+    /// assert_eq!(lines, vec!["first", "second"]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new channel
+    /// ID from the control thread, or if the thread reading the lines could
+    /// not be spawned.
+    pub fn lines_from(&self, reader: impl BufRead + Send + 'static) -> SendChannel<String> {
+        self.bridge(move |channel| {
+            for line in reader.lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        log::error!("Failed to read line: {e:?}");
+                        break;
+                    }
+                };
+                if channel.send(line).is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Create a new `SendChannel` on this `Junction` that receives every line
+    /// read from the standard input, see `Junction::lines_from`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new channel
+    /// ID from the control thread, or if the thread reading the lines could
+    /// not be spawned.
+    pub fn stdin_channel(&self) -> SendChannel<String> {
+        self.lines_from(BufReader::new(io::stdin()))
+    }
+
+    /// Create a new `SendChannel` and spawn a thread running `pump` on a
+    /// clone of it, through the `ThreadSpawner` of this `Junction`.
+    pub(crate) fn bridge<T: Any + Send>(
+        &self,
+        pump: impl FnOnce(SendChannel<T>) + Send + 'static,
+    ) -> SendChannel<T> {
+        let channel = self.send_channel::<T>();
+        let pump_channel = channel.clone();
+
+        self.thread_spawner()
+            .spawn(thread::Builder::new(), Box::new(move || pump(pump_channel)))
+            .map_err(|e| log::error!("Failed to spawn bridge thread: {e:?}"))
+            .unwrap();
+
+        channel
+    }
+}
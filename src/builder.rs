@@ -0,0 +1,1061 @@
+//! Configuration of a `Junction` before it is started.
+//!
+//! `Junction::new` starts a `Junction` with default settings for all of the
+//! options below. A `JunctionBuilder`, obtained through `Junction::builder`,
+//! allows to change them before the control thread is started.
+//!
+//! ```
+//! use rusty_junctions::{
+//!     builder::{MatchPolicy, PanicPolicy},
+//!     Junction,
+//! };
+//!
+//! let j = Junction::builder()
+//!     .thread_name("worker-junction")
+//!     .queue_capacity(1024)
+//!     .match_policy(MatchPolicy::Declaration)
+//!     .panic_policy(PanicPolicy::LogAndContinue)
+//!     .build();
+//!
+//! let value = j.send_channel::<i32>();
+//! let get = j.recv_channel::<i32>();
+//! j.when(&value).and_recv(&get).then_do(|v| v);
+//!
+//! value.send(42).unwrap();
+//! assert_eq!(get.recv().unwrap(), 42);
+//! ```
+
+use std::{
+    any::Any,
+    fmt, io,
+    sync::{mpsc::Sender, Arc},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    clock::Clock,
+    controller::ControllerOptions,
+    intercept::{Intercepted, Verdict},
+    trace::Trace,
+    types::Message,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::Junction;
+
+/// How the function bodies of fired Join Patterns are run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FireExecutor {
+    /// Run every function body in its own thread while the control thread
+    /// carries on handling messages.
+    #[default]
+    Detached,
+    /// Run every function body in its own thread, but have the control
+    /// thread wait for it to finish before handling the next message. No
+    /// two function bodies of the `Junction` ever run at the same time.
+    ///
+    /// A function body must then not wait on a channel of the same
+    /// `Junction`, as the control thread is not around to fire the Join
+    /// Pattern it would be waiting for. Neither must it send on a channel of
+    /// the same `Junction` with a bounded queue that may be full, as the
+    /// control thread is not around to make room either.
+    Joined,
+}
+
+/// Function spawning a thread through the given `thread::Builder`.
+type Spawn =
+    dyn Fn(thread::Builder, Box<dyn FnOnce() + Send>) -> io::Result<JoinHandle<()>> + Send + Sync;
+
+/// Factory through which the control thread of a `Junction` is spawned, so
+/// that embedders can name, instrument or otherwise manage it like the rest
+/// of their threads.
+///
+/// The factory is given a `thread::Builder`, already carrying the name and
+/// stack size set through the `JunctionBuilder`, along with the function to
+/// run in the new thread. It is used again whenever the control thread is
+/// restarted by `Junction::heal`.
+///
+/// The factory is also used for the function bodies of Join Patterns
+/// completed through any of the `then_do_*` methods and
+/// `Junction::register_pattern`, including those backing the
+/// synchronization primitives, combinators and actors of the crate, and
+/// for the helper threads of a `Junction`, such as those of a sharded
+/// `Junction`, a `Supervisor`, `Junction::feed_from` and
+/// `RecvChannel::into_stream`. A `TypedJunction` created through
+/// `TypedJunction::with_thread_spawner` and `Exports` created through
+/// `Exports::with_thread_spawner` take a factory of their own. Only the
+/// function bodies of Join Patterns completed through plain `then_do` are
+/// spawned by the code generated for it, which does not know of the
+/// factory, and are not passed through it.
+///
+/// ```
+/// use rusty_junctions::{builder::ThreadSpawner, Junction};
+/// use std::sync::{
+///     atomic::{AtomicUsize, Ordering},
+///     Arc,
+/// };
+///
+/// let spawned = Arc::new(AtomicUsize::new(0));
+/// let counter = spawned.clone();
+/// let j = Junction::builder()
+///     .thread_spawner(ThreadSpawner::new(move |builder, f| {
+///         counter.fetch_add(1, Ordering::Relaxed);
+///         builder.name("app-junction".to_string()).spawn(f)
+///     }))
+///     .build();
+///
+/// let value = j.send_channel::<i32>();
+/// let get = j.recv_channel::<i32>();
+/// j.when(&value).and_recv(&get).then_do(|v| v);
+///
+/// value.send(42).unwrap();
+/// assert_eq!(get.recv().unwrap(), 42);
+/// assert_eq!(spawned.load(Ordering::Relaxed), 1);
+///
+/// let thread_name = j.recv_channel::<String>();
+/// j.when(&value).and_recv(&thread_name).then_do_with_meta(|_, _| {
+///     std::thread::current().name().unwrap().to_string()
+/// });
+///
+/// value.send(1).unwrap();
+/// assert_eq!(thread_name.recv().unwrap(), "app-junction");
+/// assert_eq!(spawned.load(Ordering::Relaxed), 2);
+/// ```
+#[derive(Clone)]
+pub struct ThreadSpawner {
+    spawn: Arc<Spawn>,
+}
+
+impl ThreadSpawner {
+    /// Spawn threads through the given function.
+    pub fn new(
+        spawn: impl Fn(thread::Builder, Box<dyn FnOnce() + Send>) -> io::Result<JoinHandle<()>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> ThreadSpawner {
+        ThreadSpawner {
+            spawn: Arc::new(spawn),
+        }
+    }
+
+    /// Spawn a thread running the given function.
+    pub(crate) fn spawn(
+        &self,
+        builder: thread::Builder,
+        f: Box<dyn FnOnce() + Send>,
+    ) -> io::Result<JoinHandle<()>> {
+        (self.spawn)(builder, f)
+    }
+
+    /// Spawn a thread running the function body of a fired Join Pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread could not be spawned.
+    pub(crate) fn spawn_body(&self, f: impl FnOnce() + Send + 'static) -> JoinHandle<()> {
+        self.spawn(thread::Builder::new(), Box::new(f))
+            .map_err(|e| log::error!("Failed to spawn function body: {e:?}"))
+            .unwrap()
+    }
+}
+
+impl Default for ThreadSpawner {
+    /// Spawn threads straight through the `thread::Builder`.
+    fn default() -> ThreadSpawner {
+        ThreadSpawner::new(|builder, f| builder.spawn(f))
+    }
+}
+
+impl fmt::Debug for ThreadSpawner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadSpawner").finish_non_exhaustive()
+    }
+}
+
+/// Clock driving the timers of a `Junction`, i.e. the expiry of messages
+/// sent with a time to live and the age at which messages become dead
+/// letters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClockSource {
+    /// Wall-clock time.
+    #[default]
+    System,
+    /// Virtual time, which stands still until it is moved forward through
+    /// `Junction::advance`. Time to live is counted from when a message
+    /// arrives at the `Junction` rather than from when it is sent.
+    Virtual,
+}
+
+/// Which Join Pattern is fired if several can fire on a new message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchPolicy {
+    /// Fire the Join Pattern that has gone the longest without firing, so
+    /// that no Join Pattern is starved by others sharing its channels.
+    #[default]
+    Fair,
+    /// Fire the Join Pattern that has been declared first, giving earlier
+    /// Join Patterns priority over later ones.
+    Declaration,
+}
+
+/// Which of the messages pending on a channel a fired Join Pattern receives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageOrdering {
+    /// Receive the oldest message.
+    #[default]
+    Fifo,
+    /// Receive the most recent message.
+    Lifo,
+}
+
+/// How the control thread waits for new messages while it has nothing to do.
+///
+/// Only affects a `Junction` with a control thread of its own. A `Junction`
+/// in manual mode is driven by its caller, and a `Controller` running as a
+/// task waits on the runtime.
+///
+/// ```
+/// use rusty_junctions::{builder::IdleStrategy, Junction};
+///
+/// let j = Junction::builder()
+///     .idle_strategy(IdleStrategy::SpinThenPark { spins: 1000 })
+///     .build();
+///
+/// let value = j.send_channel::<i32>();
+/// let get = j.recv_channel::<i32>();
+/// j.when(&value).and_recv(&get).then_do(|v| v);
+///
+/// value.send(42).unwrap();
+/// assert_eq!(get.recv().unwrap(), 42);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdleStrategy {
+    /// Block on the queue until a message arrives or the next timer, e.g.
+    /// for a message to expire, is due.
+    #[default]
+    Block,
+    /// Poll the queue up to the given number of times before blocking,
+    /// which cuts the latency of waking up at the cost of burning CPU time
+    /// while the `Junction` is idle.
+    SpinThenPark { spins: u32 },
+    /// Block on the queue, but deal with timers at most once per the given
+    /// period, so that timers due close together are dealt with in a single
+    /// wake-up. Timers may then be late by up to the period.
+    Tick(Duration),
+}
+
+/// What happens when the function body of a fired Join Pattern or the
+/// control thread itself panics.
+///
+/// Panics of function bodies are noticed when the control thread collects
+/// finished function bodies, which happens whenever another Join Pattern
+/// fires, when the function body is joined by the `FireExecutor::Joined`
+/// executor and when the `Junction` is stopped.
+///
+/// ```
+/// use std::sync::mpsc::channel;
+/// use rusty_junctions::{builder::PanicPolicy, Junction};
+///
+/// let (panics, panic_receiver) = channel();
+/// let j = Junction::builder()
+///     .panic_policy(PanicPolicy::ForwardTo(panics))
+///     .build();
+///
+/// let value = j.send_channel::<u32>();
+/// j.when(&value)
+///     .then_do_named("checked", |v| assert!(v < 10, "{v} is too large"));
+///
+/// value.send(42).unwrap();
+/// drop(j);
+///
+/// let panic = panic_receiver.recv().unwrap();
+/// assert!(panic.join_pattern().unwrap().starts_with("`checked`"));
+/// assert_eq!(panic.message(), Some("42 is too large"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub enum PanicPolicy {
+    /// Ignore panics of function bodies. A panic of the control thread ends
+    /// it, after which the `Junction` can no longer be used.
+    #[default]
+    Ignore,
+    /// Log the panic as an error and carry on.
+    ///
+    /// The control thread carries on with the next `Packet` after panicking,
+    /// which may leave the `Message` or Join Pattern it was handling lost.
+    LogAndContinue,
+    /// Log the panic as an error, then abort the process.
+    Abort,
+    /// Log the panic as an error, forward it to the given `Sender` and carry
+    /// on, just like `LogAndContinue`.
+    ForwardTo(Sender<PanicReport>),
+}
+
+/// Panic of the function body of a fired Join Pattern or of the control
+/// thread, see `PanicPolicy::ForwardTo`.
+pub struct PanicReport {
+    join_pattern: Option<String>,
+    payload: Box<dyn Any + Send>,
+}
+
+impl PanicReport {
+    pub(crate) fn new(join_pattern: Option<String>, payload: Box<dyn Any + Send>) -> PanicReport {
+        PanicReport {
+            join_pattern,
+            payload,
+        }
+    }
+
+    /// Return the Join Pattern whose function body panicked, described by
+    /// its name, if it has been given one, and its ID. Return `None` if the
+    /// control thread panicked.
+    pub fn join_pattern(&self) -> Option<&str> {
+        self.join_pattern.as_deref()
+    }
+
+    /// Return the message the panic has been raised with, if it is a string.
+    pub fn message(&self) -> Option<&str> {
+        self.payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| self.payload.downcast_ref::<String>().map(String::as_str))
+    }
+
+    /// Return the payload the panic has been raised with, e.g. to resume it
+    /// through `std::panic::resume_unwind`.
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+}
+
+impl fmt::Debug for PanicReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PanicReport")
+            .field("join_pattern", &self.join_pattern)
+            .field("message", &self.message())
+            .finish_non_exhaustive()
+    }
+}
+
+/// What happens when a Join Pattern is added over exactly the same channels
+/// as a Join Pattern already registered.
+///
+/// Only one of such Join Patterns fires for any set of messages, which one
+/// being up to the `MatchPolicy`. This is rarely intended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePatternPolicy {
+    /// Add the Join Pattern.
+    #[default]
+    Allow,
+    /// Log a warning and add the Join Pattern.
+    Warn,
+    /// Log an error and do not add the Join Pattern.
+    Reject,
+}
+
+/// What happens to the Join Patterns of a channel once all of its handles,
+/// i.e. every clone of its `SendChannel`, `RecvChannel` or `BidirChannel`,
+/// have been dropped.
+///
+/// No more messages can be sent on such a channel, so its Join Patterns can
+/// only fire on the messages already pending on it.
+///
+/// ```
+/// use std::sync::mpsc::channel;
+/// use rusty_junctions::{builder::DroppedChannelPolicy, Junction};
+///
+/// let (dropped, dropped_receiver) = channel();
+/// let j = Junction::builder()
+///     .dropped_channel_policy(DroppedChannelPolicy::Notify(dropped))
+///     .build();
+///
+/// let job = j.send_channel_named::<u32>("job");
+/// let worker = j.send_channel::<()>();
+/// j.when(&job).and(&worker).then_do_named("work", |_, _| {});
+///
+/// drop(job);
+///
+/// let dropped = dropped_receiver.recv().unwrap();
+/// assert!(dropped.channel().starts_with("`job`"));
+/// assert!(dropped.join_patterns()[0].starts_with("`work`"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub enum DroppedChannelPolicy {
+    /// Keep the Join Patterns, which fire on the pending messages of the
+    /// channel until there are none left.
+    #[default]
+    Keep,
+    /// Remove the Join Patterns right away, leaving the messages pending on
+    /// their channels unconsumed.
+    ///
+    /// Messages sent on the channel before its last handle was dropped are
+    /// handled first, so they still get to fire its Join Patterns.
+    ///
+    /// ```
+    /// use std::{sync::mpsc::channel, time::Duration};
+    /// use rusty_junctions::{builder::DroppedChannelPolicy, Junction};
+    ///
+    /// let (done, done_receiver) = channel();
+    /// let j = Junction::builder()
+    ///     .dropped_channel_policy(DroppedChannelPolicy::Disable)
+    ///     .build();
+    ///
+    /// let job = j.send_channel::<u32>();
+    /// let worker = j.send_channel::<()>();
+    /// j.when(&job).and(&worker).then_do(move |n, _| done.send(n).unwrap());
+    ///
+    /// worker.send(()).unwrap();
+    /// job.send(42).unwrap();
+    /// drop(job);
+    ///
+    /// let fired = done_receiver.recv_timeout(Duration::from_secs(5));
+    /// assert_eq!(fired, Ok(42));
+    /// ```
+    Disable,
+    /// Keep the Join Patterns like `Keep`, and pass the channel and its Join
+    /// Patterns on to the given `Sender`.
+    Notify(Sender<DroppedChannel>),
+}
+
+/// Channel whose handles have all been dropped while it was part of Join
+/// Patterns, reported through `DroppedChannelPolicy::Notify`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedChannel {
+    channel: String,
+    join_patterns: Vec<String>,
+}
+
+impl DroppedChannel {
+    pub(crate) fn new(channel: String, join_patterns: Vec<String>) -> DroppedChannel {
+        DroppedChannel {
+            channel,
+            join_patterns,
+        }
+    }
+
+    /// Return the channel, described by its name, if it has been given one,
+    /// and its ID.
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Return the Join Patterns of the channel, described by their names, if
+    /// they have been given one, and their IDs.
+    pub fn join_patterns(&self) -> &[String] {
+        &self.join_patterns
+    }
+}
+
+impl fmt::Display for DroppedChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "All handles of {} have been dropped, leaving {}",
+            self.channel,
+            self.join_patterns.join(", ")
+        )
+    }
+}
+
+/// What happens to messages sent on channels that are not part of any Join
+/// Pattern.
+///
+/// Such messages are dead letters once they have been pending for the age set
+/// through `JunctionBuilder::dead_letter_age`, which allows Join Patterns to
+/// be added after messages have already been sent on their channels.
+///
+/// ```
+/// use std::sync::mpsc::channel;
+/// use rusty_junctions::{builder::DeadLetterPolicy, Junction};
+///
+/// let (dead_letters, dead_letter_receiver) = channel();
+/// let j = Junction::builder()
+///     .dead_letter_policy(DeadLetterPolicy::Forward(dead_letters))
+///     .build();
+///
+/// let orphan = j.send_channel_named::<u32>("orphan");
+/// orphan.send(7).unwrap();
+///
+/// let dead_letter = dead_letter_receiver.recv().unwrap();
+/// assert_eq!(dead_letter.channel_name(), Some("orphan"));
+/// assert_eq!(dead_letter.downcast::<u32>().ok(), Some(7));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub enum DeadLetterPolicy {
+    /// Keep the messages, in case a Join Pattern for their channel is added
+    /// eventually.
+    #[default]
+    Keep,
+    /// Log a warning and drop the messages. Callers of `RecvChannel::recv`
+    /// and `BidirChannel::send_recv` waiting on a dropped message receive an
+    /// error, as no reply can be sent to them anymore.
+    Drop,
+    /// Forward the messages to the given `Sender`.
+    Forward(Sender<DeadLetter>),
+}
+
+/// Message that has not been consumed by any Join Pattern, either because its
+/// channel is not part of any, see `DeadLetterPolicy::Forward`, or because it
+/// has expired, see `JunctionBuilder::on_expiry`.
+pub struct DeadLetter {
+    channel_name: Option<String>,
+    sequence: Option<u64>,
+    value: Box<dyn Any + Send>,
+}
+
+impl DeadLetter {
+    pub(crate) fn new(channel_name: Option<String>, message: Message) -> DeadLetter {
+        DeadLetter {
+            channel_name,
+            sequence: message.sequence(),
+            value: message.into_value(),
+        }
+    }
+
+    /// Return the name of the channel the message has been sent on, if it
+    /// has been given one.
+    pub fn channel_name(&self) -> Option<&str> {
+        self.channel_name.as_deref()
+    }
+
+    /// Return the sequence number of the message, as in the `MessageReceipt`
+    /// returned when sending it, if it has been sent on a `SendChannel`.
+    pub fn sequence(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    /// Recover the value sent as type `T`.
+    ///
+    /// For a `RecvChannel<R>`, the value is the `Sender<R>` the reply would
+    /// have been sent through, for a `BidirChannel<T, R>` it is the tuple of
+    /// the value sent and that `Sender<R>`.
+    ///
+    /// Return the `DeadLetter` itself if its value is not of type `T`.
+    pub fn downcast<T: Any + Send>(self) -> Result<T, DeadLetter> {
+        let channel_name = self.channel_name;
+        let sequence = self.sequence;
+
+        self.value
+            .downcast::<T>()
+            .map(|value| *value)
+            .map_err(|value| DeadLetter {
+                channel_name,
+                sequence,
+                value,
+            })
+    }
+}
+
+impl fmt::Debug for DeadLetter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeadLetter")
+            .field("channel_name", &self.channel_name)
+            .field("sequence", &self.sequence)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Function bodies of fired Join Patterns waiting on each other, reported
+/// through `JunctionBuilder::detect_deadlocks`.
+///
+/// Each of the function bodies is blocked on a call to a `RecvChannel` or
+/// `BidirChannel`, which can only be answered by Join Patterns that are
+/// missing a message last consumed by one of the blocked function bodies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deadlock {
+    waits: Vec<(String, String)>,
+}
+
+impl Deadlock {
+    pub(crate) fn new(waits: Vec<(String, String)>) -> Deadlock {
+        Deadlock { waits }
+    }
+
+    /// Return the blocked Join Patterns along with the channel each of them
+    /// is waiting on, both described by their names, if they have been
+    /// given one, and their IDs.
+    pub fn waits(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.waits
+            .iter()
+            .map(|(join_pattern, channel)| (join_pattern.as_str(), channel.as_str()))
+    }
+}
+
+impl fmt::Display for Deadlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Deadlock between Join Patterns")?;
+        for (i, (join_pattern, channel)) in self.waits().enumerate() {
+            let separator = if i == 0 { ":" } else { "," };
+            write!(f, "{separator} {join_pattern} waiting on {channel}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Channel whose messages pile up faster than Join Patterns consume them,
+/// reported through `JunctionBuilder::on_backlog`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backlog {
+    channel: String,
+    pending: usize,
+    high_water_mark: usize,
+    oldest_age: Duration,
+}
+
+impl Backlog {
+    pub(crate) fn new(
+        channel: String,
+        pending: usize,
+        high_water_mark: usize,
+        oldest_age: Duration,
+    ) -> Backlog {
+        Backlog {
+            channel,
+            pending,
+            high_water_mark,
+            oldest_age,
+        }
+    }
+
+    /// Return the channel, described by its name, if it has been given one,
+    /// and its ID.
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Return the number of messages pending on the channel.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Return the largest number of messages that have been pending on the
+    /// channel at once.
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+
+    /// Return for how long the oldest pending message has been waiting.
+    pub fn oldest_age(&self) -> Duration {
+        self.oldest_age
+    }
+}
+
+impl fmt::Display for Backlog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Backlog on {}: {} pending messages, at most {}, the oldest waiting for {:?}",
+            self.channel, self.pending, self.high_water_mark, self.oldest_age
+        )
+    }
+}
+
+/// Builder for a `Junction` with custom configuration.
+#[derive(Debug, Clone, Default)]
+pub struct JunctionBuilder {
+    queue_capacity: Option<usize>,
+    options: ControllerOptions,
+}
+
+impl JunctionBuilder {
+    /// Create a new `JunctionBuilder` with the default configuration.
+    pub fn new() -> JunctionBuilder {
+        JunctionBuilder::default()
+    }
+
+    /// Bound the queue into the control thread to `capacity` `Packet`s.
+    ///
+    /// Sending on a channel blocks while the queue is full. By default, the
+    /// queue is unbounded.
+    ///
+    /// Function bodies may send on the channels of the `Junction` as usual,
+    /// even when run inline on the control thread, see `then_do_inline`:
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::builder().queue_capacity(1).build();
+    /// let start = j.send_channel::<u32>();
+    /// let item = j.send_channel::<u32>();
+    /// let total = j.send_channel::<u32>();
+    /// let result = j.recv_channel::<u32>();
+    ///
+    /// // Sends more messages than the queue holds from the control thread.
+    /// let item_inner = item.clone();
+    /// j.when(&start).then_do_inline(move |n| {
+    ///     for i in 1..=n {
+    ///         item_inner.send(i).unwrap();
+    ///     }
+    /// });
+    /// let total_inner = total.clone();
+    /// j.when(&item).and(&total).then_do_inline(move |i, sum| {
+    ///     total_inner.send(sum + i).unwrap();
+    /// });
+    /// j.when(&total).and_recv(&result).then_do(|sum| sum);
+    ///
+    /// start.send(10).unwrap();
+    /// total.send(0).unwrap();
+    /// j.wait_idle();
+    /// assert_eq!(result.recv().unwrap(), 55);
+    /// ```
+    pub fn queue_capacity(mut self, capacity: usize) -> JunctionBuilder {
+        self.queue_capacity = Some(capacity);
+        self
+    }
+
+    /// Set the name of the control thread.
+    pub fn thread_name(mut self, name: impl Into<String>) -> JunctionBuilder {
+        self.options.thread_name = Some(name.into());
+        self
+    }
+
+    /// Set the factory through which the control thread is spawned.
+    pub fn thread_spawner(mut self, thread_spawner: ThreadSpawner) -> JunctionBuilder {
+        self.options.thread_spawner = thread_spawner;
+        self
+    }
+
+    /// Set the stack size of the control thread in bytes, instead of the
+    /// default of the standard library.
+    pub fn stack_size(mut self, stack_size: usize) -> JunctionBuilder {
+        self.options.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Set the niceness of the control thread, from -20 for the highest
+    /// priority to 19 for the lowest.
+    ///
+    /// The niceness is set once the control thread has started. Failing to
+    /// set it, e.g. as raising the priority requires the `CAP_SYS_NICE`
+    /// capability, is logged and otherwise ignored.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::builder()
+    ///     .thread_name("background-junction")
+    ///     .thread_priority(10)
+    ///     .thread_affinity([0])
+    ///     .build();
+    ///
+    /// let value = j.send_channel::<i32>();
+    /// let get = j.recv_channel::<i32>();
+    /// j.when(&value).and_recv(&get).then_do(|v| v);
+    ///
+    /// value.send(42).unwrap();
+    /// assert_eq!(get.recv().unwrap(), 42);
+    /// ```
+    #[cfg(all(feature = "sched", target_os = "linux"))]
+    pub fn thread_priority(mut self, niceness: i32) -> JunctionBuilder {
+        self.options.thread_priority = Some(niceness);
+        self
+    }
+
+    /// Pin the control thread to the CPUs with the given indices.
+    ///
+    /// Like the priority, the affinity is set once the control thread has
+    /// started, and failing to set it is logged and otherwise ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no CPU is given, or if a CPU index is not below
+    /// `libc::CPU_SETSIZE`.
+    #[cfg(all(feature = "sched", target_os = "linux"))]
+    pub fn thread_affinity(mut self, cpus: impl IntoIterator<Item = usize>) -> JunctionBuilder {
+        let cpus: Vec<usize> = cpus.into_iter().collect();
+        assert!(!cpus.is_empty(), "No CPU to pin the control thread to");
+        assert!(
+            cpus.iter().all(|&cpu| cpu < libc::CPU_SETSIZE as usize),
+            "CPU index out of range"
+        );
+
+        self.options.thread_affinity = Some(cpus);
+        self
+    }
+
+    /// Set the maximum number of `Packet`s handled in one batch, see
+    /// `Junction::with_max_batch`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_batch` is zero.
+    pub fn max_batch(mut self, max_batch: usize) -> JunctionBuilder {
+        assert!(max_batch > 0, "Batches need to hold at least one Packet");
+        self.options.max_batch = max_batch;
+        self
+    }
+
+    /// Set how function bodies of fired Join Patterns are run.
+    pub fn fire_executor(mut self, fire_executor: FireExecutor) -> JunctionBuilder {
+        self.options.fire_executor = fire_executor;
+        self
+    }
+
+    /// Set which Join Pattern is fired if several can fire at once.
+    pub fn match_policy(mut self, match_policy: MatchPolicy) -> JunctionBuilder {
+        self.options.match_policy = match_policy;
+        self
+    }
+
+    /// Set how the control thread waits for new messages while idle.
+    pub fn idle_strategy(mut self, idle_strategy: IdleStrategy) -> JunctionBuilder {
+        self.options.idle_strategy = idle_strategy;
+        self
+    }
+
+    /// Set which pending message of a channel a fired Join Pattern receives.
+    pub fn message_ordering(mut self, message_ordering: MessageOrdering) -> JunctionBuilder {
+        self.options.message_ordering = message_ordering;
+        self
+    }
+
+    /// Set what happens when a function body of a fired Join Pattern or the
+    /// control thread panics.
+    pub fn panic_policy(mut self, panic_policy: PanicPolicy) -> JunctionBuilder {
+        self.options.panic_policy = panic_policy;
+        self
+    }
+
+    /// Set what happens when a Join Pattern is added over the same channels
+    /// as one already registered.
+    pub fn duplicate_pattern_policy(
+        mut self,
+        duplicate_pattern_policy: DuplicatePatternPolicy,
+    ) -> JunctionBuilder {
+        self.options.duplicate_pattern_policy = duplicate_pattern_policy;
+        self
+    }
+
+    /// Set what happens to messages sent on channels that are not part of any
+    /// Join Pattern.
+    pub fn dead_letter_policy(mut self, dead_letter_policy: DeadLetterPolicy) -> JunctionBuilder {
+        self.options.dead_letter_policy = dead_letter_policy;
+        self
+    }
+
+    /// Set how long a message can be pending on a channel that is not part
+    /// of any Join Pattern before the `DeadLetterPolicy` is applied to it.
+    ///
+    /// By default, the policy is applied as soon as the message arrives.
+    pub fn dead_letter_age(mut self, age: Duration) -> JunctionBuilder {
+        self.options.dead_letter_age = age;
+        self
+    }
+
+    /// Set what happens to the Join Patterns of a channel once all of its
+    /// handles have been dropped.
+    pub fn dropped_channel_policy(
+        mut self,
+        dropped_channel_policy: DroppedChannelPolicy,
+    ) -> JunctionBuilder {
+        self.options.dropped_channel_policy = dropped_channel_policy;
+        self
+    }
+
+    /// Pass messages that expired before being consumed on to the given
+    /// `Sender`, see `SendChannel::send_with_ttl`.
+    ///
+    /// By default, expired messages are dropped.
+    pub fn on_expiry(mut self, sender: Sender<DeadLetter>) -> JunctionBuilder {
+        self.options.expiry_sender = Some(sender);
+        self
+    }
+
+    /// Report function bodies of fired Join Patterns that are deadlocked on
+    /// calls to each other to the given `Sender`.
+    ///
+    /// The control thread then keeps track of which function bodies are
+    /// blocked in `RecvChannel::recv` or `BidirChannel::send_recv` on a
+    /// channel of this `Junction`, and periodically checks whether the Join
+    /// Patterns that could answer them are all missing a message that was
+    /// last consumed by one of the blocked function bodies. Each such cycle
+    /// is reported once, rather than leaving the `Junction` to hang
+    /// silently.
+    ///
+    /// Meant for debugging, as tracking calls adds work to the control
+    /// thread. A message that would be sent by a thread outside the
+    /// `Junction` is not accounted for, so a reported cycle might still be
+    /// broken that way.
+    ///
+    /// ```
+    /// use std::sync::mpsc::channel;
+    /// use rusty_junctions::Junction;
+    ///
+    /// let (deadlocks, deadlock_receiver) = channel();
+    /// let j = Junction::builder().detect_deadlocks(deadlocks).build();
+    ///
+    /// let lock = j.send_channel_named::<()>("lock");
+    /// let run = j.bidir_channel_named::<u32, u32>("run");
+    ///
+    /// // The function body calls `run` again while holding `lock`.
+    /// let run_inner = run.clone();
+    /// j.when(&lock).and_bidir(&run).then_do_named("locked", move |_, n| {
+    ///     if n > 0 {
+    ///         run_inner.send_recv(n - 1).unwrap_or(0)
+    ///     } else {
+    ///         0
+    ///     }
+    /// });
+    ///
+    /// lock.send(()).unwrap();
+    /// std::thread::spawn(move || run.send_recv(1));
+    ///
+    /// let deadlock = deadlock_receiver.recv().unwrap();
+    /// let (join_pattern, channel) = deadlock.waits().next().unwrap();
+    /// assert!(join_pattern.starts_with("`locked`"));
+    /// assert!(channel.starts_with("`run`"));
+    ///
+    /// // Break the deadlock, so that the `Junction` can be dropped.
+    /// lock.send(()).unwrap();
+    /// ```
+    pub fn detect_deadlocks(mut self, sender: Sender<Deadlock>) -> JunctionBuilder {
+        self.options.deadlock_sender = Some(sender);
+        self
+    }
+
+    /// Report channels with more than the given number of pending messages.
+    ///
+    /// A channel whose messages pile up usually belongs to Join Patterns
+    /// that never fire, or has producers outpacing its consumers. Such a
+    /// channel is logged as a warning and passed on to the `Sender` set
+    /// through `on_backlog`, if any, once until all of its messages have
+    /// been consumed.
+    ///
+    /// ```
+    /// use std::sync::mpsc::channel;
+    /// use rusty_junctions::Junction;
+    ///
+    /// let (backlogs, backlog_receiver) = channel();
+    /// let j = Junction::builder()
+    ///     .high_water_mark(2)
+    ///     .on_backlog(backlogs)
+    ///     .build();
+    ///
+    /// let job = j.send_channel_named::<u32>("job");
+    /// let worker = j.send_channel::<()>();
+    /// j.when(&job).and(&worker).then_do(|_, _| {});
+    ///
+    /// job.send_all(0..3).unwrap();
+    ///
+    /// let backlog = backlog_receiver.recv().unwrap();
+    /// assert!(backlog.channel().starts_with("`job`"));
+    /// assert_eq!(backlog.pending(), 3);
+    /// ```
+    pub fn high_water_mark(mut self, pending: usize) -> JunctionBuilder {
+        self.options.high_water_mark = Some(pending);
+        self
+    }
+
+    /// Report channels whose oldest pending message has been waiting for
+    /// longer than the given age, just like `high_water_mark`.
+    ///
+    /// Ages are checked at intervals of half the given age.
+    pub fn max_message_age(mut self, age: Duration) -> JunctionBuilder {
+        self.options.max_message_age = Some(age);
+        self
+    }
+
+    /// Pass channels exceeding the `high_water_mark` or `max_message_age` on
+    /// to the given `Sender`.
+    pub fn on_backlog(mut self, sender: Sender<Backlog>) -> JunctionBuilder {
+        self.options.backlog_sender = Some(sender);
+        self
+    }
+
+    /// Run the timers of the `Junction` on the given `ClockSource`.
+    ///
+    /// Child `Junction`s share the clock of their parent, dealing with the
+    /// timers that have become due once they handle their next message.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use rusty_junctions::{builder::ClockSource, Junction};
+    ///
+    /// let j = Junction::builder().clock(ClockSource::Virtual).build();
+    /// let request = j.send_channel::<u32>();
+    /// let accept = j.recv_channel::<u32>();
+    /// request.send_with_ttl(1, Duration::from_secs(60)).unwrap();
+    /// request.send_with_ttl(2, Duration::from_secs(3600)).unwrap();
+    ///
+    /// // Expires the first request without waiting for a minute.
+    /// j.advance(Duration::from_secs(61));
+    ///
+    /// j.when(&request).and_recv(&accept).then_do(|r| r);
+    /// assert_eq!(accept.recv().unwrap(), 2);
+    /// ```
+    pub fn clock(mut self, clock: ClockSource) -> JunctionBuilder {
+        self.options.clock = match clock {
+            ClockSource::System => Clock::System,
+            ClockSource::Virtual => Clock::new_virtual(),
+        };
+        self
+    }
+
+    /// Record every decision of the control thread into the given `Trace`,
+    /// see the `trace` module.
+    ///
+    /// Child `Junction`s record into the same `Trace`, which should therefore
+    /// only be replayed if the `Junction` has no children.
+    pub fn record_trace(mut self, trace: Trace) -> JunctionBuilder {
+        self.options.record_trace = Some(trace);
+        self
+    }
+
+    /// Take the decisions recorded in the given `Trace` again, see the
+    /// `trace` module.
+    ///
+    /// Messages arriving earlier than in the `Trace` are held back and Join
+    /// Patterns only fire in the order of the `Trace`, until all of its
+    /// decisions have been taken. Should the program diverge from the
+    /// `Trace`, e.g. by sending a message that was never sent when
+    /// recording, the `Junction` stops firing Join Patterns altogether.
+    pub fn replay_trace(mut self, trace: Trace) -> JunctionBuilder {
+        self.options.replay_trace = Some(trace);
+        self
+    }
+
+    /// Run the given interceptor on every message arriving at the control
+    /// thread, after those added before, see the `intercept` module.
+    ///
+    /// Child `Junction`s share the interceptors of their parent.
+    pub fn intercept(
+        mut self,
+        interceptor: impl Fn(&mut Intercepted<'_>) -> Verdict + Send + Sync + 'static,
+    ) -> JunctionBuilder {
+        self.options.interceptors.push(interceptor);
+        self
+    }
+
+    /// Keep the given number of the latest Join Patterns registered with and
+    /// removed from the `Junction`, see `Junction::audit_log`.
+    ///
+    /// Defaults to 64. A capacity of zero turns the audit log off.
+    pub fn audit_capacity(mut self, capacity: usize) -> JunctionBuilder {
+        self.options.audit_capacity = capacity;
+        self
+    }
+
+    /// Create the configured `Junction` and start its control thread.
+    ///
+    /// Not available on `wasm32` targets, see `Junction::new`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the control thread could not be spawned.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build(self) -> Junction {
+        Junction::start(self.options, self.queue_capacity)
+    }
+
+    /// Create the configured `Junction` with its `Controller` running as a
+    /// task on the runtime of the given `Handle`, see `Junction::spawn_on`.
+    ///
+    /// The thread name is ignored, as there is no control thread. Not
+    /// available on `wasm32` targets, see `Junction::new`.
+    #[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+    pub fn spawn_on(self, handle: &tokio::runtime::Handle) -> Junction {
+        Junction::start_on(handle, self.options, self.queue_capacity)
+    }
+}
@@ -0,0 +1,203 @@
+//! Cooperative cancellation of the function bodies of fired Join Patterns.
+//!
+//! Join Patterns completed through `then_do_cancellable` hand a
+//! `CancellationToken` to their function body on every firing. Function
+//! bodies running for a long time are expected to check it regularly and
+//! return early once it has been cancelled, which happens through the
+//! `PatternHandle` of the Join Pattern or when the whole `Junction` is shut
+//! down through `Junction::shutdown`. The `PatternHandle` additionally
+//! allows to replace the function of the Join Pattern while it is running.
+//!
+//! ```
+//! use std::{thread, time::Duration};
+//! use rusty_junctions::Junction;
+//!
+//! let j = Junction::new();
+//! let job = j.send_channel::<()>();
+//!
+//! j.when(&job).then_do_cancellable(|token, _| {
+//!     while !token.is_cancelled() {
+//!         thread::sleep(Duration::from_millis(1));
+//!     }
+//! });
+//!
+//! job.send(()).unwrap();
+//!
+//! // Returns once the function body has noticed the cancellation.
+//! j.shutdown();
+//! ```
+
+use std::{
+    error::Error,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+/// Flag shared between a cancellable Join Pattern and its function bodies.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub(crate) fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Return `true` once the Join Pattern has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+}
+
+/// Handle to a Join Pattern registered with a `Junction`, created through
+/// `then_do_with_handle`, `then_do_cancellable` or any of the `try_then_do_*`
+/// methods.
+///
+/// The type parameter is the type of the function of the Join Pattern,
+/// e.g. `dyn Fn(u32) -> String + Send + Sync`.
+///
+/// ```
+/// let j = rusty_junctions::Junction::new();
+/// let value = j.send_channel::<u32>();
+/// let get = j.recv_channel::<u32>();
+///
+/// let handle = j.when(&value).and_recv(&get).then_do_with_handle(|v| v);
+/// value.send(1).unwrap();
+/// assert_eq!(get.recv().unwrap(), 1);
+///
+/// handle.replace(|v| v * 10);
+/// value.send(2).unwrap();
+/// assert_eq!(get.recv().unwrap(), 20);
+/// ```
+pub struct PatternHandle<F: ?Sized> {
+    token: CancellationToken,
+    function: Arc<RwLock<Arc<F>>>,
+}
+
+impl<F: ?Sized> PatternHandle<F> {
+    pub(crate) fn new(token: CancellationToken, function: Arc<RwLock<Arc<F>>>) -> PatternHandle<F> {
+        PatternHandle { token, function }
+    }
+
+    /// Cancel the Join Pattern.
+    ///
+    /// The Join Pattern does not fire anymore and the `CancellationToken`
+    /// handed to its function bodies, including those still running, is
+    /// cancelled.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Return `true` if the Join Pattern has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Replace the function of the Join Pattern with the given, already
+    /// shared one.
+    fn replace_shared(&self, function: Arc<F>) {
+        *self.function.write().unwrap() = function;
+    }
+}
+
+impl<F: ?Sized> Clone for PatternHandle<F> {
+    fn clone(&self) -> PatternHandle<F> {
+        PatternHandle {
+            token: self.token.clone(),
+            function: self.function.clone(),
+        }
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for PatternHandle<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PatternHandle")
+            .field("cancelled", &self.is_cancelled())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Implement `replace` for the `PatternHandle`s of Join Patterns with the
+/// given type of function.
+macro_rules! impl_replace {
+    ([$($generic:ident),*], $($function:tt)+) => {
+        impl<$($generic),*> PatternHandle<dyn $($function)+ + Send + Sync> {
+            /// Replace the function of the Join Pattern.
+            ///
+            /// Every firing uses the function that is current when it
+            /// starts, so function bodies that are already running finish
+            /// with the previous function, while all later firings use `f`.
+            pub fn replace<G>(&self, f: G)
+            where
+                G: $($function)+ + Send + Sync + 'static,
+            {
+                self.replace_shared(Arc::new(f));
+            }
+        }
+    };
+}
+
+impl_replace!([R], Fn() -> R);
+impl_replace!([T, R], Fn(T) -> R);
+impl_replace!([T, U, R], Fn(T, U) -> R);
+impl_replace!([T, U, V, R], Fn(T, U, V) -> R);
+
+/// Error returned by `try_then_do` and the other `try_then_do_*` methods if
+/// the Join Pattern could not be registered, as the `Junction` it has been
+/// created on has shut down.
+///
+/// ```
+/// use rusty_junctions::{cancel::JunctionClosed, Junction};
+///
+/// let j = Junction::new();
+/// let value = j.send_channel::<u32>();
+/// let pattern = j.when(&value);
+/// let ternary = j.when(&value).and(&value).and(&value);
+/// drop(j);
+///
+/// assert_eq!(pattern.try_then_do(|_| ()).unwrap_err(), JunctionClosed);
+/// assert_eq!(
+///     ternary.try_then_do_named("sum", |_, _, _| ()).unwrap_err(),
+///     JunctionClosed
+/// );
+/// ```
+///
+/// On success, they return a `PatternHandle` instead:
+///
+/// ```
+/// let j = rusty_junctions::Junction::new();
+/// let value = j.send_channel::<u32>();
+/// let scale = j.bidir_channel::<u32, u32>();
+///
+/// let handle = j
+///     .when(&value)
+///     .and(&value)
+///     .and_bidir(&scale)
+///     .try_then_do_named("scale", |a, b, n| (a + b) * n)
+///     .unwrap();
+///
+/// value.send(1).unwrap();
+/// value.send(2).unwrap();
+/// assert_eq!(scale.send_recv(10).unwrap(), 30);
+///
+/// handle.cancel();
+/// assert!(handle.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JunctionClosed;
+
+impl fmt::Display for JunctionClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Junction has shut down")
+    }
+}
+
+impl Error for JunctionClosed {}
@@ -5,14 +5,50 @@
 //! a `RecvChannel` is used to get the value generated by a Join Pattern firing
 //! asynchronously.
 
-use crate::types::{ids, Message, Packet};
+use crate::{
+    join_pattern,
+    metadata::Metadata,
+    queue::PacketSender,
+    types::{ids, Message, Packet},
+};
 use std::{
     any::Any,
+    error::Error,
+    fmt,
     marker::PhantomData,
     marker::Send,
-    sync::mpsc::{channel, RecvError, SendError, Sender},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{channel, Receiver, RecvError, SendError, Sender, TryRecvError},
+        Arc,
+    },
+    time::Duration,
 };
 
+mod adapters;
+mod broadcast;
+mod combinators;
+mod gate;
+mod persistent;
+mod raw;
+mod reply;
+mod split;
+mod state;
+mod tap;
+
+pub use crate::types::ids::{ChannelId, JunctionId};
+pub use adapters::{Contramap, Filter, Map};
+pub use broadcast::BroadcastChannel;
+pub use combinators::{merge, zip, Either};
+pub use gate::NoPatternPolicy;
+pub(crate) use gate::{pattern_gate, GateOpener};
+pub use persistent::PersistentToken;
+pub use reply::{ReplySink, ReplyStream};
+pub use split::{CallView, SendOnlyView};
+pub use state::StateChannel;
+
+use raw::RawChannel;
+
 /***************************
  * Sending Channel Structs *
  ***************************/
@@ -25,46 +61,386 @@ use std::{
 ///
 /// Sending a message this channel will *not* block the current thread, but may
 /// allow a Join Pattern that it is part of to fire.
-#[derive(Clone)]
+///
+/// Like the other channels, a `SendChannel` is `Send` and `Sync` whatever the
+/// type of its messages, as it only ever passes them on. Channels can thus be
+/// kept in shared application state and used from many threads at once
+/// through a reference, without cloning them for every thread.
+///
+/// ```
+/// use rusty_junctions::{channels::SendChannel, Junction};
+/// use std::{cell::Cell, sync::Arc, thread};
+///
+/// struct AppState {
+///     // `Cell` is not `Sync`, yet the channel sending it is.
+///     events: SendChannel<Cell<u32>>,
+/// }
+///
+/// let j = Junction::new();
+/// let events = j.send_channel::<Cell<u32>>();
+/// let total = j.recv_channel::<u32>();
+/// let state = Arc::new(AppState { events });
+///
+/// thread::scope(|s| {
+///     for n in 1..=3 {
+///         let state = &state;
+///         s.spawn(move || state.events.send(Cell::new(n)).unwrap());
+///     }
+/// });
+///
+/// j.when(&state.events).and_recv(&total).then_do(|n| n.get());
+/// let sum: u32 = total.iter().take(3).sum();
+/// assert_eq!(sum, 6);
+/// ```
 pub struct SendChannel<T> {
-    id: ids::ChannelId,
-    junction_id: ids::JunctionId,
-    sender: Sender<Packet>,
-    send_type: PhantomData<T>,
+    raw: RawChannel,
+    /// Sequence number of the next message sent, shared by all handles to
+    /// the channel.
+    sequence: Arc<AtomicU64>,
+    send_type: PhantomData<fn(T)>,
 }
 
 impl<T: Any + Send> SendChannel<T> {
     /// Return the ID of the `Junction` this channel is associated to.
     pub(crate) fn junction_id(&self) -> ids::JunctionId {
-        self.junction_id
+        self.raw.junction_id
+    }
+
+    /// Return the channel's ID.
+    pub(crate) fn id(&self) -> ids::ChannelId {
+        self.raw.id
     }
 
     /// Create a stripped down representation of this channel.
     pub(crate) fn strip(&self) -> StrippedSendChannel<T> {
-        StrippedSendChannel::new(self.id)
+        StrippedSendChannel::new(self.raw.id)
     }
 
     pub(crate) fn new(
         id: ids::ChannelId,
         junction_id: ids::JunctionId,
-        sender: Sender<Packet>,
+        sender: PacketSender,
     ) -> SendChannel<T> {
         SendChannel {
-            id,
-            junction_id,
-            sender,
+            raw: RawChannel::new(id, junction_id, sender),
+            sequence: Arc::new(AtomicU64::new(0)),
+            send_type: PhantomData,
+        }
+    }
+
+    /// Return the name given to this channel on creation, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.raw.name()
+    }
+
+    /// Give this channel handle the given name.
+    pub(crate) fn with_name(mut self, name: Arc<str>) -> SendChannel<T> {
+        self.raw.name = Some(name);
+        self
+    }
+
+    /// Send a value on this channel, returning a `MessageReceipt` with the
+    /// sequence number of the message.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let event = j.send_channel::<&str>();
+    ///
+    /// assert_eq!(event.send("first").unwrap().sequence(), 0);
+    /// assert_eq!(event.clone().send("second").unwrap().sequence(), 1);
+    /// ```
+    pub fn send(&self, value: T) -> Result<MessageReceipt, SendError<Packet>> {
+        self.send_message(Message::new(value))
+    }
+
+    /// Send a value on this channel with the given `Priority`.
+    ///
+    /// When a Join Pattern fires, it consumes the pending message of the
+    /// highest priority on each of its channels, falling back on the
+    /// `MessageOrdering` of the `Junction` among messages of equal priority.
+    /// This lets control messages overtake bulk data sent on the same
+    /// channel. Messages sent through `send` have `Priority::Normal`.
+    ///
+    /// ```
+    /// use rusty_junctions::{channels::Priority, Junction};
+    ///
+    /// let j = Junction::new();
+    /// let command = j.send_channel::<&str>();
+    /// let next = j.recv_channel::<&str>();
+    ///
+    /// command.send("process chunk").unwrap();
+    /// command.send_with_priority("stop", Priority::High).unwrap();
+    ///
+    /// // Added only now, so that both messages are pending when it fires.
+    /// j.when(&command).and_recv(&next).then_do(|c| c);
+    /// assert_eq!(next.recv().unwrap(), "stop");
+    /// ```
+    pub fn send_with_priority(
+        &self,
+        value: T,
+        priority: Priority,
+    ) -> Result<MessageReceipt, SendError<Packet>> {
+        self.send_message(Message::new(value).with_priority(priority))
+    }
+
+    /// Send a value on this channel along with the given `Metadata`, which
+    /// is handed to function bodies declared through `then_do_with_meta`.
+    pub fn send_with_meta(
+        &self,
+        value: T,
+        metadata: Metadata,
+    ) -> Result<MessageReceipt, SendError<Packet>> {
+        self.send_message(Message::new(value).with_metadata(metadata))
+    }
+
+    /// Send all of the given values on this channel at once, returning the
+    /// `MessageReceipt`s of the messages in order.
+    ///
+    /// The values are passed on to the `Junction` together, so they arrive
+    /// in order without messages sent on the channel concurrently in
+    /// between, and at a fraction of the cost of sending them one by one.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let value = j.send_channel::<u32>();
+    /// let next = j.recv_channel::<u32>();
+    /// j.when(&value).and_recv(&next).then_do(|v| v);
+    ///
+    /// let receipts = value.send_all(1..=3).unwrap();
+    /// assert_eq!(receipts.len(), 3);
+    ///
+    /// let mut values: Vec<u32> = next.iter().take(3).collect();
+    /// values.sort();
+    /// assert_eq!(values, vec![1, 2, 3]);
+    /// ```
+    pub fn send_all(
+        &self,
+        values: impl IntoIterator<Item = T>,
+    ) -> Result<Vec<MessageReceipt>, SendError<Packet>> {
+        let values: Vec<T> = values.into_iter().collect();
+        let first = self
+            .sequence
+            .fetch_add(values.len() as u64, Ordering::Relaxed);
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            channel_id = ?self.raw.id,
+            channel_name = self.name(),
+            count = values.len(),
+            "messages sent"
+        );
+
+        let receipts = (first..)
+            .take(values.len())
+            .map(|sequence| MessageReceipt {
+                channel_id: self.raw.id,
+                sequence,
+            })
+            .collect();
+
+        self.raw.send_all(
+            values
+                .into_iter()
+                .zip(first..)
+                .map(|(value, sequence)| Message::new(value).with_sequence(sequence))
+                .collect(),
+        )?;
+
+        Ok(receipts)
+    }
+
+    /// Send a value on this channel and block until it has been consumed by a
+    /// fired Join Pattern, rather than only passed on to the `Junction`.
+    ///
+    /// This keeps producers from running ahead of the Join Patterns
+    /// processing their values. The function body of the Join Pattern may
+    /// still be running when this returns.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let job = j.send_channel::<u32>();
+    /// let worker = j.send_channel::<()>();
+    /// j.when(&job).and(&worker).then_do(|_, _| {});
+    ///
+    /// worker.send(()).unwrap();
+    ///
+    /// // Returns once the only worker has taken the job.
+    /// job.send_sync(1).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `RecvError` if the `Junction` has shut down, or if the value
+    /// has been dropped without being consumed, e.g. as a dead letter or
+    /// because it expired.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a function body run inline, see `then_do_inline`.
+    pub fn send_sync(&self, value: T) -> Result<MessageReceipt, RecvError> {
+        join_pattern::assert_not_inline("SendChannel::send_sync");
+
+        let (ack, ack_receiver) = channel();
+
+        // Drop the `Packet` of a failed send, disconnecting `ack`.
+        let receipt = self
+            .send_message(Message::new(value).with_ack(ack))
+            .map_err(|_| RecvError)?;
+        ack_receiver.recv()?;
+
+        Ok(receipt)
+    }
+
+    /// Number the given `Message` and send it on this channel.
+    fn send_message(&self, msg: Message) -> Result<MessageReceipt, SendError<Packet>> {
+        self.raw.send_numbered(&self.sequence, msg)
+    }
+
+    /// Migrate this channel to the `Junction` behind `to`, where it is
+    /// identified by `to_channel_id`, and wait until all of its pending
+    /// messages have been passed on.
+    pub(crate) fn migrate(
+        &self,
+        to: PacketSender,
+        to_channel_id: ids::ChannelId,
+    ) -> Result<(), RecvError> {
+        self.raw.migrate(to, to_channel_id)
+    }
+
+    /// Send a value that expires if no Join Pattern has consumed it within
+    /// the given time to live.
+    ///
+    /// Expired values are removed from the `Junction` and passed on to the
+    /// `Sender` set through `JunctionBuilder::on_expiry`, if any. This suits
+    /// requests for which firing late is worse than not firing at all.
+    ///
+    /// ```
+    /// use std::{sync::mpsc::channel, time::Duration};
+    /// use rusty_junctions::Junction;
+    ///
+    /// let (expired, expired_receiver) = channel();
+    /// let j = Junction::builder().on_expiry(expired).build();
+    ///
+    /// let request = j.send_channel_named::<u32>("request");
+    /// let worker = j.send_channel::<()>();
+    /// j.when(&request).and(&worker).then_do(|_, _| {});
+    ///
+    /// request.send_with_ttl(1, Duration::from_millis(10)).unwrap();
+    ///
+    /// let expired = expired_receiver.recv().unwrap();
+    /// assert_eq!(expired.channel_name(), Some("request"));
+    /// assert_eq!(expired.downcast::<u32>().ok(), Some(1));
+    /// ```
+    pub fn send_with_ttl(
+        &self,
+        value: T,
+        ttl: Duration,
+    ) -> Result<MessageReceipt, SendError<Packet>> {
+        self.send_message(Message::with_ttl(value, ttl))
+    }
+
+    /// Close this channel for all of its handles, so that sending on it
+    /// fails from now on, while the messages already sent are still
+    /// consumed by its Join Patterns as usual.
+    ///
+    /// Channels created through `Junction::on_close` receive a message once
+    /// the channel is closed, allowing Join Patterns to e.g. flush or tear
+    /// down state tied to the end of a stream of messages.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let events = j.send_channel::<u32>();
+    /// let finished = j.recv_channel::<()>();
+    /// j.when(&j.on_close(&events)).and_recv(&finished).then_do(|()| ());
+    ///
+    /// assert!(events.close());
+    /// assert!(events.clone().send(1).is_err());
+    /// finished.recv().unwrap();
+    /// ```
+    ///
+    /// Return `false` if the channel had already been closed.
+    pub fn close(&self) -> bool {
+        self.raw.close()
+    }
+
+    /// Return `true` if this channel has been closed, see
+    /// `SendChannel::close`.
+    pub fn is_closed(&self) -> bool {
+        self.raw.is_closed()
+    }
+
+    /// Send on the given channel once this channel is closed.
+    pub(crate) fn signal_on_close(&self, close_signal: SendChannel<()>) {
+        self.raw.signal_on_close(close_signal);
+    }
+}
+
+// Implemented manually since deriving would require `T: Clone`, while only
+// the handle to the `Junction` is being cloned.
+impl<T> Clone for SendChannel<T> {
+    fn clone(&self) -> SendChannel<T> {
+        SendChannel {
+            raw: self.raw.clone(),
+            sequence: self.sequence.clone(),
             send_type: PhantomData,
         }
     }
+}
+
+/// Receipt for a message sent on a `SendChannel`.
+///
+/// Messages sent on a channel, through any of its handles, are numbered in
+/// the order they are sent in, so that a send can be correlated with later
+/// diagnostics, such as a `DeadLetter` carrying the same sequence number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageReceipt {
+    channel_id: ids::ChannelId,
+    sequence: u64,
+}
+
+impl MessageReceipt {
+    /// Return the ID of the channel the message has been sent on.
+    pub fn channel_id(&self) -> ids::ChannelId {
+        self.channel_id
+    }
 
-    pub fn send(&self, value: T) -> Result<(), SendError<Packet>> {
-        self.sender.send(Packet::Message {
-            channel_id: self.id,
-            msg: Message::new(value),
-        })
+    /// Return the sequence number of the message on its channel, starting
+    /// at 0 for the first message sent on the channel.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
     }
 }
 
+/// Priority of a message among the messages pending on its channel, see
+/// `SendChannel::send_with_priority`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// `SendChannel` for payloads shared between all channels they are passed on
+/// to, created by `Junction::shared_channel`.
+pub type SharedChannel<T> = SendChannel<Arc<T>>;
+
+/// `SendChannel` for messages answered with any number of replies through a
+/// `ReplySink`, created by `Junction::bidir_stream_channel`.
+pub type BidirStreamChannel<T, R> = SendChannel<(T, ReplySink<R>)>;
+
+/// `SendChannel` for network payloads, created by `Junction::bytes_channel`.
+#[cfg(feature = "bytes")]
+pub type BytesChannel = SendChannel<bytes::Bytes>;
+
 /// Stripped down version of `SendChannel`.
 ///
 /// The main purpose of this struct is to be used in the Join Pattern types to
@@ -106,55 +482,233 @@ impl<T> StrippedSendChannel<T> {
 ///
 /// Sending a message on this channel *will* block the current thread until a Join
 /// Pattern that this channel is part of has fired.
-#[derive(Clone)]
 pub struct RecvChannel<R> {
-    id: ids::ChannelId,
-    junction_id: ids::JunctionId,
-    sender: Sender<Packet>,
-    recv_type: PhantomData<R>,
+    raw: RawChannel,
+    recv_type: PhantomData<fn() -> R>,
 }
 
 impl<R: Any + Send> RecvChannel<R> {
     /// Return the ID of the `Junction` this channel is associated to.
     pub(crate) fn junction_id(&self) -> ids::JunctionId {
-        self.junction_id
+        self.raw.junction_id
+    }
+
+    /// Return the `ThreadSpawner` of the `Junction` this channel is
+    /// associated to.
+    #[cfg(feature = "futures")]
+    pub(crate) fn thread_spawner(&self) -> &crate::builder::ThreadSpawner {
+        self.raw.sender.thread_spawner()
     }
 
     /// Create a stripped down representation of this channel.
     pub(crate) fn strip(&self) -> StrippedRecvChannel<R> {
-        StrippedRecvChannel::new(self.id)
+        StrippedRecvChannel::new(self.raw.id)
     }
 
     pub(crate) fn new(
         id: ids::ChannelId,
         junction_id: ids::JunctionId,
-        sender: Sender<Packet>,
+        sender: PacketSender,
     ) -> RecvChannel<R> {
         RecvChannel {
-            id,
-            junction_id,
-            sender,
+            raw: RawChannel::new(id, junction_id, sender),
             recv_type: PhantomData,
         }
     }
 
+    /// Return the name given to this channel on creation, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.raw.name()
+    }
+
+    /// Give this channel handle the given name.
+    pub(crate) fn with_name(mut self, name: Arc<str>) -> RecvChannel<R> {
+        self.raw.name = Some(name);
+        self
+    }
+
     /// Receive value generated by fired Join Pattern.
     ///
+    /// # Errors
+    ///
+    /// Returns an error if no value will ever be received, e.g. because the
+    /// `Junction` has shut down or is poisoned, see `Junction::is_poisoned`.
+    ///
     /// # Panics
     ///
-    /// Panics if it was not possible to send a return `Sender` to the Junction.
+    /// Panics if called from a function body run inline, see `then_do_inline`.
     pub fn recv(&self) -> Result<R, RecvError> {
+        join_pattern::assert_not_inline("RecvChannel::recv");
+
         let (tx, rx) = channel::<R>();
 
-        self.sender
-            .send(Packet::Message {
-                channel_id: self.id,
-                msg: Message::new(tx),
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::trace_span!("recv", channel_id = ?self.raw.id, channel_name = self.name())
+                .entered();
+        self.raw.send_call(Message::new(tx))?;
+
+        let reply = rx.recv();
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(received = reply.is_ok(), "reply received");
+
+        reply
+    }
+
+    /// Receive `n` values generated by fired Join Patterns, in the order they
+    /// have been generated.
+    ///
+    /// All `n` requests are sent to the `Junction` at once, so Join Patterns
+    /// can fire for all of them as soon as their other channels allow,
+    /// instead of one after the other as when calling `recv` in a loop.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let value = j.send_channel::<u32>();
+    /// let next = j.recv_channel::<u32>();
+    /// j.when(&value).and_recv(&next).then_do(|v| v);
+    ///
+    /// value.send_all(1..=3).unwrap();
+    ///
+    /// let mut values = next.recv_n(3).unwrap();
+    /// values.sort();
+    /// assert_eq!(values, vec![1, 2, 3]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fewer than `n` values will ever be received, e.g.
+    /// because the `Junction` has shut down.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it was not possible to send the return `Sender`s to the
+    /// Junction.
+    ///
+    /// Panics if called from a function body run inline, see `then_do_inline`.
+    pub fn recv_n(&self, n: usize) -> Result<Vec<R>, RecvError> {
+        join_pattern::assert_not_inline("RecvChannel::recv_n");
+
+        let (tx, rx) = channel::<R>();
+
+        self.raw
+            .send_all(
+                (0..n)
+                    .map(|_| Message::new(tx.clone()).with_caller())
+                    .collect(),
+            )
+            .map_err(|e| log::error!("Failed to send Recv Messages: {e:?}"))
+            .unwrap();
+        drop(tx);
+
+        (0..n).map(|_| rx.recv()).collect()
+    }
+
+    /// Receive the values of all Join Patterns that can fire right away,
+    /// without waiting for any further messages.
+    ///
+    /// The `Junction` keeps firing Join Patterns with this channel for as
+    /// long as their other channels have messages pending. If messages of
+    /// other callers of `recv` are already pending on this channel, these
+    /// are served first and nothing is received.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let job = j.send_channel::<u32>();
+    /// let done = j.recv_channel::<u32>();
+    /// j.when(&job).and_recv(&done).then_do(|n| n * 10);
+    ///
+    /// job.send_all([1, 2]).unwrap();
+    ///
+    /// let mut values = done.drain_available();
+    /// values.sort();
+    /// assert_eq!(values, vec![10, 20]);
+    /// assert!(done.drain_available().is_empty());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it was not possible to send the request to the Junction.
+    ///
+    /// Panics if called from a function body run inline, see `then_do_inline`.
+    pub fn drain_available(&self) -> Vec<R> {
+        join_pattern::assert_not_inline("RecvChannel::drain_available");
+
+        let (tx, rx) = channel::<R>();
+        let (count_sender, count_receiver) = channel::<usize>();
+
+        self.raw
+            .sender
+            .send(Packet::DrainRequest {
+                channel_id: self.raw.id,
+                make_message: Box::new(move || Message::new(tx.clone())),
+                return_sender: count_sender,
             })
-            .map_err(|e| log::error!("Failed to send Recv Message: {e:?}"))
+            .map_err(|e| log::error!("Failed to send DrainRequest: {e:?}"))
             .unwrap();
 
-        rx.recv()
+        let count = count_receiver.recv().unwrap_or(0);
+        rx.iter().take(count).collect()
+    }
+
+    /// Return an iterator that blocks on `recv` for every value, until the
+    /// `Junction` has shut down.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let value = j.send_channel::<u32>();
+    /// let next = j.recv_channel::<u32>();
+    /// j.when(&value).and_recv(&next).then_do(|v| v);
+    ///
+    /// for v in 1..=3 {
+    ///     value.send(v).unwrap();
+    /// }
+    ///
+    /// let mut values: Vec<u32> = next.iter().take(3).collect();
+    /// values.sort();
+    /// assert_eq!(values, vec![1, 2, 3]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, R> {
+        Iter { channel: self }
+    }
+}
+
+impl<'a, R: Any + Send> IntoIterator for &'a RecvChannel<R> {
+    type Item = R;
+    type IntoIter = Iter<'a, R>;
+
+    fn into_iter(self) -> Iter<'a, R> {
+        self.iter()
+    }
+}
+
+/// Iterator over the values received on a `RecvChannel`, see
+/// `RecvChannel::iter`.
+pub struct Iter<'a, R> {
+    channel: &'a RecvChannel<R>,
+}
+
+impl<R: Any + Send> Iterator for Iter<'_, R> {
+    type Item = R;
+
+    fn next(&mut self) -> Option<R> {
+        self.channel.recv().ok()
+    }
+}
+
+impl<R> Clone for RecvChannel<R> {
+    fn clone(&self) -> RecvChannel<R> {
+        RecvChannel {
+            raw: self.raw.clone(),
+            recv_type: PhantomData,
+        }
     }
 }
 
@@ -207,58 +761,272 @@ impl<R> StrippedRecvChannel<R> {
 ///
 /// Sending a message on this channel *will* block the current thread until a Join
 /// Pattern that this channel is part of has fired.
-#[derive(Clone)]
 pub struct BidirChannel<T, R> {
-    id: ids::ChannelId,
-    junction_id: ids::JunctionId,
-    sender: Sender<Packet>,
-    send_type: PhantomData<T>,
-    recv_type: PhantomData<R>,
+    raw: RawChannel,
+    send_type: PhantomData<fn(T)>,
+    recv_type: PhantomData<fn() -> R>,
 }
 
 impl<T: Any + Send, R: Any + Send> BidirChannel<T, R> {
     /// Return the ID of the `Junction` this channel is associated to.
     pub(crate) fn junction_id(&self) -> ids::JunctionId {
-        self.junction_id
+        self.raw.junction_id
     }
 
     /// Create a stripped down representation of this channel.
     pub(crate) fn strip(&self) -> StrippedBidirChannel<T, R> {
-        StrippedBidirChannel::new(self.id)
+        StrippedBidirChannel::new(self.raw.id)
     }
 
     pub(crate) fn new(
         id: ids::ChannelId,
         junction_id: ids::JunctionId,
-        sender: Sender<Packet>,
+        sender: PacketSender,
     ) -> BidirChannel<T, R> {
         BidirChannel {
-            id,
-            junction_id,
-            sender,
+            raw: RawChannel::new(id, junction_id, sender),
             send_type: PhantomData,
             recv_type: PhantomData,
         }
     }
 
+    /// Return the name given to this channel on creation, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.raw.name()
+    }
+
+    /// Give this channel handle the given name.
+    pub(crate) fn with_name(mut self, name: Arc<str>) -> BidirChannel<T, R> {
+        self.raw.name = Some(name);
+        self
+    }
+
     /// Send a message and receive value generated by fired Junction.
     ///
+    /// # Errors
+    ///
+    /// Returns an error if no value will ever be received, e.g. because the
+    /// `Junction` has shut down or is poisoned, see `Junction::is_poisoned`.
+    pub fn send_recv(&self, msg: T) -> Result<R, RecvError> {
+        self.send_message_recv(|tx| Message::new((msg, tx)))
+    }
+
+    /// Send a message that expires if no Join Pattern has consumed it within
+    /// the given time to live, and receive the value generated by the fired
+    /// Join Pattern otherwise.
+    ///
+    /// If the message expires, an error is returned once it has been removed
+    /// from the `Junction`, see `SendChannel::send_with_ttl`. If expired
+    /// messages are passed on through `JunctionBuilder::on_expiry`, this
+    /// only happens once the `DeadLetter` holding the message is dropped.
+    ///
     /// # Panics
     ///
     /// Panics if it was not possible to send the given message and return
     /// `Sender` to the Junction.
-    pub fn send_recv(&self, msg: T) -> Result<R, RecvError> {
+    pub fn send_recv_with_ttl(&self, msg: T, ttl: Duration) -> Result<R, RecvError> {
+        self.send_message_recv(|tx| Message::with_ttl((msg, tx), ttl))
+    }
+
+    /// Send a message along with the given `Metadata`, which is handed to
+    /// function bodies declared through `then_do_with_meta`, and receive
+    /// the value generated by the fired Join Pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no value will ever be received, see
+    /// `BidirChannel::send_recv`.
+    pub fn send_recv_with_meta(&self, msg: T, metadata: Metadata) -> Result<R, RecvError> {
+        self.send_message_recv(|tx| Message::new((msg, tx)).with_metadata(metadata))
+    }
+
+    /// Send a message without waiting for the reply, which is collected
+    /// later through the returned `ReplyTicket`.
+    ///
+    /// Issuing several calls before collecting any of their replies lets
+    /// their Join Patterns fire concurrently, instead of the caller waiting
+    /// for each reply in turn.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let square = j.bidir_channel::<u64, u64>();
+    /// let worker = j.send_channel::<()>();
+    /// let worker_inner = worker.clone();
+    /// j.when(&worker).and_bidir(&square).then_do(move |_, n| {
+    ///     worker_inner.send(()).unwrap();
+    ///     n * n
+    /// });
+    /// worker.send(()).unwrap();
+    ///
+    /// let tickets: Vec<_> = (1..=3).map(|n| square.call_detached(n)).collect();
+    /// let squares: Vec<u64> = tickets.into_iter().map(|t| t.wait().unwrap()).collect();
+    /// assert_eq!(squares, vec![1, 4, 9]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it was not possible to send the given message and return
+    /// `Sender` to the Junction.
+    pub fn call_detached(&self, msg: T) -> ReplyTicket<R> {
         let (tx, rx) = channel::<R>();
 
-        self.sender
-            .send(Packet::Message {
-                channel_id: self.id,
-                msg: Message::new((msg, tx)),
-            })
+        #[cfg(feature = "tracing")]
+        tracing::trace!(channel_id = ?self.raw.id, channel_name = self.name(), "message sent");
+
+        // Not marked as sent by a waiting caller, since the caller carries on
+        // until it collects the reply.
+        self.raw
+            .send(Message::new((msg, tx)))
             .map_err(|e| log::error!("Failed to send Bidir Message: {e:?}"))
             .unwrap();
 
-        rx.recv()
+        ReplyTicket { receiver: rx }
+    }
+
+    /// Send the `Message` built around a return `Sender` and wait for the
+    /// reply.
+    fn send_message_recv(
+        &self,
+        message: impl FnOnce(Sender<R>) -> Message,
+    ) -> Result<R, RecvError> {
+        join_pattern::assert_not_inline("BidirChannel::send_recv");
+
+        let (tx, rx) = channel::<R>();
+
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::trace_span!("send_recv", channel_id = ?self.raw.id, channel_name = self.name())
+                .entered();
+        self.raw.send_call(message(tx))?;
+
+        let reply = rx.recv();
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(received = reply.is_ok(), "reply received");
+
+        reply
+    }
+}
+
+impl<T: Any + Send, R: Any + Send, E: Any + Send> BidirChannel<T, Result<R, E>> {
+    /// Send a message to a Join Pattern whose function may fail and receive
+    /// the value it generated, or the error it failed with.
+    ///
+    /// Join Patterns over a `BidirChannel<T, Result<R, E>>` are completed
+    /// with functions returning `Result<R, E>`, whose errors are surfaced
+    /// here as `CallError::Handler` instead of having to be encoded within
+    /// the value generated.
+    ///
+    /// ```
+    /// use rusty_junctions::{channels::CallError, Junction};
+    ///
+    /// let j = Junction::new();
+    /// let balance = j.send_channel::<u32>();
+    /// let withdraw = j.bidir_channel::<u32, Result<u32, String>>();
+    ///
+    /// let balance_inner = balance.clone();
+    /// j.when(&balance).and_bidir(&withdraw).then_do(move |b, amount| {
+    ///     if amount > b {
+    ///         balance_inner.send(b).unwrap();
+    ///         return Err(format!("cannot withdraw {amount} of {b}"));
+    ///     }
+    ///     balance_inner.send(b - amount).unwrap();
+    ///     Ok(b - amount)
+    /// });
+    ///
+    /// balance.send(10).unwrap();
+    /// assert_eq!(withdraw.call(4).unwrap(), 6);
+    /// assert!(matches!(withdraw.call(7), Err(CallError::Handler(_))));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it was not possible to send the given message and return
+    /// `Sender` to the Junction.
+    pub fn call(&self, msg: T) -> Result<R, CallError<E>> {
+        self.send_recv(msg)?.map_err(CallError::Handler)
+    }
+}
+
+/// Error returned by `BidirChannel::call`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError<E> {
+    /// The function of the fired Join Pattern failed with the given error.
+    Handler(E),
+    /// No reply will ever be received, e.g. because the `Junction` has shut
+    /// down or the message has been dropped as a dead letter.
+    Disconnected,
+}
+
+impl<E> From<RecvError> for CallError<E> {
+    fn from(_: RecvError) -> CallError<E> {
+        CallError::Disconnected
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for CallError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Handler(e) => write!(f, "Join Pattern failed: {e}"),
+            CallError::Disconnected => write!(f, "no reply received from the Junction"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for CallError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CallError::Handler(e) => Some(e),
+            CallError::Disconnected => None,
+        }
+    }
+}
+
+/// Reply to a message sent through `BidirChannel::call_detached`, to be
+/// collected once it is needed.
+#[derive(Debug)]
+pub struct ReplyTicket<R> {
+    receiver: Receiver<R>,
+}
+
+impl<R> ReplyTicket<R> {
+    /// Block until the reply has arrived and return it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no reply will ever arrive, e.g. because the
+    /// `Junction` has shut down or the message has been dropped as a dead
+    /// letter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a function body run inline, see `then_do_inline`.
+    pub fn wait(self) -> Result<R, RecvError> {
+        join_pattern::assert_not_inline("ReplyTicket::wait");
+
+        self.receiver.recv()
+    }
+
+    /// Return the reply if it has already arrived, without blocking.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TryRecvError::Empty` if the reply has not arrived yet, and
+    /// `TryRecvError::Disconnected` if it never will.
+    pub fn try_wait(&self) -> Result<R, TryRecvError> {
+        self.receiver.try_recv()
+    }
+}
+
+impl<T, R> Clone for BidirChannel<T, R> {
+    fn clone(&self) -> BidirChannel<T, R> {
+        BidirChannel {
+            raw: self.raw.clone(),
+            send_type: PhantomData,
+            recv_type: PhantomData,
+        }
     }
 }
 
@@ -292,3 +1060,105 @@ impl<T, R> StrippedBidirChannel<T, R> {
         self.id
     }
 }
+
+/*****************
+ * Channel Trait *
+ *****************/
+
+/// Kind of a channel, see `JunctionChannel::kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelKind {
+    Send,
+    Recv,
+    Bidir,
+}
+
+/// Information shared by all kinds of channels.
+///
+/// Implemented by `SendChannel`, `RecvChannel` and `BidirChannel`, so that
+/// code such as registries, debug printers or bridges can handle channels
+/// regardless of their kind.
+///
+/// ```
+/// use rusty_junctions::{channels::JunctionChannel, Junction};
+///
+/// fn describe(channel: &dyn JunctionChannel) -> String {
+///     format!("{:?} channel {}", channel.kind(), channel.name().unwrap_or("?"))
+/// }
+///
+/// let j = Junction::new();
+/// let put = j.send_channel_named::<u32>("put");
+/// let get = j.recv_channel_named::<u32>("get");
+///
+/// assert_eq!(describe(&put), "Send channel put");
+/// assert_eq!(describe(&get), "Recv channel get");
+/// assert_eq!(put.junction_id(), get.junction_id());
+/// assert_ne!(put.channel_id(), get.channel_id());
+/// ```
+pub trait JunctionChannel {
+    /// Return the ID of the `Junction` the channel is associated to.
+    fn junction_id(&self) -> JunctionId;
+
+    /// Return the ID of the channel within its `Junction`.
+    fn channel_id(&self) -> ChannelId;
+
+    /// Return the name given to the channel on creation, if any.
+    fn name(&self) -> Option<&str>;
+
+    /// Return the kind of the channel.
+    fn kind(&self) -> ChannelKind;
+}
+
+impl<T> JunctionChannel for SendChannel<T> {
+    fn junction_id(&self) -> JunctionId {
+        self.raw.junction_id
+    }
+
+    fn channel_id(&self) -> ChannelId {
+        self.raw.id
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.raw.name()
+    }
+
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Send
+    }
+}
+
+impl<R> JunctionChannel for RecvChannel<R> {
+    fn junction_id(&self) -> JunctionId {
+        self.raw.junction_id
+    }
+
+    fn channel_id(&self) -> ChannelId {
+        self.raw.id
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.raw.name()
+    }
+
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Recv
+    }
+}
+
+impl<T, R> JunctionChannel for BidirChannel<T, R> {
+    fn junction_id(&self) -> JunctionId {
+        self.raw.junction_id
+    }
+
+    fn channel_id(&self) -> ChannelId {
+        self.raw.id
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.raw.name()
+    }
+
+    fn kind(&self) -> ChannelKind {
+        ChannelKind::Bidir
+    }
+}
@@ -0,0 +1,227 @@
+//! Adapters converting or filtering the values passed through a channel.
+//!
+//! Adapters are lightweight wrappers around a channel handle that apply a
+//! conversion or check right before a value is sent or right after it is
+//! received, so that parts of an application can be given a view of a
+//! channel with a narrower or more convenient type, or one that only lets
+//! valid values through, without adding Join Patterns.
+
+use std::{
+    any::Any,
+    sync::{
+        mpsc::{RecvError, SendError},
+        Arc,
+    },
+};
+
+use crate::{
+    channels::{MessageReceipt, RecvChannel, SendChannel},
+    types::Packet,
+};
+
+impl<T: Any + Send> SendChannel<T> {
+    /// Return a view of this channel that sends values of type `B`,
+    /// converted by the given function before being sent on this channel.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let event = j.send_channel::<String>();
+    /// let next = j.recv_channel::<String>();
+    /// j.when(&event).and_recv(&next).then_do(|e| e);
+    ///
+    /// let status = event.contramap(|code: u16| format!("status {code}"));
+    /// status.send(404).unwrap();
+    ///
+    /// assert_eq!(next.recv().unwrap(), "status 404");
+    /// ```
+    pub fn contramap<B, F>(&self, f: F) -> Contramap<B, T>
+    where
+        F: Fn(B) -> T + Send + Sync + 'static,
+    {
+        Contramap {
+            channel: self.clone(),
+            f: Arc::new(f),
+        }
+    }
+
+    /// Return a view of this channel that only sends values matching the
+    /// given predicate, silently dropping all others.
+    ///
+    /// Values are checked before being sent, so rejected values never reach
+    /// the `Junction`. Use `Filter::reject_to` to pass them on to another
+    /// channel instead of dropping them.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let age = j.send_channel::<i32>();
+    /// let invalid = j.send_channel::<i32>();
+    /// let next = j.recv_channel::<i32>();
+    /// let next_invalid = j.recv_channel::<i32>();
+    /// j.when(&age).and_recv(&next).then_do(|a| a);
+    /// j.when(&invalid).and_recv(&next_invalid).then_do(|a| a);
+    ///
+    /// let age_input = age.filter(|a| (0..150).contains(a)).reject_to(&invalid);
+    /// age_input.send(-1).unwrap();
+    /// age_input.send(42).unwrap();
+    ///
+    /// assert_eq!(next.recv().unwrap(), 42);
+    /// assert_eq!(next_invalid.recv().unwrap(), -1);
+    /// ```
+    pub fn filter<P>(&self, predicate: P) -> Filter<T>
+    where
+        P: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        Filter {
+            channel: self.clone(),
+            predicate: Arc::new(predicate),
+            rejected: None,
+        }
+    }
+}
+
+/// View of a `SendChannel` sending values of another type, see
+/// `SendChannel::contramap`.
+pub struct Contramap<B, T> {
+    channel: SendChannel<T>,
+    f: Arc<dyn Fn(B) -> T + Send + Sync>,
+}
+
+impl<B, T: Any + Send> Contramap<B, T> {
+    /// Convert the given value and send it on the underlying channel.
+    pub fn send(&self, value: B) -> Result<MessageReceipt, SendError<Packet>> {
+        self.channel.send((self.f)(value))
+    }
+
+    /// Return the underlying channel.
+    pub fn channel(&self) -> &SendChannel<T> {
+        &self.channel
+    }
+}
+
+// Implemented manually since deriving would require `B: Clone` and
+// `T: Clone`, while only the handle and conversion are being cloned.
+impl<B, T> Clone for Contramap<B, T> {
+    fn clone(&self) -> Contramap<B, T> {
+        Contramap {
+            channel: self.channel.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+/// View of a `SendChannel` only sending values matching a predicate, see
+/// `SendChannel::filter`.
+pub struct Filter<T> {
+    channel: SendChannel<T>,
+    predicate: Arc<dyn Fn(&T) -> bool + Send + Sync>,
+    /// Channel to send rejected values on, instead of dropping them.
+    rejected: Option<SendChannel<T>>,
+}
+
+impl<T: Any + Send> Filter<T> {
+    /// Send values failing the predicate on the given channel rather than
+    /// dropping them.
+    pub fn reject_to(mut self, rejected: &SendChannel<T>) -> Filter<T> {
+        self.rejected = Some(rejected.clone());
+        self
+    }
+
+    /// Send the given value on the underlying channel if it matches the
+    /// predicate, and on the reject channel otherwise, if any.
+    ///
+    /// Return the `MessageReceipt` of the message sent on either channel, or
+    /// `None` if the value has been dropped.
+    pub fn send(&self, value: T) -> Result<Option<MessageReceipt>, SendError<Packet>> {
+        if (self.predicate)(&value) {
+            return self.channel.send(value).map(Some);
+        }
+
+        match &self.rejected {
+            Some(rejected) => rejected.send(value).map(Some),
+            None => {
+                log::debug!("Dropping value rejected by filter");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Return the underlying channel.
+    pub fn channel(&self) -> &SendChannel<T> {
+        &self.channel
+    }
+}
+
+// Implemented manually since deriving would require `T: Clone`, while only
+// the handles and predicate are being cloned.
+impl<T> Clone for Filter<T> {
+    fn clone(&self) -> Filter<T> {
+        Filter {
+            channel: self.channel.clone(),
+            predicate: self.predicate.clone(),
+            rejected: self.rejected.clone(),
+        }
+    }
+}
+
+impl<R: Any + Send> RecvChannel<R> {
+    /// Return a view of this channel that receives values of type `S`,
+    /// converted by the given function after being received on this
+    /// channel.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let value = j.send_channel::<u32>();
+    /// let next = j.recv_channel::<u32>();
+    /// j.when(&value).and_recv(&next).then_do(|v| v);
+    ///
+    /// let is_even = next.map(|v| v % 2 == 0);
+    /// value.send(4).unwrap();
+    ///
+    /// assert!(is_even.recv().unwrap());
+    /// ```
+    pub fn map<S, F>(&self, f: F) -> Map<R, S>
+    where
+        F: Fn(R) -> S + Send + Sync + 'static,
+    {
+        Map {
+            channel: self.clone(),
+            f: Arc::new(f),
+        }
+    }
+}
+
+/// View of a `RecvChannel` receiving values of another type, see
+/// `RecvChannel::map`.
+pub struct Map<R, S> {
+    channel: RecvChannel<R>,
+    f: Arc<dyn Fn(R) -> S + Send + Sync>,
+}
+
+impl<R: Any + Send, S> Map<R, S> {
+    /// Receive a value on the underlying channel and convert it.
+    pub fn recv(&self) -> Result<S, RecvError> {
+        self.channel.recv().map(|value| (self.f)(value))
+    }
+
+    /// Return the underlying channel.
+    pub fn channel(&self) -> &RecvChannel<R> {
+        &self.channel
+    }
+}
+
+// Implemented manually since deriving would require `R: Clone` and
+// `S: Clone`, while only the handle and conversion are being cloned.
+impl<R, S> Clone for Map<R, S> {
+    fn clone(&self) -> Map<R, S> {
+        Map {
+            channel: self.channel.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Channels whose messages are delivered to every subscriber.
+//!
+//! A message sent on a `SendChannel` is consumed by a single Join Pattern.
+//! Publishing an event to several independent parts of an application
+//! would therefore take a channel per part, and a send on each of them.
+//! A `BroadcastChannel` instead has the control thread store a copy of
+//! every message on each of its subscribers, which are `SendChannel`s
+//! created through `Junction::subscribe` and used in Join Patterns like any
+//! other.
+
+use std::{any::Any, fmt, sync::mpsc::SendError};
+
+use crate::{
+    channels::{MessageReceipt, SendChannel},
+    types::{ids, Packet},
+};
+
+/// Channel passing a copy of every message sent on it to each of its
+/// subscribers, see `Junction::broadcast_channel`.
+pub struct BroadcastChannel<T> {
+    channel: SendChannel<T>,
+}
+
+impl<T: Any + Send + Clone> BroadcastChannel<T> {
+    pub(crate) fn new(channel: SendChannel<T>) -> BroadcastChannel<T> {
+        BroadcastChannel { channel }
+    }
+
+    /// Return the ID of the `Junction` this channel is associated to.
+    pub(crate) fn junction_id(&self) -> ids::JunctionId {
+        self.channel.junction_id()
+    }
+
+    /// Return the channel's ID.
+    pub(crate) fn id(&self) -> ids::ChannelId {
+        self.channel.id()
+    }
+
+    /// Return the name given to this channel on creation, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.channel.name()
+    }
+
+    /// Send a message to all subscribers of this channel, including those
+    /// subscribing while it is on its way to the control thread.
+    ///
+    /// Messages sent before there are any subscribers are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message could not be sent, e.g. because the
+    /// `Junction` has shut down.
+    pub fn send(&self, value: T) -> Result<MessageReceipt, SendError<Packet>> {
+        self.channel.send(value)
+    }
+}
+
+// Implemented manually since deriving would require `T: Clone`.
+impl<T> Clone for BroadcastChannel<T> {
+    fn clone(&self) -> BroadcastChannel<T> {
+        BroadcastChannel {
+            channel: self.channel.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for BroadcastChannel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BroadcastChannel").finish_non_exhaustive()
+    }
+}
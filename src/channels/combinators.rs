@@ -0,0 +1,107 @@
+//! Combinators joining the messages of several channels into one.
+//!
+//! Combinators add Join Patterns passing the messages of their input
+//! channels on to a new channel, on which the combined messages can then be
+//! used in Join Patterns like any other.
+
+use std::any::Any;
+
+use crate::{channels::SendChannel, junction::Junction, then_do::ThenDoSpawned};
+
+/// Value of one of two types, sent by the channel created through `merge`
+/// and passed to the function given to `Junction::select`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+/// Create a channel on the given `Junction` receiving every message sent on
+/// `a` as `Either::Left` and every message sent on `b` as `Either::Right`.
+///
+/// Messages sent on `a` and `b` are consumed by the Join Patterns added to
+/// pass them on, so they should not be part of any other Join Pattern. As
+/// the Join Patterns fire independently, messages may arrive on the new
+/// channel in a different order than they were sent in.
+///
+/// ```
+/// use rusty_junctions::{channels::{self, Either}, Junction};
+///
+/// let j = Junction::new();
+/// let clicks = j.send_channel::<(u32, u32)>();
+/// let keys = j.send_channel::<char>();
+/// let next = j.recv_channel::<Either<(u32, u32), char>>();
+///
+/// let events = channels::merge(&j, &clicks, &keys);
+/// j.when(&events).and_recv(&next).then_do(|e| e);
+///
+/// keys.send('q').unwrap();
+/// assert_eq!(next.recv().unwrap(), Either::Right('q'));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `a` or `b` is not associated with the given `Junction`.
+pub fn merge<T, U>(
+    junction: &Junction,
+    a: &SendChannel<T>,
+    b: &SendChannel<U>,
+) -> SendChannel<Either<T, U>>
+where
+    T: Any + Send,
+    U: Any + Send,
+{
+    let merged = junction.send_channel::<Either<T, U>>();
+
+    let left = merged.clone();
+    junction.when(a).then_do_spawned(move |t| {
+        let _ = left.send(Either::Left(t));
+    });
+
+    let right = merged.clone();
+    junction.when(b).then_do_spawned(move |u| {
+        let _ = right.send(Either::Right(u));
+    });
+
+    merged
+}
+
+/// Create a channel on the given `Junction` receiving a pair for every
+/// message sent on `a` along with a message sent on `b`.
+///
+/// Messages sent on `a` and `b` are consumed by the Join Pattern added to
+/// pair them up, so they should not be part of any other Join Pattern.
+///
+/// ```
+/// use rusty_junctions::{channels, Junction};
+///
+/// let j = Junction::new();
+/// let name = j.send_channel::<&'static str>();
+/// let age = j.send_channel::<u32>();
+/// let next = j.recv_channel::<(&'static str, u32)>();
+///
+/// let people = channels::zip(&j, &name, &age);
+/// j.when(&people).and_recv(&next).then_do(|p| p);
+///
+/// name.send("Ada").unwrap();
+/// age.send(36).unwrap();
+/// assert_eq!(next.recv().unwrap(), ("Ada", 36));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `a` or `b` is not associated with the given `Junction`.
+pub fn zip<T, U>(junction: &Junction, a: &SendChannel<T>, b: &SendChannel<U>) -> SendChannel<(T, U)>
+where
+    T: Any + Send,
+    U: Any + Send,
+{
+    let zipped = junction.send_channel::<(T, U)>();
+
+    let pairs = zipped.clone();
+    junction.when(a).and(b).then_do_spawned(move |t, u| {
+        let _ = pairs.send((t, u));
+    });
+
+    zipped
+}
@@ -0,0 +1,158 @@
+//! Sending on channels that no Join Pattern has been registered for yet.
+//!
+//! Messages sent on a channel before any of its Join Patterns has been
+//! registered are stored until one is, which hides startup races between
+//! producers and the code declaring the Join Patterns. Such messages may
+//! instead be rejected or hold up the producer, see `NoPatternPolicy`.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, RecvTimeoutError, SendError, Sender},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    channels::SendChannel,
+    join_pattern,
+    queue::PacketSender,
+    types::{ids::ChannelId, Packet},
+};
+
+/// Interval at which the control thread is woken up while a sender is
+/// blocked on a closed gate.
+const WAKE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// What happens to messages sent on a channel before any Join Pattern of
+/// the channel has been registered, see `Junction::send_channel_with_policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoPatternPolicy {
+    /// Store the messages until a Join Pattern consumes them, like on any
+    /// other channel.
+    #[default]
+    Queue,
+    /// Fail to send the messages, handing them back in the `SendError`.
+    Reject,
+    /// Block the sending thread until a Join Pattern of the channel has been
+    /// registered, failing if the `Junction` shuts down before.
+    Block,
+}
+
+/// State shared by all handles to a channel whose `NoPatternPolicy` is not
+/// `Queue`, opened once a Join Pattern of the channel has been registered.
+pub(crate) struct PatternGate {
+    policy: NoPatternPolicy,
+    open: AtomicBool,
+    /// Disconnected once the `GateOpener` has been dropped, i.e. once the
+    /// gate is open or the `Controller` has shut down.
+    opened: Mutex<Receiver<()>>,
+}
+
+/// Opener of a `PatternGate`, held by the `Controller` until a Join Pattern
+/// of the channel has been registered.
+pub struct GateOpener {
+    gate: Arc<PatternGate>,
+    _opened: Sender<()>,
+}
+
+impl GateOpener {
+    /// Open the gate, waking the threads blocked on it.
+    pub(crate) fn open(self) {
+        self.gate.open.store(true, Ordering::Release);
+    }
+}
+
+/// Create a closed `PatternGate` with the given policy, along with its
+/// opener.
+pub(crate) fn pattern_gate(policy: NoPatternPolicy) -> (Arc<PatternGate>, GateOpener) {
+    let (sender, receiver) = channel();
+    let gate = Arc::new(PatternGate {
+        policy,
+        open: AtomicBool::new(false),
+        opened: Mutex::new(receiver),
+    });
+
+    (
+        Arc::clone(&gate),
+        GateOpener {
+            gate,
+            _opened: sender,
+        },
+    )
+}
+
+impl PatternGate {
+    /// Return the given `Packet` for the channel of the given ID if it may
+    /// be sent according to the policy, waiting for the gate to open first
+    /// if the policy is `NoPatternPolicy::Block`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `Packet` if it is rejected, or if the `Junction` has shut
+    /// down while waiting.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy is `NoPatternPolicy::Block` and the gate is
+    /// closed while called from a function body run inline.
+    pub(in crate::channels) fn pass(
+        &self,
+        channel_id: ChannelId,
+        sender: &PacketSender,
+        packet: Packet,
+    ) -> Result<Packet, SendError<Packet>> {
+        if self.open.load(Ordering::Acquire) {
+            return Ok(packet);
+        }
+
+        match self.policy {
+            NoPatternPolicy::Queue => return Ok(packet),
+            // Join Patterns registered by the calling thread may not have
+            // been handled by the `Controller` yet, so it is asked to open
+            // the gate after handling them.
+            NoPatternPolicy::Reject if !join_pattern::is_inline() => {
+                let (return_sender, return_receiver) = channel();
+                if sender
+                    .send(Packet::GateRequest {
+                        channel_id,
+                        return_sender,
+                    })
+                    .is_ok()
+                {
+                    let _ = return_receiver.recv();
+                }
+            }
+            NoPatternPolicy::Reject => {}
+            NoPatternPolicy::Block => {
+                join_pattern::assert_not_inline("send");
+
+                // Only stops timing out once the `GateOpener` has been
+                // dropped, as nothing is ever sent through it. Join Patterns
+                // are registered through the control queue, which does not
+                // wake up the control thread, so it is woken up meanwhile.
+                let opened = self.opened.lock().unwrap();
+                while let Err(RecvTimeoutError::Timeout) = opened.recv_timeout(WAKE_INTERVAL) {
+                    if sender.send(Packet::Wake).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        match self.open.load(Ordering::Acquire) {
+            true => Ok(packet),
+            false => Err(SendError(packet)),
+        }
+    }
+}
+
+impl<T> SendChannel<T> {
+    /// Apply the policy of the given `PatternGate` to all messages sent on
+    /// this channel.
+    pub(crate) fn with_gate(mut self, gate: Arc<PatternGate>) -> SendChannel<T> {
+        self.raw.gate = Some(gate);
+        self
+    }
+}
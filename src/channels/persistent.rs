@@ -0,0 +1,93 @@
+//! Persistent messages, satisfying Join Patterns without being consumed.
+//!
+//! In the join calculus, a replicated message stands for a fact that holds
+//! until further notice, such as a permission or a piece of configuration.
+//! A persistent message is stored like any other, but every Join Pattern
+//! firing on it receives a copy, leaving the message in place until it is
+//! revoked through its `PersistentToken`.
+
+use std::{any::Any, fmt, sync::mpsc::SendError};
+
+use crate::{
+    channels::{MessageReceipt, SendChannel},
+    queue::PacketSender,
+    types::{Message, Packet},
+};
+
+impl<T: Any + Send + Clone> SendChannel<T> {
+    /// Send a persistent value on this channel, which satisfies Join
+    /// Patterns without being consumed, until revoked through the returned
+    /// `PersistentToken`.
+    ///
+    /// Each Join Pattern firing on the message receives a copy of its
+    /// value. Dropping the `PersistentToken` leaves the message in place
+    /// for as long as the `Junction` runs.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let admin = j.send_channel::<&'static str>();
+    /// let delete = j.bidir_channel::<u32, String>();
+    /// j.when(&admin)
+    ///     .and_bidir(&delete)
+    ///     .then_do(|user, id| format!("{user} deleted {id}"));
+    ///
+    /// let grant = admin.send_persistent("ada").unwrap();
+    /// assert_eq!(delete.send_recv(1).unwrap(), "ada deleted 1");
+    /// assert_eq!(delete.send_recv(2).unwrap(), "ada deleted 2");
+    ///
+    /// grant.revoke();
+    /// admin.send("grace").unwrap();
+    /// assert_eq!(delete.send_recv(3).unwrap(), "grace deleted 3");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value could not be sent, e.g. because the
+    /// channel has been closed or the `Junction` has shut down.
+    pub fn send_persistent(&self, value: T) -> Result<PersistentToken, SendError<Packet>> {
+        let receipt =
+            self.raw
+                .send_persistent(&self.sequence, Message::new(value), Message::copy::<T>)?;
+
+        Ok(PersistentToken {
+            receipt,
+            sender: self.raw.sender.clone(),
+        })
+    }
+}
+
+/// Token revoking a persistent message, see `SendChannel::send_persistent`.
+pub struct PersistentToken {
+    receipt: MessageReceipt,
+    sender: PacketSender,
+}
+
+impl PersistentToken {
+    /// Return the `MessageReceipt` of the persistent message.
+    pub fn receipt(&self) -> MessageReceipt {
+        self.receipt
+    }
+
+    /// Remove the persistent message from its channel, once all messages
+    /// sent on the `Junction` before have been handled.
+    ///
+    /// Join Patterns already fired on the message keep their copies.
+    /// Revoking a message of a `Junction` that has shut down does nothing.
+    pub fn revoke(self) {
+        // The `Junction` may have shut down already, which is fine.
+        let _ = self.sender.send(Packet::Revoke {
+            channel_id: self.receipt.channel_id,
+            sequence: self.receipt.sequence,
+        });
+    }
+}
+
+impl fmt::Debug for PersistentToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersistentToken")
+            .field("receipt", &self.receipt)
+            .finish()
+    }
+}
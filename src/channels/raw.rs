@@ -0,0 +1,267 @@
+//! Type-erased core shared by all channel handles.
+//!
+//! `SendChannel`, `RecvChannel` and `BidirChannel` are thin generic fronts
+//! turning values into `Message`s, whose type is erased, and replies back
+//! into values. Everything else, i.e. numbering and sending the `Message`s
+//! along with the logging and tracing around them, lives in `RawChannel`,
+//! which is compiled once rather than for every message type a channel is
+//! created for.
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    mpsc::{channel, RecvError, SendError},
+    Arc, Mutex,
+};
+
+use crate::{
+    channels::{gate::PatternGate, MessageReceipt, SendChannel},
+    queue::PacketSender,
+    types::{ids, CopyMessage, Message, Packet},
+};
+
+/// Handle to a channel of a `Junction`, regardless of the types of the
+/// messages sent on it.
+#[derive(Clone)]
+pub(crate) struct RawChannel {
+    pub(in crate::channels) id: ids::ChannelId,
+    pub(in crate::channels) junction_id: ids::JunctionId,
+    pub(in crate::channels) sender: PacketSender,
+    pub(in crate::channels) name: Option<Arc<str>>,
+    /// Gate holding back messages until a Join Pattern of the channel has
+    /// been registered, if any, see `NoPatternPolicy`.
+    pub(in crate::channels) gate: Option<Arc<PatternGate>>,
+    shared: Arc<Shared>,
+}
+
+/// State shared by all handles to a channel, reporting to the `Controller`
+/// once the last of them has been dropped, see `DroppedChannelPolicy`.
+struct Shared {
+    id: ids::ChannelId,
+    sender: PacketSender,
+    /// Set once the channel has been closed, see `SendChannel::close`.
+    closed: AtomicBool,
+    /// Channels to signal once the channel is closed, `None` once it has
+    /// been closed.
+    close_signals: Mutex<Option<Vec<SendChannel<()>>>>,
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        // Sent through the main queue, so that the `DroppedChannelPolicy` is
+        // only applied once the `Message`s sent before have been handled.
+        // This blocks while a bounded queue is full, except on the control
+        // thread, see `queue::handling`. The `Junction` may have shut down
+        // already, which is fine.
+        let _ = self.sender.send(Packet::ChannelDropped {
+            channel_id: self.id,
+        });
+    }
+}
+
+impl RawChannel {
+    pub(in crate::channels) fn new(
+        id: ids::ChannelId,
+        junction_id: ids::JunctionId,
+        sender: PacketSender,
+    ) -> RawChannel {
+        RawChannel {
+            id,
+            junction_id,
+            shared: Arc::new(Shared {
+                id,
+                sender: sender.clone(),
+                closed: AtomicBool::new(false),
+                close_signals: Mutex::new(Some(Vec::new())),
+            }),
+            sender,
+            name: None,
+            gate: None,
+        }
+    }
+
+    pub(in crate::channels) fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Send the given `Message` on this channel, failing if it has been
+    /// closed.
+    pub(in crate::channels) fn send(&self, msg: Message) -> Result<(), SendError<Packet>> {
+        #[cfg(feature = "otel")]
+        let msg = crate::otel::inject(msg);
+
+        self.send_packet(Packet::Message {
+            channel_id: self.id,
+            msg,
+        })
+    }
+
+    /// Send the given `Message`s on this channel at once, in order, failing
+    /// if it has been closed.
+    pub(in crate::channels) fn send_all(
+        &self,
+        msgs: Vec<Message>,
+    ) -> Result<(), SendError<Packet>> {
+        #[cfg(feature = "otel")]
+        let msgs = msgs.into_iter().map(crate::otel::inject).collect();
+
+        self.send_packet(Packet::Messages {
+            channel_id: self.id,
+            msgs,
+        })
+    }
+
+    fn send_packet(&self, packet: Packet) -> Result<(), SendError<Packet>> {
+        if self.is_closed() {
+            return Err(SendError(packet));
+        }
+        let packet = match &self.gate {
+            Some(gate) => gate.pass(self.id, &self.sender, packet)?,
+            None => packet,
+        };
+
+        self.sender.send(packet)
+    }
+
+    /// Close this channel, telling the `Controller` and signalling the
+    /// channels waiting for it to be closed.
+    ///
+    /// Return `false` if the channel had already been closed.
+    pub(in crate::channels) fn close(&self) -> bool {
+        let Some(close_signals) = self.shared.close_signals.lock().unwrap().take() else {
+            return false;
+        };
+        self.shared.closed.store(true, Ordering::Release);
+
+        self.sender
+            .send(Packet::CloseChannel {
+                channel_id: self.id,
+            })
+            .unwrap_or_else(|e| log::error!("Failed to send CloseChannel: {e:?}"));
+        for close_signal in close_signals {
+            // Nobody may be listening anymore, which is fine.
+            let _ = close_signal.send(());
+        }
+
+        true
+    }
+
+    /// Return `true` if this channel has been closed.
+    pub(in crate::channels) fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire)
+    }
+
+    /// Send on the given channel once this channel is closed, right away if
+    /// it has been closed already.
+    pub(in crate::channels) fn signal_on_close(&self, close_signal: SendChannel<()>) {
+        let mut close_signals = self.shared.close_signals.lock().unwrap();
+        match close_signals.as_mut() {
+            Some(close_signals) => close_signals.push(close_signal),
+            None => {
+                drop(close_signals);
+                let _ = close_signal.send(());
+            }
+        }
+    }
+
+    /// Number the given `Message` through the given sequence and send it on
+    /// this channel.
+    pub(in crate::channels) fn send_numbered(
+        &self,
+        sequence: &AtomicU64,
+        msg: Message,
+    ) -> Result<MessageReceipt, SendError<Packet>> {
+        let receipt = self.next_receipt(sequence);
+
+        self.send_with_receipt(receipt, msg)
+    }
+
+    /// Number the given `Message` through the given sequence and send it on
+    /// this channel as a persistent `Message`, copied by `copy` whenever a
+    /// Join Pattern reads it.
+    pub(in crate::channels) fn send_persistent(
+        &self,
+        sequence: &AtomicU64,
+        msg: Message,
+        copy: CopyMessage,
+    ) -> Result<MessageReceipt, SendError<Packet>> {
+        let receipt = self.next_receipt(sequence);
+
+        // Sent through the control queue, so that the `Controller` knows the
+        // `Message` to be persistent by the time it arrives.
+        self.send_packet(Packet::Persist {
+            channel_id: self.id,
+            sequence: receipt.sequence,
+            copy,
+        })?;
+
+        self.send_with_receipt(receipt, msg)
+    }
+
+    fn next_receipt(&self, sequence: &AtomicU64) -> MessageReceipt {
+        MessageReceipt {
+            channel_id: self.id,
+            sequence: sequence.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    fn send_with_receipt(
+        &self,
+        receipt: MessageReceipt,
+        msg: Message,
+    ) -> Result<MessageReceipt, SendError<Packet>> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            channel_id = ?self.id,
+            channel_name = self.name(),
+            sequence = receipt.sequence,
+            expires_at = ?msg.expires_at(),
+            "message sent"
+        );
+
+        self.send(msg.with_sequence(receipt.sequence))?;
+
+        Ok(receipt)
+    }
+
+    /// Send the `Message` of a caller about to wait for the reply.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RecvError` if the `Message` could not be sent, in which case
+    /// no reply will ever arrive.
+    pub(in crate::channels) fn send_call(&self, msg: Message) -> Result<(), RecvError> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!("message sent");
+
+        self.send(msg.with_caller()).map_err(|e| {
+            log::error!("Failed to send call Message: {e:?}");
+            RecvError
+        })
+    }
+
+    /// Migrate this channel to the `Junction` behind `to`, see
+    /// `SendChannel::migrate`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request could not be sent to the `Junction`.
+    pub(in crate::channels) fn migrate(
+        &self,
+        to: PacketSender,
+        to_channel_id: ids::ChannelId,
+    ) -> Result<(), RecvError> {
+        let (ack, ack_receiver) = channel();
+
+        self.sender
+            .send(Packet::MigrateRequest {
+                channel_id: self.id,
+                to: Box::new(to),
+                to_channel_id,
+                ack,
+            })
+            .map_err(|e| log::error!("Failed to send MigrateRequest: {e:?}"))
+            .unwrap();
+
+        ack_receiver.recv()
+    }
+}
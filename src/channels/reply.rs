@@ -0,0 +1,143 @@
+//! Bidirectional channels whose Join Patterns reply more than once.
+//!
+//! A `BidirChannel` receives exactly one reply per message. Requests such
+//! as paginated queries or long-running jobs reporting their progress
+//! instead send a `ReplySink` along with their message, through which the
+//! function body of the fired Join Pattern sends as many replies as it
+//! likes. The caller receives them through a `ReplyStream`, which ends once
+//! all handles to the `ReplySink` have been dropped.
+
+use std::{
+    any::Any,
+    fmt,
+    sync::mpsc::{channel, Receiver, RecvError, SendError, Sender},
+};
+
+use crate::{
+    channels::{BidirStreamChannel, SendChannel},
+    join_pattern, Junction,
+};
+
+/// Handle through which the function body of a Join Pattern sends replies
+/// to a message sent through `BidirStreamChannel::send_recv_stream`.
+pub struct ReplySink<R> {
+    sender: Sender<R>,
+}
+
+impl<R> ReplySink<R> {
+    /// Send a reply to the caller.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reply if the caller has dropped its `ReplyStream`.
+    pub fn send(&self, reply: R) -> Result<(), SendError<R>> {
+        self.sender.send(reply)
+    }
+}
+
+// Implemented manually since deriving would require `R: Clone`.
+impl<R> Clone for ReplySink<R> {
+    fn clone(&self) -> ReplySink<R> {
+        ReplySink {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<R> fmt::Debug for ReplySink<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplySink").finish_non_exhaustive()
+    }
+}
+
+/// Replies to a message sent through `BidirStreamChannel::send_recv_stream`,
+/// in the order they have been sent.
+///
+/// Iterating blocks until the next reply arrives, and ends once all handles
+/// to the `ReplySink` of the message have been dropped, including when the
+/// message itself is dropped without a Join Pattern having fired.
+pub struct ReplyStream<R> {
+    receiver: Receiver<R>,
+}
+
+impl<R> ReplyStream<R> {
+    /// Block until the next reply arrives.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error once no further replies will arrive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a function body run inline, see `then_do_inline`.
+    pub fn recv(&self) -> Result<R, RecvError> {
+        join_pattern::assert_not_inline("ReplyStream::recv");
+
+        self.receiver.recv()
+    }
+}
+
+impl<R> Iterator for ReplyStream<R> {
+    type Item = R;
+
+    fn next(&mut self) -> Option<R> {
+        self.recv().ok()
+    }
+}
+
+impl<T: Any + Send, R: Any + Send> SendChannel<(T, ReplySink<R>)> {
+    /// Send a message and return the stream of replies sent by the function
+    /// body of the Join Pattern consuming it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message could not be sent to the `Junction`.
+    pub fn send_recv_stream(&self, msg: T) -> ReplyStream<R> {
+        let (sender, receiver) = channel::<R>();
+
+        self.send((msg, ReplySink { sender }))
+            .map_err(|e| log::error!("Failed to send BidirStream Message: {e:?}"))
+            .unwrap();
+
+        ReplyStream { receiver }
+    }
+}
+
+impl Junction {
+    /// Create and return a new `BidirStreamChannel` on this `Junction`.
+    ///
+    /// Join Patterns over the channel receive each message along with the
+    /// `ReplySink` to send its replies to.
+    ///
+    /// ```
+    /// use rusty_junctions::{channels::ReplySink, Junction};
+    ///
+    /// let j = Junction::new();
+    /// let pages = j.bidir_stream_channel::<usize, Vec<u32>>();
+    /// let rows = j.send_channel::<Vec<u32>>();
+    ///
+    /// j.when(&pages)
+    ///     .and(&rows)
+    ///     .then_do(|(size, sink): (usize, ReplySink<Vec<u32>>), rows| {
+    ///         for page in rows.chunks(size) {
+    ///             sink.send(page.to_vec()).unwrap();
+    ///         }
+    ///     });
+    ///
+    /// rows.send((1..=5).collect()).unwrap();
+    /// let received: Vec<Vec<u32>> = pages.send_recv_stream(2).collect();
+    /// assert_eq!(received, vec![vec![1, 2], vec![3, 4], vec![5]]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new
+    /// channel ID from the control thread.
+    pub fn bidir_stream_channel<T, R>(&self) -> BidirStreamChannel<T, R>
+    where
+        T: Any + Send,
+        R: Any + Send,
+    {
+        self.send_channel::<(T, ReplySink<R>)>()
+    }
+}
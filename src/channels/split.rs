@@ -0,0 +1,151 @@
+//! Separate views of a `BidirChannel` for sending requests and for calling.
+//!
+//! A `BidirChannel` lets whoever holds it both inject requests and wait for
+//! their replies. Splitting it hands these capabilities to different parts
+//! of an application, e.g. a producer that may only enqueue work, while the
+//! module owning the channel keeps calling it.
+
+use std::{
+    any::Any,
+    fmt,
+    sync::{
+        mpsc::{channel, RecvError, SendError},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    channels::{BidirChannel, ReplyTicket},
+    types::{Message, Packet},
+};
+
+/// Function sending a request without waiting for its reply.
+type SendRequest<T> = dyn Fn(T) -> Result<(), SendError<Packet>> + Send + Sync;
+
+impl<T: Any + Send, R: Any + Send> BidirChannel<T, R> {
+    /// Split this channel into a view that only sends requests, discarding
+    /// their replies, and a view that calls the channel as before.
+    ///
+    /// Replies to requests sent through the `SendOnlyView` are received on
+    /// its behalf and dropped, so that function bodies can deliver them for
+    /// as long as any handle to the view is alive.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let enqueue = j.bidir_channel::<u32, usize>();
+    /// let queued = j.send_channel::<Vec<u32>>();
+    /// let queued_inner = queued.clone();
+    /// j.when(&queued).and_bidir(&enqueue).then_do(move |mut jobs, job| {
+    ///     jobs.push(job);
+    ///     let len = jobs.len();
+    ///     queued_inner.send(jobs).unwrap();
+    ///     len
+    /// });
+    /// queued.send(Vec::new()).unwrap();
+    ///
+    /// let (producer, caller) = enqueue.split();
+    /// producer.send(1).unwrap();
+    /// producer.send(2).unwrap();
+    /// assert_eq!(caller.send_recv(3).unwrap(), 3);
+    /// ```
+    pub fn split(self) -> (SendOnlyView<T>, CallView<T, R>) {
+        let bidir = self.clone();
+        let replies = Mutex::new(channel::<R>());
+        let send = move |msg: T| {
+            let replies = replies.lock().unwrap();
+            // Replies are only kept around for function bodies to send them
+            // successfully, so those that have arrived are dropped.
+            while replies.1.try_recv().is_ok() {}
+
+            bidir.raw.send(Message::new((msg, replies.0.clone())))
+        };
+
+        (
+            SendOnlyView {
+                send: Arc::new(send),
+            },
+            CallView { channel: self },
+        )
+    }
+}
+
+/// View of a `BidirChannel` that only sends requests, see
+/// `BidirChannel::split`.
+pub struct SendOnlyView<T> {
+    send: Arc<SendRequest<T>>,
+}
+
+impl<T> SendOnlyView<T> {
+    /// Send a request without waiting for its reply, which is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request could not be sent, e.g. because the
+    /// `Junction` has shut down.
+    pub fn send(&self, msg: T) -> Result<(), SendError<Packet>> {
+        (self.send)(msg)
+    }
+}
+
+// Implemented manually since deriving would require `T: Clone`.
+impl<T> Clone for SendOnlyView<T> {
+    fn clone(&self) -> SendOnlyView<T> {
+        SendOnlyView {
+            send: Arc::clone(&self.send),
+        }
+    }
+}
+
+impl<T> fmt::Debug for SendOnlyView<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendOnlyView").finish_non_exhaustive()
+    }
+}
+
+/// View of a `BidirChannel` that sends requests and waits for their
+/// replies, see `BidirChannel::split`.
+pub struct CallView<T, R> {
+    channel: BidirChannel<T, R>,
+}
+
+impl<T: Any + Send, R: Any + Send> CallView<T, R> {
+    /// Return the name given to the channel on creation, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.channel.name()
+    }
+
+    /// Send a request and wait for its reply, see
+    /// `BidirChannel::send_recv`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no reply will ever be received.
+    pub fn send_recv(&self, msg: T) -> Result<R, RecvError> {
+        self.channel.send_recv(msg)
+    }
+
+    /// Send a request without waiting for its reply, which is collected
+    /// later through the returned `ReplyTicket`, see
+    /// `BidirChannel::call_detached`.
+    pub fn call_detached(&self, msg: T) -> ReplyTicket<R> {
+        self.channel.call_detached(msg)
+    }
+}
+
+// Implemented manually since deriving would require `T: Clone` and
+// `R: Clone`.
+impl<T, R> Clone for CallView<T, R> {
+    fn clone(&self) -> CallView<T, R> {
+        CallView {
+            channel: self.channel.clone(),
+        }
+    }
+}
+
+impl<T, R> fmt::Debug for CallView<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallView").finish_non_exhaustive()
+    }
+}
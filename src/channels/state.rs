@@ -0,0 +1,50 @@
+//! Channels retaining their latest value.
+//!
+//! A message sent on a `SendChannel` is consumed by the Join Pattern it
+//! fires. Configuration or other shared state would therefore have to be
+//! sent back by every function body reading it. A `StateChannel` instead
+//! always holds its latest value, which Join Patterns read without
+//! consuming it, so that they fire whenever their other channels are ready.
+
+use std::{any::Any, fmt, ops::Deref};
+
+use crate::channels::SendChannel;
+
+/// Channel retaining the latest value sent on it, see
+/// `Junction::state_channel`.
+///
+/// A `StateChannel` dereferences to the `SendChannel` it is built upon, so
+/// that new values are sent and Join Patterns are declared on it as on any
+/// other `SendChannel`.
+pub struct StateChannel<T> {
+    channel: SendChannel<T>,
+}
+
+impl<T: Any + Send + Clone> StateChannel<T> {
+    pub(crate) fn new(channel: SendChannel<T>) -> StateChannel<T> {
+        StateChannel { channel }
+    }
+}
+
+impl<T> Deref for StateChannel<T> {
+    type Target = SendChannel<T>;
+
+    fn deref(&self) -> &SendChannel<T> {
+        &self.channel
+    }
+}
+
+// Implemented manually since deriving would require `T: Clone`.
+impl<T> Clone for StateChannel<T> {
+    fn clone(&self) -> StateChannel<T> {
+        StateChannel {
+            channel: self.channel.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for StateChannel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateChannel").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,74 @@
+//! Observing the messages sent on a channel.
+
+use std::{
+    any::Any,
+    sync::mpsc::{channel, Receiver},
+};
+
+use crate::{
+    channels::SendChannel,
+    types::{Message, Packet, Tap},
+};
+
+impl<T: Any + Send> SendChannel<T> {
+    /// Pass every message arriving on this channel to the given `Tap` from
+    /// now on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request to observe the channel could not be sent to the
+    /// `Junction`.
+    pub(crate) fn add_tap(&self, tap: Tap) {
+        self.raw
+            .sender
+            .send(Packet::TapRequest {
+                channel_id: self.raw.id,
+                tap,
+            })
+            .map_err(|e| log::error!("Failed to send TapRequest: {e:?}"))
+            .unwrap();
+    }
+}
+
+impl<T: Any + Send + Clone> SendChannel<T> {
+    /// Return a `Receiver` of a copy of every message sent on this channel
+    /// from now on, through any of its handles, as well as of those still
+    /// waiting to be handled by the `Junction`.
+    ///
+    /// Observing messages does not consume them, so they remain available to
+    /// the Join Patterns of the channel. This suits logging, auditing and
+    /// assertions in tests. Once the `Receiver` is dropped, the channel is no
+    /// longer observed after the next message.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let deposit = j.send_channel::<u32>();
+    /// let balance = j.recv_channel::<u32>();
+    /// j.when(&deposit).and_recv(&balance).then_do(|d| d);
+    ///
+    /// let audit = deposit.tap();
+    /// deposit.send(100).unwrap();
+    ///
+    /// assert_eq!(audit.recv().unwrap(), 100);
+    /// assert_eq!(balance.recv().unwrap(), 100);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request to observe the channel could not be sent to the
+    /// `Junction`.
+    pub fn tap(&self) -> Receiver<T> {
+        let (sender, receiver) = channel();
+
+        self.add_tap(Box::new(move |msg: &Message| {
+            match msg.downcast_ref::<T>() {
+                Some(value) => sender.send(value.clone()).is_ok(),
+                None => true,
+            }
+        }));
+
+        receiver
+    }
+}
@@ -0,0 +1,91 @@
+//! Source of the current time for the timers of a `Controller`, such as the
+//! expiry of `Message`s sent with a time to live.
+//!
+//! A `Junction` built with `ClockSource::Virtual` runs its timers on a
+//! virtual clock, which stands still until it is advanced through
+//! `Junction::advance`, so that code relying on timeouts can be tested
+//! instantly and deterministically.
+
+use std::time::{Duration, Instant};
+
+use crate::primitives::{Arc, Mutex};
+
+/// Clock shared by a `Junction` and its `Controller`.
+#[derive(Debug, Clone, Default)]
+pub(crate) enum Clock {
+    /// Wall-clock time.
+    #[default]
+    System,
+    /// Virtual time, counted from the instant the clock has been created.
+    Virtual(Arc<VirtualClock>),
+}
+
+#[derive(Debug)]
+pub(crate) struct VirtualClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+}
+
+impl Clock {
+    /// Create a new virtual clock standing at the current instant.
+    pub(crate) fn new_virtual() -> Clock {
+        Clock::Virtual(Arc::new(VirtualClock {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }))
+    }
+
+    /// Return `true` if the clock is virtual.
+    pub(crate) fn is_virtual(&self) -> bool {
+        matches!(self, Clock::Virtual(_))
+    }
+
+    /// Return the current instant according to the clock.
+    pub(crate) fn now(&self) -> Instant {
+        match self {
+            Clock::System => Instant::now(),
+            Clock::Virtual(clock) => clock.start + *clock.elapsed.lock().unwrap(),
+        }
+    }
+
+    /// Return the time that has passed on a virtual clock, `None` if the
+    /// clock is not virtual.
+    pub(crate) fn elapsed(&self) -> Option<Duration> {
+        match self {
+            Clock::System => None,
+            Clock::Virtual(clock) => Some(*clock.elapsed.lock().unwrap()),
+        }
+    }
+
+    /// Move a virtual clock forward until the given time has passed on it,
+    /// unless it already has.
+    pub(crate) fn advance_to(&self, elapsed: Duration) {
+        if let Clock::Virtual(clock) = self {
+            let mut current = clock.elapsed.lock().unwrap();
+            *current = elapsed.max(*current);
+        }
+    }
+}
+
+#[cfg(all(test, loom))]
+mod tests {
+    use std::time::Duration;
+
+    use loom::thread;
+
+    use super::Clock;
+
+    #[test]
+    fn advance_to_never_moves_backwards() {
+        loom::model(|| {
+            let clock = Clock::new_virtual();
+            let clock_clone = clock.clone();
+
+            let advancing = thread::spawn(move || clock_clone.advance_to(Duration::from_secs(2)));
+            clock.advance_to(Duration::from_secs(1));
+            advancing.join().unwrap();
+
+            assert_eq!(clock.elapsed(), Some(Duration::from_secs(2)));
+        });
+    }
+}
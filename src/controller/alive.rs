@@ -7,41 +7,46 @@ use crate::{
 };
 
 impl Controller {
-    /// Return the `JoinPatternId`s of all alive `JoinPattern`s.
+    /// Add the `JoinPatternId`s of all alive `JoinPattern`s among the given
+    /// ones to `alive`.
     ///
     /// A `JoinPattern` is considered alive if for each of the channels
     /// involved in it, there is at least one `Message` available.
     pub(in crate::controller) fn alive_join_patterns(
         &self,
         join_pattern_ids: &LinkedList<JoinPatternId>,
-    ) -> Vec<JoinPatternId> {
+        alive: &mut Vec<JoinPatternId>,
+    ) {
         log::debug!("Checking for alive JoinPatterns");
-        // We need clone the `JoinPatternId`s at this point to avoid
-        // because need to avoid the issue of `peek_all` borrowing mutably,
-        // but then needing to mutably borrow again later to update the
-        // latest fired `JoinPatternId`.
-        let alive_join_patterns = join_pattern_ids
-            .iter()
-            .filter(|&jp_id| self.is_alive(*jp_id))
-            .cloned()
-            .collect();
+        alive.extend(
+            join_pattern_ids
+                .iter()
+                .filter(|&jp_id| self.is_alive(*jp_id)),
+        );
 
-        log::debug!("Retriving all of the alive JoinPatterns: {alive_join_patterns:?}");
-
-        alive_join_patterns
+        log::debug!("Retriving all of the alive JoinPatterns: {alive:?}");
     }
 
     /// Return `true` if Join Pattern with given `JoinPatternId` is alive.
     ///
     /// A Join Pattern is considered alive if there is at least one `Message` for
-    /// each of the channels involved in it.
-    fn is_alive(&self, join_pattern_id: JoinPatternId) -> bool {
+    /// each of the channels involved in it, and it is not at its limit of
+    /// function bodies running at once.
+    pub(in crate::controller) fn is_alive(&self, join_pattern_id: JoinPatternId) -> bool {
         let is_alive = self
             .join_patterns
             .get(&join_pattern_id)
-            .map_or(false, |jp| jp.is_alive(&self.messages));
+            .is_some_and(|jp| !jp.is_cancelled())
+            && self.has_messages(join_pattern_id)
+            && !self.is_at_limit(join_pattern_id);
         log::debug!("Checking if JoinPattern: {join_pattern_id:?} is alive: {is_alive}");
 
         is_alive
     }
+
+    /// Return `true` if there are enough `Message`s pending on the channels
+    /// of the given Join Pattern for it to fire.
+    pub(in crate::controller) fn has_messages(&self, join_pattern_id: JoinPatternId) -> bool {
+        self.messages.is_ready(join_pattern_id)
+    }
 }
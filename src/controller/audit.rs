@@ -0,0 +1,43 @@
+use crate::{
+    controller::Controller,
+    junction::{AuditAction, AuditEvent, Registration},
+    types::ids::ChannelId,
+};
+
+impl Controller {
+    /// Record the given action on the Join Pattern of the given description
+    /// and channels in the audit log, dropping the oldest event if full.
+    ///
+    /// Events are not recorded at all if the capacity of the audit log is
+    /// zero.
+    pub(in crate::controller) fn record_audit(
+        &mut self,
+        action: AuditAction,
+        join_pattern: String,
+        channels: &[ChannelId],
+        registration: Option<Registration>,
+    ) {
+        if self.options.audit_capacity == 0 {
+            return;
+        }
+        if self.audit_log.len() == self.options.audit_capacity {
+            self.audit_log.pop_front();
+        }
+
+        let channels = channels
+            .iter()
+            .map(|channel_id| self.describe_channel(*channel_id))
+            .collect();
+        self.audit_log.push_back(AuditEvent::new(
+            action,
+            join_pattern,
+            channels,
+            registration,
+        ));
+    }
+
+    /// Return the events in the audit log, oldest first.
+    pub(in crate::controller) fn audit_log(&self) -> Vec<AuditEvent> {
+        self.audit_log.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+use crate::{builder::Backlog, controller::Controller, types::ids::ChannelId};
+
+/// Shortest interval at which a `Controller` checks the age of pending
+/// `Message`s.
+const MIN_AGE_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+impl Controller {
+    /// Return `true` if channels with a backlog are to be reported.
+    fn reports_backlogs(&self) -> bool {
+        self.options.high_water_mark.is_some() || self.options.max_message_age.is_some()
+    }
+
+    /// Update the high-water mark of the given channel after a `Message` has
+    /// been stored for it, reporting the channel if it exceeds the limit.
+    pub(in crate::controller) fn record_pending(&mut self, channel_id: ChannelId) {
+        if !self.reports_backlogs() {
+            return;
+        }
+
+        let pending = self.messages.count_items(&channel_id);
+        if pending == 1 {
+            self.backlogs.reported.remove(&channel_id);
+        }
+
+        let high_water_mark = self
+            .backlogs
+            .high_water_marks
+            .entry(channel_id)
+            .or_default();
+        *high_water_mark = pending.max(*high_water_mark);
+
+        if self
+            .options
+            .high_water_mark
+            .is_some_and(|limit| pending > limit)
+        {
+            self.report_backlog(channel_id);
+        }
+    }
+
+    /// Return the next instant at which to check the age of pending
+    /// `Message`s, `None` if it is not checked.
+    pub(in crate::controller) fn next_age_check(&self) -> Option<Instant> {
+        let max_age = self.options.max_message_age?;
+        if self.backlogs.high_water_marks.is_empty() {
+            return None;
+        }
+
+        Some(self.backlogs.last_check + (max_age / 2).max(MIN_AGE_CHECK_INTERVAL))
+    }
+
+    /// Report all channels whose oldest pending `Message` has exceeded the
+    /// maximum age, if due.
+    pub(in crate::controller) fn check_message_ages(&mut self) {
+        let Some(next_check) = self.next_age_check() else {
+            return;
+        };
+        let now = Instant::now();
+        if now < next_check {
+            return;
+        }
+        self.backlogs.last_check = now;
+
+        let max_age = self.options.max_message_age.unwrap_or_default();
+        let old: Vec<ChannelId> = self
+            .backlogs
+            .high_water_marks
+            .keys()
+            .filter(|channel_id| {
+                self.messages
+                    .peek(channel_id)
+                    .is_some_and(|msg| now.duration_since(msg.sent_at()) > max_age)
+            })
+            .copied()
+            .collect();
+
+        old.into_iter()
+            .for_each(|channel_id| self.report_backlog(channel_id));
+    }
+
+    /// Log the backlog of the given channel and pass it on to the backlog
+    /// `Sender`, if any, unless it has already been reported.
+    fn report_backlog(&mut self, channel_id: ChannelId) {
+        if !self.backlogs.reported.insert(channel_id) {
+            return;
+        }
+
+        let oldest_age = self
+            .messages
+            .peek(&channel_id)
+            .map(|msg| msg.sent_at().elapsed())
+            .unwrap_or_default();
+        let backlog = Backlog::new(
+            self.describe_channel(channel_id),
+            self.messages.count_items(&channel_id),
+            self.backlogs.high_water_marks[&channel_id],
+            oldest_age,
+        );
+        log::warn!("{backlog}");
+
+        if let Some(sender) = &self.options.backlog_sender {
+            if sender.send(backlog).is_err() {
+                log::warn!("Dropping backlog, as backlogs are no longer received");
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{
+    controller::Controller,
+    types::{ids::ChannelId, CopyMessage, Message},
+};
+
+/// Subscribers of a broadcast channel, see `Junction::subscribe`.
+pub(in crate::controller) struct Broadcast {
+    pub(in crate::controller) subscribers: Vec<ChannelId>,
+    pub(in crate::controller) copy: CopyMessage,
+}
+
+impl Controller {
+    /// Store a copy of every `Message` arriving on the given broadcast
+    /// channel on the given subscriber from now on.
+    pub(in crate::controller) fn subscribe(
+        &mut self,
+        channel_id: ChannelId,
+        subscriber: ChannelId,
+        copy: CopyMessage,
+    ) {
+        self.broadcasts
+            .entry(channel_id)
+            .or_insert_with(|| Broadcast {
+                subscribers: Vec::new(),
+                copy,
+            })
+            .subscribers
+            .push(subscriber);
+    }
+
+    /// Pass a copy of the given `Message` to every subscriber of the given
+    /// channel, firing their Join Patterns as usual.
+    ///
+    /// Return the `Message` if the channel is not a broadcast channel.
+    pub(in crate::controller) fn broadcast(
+        &mut self,
+        channel_id: ChannelId,
+        msg: Message,
+    ) -> Option<Message> {
+        let Some(broadcast) = self.broadcasts.get(&channel_id) else {
+            return Some(msg);
+        };
+
+        let copies: Vec<_> = broadcast
+            .subscribers
+            .iter()
+            .filter_map(|&subscriber| Some((subscriber, (broadcast.copy)(&msg)?)))
+            .collect();
+        log::debug!(
+            "Broadcasting Message on {} to {} subscribers",
+            self.describe_channel(channel_id),
+            copies.len()
+        );
+
+        copies
+            .into_iter()
+            .for_each(|(subscriber, copy)| self.handle_message(subscriber, copy));
+
+        None
+    }
+}
@@ -0,0 +1,44 @@
+//! Channels of the registered Join Patterns, kept by the `Controller` so
+//! that firing Join Patterns does not allocate.
+//!
+//! `JoinPattern::channels` returns a fresh `Vec` on every call. A
+//! `ChannelSet` is built once, when the Join Pattern is registered, and
+//! stores the channels of all but the largest Join Patterns inline.
+
+use crate::types::ids::ChannelId;
+
+/// Number of channels a `ChannelSet` stores without allocating.
+const INLINE_CHANNELS: usize = 4;
+
+/// Channels of a Join Pattern in the order its function body takes their
+/// `Message`s, a channel appearing once per `Message` it contributes.
+pub(in crate::controller) enum ChannelSet {
+    Inline {
+        len: usize,
+        channels: [ChannelId; INLINE_CHANNELS],
+    },
+    Spilled(Box<[ChannelId]>),
+}
+
+impl ChannelSet {
+    pub(in crate::controller) fn new(channels: Vec<ChannelId>) -> ChannelSet {
+        if channels.len() > INLINE_CHANNELS {
+            return ChannelSet::Spilled(channels.into_boxed_slice());
+        }
+
+        let mut inline = [ChannelId::default(); INLINE_CHANNELS];
+        inline[..channels.len()].copy_from_slice(&channels);
+
+        ChannelSet::Inline {
+            len: channels.len(),
+            channels: inline,
+        }
+    }
+
+    pub(in crate::controller) fn as_slice(&self) -> &[ChannelId] {
+        match self {
+            ChannelSet::Inline { len, channels } => &channels[..*len],
+            ChannelSet::Spilled(channels) => channels,
+        }
+    }
+}
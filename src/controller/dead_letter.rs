@@ -0,0 +1,91 @@
+use std::time::Instant;
+
+use crate::{
+    builder::{DeadLetter, DeadLetterPolicy},
+    controller::Controller,
+    types::{ids::ChannelId, Message},
+};
+
+impl Controller {
+    /// Return `true` if a `Message` arriving on the given channel becomes a
+    /// dead letter once it reaches the dead letter age, i.e. the channel is
+    /// neither part of any Join Pattern nor a state channel and dead letters
+    /// are not simply kept.
+    pub(in crate::controller) fn may_become_dead_letter(&self, channel_id: ChannelId) -> bool {
+        !matches!(self.options.dead_letter_policy, DeadLetterPolicy::Keep)
+            && !self.has_join_patterns(channel_id)
+            && !self.state_channels.contains_key(&channel_id)
+    }
+
+    /// Record when the `Message` arriving on the given channel becomes a
+    /// dead letter.
+    pub(in crate::controller) fn schedule_dead_letter(&mut self, channel_id: ChannelId) {
+        self.dead_letter_deadlines.push_back((
+            self.options.clock.now() + self.options.dead_letter_age,
+            channel_id,
+        ));
+    }
+
+    /// Apply the `DeadLetterPolicy` to all stored `Message`s that have
+    /// reached the dead letter age while no Join Pattern has been added for
+    /// their channels.
+    pub(in crate::controller) fn sweep_dead_letters(&mut self) {
+        let now = self.options.clock.now();
+
+        while let Some(&(deadline, channel_id)) = self.dead_letter_deadlines.front() {
+            if deadline > now {
+                break;
+            }
+            self.dead_letter_deadlines.pop_front();
+
+            if self.has_join_patterns(channel_id) {
+                continue;
+            }
+
+            if let Some(msg) = self.messages.retrieve(&channel_id) {
+                self.dead_letter(channel_id, msg);
+            }
+        }
+    }
+
+    /// Return the next instant at which a stored `Message` becomes a dead
+    /// letter, if any and if the clock is not virtual, in which case dead
+    /// letters are only swept once the clock has been advanced.
+    pub(in crate::controller) fn next_dead_letter_deadline(&self) -> Option<Instant> {
+        if self.options.clock.is_virtual() {
+            return None;
+        }
+
+        self.dead_letter_deadlines
+            .front()
+            .map(|(deadline, _)| *deadline)
+    }
+
+    /// Apply the `DeadLetterPolicy` to the given `Message`.
+    pub(in crate::controller) fn dead_letter(&self, channel_id: ChannelId, msg: Message) {
+        match &self.options.dead_letter_policy {
+            DeadLetterPolicy::Keep => {}
+            DeadLetterPolicy::Drop => log::warn!(
+                "Dropping Message to {}, which is not part of any Join Pattern",
+                self.describe_channel(channel_id)
+            ),
+            DeadLetterPolicy::Forward(sender) => {
+                let channel_name = self.channel_names.get(&channel_id).cloned();
+
+                if sender.send(DeadLetter::new(channel_name, msg)).is_err() {
+                    log::warn!(
+                        "Dropping Message to {}, as dead letters are no longer received",
+                        self.describe_channel(channel_id)
+                    );
+                }
+            }
+        }
+    }
+
+    /// Return `true` if the given channel is part of any Join Pattern.
+    pub(in crate::controller) fn has_join_patterns(&self, channel_id: ChannelId) -> bool {
+        self.join_pattern_index
+            .peek_all(&channel_id)
+            .is_some_and(|jp_ids| !jp_ids.is_empty())
+    }
+}
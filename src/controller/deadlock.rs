@@ -0,0 +1,173 @@
+use std::{
+    collections::{HashMap, HashSet},
+    thread::{JoinHandle, ThreadId},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    builder::Deadlock,
+    controller::Controller,
+    types::ids::{ChannelId, JoinPatternId},
+};
+
+/// Interval at which a `Controller` detecting deadlocks checks for them
+/// while function bodies of fired Join Patterns are running.
+const DEADLOCK_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Controller {
+    /// Return `true` if deadlocks are to be detected.
+    fn detects_deadlocks(&self) -> bool {
+        self.options.deadlock_sender.is_some()
+    }
+
+    /// Record that a caller waits for a reply on the given channel.
+    pub(in crate::controller) fn record_call(&mut self, channel_id: ChannelId) {
+        if self.detects_deadlocks() {
+            self.deadlocks.call_channels.insert(channel_id);
+        }
+    }
+
+    /// Record the thread running the function body of the given Join Pattern
+    /// as the last consumer of the `Message`s of its channels.
+    pub(in crate::controller) fn record_consumers(
+        &mut self,
+        join_pattern_id: JoinPatternId,
+        thread_handle: &JoinHandle<()>,
+    ) {
+        if !self.detects_deadlocks() {
+            return;
+        }
+
+        let thread_id = thread_handle.thread().id();
+        for channel_id in self.join_patterns[&join_pattern_id].channels() {
+            self.deadlocks.last_consumers.insert(channel_id, thread_id);
+        }
+    }
+
+    /// Return the next instant at which to check for deadlocks, `None` if
+    /// they are not detected or no function body is running.
+    pub(in crate::controller) fn next_deadlock_check(&self) -> Option<Instant> {
+        if !self.detects_deadlocks() || self.firing_join_patterns.is_empty() {
+            return None;
+        }
+
+        Some(self.deadlocks.last_check + DEADLOCK_CHECK_INTERVAL)
+    }
+
+    /// Report function bodies deadlocked on calls to each other, unless they
+    /// have already been reported.
+    ///
+    /// A function body waiting on a channel is deadlocked if every Join
+    /// Pattern of the channel is missing a `Message` on one of its other
+    /// channels that has last been consumed by a deadlocked function body,
+    /// which is found by starting from all waiting function bodies and
+    /// removing those that could still be answered until none are left to
+    /// remove.
+    pub(in crate::controller) fn detect_deadlocks(&mut self) {
+        match self.next_deadlock_check() {
+            Some(check) if check <= Instant::now() => {}
+            _ => return,
+        }
+        self.deadlocks.last_check = Instant::now();
+        self.prune_firing_join_patterns();
+
+        let waits = self.waiting_join_patterns();
+        let mut deadlocked: HashSet<ThreadId> = waits.keys().copied().collect();
+
+        loop {
+            let answerable: Vec<ThreadId> = deadlocked
+                .iter()
+                .filter(|thread_id| !self.is_deadlocked(waits[thread_id].1, &deadlocked))
+                .copied()
+                .collect();
+            if answerable.is_empty() {
+                break;
+            }
+            answerable.iter().for_each(|thread_id| {
+                deadlocked.remove(thread_id);
+            });
+        }
+
+        if deadlocked.is_subset(&self.deadlocks.reported) {
+            return;
+        }
+
+        let deadlock = Deadlock::new(
+            deadlocked
+                .iter()
+                .map(|thread_id| {
+                    let (join_pattern_id, channel_id) = waits[thread_id];
+                    (
+                        self.describe_join_pattern(join_pattern_id),
+                        self.describe_channel(channel_id),
+                    )
+                })
+                .collect(),
+        );
+        log::error!("{deadlock}");
+
+        if let Some(sender) = &self.options.deadlock_sender {
+            sender
+                .send(deadlock)
+                .unwrap_or_else(|_| log::warn!("Deadlocks are no longer received"));
+        }
+        self.deadlocks.reported = deadlocked;
+    }
+
+    /// Return the function bodies waiting for a reply on a channel of this
+    /// `Controller`, by their threads, along with their Join Pattern and the
+    /// channel they are waiting on.
+    ///
+    /// Stored `Message`s are put back in their original order.
+    fn waiting_join_patterns(&mut self) -> HashMap<ThreadId, (JoinPatternId, ChannelId)> {
+        let firing: HashMap<ThreadId, JoinPatternId> = self
+            .firing_join_patterns
+            .iter()
+            .map(|(jp_id, handle)| (handle.thread().id(), *jp_id))
+            .collect();
+
+        let mut waits = HashMap::new();
+        for channel_id in std::mem::take(&mut self.deadlocks.call_channels) {
+            let messages = self.messages.take_all(&channel_id);
+            if messages.is_empty() {
+                continue;
+            }
+
+            for msg in messages {
+                if let Some(&jp_id) = msg.caller().and_then(|caller| firing.get(&caller)) {
+                    waits.insert(msg.caller().unwrap(), (jp_id, channel_id));
+                }
+                self.messages.add(channel_id, msg);
+            }
+            self.deadlocks.call_channels.insert(channel_id);
+        }
+
+        waits
+    }
+
+    /// Return `true` if a caller waiting on the given channel can only be
+    /// answered once one of the given deadlocked function bodies carries on.
+    ///
+    /// This is the case if the channel is part of any Join Patterns and each
+    /// of them is missing a `Message` last consumed by one of them.
+    fn is_deadlocked(&self, channel_id: ChannelId, deadlocked: &HashSet<ThreadId>) -> bool {
+        let Some(jp_ids) = self.relevant_join_patterns(channel_id) else {
+            return false;
+        };
+
+        !jp_ids.is_empty()
+            && jp_ids.iter().all(|jp_id| {
+                self.join_patterns[jp_id]
+                    .channels()
+                    .iter()
+                    .any(|channel_id| {
+                        !self.messages.contains_items(channel_id)
+                            && self
+                                .deadlocks
+                                .last_consumers
+                                .get(channel_id)
+                                .is_some_and(|consumer| deadlocked.contains(consumer))
+                    })
+            })
+    }
+}
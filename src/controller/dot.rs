@@ -0,0 +1,77 @@
+use std::fmt::Write;
+
+use crate::{
+    controller::Controller,
+    types::ids::{ChannelId, JoinPatternId},
+};
+
+impl Controller {
+    /// Return the statements of a DOT graph with a node for each channel
+    /// that is named or part of a Join Pattern, and a node for each Join
+    /// Pattern with an edge from each of its channels.
+    ///
+    /// Join Pattern nodes are identified with the given prefix, so that the
+    /// statements of several `Controller`s can be combined into one graph.
+    pub(in crate::controller) fn export_dot(&self, prefix: &str) -> String {
+        let mut jp_ids: Vec<JoinPatternId> = self.join_patterns.keys().copied().collect();
+        jp_ids.sort_unstable();
+
+        let mut channel_ids: Vec<ChannelId> = self
+            .join_patterns
+            .values()
+            .flat_map(|join_pattern| join_pattern.channels())
+            .chain(self.channel_names.keys().copied())
+            .collect();
+        channel_ids.sort_unstable();
+        channel_ids.dedup();
+
+        let mut dot = String::new();
+
+        for channel_id in channel_ids {
+            let name = match self.channel_names.get(&channel_id) {
+                Some(name) => name.clone(),
+                None => format!("channel {}", channel_id.value()),
+            };
+            let mut label = escape(&name);
+            if let Some(message_type) = self.channel_types.get(&channel_id) {
+                label = format!("{label}\\n{}", escape(message_type.name));
+            }
+            if self.closed_channels.contains(&channel_id) {
+                label.push_str("\\nclosed");
+            }
+            let pending = self.messages.count_items(&channel_id);
+
+            let _ = writeln!(
+                dot,
+                "    c{} [shape=ellipse, label=\"{label}\\n{pending} pending\"];",
+                channel_id.value()
+            );
+        }
+
+        for jp_id in jp_ids {
+            let join_pattern = &self.join_patterns[&jp_id];
+            let name = match join_pattern.name() {
+                Some(name) => name.to_string(),
+                None => format!("Join Pattern {}", jp_id.value()),
+            };
+            let fired = self.fire_counts.get(&jp_id).copied().unwrap_or_default();
+            let node = format!("p{prefix}{}", jp_id.value());
+
+            let _ = writeln!(
+                dot,
+                "    {node} [shape=box, label=\"{}\\nfired {fired} times\"];",
+                escape(&name)
+            );
+            for channel_id in join_pattern.channels() {
+                let _ = writeln!(dot, "    c{} -> {node};", channel_id.value());
+            }
+        }
+
+        dot
+    }
+}
+
+/// Escape the given label for use in a quoted DOT string.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,47 @@
+use crate::{
+    controller::Controller,
+    types::{ids::ChannelId, MakeMessage},
+};
+
+impl Controller {
+    /// Store `Message`s created by the given function on the given channel
+    /// for as long as each of them is consumed right away by a firing Join
+    /// Pattern, see `RecvChannel::drain_available`.
+    ///
+    /// Nothing is drained while other `Message`s are pending on the channel,
+    /// as these would be consumed first, or if the channel has no Join
+    /// Patterns, is handed over elsewhere or a `Trace` is being replayed.
+    ///
+    /// Return the number of `Message`s consumed.
+    pub(in crate::controller) fn drain(
+        &mut self,
+        channel_id: ChannelId,
+        make_message: &MakeMessage,
+    ) -> usize {
+        if !self.has_join_patterns(channel_id)
+            || self.forwards.contains_key(&channel_id)
+            || self.replay.is_some()
+        {
+            return 0;
+        }
+
+        let mut drained = 0;
+
+        while !self.messages.contains_items(&channel_id) {
+            if !self.store_message(channel_id, make_message()) {
+                break;
+            }
+            self.handle_join_pattern_firing(channel_id);
+
+            // The `Message` just stored is the only one of the channel, so it
+            // is still pending if no Join Pattern could consume it.
+            if self.messages.contains_items(&channel_id) {
+                self.messages.retrieve_last(&channel_id);
+                break;
+            }
+            drained += 1;
+        }
+
+        drained
+    }
+}
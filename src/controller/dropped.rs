@@ -0,0 +1,87 @@
+use crate::{
+    builder::{DroppedChannel, DroppedChannelPolicy},
+    controller::Controller,
+    junction::AuditAction,
+    types::ids::{ChannelId, JoinPatternId},
+};
+
+impl Controller {
+    /// Apply the `DroppedChannelPolicy` to the Join Patterns of the given
+    /// channel, whose handles have all been dropped.
+    pub(in crate::controller) fn handle_channel_dropped(&mut self, channel_id: ChannelId) {
+        let jp_ids: Vec<JoinPatternId> = self
+            .relevant_join_patterns(channel_id)
+            .map(|jp_ids| jp_ids.iter().copied().collect())
+            .unwrap_or_default();
+        if jp_ids.is_empty() {
+            return;
+        }
+
+        match &self.options.dropped_channel_policy {
+            DroppedChannelPolicy::Keep => {
+                log::debug!(
+                    "All handles of {} have been dropped, leaving its Join Patterns to fire on \
+                     its pending Messages",
+                    self.describe_channel(channel_id)
+                );
+            }
+            DroppedChannelPolicy::Disable => {
+                log::info!(
+                    "Removing the Join Patterns of {}, as all of its handles have been dropped",
+                    self.describe_channel(channel_id)
+                );
+                jp_ids
+                    .into_iter()
+                    .for_each(|jp_id| self.remove_join_pattern(jp_id));
+            }
+            DroppedChannelPolicy::Notify(sender) => {
+                let dropped = DroppedChannel::new(
+                    self.describe_channel(channel_id),
+                    jp_ids
+                        .into_iter()
+                        .map(|jp_id| self.describe_join_pattern(jp_id))
+                        .collect(),
+                );
+                if sender.send(dropped).is_err() {
+                    log::warn!(
+                        "Not reporting the dropped {}, as dropped channels are no longer received",
+                        self.describe_channel(channel_id)
+                    );
+                }
+            }
+        }
+    }
+
+    /// Remove the given Join Pattern, so that it never fires again.
+    fn remove_join_pattern(&mut self, join_pattern_id: JoinPatternId) {
+        if let Some(join_pattern) = self.join_patterns.get(&join_pattern_id) {
+            let channels = join_pattern.channels();
+            self.record_audit(
+                AuditAction::Removed,
+                self.describe_join_pattern(join_pattern_id),
+                &channels,
+                None,
+            );
+        }
+
+        if let Some(channel_set) = self.channel_sets.remove(&join_pattern_id) {
+            self.messages
+                .unregister(join_pattern_id, channel_set.as_slice());
+
+            for channel_id in channel_set.as_slice() {
+                if let Some(jp_ids) = self.join_pattern_index.remove(channel_id) {
+                    self.join_pattern_index.insert_multiple(
+                        *channel_id,
+                        jp_ids.into_iter().filter(|jp_id| *jp_id != join_pattern_id),
+                    );
+                }
+            }
+        }
+
+        self.join_patterns.remove(&join_pattern_id);
+        self.join_pattern_last_fired.remove(&join_pattern_id);
+        self.limited_join_patterns.remove(&join_pattern_id);
+        self.fire_counts.remove(&join_pattern_id);
+        self.latencies.remove(&join_pattern_id);
+    }
+}
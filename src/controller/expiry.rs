@@ -0,0 +1,75 @@
+use std::{cmp::Reverse, time::Instant};
+
+use crate::{
+    builder::DeadLetter,
+    controller::Controller,
+    types::{ids::ChannelId, Message},
+};
+
+impl Controller {
+    /// Record when a `Message` stored on the given channel expires.
+    pub(in crate::controller) fn schedule_expiry(
+        &mut self,
+        channel_id: ChannelId,
+        expires_at: Instant,
+    ) {
+        self.expiry_deadlines
+            .push(Reverse((expires_at, channel_id)));
+    }
+
+    /// Return the next instant at which a stored `Message` expires, if any
+    /// and if the clock is not virtual, in which case `Message`s only expire
+    /// once the clock has been advanced.
+    pub(in crate::controller) fn next_expiry(&self) -> Option<Instant> {
+        if self.options.clock.is_virtual() {
+            return None;
+        }
+
+        self.expiry_deadlines
+            .peek()
+            .map(|Reverse((expires_at, _))| *expires_at)
+    }
+
+    /// Remove all stored `Message`s that have expired.
+    ///
+    /// Only channels with a `Message` due to expire are looked at, keeping
+    /// the remaining `Message`s of these channels in their original order.
+    pub(in crate::controller) fn evict_expired(&mut self) {
+        if self.expiry_deadlines.is_empty() {
+            return;
+        }
+
+        let now = self.options.clock.now();
+
+        while let Some(Reverse((expires_at, channel_id))) = self.expiry_deadlines.peek().copied() {
+            if expires_at > now {
+                break;
+            }
+            self.expiry_deadlines.pop();
+
+            for msg in self.messages.take_all(&channel_id) {
+                if msg.is_expired(now) {
+                    self.expire(channel_id, msg);
+                } else {
+                    self.messages.add(channel_id, msg);
+                }
+            }
+        }
+    }
+
+    /// Pass the expired `Message` on to the expiry `Sender`, if any.
+    fn expire(&self, channel_id: ChannelId, msg: Message) {
+        log::debug!("Message to {} expired", self.describe_channel(channel_id));
+
+        if let Some(sender) = &self.options.expiry_sender {
+            let channel_name = self.channel_names.get(&channel_id).cloned();
+
+            if sender.send(DeadLetter::new(channel_name, msg)).is_err() {
+                log::warn!(
+                    "Dropping expired Message to {}, as expired Messages are no longer received",
+                    self.describe_channel(channel_id)
+                );
+            }
+        }
+    }
+}
@@ -1,7 +1,14 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    time::Duration,
+};
 
 use crate::{
+    builder::{FireExecutor, MatchPolicy, MessageOrdering},
     controller::Controller,
+    junction::Histogram,
     types::{ids::JoinPatternId, Message},
 };
 
@@ -23,12 +30,18 @@ impl Controller {
     /// by which if a `JoinPattern` has been alive an infinite amount of times,
     /// it will fire at least once. In practice, this should amount to each
     /// `JoinPattern` being incapable of getting deadlocked by others.
+    ///
+    /// With `MatchPolicy::Declaration`, the `JoinPattern` declared first is
+    /// selected instead, trading this fairness for predictable priorities.
     pub(in crate::controller) fn select_to_fire<'a>(
         &self,
-        alive_jp_ids: &'a mut Vec<JoinPatternId>,
+        alive_jp_ids: &'a mut [JoinPatternId],
     ) -> Option<&'a JoinPatternId> {
-        alive_jp_ids
-            .sort_unstable_by(|&jp_id_1, &jp_id_2| self.compare_last_fired(jp_id_1, jp_id_2));
+        match self.options.match_policy {
+            MatchPolicy::Fair => alive_jp_ids
+                .sort_unstable_by(|&jp_id_1, &jp_id_2| self.compare_last_fired(jp_id_1, jp_id_2)),
+            MatchPolicy::Declaration => alive_jp_ids.sort_unstable(),
+        }
 
         alive_jp_ids.first()
     }
@@ -37,13 +50,13 @@ impl Controller {
     ///
     /// Rules for Order:
     /// 1. If neither `JoinPatternId` has a last alive `Counter`, then neither
-    /// has been fired yet, so they can be viewed as equal in this ordering.
+    ///    has been fired yet, so they can be viewed as equal in this ordering.
     /// 2. If only one `JoinPatternId` has no last alive `Counter`, then that
-    /// one has to be ordered as less than the other since having been fired
-    /// at least once will always be a later point of firing than not having
-    /// been fired yet.
+    ///    one has to be ordered as less than the other since having been fired
+    ///    at least once will always be a later point of firing than not having
+    ///    been fired yet.
     /// 3. If both `JoinPatternId`s have last alive `Counter`s, use the ordering
-    /// of these.
+    ///    of these.
     ///
     /// # Panics
     ///
@@ -76,8 +89,15 @@ impl Controller {
     ///
     /// The processs of firing a `JoinPattern` consists of first retrieving
     /// a `Message` for each of the channels involved in the `JoinPattern`,
-    /// then passing these `Messages`s to the `JoinPattern` to handle the
-    /// firing.
+    /// of the highest `Priority` pending on the channel, then passing these
+    /// `Messages`s to the `JoinPattern` to handle the firing. `Message`s sent
+    /// through `SendChannel::send_sync` are acknowledged once retrieved.
+    /// State channels and persistent `Message`s are retained and copied
+    /// instead. The time the `Message`s have been waiting to be consumed is
+    /// only recorded for those sent on their channels, not for copies of
+    /// retained ones.
+    /// Function bodies to be run inline are run right away on the calling
+    /// thread instead.
     ///
     /// # Panics
     ///
@@ -85,28 +105,107 @@ impl Controller {
     /// `JoinPatternId`.
     pub(in crate::controller) fn fire_join_pattern(&mut self, join_pattern_id: JoinPatternId) {
         let join_pattern = self.join_patterns.get(&join_pattern_id).unwrap();
+        let channels = self.channel_sets[&join_pattern_id].as_slice();
 
-        let mut messages_for_channels: Vec<Message> = Vec::new();
-        for chan in join_pattern.channels() {
-            let message = self.messages.retrieve(&chan).unwrap();
+        let mut messages_for_channels: Vec<Message> = Vec::with_capacity(channels.len());
+        let mut waited: Vec<Duration> = Vec::with_capacity(channels.len());
+        for &chan in channels {
+            if let Some(copy) = self.state_channels.get(&chan) {
+                let message = self.messages.peek(&chan).and_then(copy).unwrap();
+                messages_for_channels.push(message);
+                continue;
+            }
+
+            let prioritized = self.prioritized_channels.contains(&chan);
+            let message = match self.options.message_ordering {
+                MessageOrdering::Fifo if prioritized => {
+                    self.messages.retrieve_max_by_key(&chan, Message::priority)
+                }
+                MessageOrdering::Lifo if prioritized => self
+                    .messages
+                    .retrieve_last_max_by_key(&chan, Message::priority),
+                MessageOrdering::Fifo => self.messages.retrieve(&chan),
+                MessageOrdering::Lifo => self.messages.retrieve_last(&chan),
+            };
+            let mut message = message.unwrap();
+            if !message.was_consumed() {
+                waited.push(message.sent_at().elapsed());
+            }
+            // Persistent `Message`s are stored again, passing on a copy.
+            let replica = self
+                .persistent
+                .get(&chan)
+                .and_then(|persistent| persistent.get(&message.sequence()?))
+                .and_then(|copy| copy(&message));
+            if let Some(replica) = replica {
+                let mut stored = mem::replace(&mut message, replica);
+                stored.set_consumed();
+                self.messages.add(chan, stored);
+            }
+            message.acknowledge();
             messages_for_channels.push(message);
         }
 
+        *self.fire_counts.entry(join_pattern_id).or_default() += 1;
+        let latencies = self
+            .latencies
+            .entry(join_pattern_id)
+            .or_insert_with(Histogram::new);
+        waited.iter().for_each(|&latency| latencies.record(latency));
+        self.record_fired(join_pattern_id, channels, &messages_for_channels);
+
         // Get a handle to the firing Join Pattern
-        log::debug!("Firing JoinPattern: {join_pattern_id:?}");
-        let thread_handle = join_pattern.fire(messages_for_channels);
+        log::debug!(
+            "Firing JoinPattern: {}",
+            self.describe_join_pattern(join_pattern_id)
+        );
+        #[cfg(feature = "metrics")]
+        self.record_fire(join_pattern_id, &waited);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            join_pattern_id = ?join_pattern_id,
+            join_pattern_name = join_pattern.name(),
+            "join pattern fired"
+        );
+        if join_pattern.is_inline() {
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| join_pattern.run(messages_for_channels)));
+            self.handle_fired(join_pattern_id, result);
+            return;
+        }
+
+        let thread_handle =
+            join_pattern.fire_with(messages_for_channels, &self.options.thread_spawner);
+
+        if self.options.fire_executor == FireExecutor::Joined {
+            self.handle_fired(join_pattern_id, thread_handle.join());
+            return;
+        }
+
+        self.record_consumers(join_pattern_id, &thread_handle);
 
         // Add the pattern to set of patterns that are firing
-        self.firing_join_patterns.push(thread_handle);
+        self.firing_join_patterns
+            .push((join_pattern_id, thread_handle));
 
-        // Prune the threads that have completed firing
-        // i.e. Keep all of the JoinHandle that are still running
+        self.prune_firing_join_patterns();
+    }
+
+    /// Prune the threads that have completed firing, i.e. keep all of the
+    /// `JoinHandle`s that are still running.
+    pub(in crate::controller) fn prune_firing_join_patterns(&mut self) {
         log::debug!(
             "Current Firing Join Patterns: {:?}",
             self.firing_join_patterns
         );
-        self.firing_join_patterns
-            .retain(|handle| handle.is_running());
+        let (running, finished): (Vec<_>, Vec<_>) = std::mem::take(&mut self.firing_join_patterns)
+            .into_iter()
+            .partition(|(_, handle)| handle.is_running());
+        self.firing_join_patterns = running;
+        finished
+            .into_iter()
+            .for_each(|(jp_id, handle)| self.handle_fired(jp_id, handle.join()));
         log::debug!(
             "Purged Firing Join Patterns: {:?}",
             self.firing_join_patterns
@@ -123,4 +222,15 @@ impl Controller {
     pub(in crate::controller) fn initialize_last_fired(&mut self, join_pattern_id: JoinPatternId) {
         self.join_pattern_last_fired.insert(join_pattern_id, None);
     }
+
+    /// Return the number of times the Join Patterns of the given name have
+    /// been fired.
+    #[cfg(feature = "testing")]
+    pub(in crate::controller) fn fire_count(&self, name: &str) -> u64 {
+        self.join_patterns
+            .iter()
+            .filter(|(_, join_pattern)| join_pattern.name() == Some(name))
+            .filter_map(|(jp_id, _)| self.fire_counts.get(jp_id))
+            .sum()
+    }
 }
@@ -0,0 +1,24 @@
+use crate::{channels::GateOpener, controller::Controller, types::ids::ChannelId};
+
+impl Controller {
+    /// Hold the gate of the given channel closed until a Join Pattern of the
+    /// channel has been registered, opening it right away if there is one
+    /// already.
+    pub(in crate::controller) fn gate(&mut self, channel_id: ChannelId, opener: GateOpener) {
+        match self.relevant_join_patterns(channel_id) {
+            Some(jp_ids) if !jp_ids.is_empty() => opener.open(),
+            _ => {
+                self.gate_openers.insert(channel_id, opener);
+            }
+        }
+    }
+
+    /// Open the gates of the given channels, which a Join Pattern has just
+    /// been registered for.
+    pub(in crate::controller) fn open_gates(&mut self, channels: &[ChannelId]) {
+        channels
+            .iter()
+            .filter_map(|channel_id| self.gate_openers.remove(channel_id))
+            .for_each(GateOpener::open);
+    }
+}
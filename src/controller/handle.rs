@@ -1,29 +1,57 @@
+#[cfg(feature = "async")]
+use std::sync::mpsc::Receiver;
 use std::{
-    sync::mpsc::Sender,
+    sync::{Arc, Mutex},
     thread::{JoinHandle, Thread},
 };
 
-use crate::types::Packet;
+use crate::{controller::Salvage, queue::PacketSender, types::Packet};
 
 /// Handle to a `Junction`'s underlying `Controller`.
 ///
 /// This struct carries a `JoinHandle` to the thread that the `Controller` of
-/// a `Junction` is running in. It allows for the `Controller` and its thread
-/// to be stopped gracefully at any point.
+/// a `Junction` is running in, or a way to wait for the task it is running
+/// as, see `Junction::spawn_on`. It allows for the `Controller` and its
+/// thread to be stopped gracefully at any point.
 pub struct ControllerHandle {
-    sender: Sender<Packet>,
+    sender: PacketSender,
     control_thread_handle: Option<JoinHandle<()>>,
+    /// What is left of the `Controller` once its control thread has
+    /// panicked.
+    salvage: Arc<Mutex<Option<Salvage>>>,
+    /// `Receiver` disconnected once the task of the `Controller` has
+    /// finished, behind a `Mutex` to keep the handle `Sync`.
+    #[cfg(feature = "async")]
+    task_done: Option<Mutex<Receiver<()>>>,
 }
 
 impl ControllerHandle {
-    pub(crate) fn new(sender: Sender<Packet>, handle: JoinHandle<()>) -> ControllerHandle {
+    pub(crate) fn new(
+        sender: PacketSender,
+        handle: JoinHandle<()>,
+        salvage: Arc<Mutex<Option<Salvage>>>,
+    ) -> ControllerHandle {
         ControllerHandle {
             sender,
             control_thread_handle: Some(handle),
+            salvage,
+            #[cfg(feature = "async")]
+            task_done: None,
         }
     }
 
-    /// Extracts a handle to the underlying thread.
+    #[cfg(feature = "async")]
+    pub(crate) fn for_task(sender: PacketSender, task_done: Receiver<()>) -> ControllerHandle {
+        ControllerHandle {
+            sender,
+            control_thread_handle: None,
+            salvage: Arc::default(),
+            task_done: Some(Mutex::new(task_done)),
+        }
+    }
+
+    /// Extracts a handle to the underlying thread, `None` if the
+    /// `Controller` is running as a task.
     pub fn thread(&self) -> Option<&Thread> {
         match &self.control_thread_handle {
             Some(h) => Some(h.thread()),
@@ -33,15 +61,31 @@ impl ControllerHandle {
 
     /// Request the `Controller` to stop gracefully, then join its thread.
     ///
+    /// A `Controller` running as a task is waited for instead, blocking the
+    /// calling thread until the task has finished.
+    ///
+    /// A `Controller` whose control thread has panicked, poisoning the
+    /// `Junction`, has already stopped and is only joined.
+    ///
     /// # Panics
     ///
     /// Panics if it was unable to send shut-down request to the control thread.
     pub fn stop(&mut self) {
         log::debug!("Controller asked to shutdown");
-        self.sender
-            .send(Packet::ShutDownRequest)
-            .map_err(|e| log::error!("Failed to send ShutDownRequest: {e:?}"))
-            .unwrap();
+        if !self.sender.is_poisoned() {
+            self.sender
+                .send(Packet::ShutDownRequest)
+                .map_err(|e| log::error!("Failed to send ShutDownRequest: {e:?}"))
+                .unwrap();
+        }
+
+        #[cfg(feature = "async")]
+        if let Some(task_done) = self.task_done.take() {
+            // Only ever disconnected, once the task has finished.
+            let _ = task_done.into_inner().unwrap().recv();
+            log::debug!("Controller shutdown");
+            return;
+        }
 
         let controller_handle = self.control_thread_handle.take();
 
@@ -57,4 +101,16 @@ impl ControllerHandle {
 
         log::debug!("Controller shutdown");
     }
+
+    /// Join the control thread of a poisoned `Junction` and return what is
+    /// left of its `Controller`, `None` if the control thread has not
+    /// panicked.
+    pub(crate) fn into_salvage(mut self) -> Option<Salvage> {
+        if !self.sender.is_poisoned() {
+            return None;
+        }
+
+        self.control_thread_handle.take()?.join().ok()?;
+        self.salvage.lock().unwrap().take()
+    }
 }
@@ -1,11 +1,19 @@
 use std::{
-    collections::LinkedList,
-    sync::mpsc::{Receiver, Sender},
+    collections::{HashSet, LinkedList},
+    iter, mem,
+    ops::ControlFlow,
+    sync::mpsc::{RecvTimeoutError, Sender},
+    thread,
+    time::Instant,
 };
 
 use crate::{
-    controller::Controller,
+    builder::DuplicatePatternPolicy,
+    channels::Priority,
+    controller::{ChannelSet, Controller},
     join_pattern::JoinPattern,
+    junction::{AuditAction, Registration},
+    queue::{PacketReceiver, PacketSender},
     types::{
         ids::{ChannelId, JoinPatternId},
         Message, Packet,
@@ -18,36 +26,439 @@ impl Controller {
     /// This function will continuously receive `Packet`s sent from structs
     /// associated with the `Junction` that created and started this `Controller`
     /// until a `Packet::ShutDownRequest` has been sent.
-    pub(in crate::controller) fn handle_packets(mut self, receiver: Receiver<Packet>) {
+    ///
+    /// While `Message`s are waiting to become dead letters or to expire, or
+    /// the `Controller` is asked to signal once it is idle, receiving times
+    /// out in time to deal with them. How the `Controller` waits is up to
+    /// its `IdleStrategy`.
+    ///
+    /// Panics while handling `Packet`s are dealt with according to the
+    /// `PanicPolicy`. The function bodies of fired Join Patterns that are
+    /// still running are left to be joined by the caller.
+    pub(in crate::controller) fn handle_packets(&mut self, receiver: &PacketReceiver) {
+        loop {
+            let deadline = self.next_deadline();
+            let packet = match self.options.idle_strategy.wait(receiver, deadline) {
+                Ok(packet) => packet,
+                Err(RecvTimeoutError::Timeout) => {
+                    self.guard(Controller::handle_deadlines);
+
+                    if self.idle_signals.is_empty() || !self.is_done_firing() {
+                        continue;
+                    }
+                    match receiver.try_recv() {
+                        Ok(packet) => packet,
+                        Err(_) => {
+                            self.signal_idle();
+                            continue;
+                        }
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            let handled = self.guard(|controller| controller.handle_batch(packet, receiver));
+            if handled.is_some_and(|flow| flow.is_break()) {
+                break;
+            }
+        }
+    }
+
+    /// Handle the given `Packet` along with further `Packet`s already queued.
+    ///
+    /// Up to `max_batch` `Packet`s are handled in total. `Message`s are only
+    /// stored at first and the Join Patterns of their channels are checked
+    /// for firing once all queued `Message`s have been stored, or before the
+    /// next `Packet` of any other kind is handled. This amortizes the cost of
+    /// matching across bursts of `Message`s.
+    ///
+    /// Return `ControlFlow::Break` if one of the `Packet`s was a request to
+    /// shut down the `Controller`.
+    pub(in crate::controller) fn handle_batch(
+        &mut self,
+        packet: Packet,
+        receiver: &PacketReceiver,
+    ) -> ControlFlow<()> {
+        let mut arrived = mem::take(&mut self.arrived);
+        let mut next = Some(packet);
+        let mut handled = 0;
+
+        #[cfg(feature = "metrics")]
+        self.record_queue_depth(receiver.depth());
+
+        self.handle_deadlines();
+
+        while let Some(packet) = next.take() {
+            handled += 1;
+
+            match self.intercept(packet) {
+                None => {}
+                Some(Packet::Message { channel_id, msg }) => {
+                    log::debug!(
+                        "Handling a Packet::Message to: {}",
+                        self.describe_channel(channel_id)
+                    );
+                    if self.store_message(channel_id, msg) {
+                        arrived.push(channel_id);
+                    }
+                }
+                Some(Packet::Messages { channel_id, msgs }) => {
+                    log::debug!(
+                        "Handling a Packet::Messages with {} Messages to: {}",
+                        msgs.len(),
+                        self.describe_channel(channel_id)
+                    );
+                    for msg in msgs {
+                        if self.store_message(channel_id, msg) {
+                            arrived.push(channel_id);
+                        }
+                    }
+                }
+                Some(packet) => {
+                    self.handle_arrived_messages(&mut arrived);
+
+                    if self.handle_packet(packet).is_break() {
+                        self.arrived = arrived;
+                        return ControlFlow::Break(());
+                    }
+                }
+            }
+
+            if handled < self.options.max_batch {
+                next = receiver.try_recv().ok();
+            }
+        }
+
+        log::debug!("Handled a batch of {handled} Packets");
+        self.handle_arrived_messages(&mut arrived);
+        self.arrived = arrived;
+
+        ControlFlow::Continue(())
+    }
+
+    /// Deal with all `Message`s that have become dead letters or expired, and
+    /// check for deadlocks and old `Message`s if due.
+    pub(in crate::controller) fn handle_deadlines(&mut self) {
+        self.sweep_dead_letters();
+        self.evict_expired();
+        self.detect_deadlocks();
+        self.check_message_ages();
+        self.release_throttled();
+        self.fire_held_join_patterns();
+    }
+
+    /// Return the next instant at which a stored `Message` becomes a dead
+    /// letter or expires, a held back `Message` of a rate limited channel is
+    /// released, or to check whether the `Controller` is idle or deadlocked
+    /// or has old `Message`s, or whether Join Patterns held back by their
+    /// limit of running function bodies can fire, if any.
+    pub(in crate::controller) fn next_deadline(&self) -> Option<Instant> {
+        [
+            self.next_dead_letter_deadline(),
+            self.next_expiry(),
+            self.next_idle_check(),
+            self.next_deadlock_check(),
+            self.next_age_check(),
+            self.next_release(),
+            self.next_held_check(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Handle a single `Packet` from associated `Junction`.
+    ///
+    /// Return `ControlFlow::Break` if the `Packet` was a request to shut down
+    /// the `Controller`, in which case no further `Packet`s should be handled.
+    pub(in crate::controller) fn handle_packet(&mut self, packet: Packet) -> ControlFlow<()> {
         use Packet::*;
 
-        while let Ok(packet) = receiver.recv() {
-            match packet {
-                Message { channel_id, msg } => {
-                    log::debug!("Handling a Packet::Message to: {channel_id:?}");
+        match packet {
+            Message { channel_id, msg } => {
+                log::debug!(
+                    "Handling a Packet::Message to: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.handle_message(channel_id, msg);
+            }
+            Messages { channel_id, msgs } => {
+                log::debug!(
+                    "Handling a Packet::Messages with {} Messages to: {}",
+                    msgs.len(),
+                    self.describe_channel(channel_id)
+                );
+                for msg in msgs {
                     self.handle_message(channel_id, msg);
                 }
-                NewChannelIdRequest { return_sender } => {
-                    log::debug!("Handling a Packet::NewChannelIdRequest");
-                    self.handle_new_channel_id_request(return_sender)
+            }
+            NameChannel { channel_id, name } => {
+                log::debug!("Handling a Packet::NameChannel for: {channel_id:?}");
+                self.channel_names.insert(channel_id, name);
+            }
+            Persist {
+                channel_id,
+                sequence,
+                copy,
+            } => {
+                log::debug!(
+                    "Handling a Packet::Persist of Message {sequence} on: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.persist(channel_id, sequence, copy);
+            }
+            Revoke {
+                channel_id,
+                sequence,
+            } => {
+                log::debug!(
+                    "Handling a Packet::Revoke of Message {sequence} on: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.revoke(channel_id, sequence);
+            }
+            RetainChannel { channel_id, copy } => {
+                log::debug!(
+                    "Handling a Packet::RetainChannel for: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.state_channels.insert(channel_id, copy);
+            }
+            GatePatterns { channel_id, opener } => {
+                log::debug!(
+                    "Handling a Packet::GatePatterns for: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.gate(channel_id, opener);
+            }
+            GateRequest {
+                channel_id,
+                return_sender,
+            } => {
+                log::debug!(
+                    "Handling a Packet::GateRequest for: {}",
+                    self.describe_channel(channel_id)
+                );
+                if return_sender.send(()).is_err() {
+                    log::warn!("Dropping reply to GateRequest, as it is no longer waited for");
+                }
+            }
+            Subscribe {
+                channel_id,
+                subscriber,
+                copy,
+            } => {
+                log::debug!(
+                    "Handling a Packet::Subscribe of {} to: {}",
+                    self.describe_channel(subscriber),
+                    self.describe_channel(channel_id)
+                );
+                self.subscribe(channel_id, subscriber, copy);
+            }
+            CloseChannel { channel_id } => {
+                log::debug!(
+                    "Handling a Packet::CloseChannel for: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.closed_channels.insert(channel_id);
+            }
+            ChannelDropped { channel_id } => {
+                log::debug!(
+                    "Handling a Packet::ChannelDropped for: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.handle_channel_dropped(channel_id)
+            }
+            TypeChannel {
+                channel_id,
+                message_type,
+            } => {
+                log::debug!("Handling a Packet::TypeChannel for: {channel_id:?}");
+                self.channel_types.insert(channel_id, message_type);
+            }
+            NewChannelIdRequest { return_sender } => {
+                log::debug!("Handling a Packet::NewChannelIdRequest");
+                self.handle_new_channel_id_request(return_sender)
+            }
+            RateLimit { channel_id, rate } => {
+                log::debug!(
+                    "Handling a Packet::RateLimit for: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.rate_limit(channel_id, rate);
+            }
+            TapRequest { channel_id, tap } => {
+                log::debug!(
+                    "Handling a Packet::TapRequest for: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.add_tap(channel_id, tap);
+            }
+            #[cfg(feature = "snapshot")]
+            SnapshotChannel {
+                channel_id,
+                name,
+                encode,
+                decode,
+            } => {
+                log::debug!(
+                    "Handling a Packet::SnapshotChannel for: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.add_snapshot_channel(channel_id, name, encode, decode);
+            }
+            #[cfg(feature = "snapshot")]
+            RestoreRequest { channels } => {
+                log::debug!("Handling a Packet::RestoreRequest");
+                self.restored.extend(channels);
+            }
+            #[cfg(feature = "snapshot")]
+            SnapshotRequest { return_sender } => {
+                log::debug!("Handling a Packet::SnapshotRequest");
+                if return_sender.send(self.snapshot()).is_err() {
+                    log::warn!("Dropping snapshot, as it is no longer waited for");
+                }
+            }
+            AddJoinPatternRequest {
+                join_pattern,
+                registration,
+            } => {
+                match join_pattern.name() {
+                    Some(name) => {
+                        log::debug!("Handling a Packet::AddJoinPatternRequest for: `{name}`")
+                    }
+                    None => log::debug!("Handling a Packet::AddJoinPatternRequest"),
                 }
-                AddJoinPatternRequest { join_pattern } => {
-                    log::debug!("Handling a Packet::AddJoinPatternRequest");
-                    self.handle_add_join_pattern_request(join_pattern)
+                self.handle_registration(join_pattern, Some(registration))
+            }
+            MigrateRequest {
+                channel_id,
+                to,
+                to_channel_id,
+                ack,
+            } => {
+                log::debug!(
+                    "Handling a Packet::MigrateRequest for: {}",
+                    self.describe_channel(channel_id)
+                );
+                self.handle_migrate_request(channel_id, *to, to_channel_id, ack)
+            }
+            MergeRequest {
+                to,
+                to_channel_ids,
+                ack,
+            } => {
+                log::debug!("Handling a Packet::MergeRequest");
+                self.handle_merge_request(*to, to_channel_ids, ack)
+            }
+            HandOffRequest { channels, to, ack } => {
+                log::debug!("Handling a Packet::HandOffRequest for: {channels:?}");
+                self.handle_hand_off_request(channels, *to, ack)
+            }
+            Adopt {
+                channels,
+                messages,
+                join_patterns,
+                channel_names,
+                registration,
+            } => {
+                log::debug!("Handling a Packet::Adopt for: {channels:?}");
+                self.channel_names.extend(channel_names);
+                self.handle_adopt(
+                    channels,
+                    messages,
+                    join_patterns,
+                    registration.map(|registration| *registration),
+                )
+            }
+            DrainRequest {
+                channel_id,
+                make_message,
+                return_sender,
+            } => {
+                log::debug!(
+                    "Handling a Packet::DrainRequest for: {}",
+                    self.describe_channel(channel_id)
+                );
+                if return_sender
+                    .send(self.drain(channel_id, &make_message))
+                    .is_err()
+                {
+                    log::warn!(
+                        "Dropping number of drained Messages, as it is no longer waited for"
+                    );
                 }
-                ShutDownRequest => {
-                    log::debug!("Handling a Packet::ShutDownRequest");
-                    break;
+            }
+            ExportDotRequest {
+                prefix,
+                return_sender,
+            } => {
+                log::debug!("Handling a Packet::ExportDotRequest");
+                if return_sender.send(self.export_dot(&prefix)).is_err() {
+                    log::warn!("Dropping DOT graph, as it is no longer waited for");
+                }
+            }
+            LatencyStatsRequest { return_sender } => {
+                log::debug!("Handling a Packet::LatencyStatsRequest");
+                if return_sender.send(self.latency_stats()).is_err() {
+                    log::warn!("Dropping latency stats, as they are no longer waited for");
                 }
             }
+            AuditLogRequest { return_sender } => {
+                log::debug!("Handling a Packet::AuditLogRequest");
+                if return_sender.send(self.audit_log()).is_err() {
+                    log::warn!("Dropping audit log, as it is no longer waited for");
+                }
+            }
+            #[cfg(feature = "testing")]
+            FireCountRequest {
+                name,
+                return_sender,
+            } => {
+                log::debug!("Handling a Packet::FireCountRequest for: `{name}`");
+                if return_sender.send(self.fire_count(&name)).is_err() {
+                    log::warn!("Dropping fire count, as it is no longer waited for");
+                }
+            }
+            AdvanceClock { to, ack } => {
+                log::debug!("Handling a Packet::AdvanceClock to: {to:?}");
+                self.options.clock.advance_to(to);
+                self.handle_deadlines();
+                let _ = ack.send(());
+            }
+            ProbeRequest { return_sender } => {
+                log::debug!("Handling a Packet::ProbeRequest");
+                let _ = return_sender.send(thread::current().id());
+            }
+            CancelRequest => {
+                log::debug!("Handling a Packet::CancelRequest");
+                self.join_patterns
+                    .values()
+                    .for_each(|join_pattern| join_pattern.cancel());
+            }
+            IdleRequest { signal } => {
+                log::debug!("Handling a Packet::IdleRequest");
+                self.idle_signals.push(signal);
+            }
+            ShutDownRequest => {
+                log::debug!("Handling a Packet::ShutDownRequest");
+                return ControlFlow::Break(());
+            }
+            Wake => {}
         }
 
-        // Join all of the `JoinHandle`s of the firing `JoinPattern`
+        ControlFlow::Continue(())
+    }
+
+    /// Join all of the `JoinHandle`s of the firing `JoinPattern`s.
+    pub(in crate::controller) fn join_firing_join_patterns(mut self) {
         log::debug!("Starting to join all of the firing threads");
-        self.firing_join_patterns.into_iter().for_each(|handle| {
-            handle.join().ok();
-        });
+        std::mem::take(&mut self.firing_join_patterns)
+            .into_iter()
+            .for_each(|(jp_id, handle)| self.handle_fired(jp_id, handle.join()));
         log::debug!("Finished joining all of the firing threads");
+
+        self.signal_idle();
     }
 
     /// Handle a received `Message` from a given channel.
@@ -57,11 +468,114 @@ impl Controller {
     ///
     /// The second action is to start determining if any of the Join Patterns stored
     /// with the `Controller` are alive and if so, which of these to fire.
-    fn handle_message(&mut self, channel_id: ChannelId, msg: Message) {
+    pub(in crate::controller) fn handle_message(&mut self, channel_id: ChannelId, msg: Message) {
+        if self.store_message(channel_id, msg) {
+            self.handle_join_pattern_firing(channel_id);
+        }
+    }
+
+    /// Store a received `Message` without checking for Join Patterns to fire.
+    ///
+    /// Return `false` if the `Message` has been held back by the rate limit
+    /// of its channel, forwarded to another shard, dealt with as a dead
+    /// letter, passed on to the subscribers of a broadcast channel or
+    /// dropped for holding a value of the wrong type instead.
+    pub(in crate::controller) fn store_message(
+        &mut self,
+        channel_id: ChannelId,
+        msg: Message,
+    ) -> bool {
+        if !self.has_channel_type(channel_id, &msg) {
+            return false;
+        }
+        let Some(msg) = self.broadcast(channel_id, msg) else {
+            return false;
+        };
+
+        match self.throttle(channel_id, msg) {
+            Some(msg) => self.store_released_message(channel_id, msg),
+            None => false,
+        }
+    }
+
+    /// Store a received `Message` that has been released by the rate limit
+    /// of its channel, if any, see `Controller::store_message`.
+    pub(in crate::controller) fn store_released_message(
+        &mut self,
+        channel_id: ChannelId,
+        msg: Message,
+    ) -> bool {
+        let Some(msg) = self.replay_arrival(channel_id, msg) else {
+            return false;
+        };
+        self.record_arrival(channel_id);
+        self.notify_taps(channel_id, &msg);
+
+        if let Some((to, to_channel_id)) = self.forwards.get(&channel_id) {
+            log::debug!(
+                "Forwarding Message to handed over channel: {}",
+                self.describe_channel(channel_id)
+            );
+            to.send(Packet::Message {
+                channel_id: *to_channel_id,
+                msg,
+            })
+            .unwrap_or_else(|e| log::error!("Failed to forward Message: {e:?}"));
+            return false;
+        }
+
+        #[cfg(feature = "metrics")]
+        self.record_message(channel_id);
+
+        self.store_arrived_message(channel_id, msg)
+    }
+
+    /// Store a `Message` whose arrival has been recorded already, either by
+    /// `Controller::store_released_message` or by the shard that handed its
+    /// channel over.
+    ///
+    /// Return `false` if the `Message` has been dealt with as a dead letter
+    /// instead.
+    fn store_arrived_message(&mut self, channel_id: ChannelId, mut msg: Message) -> bool {
+        if self.may_become_dead_letter(channel_id) {
+            if self.options.dead_letter_age.is_zero() {
+                self.dead_letter(channel_id, msg);
+                return false;
+            }
+
+            self.schedule_dead_letter(channel_id);
+        }
+
+        if self.options.clock.is_virtual() {
+            msg.restart_ttl(self.options.clock.now());
+        }
+        if let Some(expires_at) = msg.expires_at() {
+            self.schedule_expiry(channel_id, expires_at);
+        }
+        if msg.caller().is_some() {
+            self.record_call(channel_id);
+        }
+        if msg.priority() != Priority::default() {
+            self.prioritized_channels.insert(channel_id);
+        }
+
+        if self.state_channels.contains_key(&channel_id) {
+            // Only the latest value of a state channel is retained.
+            self.messages.take_all(&channel_id);
+        }
         self.messages.add(channel_id, msg);
         self.message_counter.increment();
+        self.record_pending(channel_id);
+
+        true
+    }
 
-        self.handle_join_pattern_firing(channel_id);
+    /// Check for Join Patterns to fire once for each stored `Message`, in
+    /// order of arrival.
+    pub(in crate::controller) fn handle_arrived_messages(&mut self, arrived: &mut Vec<ChannelId>) {
+        arrived
+            .drain(..)
+            .for_each(|channel_id| self.handle_join_pattern_firing(channel_id));
     }
 
     /// Handle the firing of a `JoinPattern`, if possible.
@@ -71,17 +585,39 @@ impl Controller {
     /// available for each of their channels, i.e. are alive, then select
     /// one `JoinPattern` to be fired. If at any point during this process
     /// no more `JoinPattern`s remain, nothing will be done.
-    fn handle_join_pattern_firing(&mut self, channel_id: ChannelId) {
-        let mut alive_join_patterns: Vec<JoinPatternId> = Vec::new();
+    pub(in crate::controller) fn handle_join_pattern_firing(&mut self, channel_id: ChannelId) {
+        // Join Patterns fire in the order of the `Trace` being replayed.
+        if self.replay.is_some() {
+            self.advance_replay();
+            return;
+        }
+
+        let mut alive_join_patterns = mem::take(&mut self.alive);
+
+        // Expired `Message`s must not be consumed by the Join Pattern
+        self.evict_expired();
 
         if let Some(jp_ids) = self.relevant_join_patterns(channel_id) {
-            alive_join_patterns = self.alive_join_patterns(jp_ids);
+            self.alive_join_patterns(jp_ids, &mut alive_join_patterns);
         }
 
-        if let Some(jp_id_to_fire) = self.select_to_fire(&mut alive_join_patterns) {
-            self.fire_join_pattern(*jp_id_to_fire);
-            self.reset_last_fired(*jp_id_to_fire);
+        #[cfg(feature = "tracing")]
+        if !alive_join_patterns.is_empty() {
+            tracing::trace!(
+                channel_id = ?channel_id,
+                channel_name = self.channel_names.get(&channel_id).map(String::as_str),
+                join_pattern_ids = ?alive_join_patterns,
+                "join patterns ready"
+            );
         }
+
+        if let Some(&jp_id_to_fire) = self.select_to_fire(&mut alive_join_patterns) {
+            self.fire_join_pattern(jp_id_to_fire);
+            self.reset_last_fired(jp_id_to_fire);
+        }
+
+        alive_join_patterns.clear();
+        self.alive = alive_join_patterns;
     }
 
     /// Send new, *unique* `ChannelId` back to the requesting `Junction`.
@@ -96,20 +632,301 @@ impl Controller {
             .unwrap();
     }
 
-    /// Add new Join Pattern to `Controller` storage.
-    fn handle_add_join_pattern_request(&mut self, join_pattern: Box<dyn JoinPattern>) {
+    /// Add a newly registered Join Pattern, applying the
+    /// `DuplicatePatternPolicy` if it is over the same channels as a Join
+    /// Pattern already registered.
+    pub(in crate::controller) fn handle_registration(
+        &mut self,
+        join_pattern: Box<dyn JoinPattern>,
+        registration: Option<Registration>,
+    ) {
+        if self.options.duplicate_pattern_policy != DuplicatePatternPolicy::Allow {
+            if let Some(duplicate) = self.find_duplicate(join_pattern.as_ref()) {
+                let new = join_pattern.name().unwrap_or("unnamed");
+                let existing = self.describe_join_pattern(duplicate);
+
+                if self.options.duplicate_pattern_policy == DuplicatePatternPolicy::Reject {
+                    log::error!(
+                        "Rejecting Join Pattern `{new}` over the same channels as Join Pattern {existing}"
+                    );
+                    if let Some(registration) = registration {
+                        self.record_audit(
+                            AuditAction::Rejected,
+                            format!("`{new}`"),
+                            &join_pattern.channels(),
+                            Some(registration),
+                        );
+                    }
+                    return;
+                }
+                log::warn!(
+                    "Adding Join Pattern `{new}` over the same channels as Join Pattern {existing}"
+                );
+            }
+        }
+
+        let jp_id = self.handle_add_join_pattern_request(join_pattern);
+        if let Some(registration) = registration {
+            self.record_registered(jp_id, registration);
+        }
+    }
+
+    /// Record the registration of the given Join Pattern in the audit log.
+    fn record_registered(&mut self, join_pattern_id: JoinPatternId, registration: Registration) {
+        let channels = self.join_patterns[&join_pattern_id].channels();
+
+        self.record_audit(
+            AuditAction::Registered,
+            self.describe_join_pattern(join_pattern_id),
+            &channels,
+            Some(registration),
+        );
+    }
+
+    /// Return the ID of a registered Join Pattern over exactly the same
+    /// channels as the given one, if any.
+    fn find_duplicate(&self, join_pattern: &dyn JoinPattern) -> Option<JoinPatternId> {
+        let mut channels = join_pattern.channels();
+        channels.sort_unstable();
+
+        self.relevant_join_patterns(*channels.first()?)?
+            .iter()
+            .find(|jp_id| {
+                let mut existing = self.join_patterns[jp_id].channels();
+                existing.sort_unstable();
+
+                existing == channels
+            })
+            .copied()
+    }
+
+    /// Add new Join Pattern to `Controller` storage, returning its ID.
+    fn handle_add_join_pattern_request(
+        &mut self,
+        join_pattern: Box<dyn JoinPattern>,
+    ) -> JoinPatternId {
         let jp_id = self.new_join_pattern_id();
 
         self.initialize_last_fired(jp_id);
+        if join_pattern.max_in_flight().is_some() {
+            self.limited_join_patterns.insert(jp_id);
+        }
 
         self.insert_join_pattern(jp_id, join_pattern);
+
+        // The Join Pattern may be the next to fire in the `Trace` being
+        // replayed.
+        if self.replay.is_some() {
+            self.advance_replay();
+        }
+
+        jp_id
+    }
+
+    /// Hand the given channels over to another shard of a sharded `Junction`.
+    ///
+    /// All `Message`s stored for the channels, their `Tap`s and all Join
+    /// Patterns they are part of are removed and sent to the new shard, which
+    /// from then on owns the channels. The channels must include all channels of these Join
+    /// Patterns. `Message`s for them that arrive later on are forwarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hand-off could not be acknowledged.
+    fn handle_hand_off_request(
+        &mut self,
+        channels: Vec<ChannelId>,
+        to: PacketSender,
+        ack: Sender<()>,
+    ) {
+        let mut messages = Vec::new();
+        let mut jp_ids = HashSet::new();
+        let mut channel_names = Vec::new();
+
+        for channel_id in channels.iter() {
+            messages.extend(
+                self.messages
+                    .take_all(channel_id)
+                    .into_iter()
+                    .map(|msg| (*channel_id, msg)),
+            );
+
+            if let Some(ids) = self.join_pattern_index.remove(channel_id) {
+                jp_ids.extend(ids);
+            }
+
+            if let Some(name) = self.channel_names.remove(channel_id) {
+                channel_names.push((*channel_id, name));
+            }
+            // Sent ahead of the `Packet::Adopt`, so that the types of the
+            // channels are known by the time they are adopted.
+            if let Some(message_type) = self.channel_types.remove(channel_id) {
+                to.send(Packet::TypeChannel {
+                    channel_id: *channel_id,
+                    message_type,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to hand off channel type: {e:?}"));
+            }
+            if self.closed_channels.remove(channel_id) {
+                to.send(Packet::CloseChannel {
+                    channel_id: *channel_id,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to hand off closed channel: {e:?}"));
+            }
+            if let Some(copy) = self.state_channels.remove(channel_id) {
+                to.send(Packet::RetainChannel {
+                    channel_id: *channel_id,
+                    copy,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to hand off state channel: {e:?}"));
+            }
+            if let Some(opener) = self.gate_openers.remove(channel_id) {
+                to.send(Packet::GatePatterns {
+                    channel_id: *channel_id,
+                    opener,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to hand off channel gate: {e:?}"));
+            }
+            for tap in self.taps.remove(channel_id).unwrap_or_default() {
+                to.send(Packet::TapRequest {
+                    channel_id: *channel_id,
+                    tap,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to hand off channel tap: {e:?}"));
+            }
+            for (sequence, copy) in self.persistent.remove(channel_id).unwrap_or_default() {
+                to.send(Packet::Persist {
+                    channel_id: *channel_id,
+                    sequence,
+                    copy,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to hand off persistent Message: {e:?}"));
+            }
+
+            self.forwards.insert(*channel_id, (to.clone(), *channel_id));
+        }
+
+        let join_patterns = jp_ids
+            .into_iter()
+            .filter_map(|jp_id| {
+                self.join_pattern_last_fired.remove(&jp_id);
+                self.fire_counts.remove(&jp_id);
+                self.latencies.remove(&jp_id);
+                if let Some(channel_set) = self.channel_sets.remove(&jp_id) {
+                    self.messages.unregister(jp_id, channel_set.as_slice());
+                }
+                self.join_patterns.remove(&jp_id)
+            })
+            .collect();
+
+        to.send(Packet::Adopt {
+            channels,
+            messages,
+            join_patterns,
+            channel_names,
+            registration: None,
+        })
+        .unwrap_or_else(|e| log::error!("Failed to send Adopt: {e:?}"));
+
+        ack.send(())
+            .map_err(|e| log::error!("Failed to acknowledge HandOffRequest: {e:?}"))
+            .unwrap();
+    }
+
+    /// Pass all `Message`s of the given channel on to another `Junction`,
+    /// along with those still arriving, then acknowledge the request.
+    ///
+    /// The Join Patterns of the channel stay behind, as they may involve
+    /// channels that are not migrated, and never fire again.
+    fn handle_migrate_request(
+        &mut self,
+        channel_id: ChannelId,
+        to: PacketSender,
+        to_channel_id: ChannelId,
+        ack: Sender<()>,
+    ) {
+        for msg in self.messages.take_all(&channel_id) {
+            to.send(Packet::Message {
+                channel_id: to_channel_id,
+                msg,
+            })
+            .unwrap_or_else(|e| log::error!("Failed to migrate Message: {e:?}"));
+        }
+
+        self.channel_names.remove(&channel_id);
+        self.channel_types.remove(&channel_id);
+        self.closed_channels.remove(&channel_id);
+        self.state_channels.remove(&channel_id);
+        self.persistent.remove(&channel_id);
+        if let Some(opener) = self.gate_openers.remove(&channel_id) {
+            to.send(Packet::GatePatterns {
+                channel_id: to_channel_id,
+                opener,
+            })
+            .unwrap_or_else(|e| log::error!("Failed to migrate channel gate: {e:?}"));
+        }
+        self.forwards.insert(channel_id, (to, to_channel_id));
+
+        ack.send(())
+            .map_err(|e| log::error!("Failed to acknowledge MigrateRequest: {e:?}"))
+            .unwrap();
+    }
+
+    /// Take ownership of channels and Join Patterns from another shard.
+    ///
+    /// The `Message`s handed over have been handled by the other shard
+    /// already, so they are only stored. `Message`s may have reached this
+    /// shard before the Join Patterns they belong to, so once the Join
+    /// Patterns have been added, they are checked for firing once for every
+    /// `Message` pending on their channels.
+    /// Join Patterns adopted along with a `Registration` are recorded in the
+    /// audit log as registered.
+    fn handle_adopt(
+        &mut self,
+        channels: Vec<ChannelId>,
+        messages: Vec<(ChannelId, Message)>,
+        join_patterns: Vec<Box<dyn JoinPattern>>,
+        registration: Option<Registration>,
+    ) {
+        channels.iter().for_each(|channel_id| {
+            self.forwards.remove(channel_id);
+        });
+
+        let mut pattern_channels: Vec<ChannelId> = join_patterns
+            .iter()
+            .flat_map(|join_pattern| join_pattern.channels())
+            .collect();
+        pattern_channels.sort_unstable();
+        pattern_channels.dedup();
+
+        for join_pattern in join_patterns {
+            let jp_id = self.handle_add_join_pattern_request(join_pattern);
+            if let Some(registration) = &registration {
+                self.record_registered(jp_id, registration.clone());
+            }
+        }
+
+        for (channel_id, msg) in messages {
+            self.store_arrived_message(channel_id, msg);
+        }
+
+        let mut pending: Vec<ChannelId> = pattern_channels
+            .into_iter()
+            .flat_map(|channel_id| {
+                iter::repeat_n(channel_id, self.messages.count_items(&channel_id))
+            })
+            .collect();
+        self.handle_arrived_messages(&mut pending);
     }
 
     /// Return the `JoinPatternId`s of relevant Join Patterns for given `ChannelId`.
     ///
     /// A Join Pattern is considered relevant for a given `ChannelId` if at least
     /// one of its channels has the `ChannelId`.
-    fn relevant_join_patterns(&self, channel_id: ChannelId) -> Option<&LinkedList<JoinPatternId>> {
+    pub(in crate::controller) fn relevant_join_patterns(
+        &self,
+        channel_id: ChannelId,
+    ) -> Option<&LinkedList<JoinPatternId>> {
         self.join_pattern_index.peek_all(&channel_id)
     }
 
@@ -123,15 +940,20 @@ impl Controller {
         join_pattern_id: JoinPatternId,
         join_pattern: Box<dyn JoinPattern>,
     ) {
-        join_pattern.channels().iter().for_each(|chan| {
+        let channel_set = ChannelSet::new(join_pattern.channels());
+        channel_set.as_slice().iter().for_each(|chan| {
             self.join_pattern_index
                 .insert_single(*chan, join_pattern_id)
         });
+        self.messages
+            .register(join_pattern_id, channel_set.as_slice());
+        self.open_gates(channel_set.as_slice());
+        self.channel_sets.insert(join_pattern_id, channel_set);
         self.join_patterns.insert(join_pattern_id, join_pattern);
     }
 
     /// Generate new, *unique* `ChannelId`.
-    fn new_channel_id(&mut self) -> ChannelId {
+    pub(in crate::controller) fn new_channel_id(&mut self) -> ChannelId {
         let ch_id = self.latest_channel_id;
         self.latest_channel_id.increment();
 
@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+use crate::controller::Controller;
+
+/// Interval at which a `Controller` asked to signal once it is idle checks
+/// whether the function bodies of fired Join Patterns have finished.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_millis(1);
+
+impl Controller {
+    /// Return the next instant at which to check whether the `Controller` is
+    /// idle, `None` if nobody is waiting for it to be.
+    pub(in crate::controller) fn next_idle_check(&self) -> Option<Instant> {
+        if self.idle_signals.is_empty() {
+            return None;
+        }
+
+        Some(Instant::now() + IDLE_CHECK_INTERVAL)
+    }
+
+    /// Set all pending idle signals.
+    ///
+    /// Must only be called once no function body of a fired Join Pattern is
+    /// running and no `Packet`s are left to handle, checked in this order,
+    /// since function bodies may send further `Message`s right until they
+    /// finish.
+    pub(in crate::controller) fn signal_idle(&mut self) {
+        if self.idle_signals.is_empty() {
+            return;
+        }
+
+        log::debug!("Controller is idle");
+        std::mem::take(&mut self.idle_signals)
+            .into_iter()
+            .for_each(|signal| signal.set(self.message_counter.clone()));
+    }
+
+    /// Return `true` if no function body of a fired Join Pattern is running.
+    pub(in crate::controller) fn is_done_firing(&mut self) -> bool {
+        self.prune_firing_join_patterns();
+
+        self.firing_join_patterns.is_empty()
+    }
+}
@@ -0,0 +1,33 @@
+use std::slice;
+
+use crate::{controller::Controller, intercept::Intercepted, types::Packet};
+
+impl Controller {
+    /// Run the interceptors on the `Message`s of the given `Packet`, if it
+    /// holds any.
+    ///
+    /// Return `None` if an interceptor has dropped them.
+    pub(in crate::controller) fn intercept(&self, mut packet: Packet) -> Option<Packet> {
+        if self.options.interceptors.is_empty() {
+            return Some(packet);
+        }
+
+        let (channel_id, msgs) = match &mut packet {
+            Packet::Message { channel_id, msg } => (*channel_id, slice::from_mut(msg)),
+            Packet::Messages { channel_id, msgs } => (*channel_id, msgs.as_mut_slice()),
+            _ => return Some(packet),
+        };
+
+        let channel_name = self.channel_names.get(&channel_id).map(String::as_str);
+        let mut intercepted = Intercepted::new(channel_id, channel_name, msgs);
+        if self.options.interceptors.run(&mut intercepted) {
+            Some(packet)
+        } else {
+            log::debug!(
+                "Dropping Messages to {}, as an interceptor rejected them",
+                self.describe_channel(channel_id)
+            );
+            None
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::{controller::Controller, junction::LatencyStats, types::ids::JoinPatternId};
+
+impl Controller {
+    /// Return the latencies of every Join Pattern that has fired, in the
+    /// order the Join Patterns have been added.
+    pub(in crate::controller) fn latency_stats(&self) -> Vec<LatencyStats> {
+        let mut jp_ids: Vec<JoinPatternId> = self.latencies.keys().copied().collect();
+        jp_ids.sort_unstable();
+
+        jp_ids
+            .into_iter()
+            .map(|jp_id| {
+                LatencyStats::new(
+                    self.describe_join_pattern(jp_id),
+                    self.latencies[&jp_id].clone(),
+                )
+            })
+            .collect()
+    }
+}
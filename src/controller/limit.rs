@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+use crate::{controller::Controller, types::ids::JoinPatternId};
+
+/// Interval at which a `Controller` holding back Join Patterns at their
+/// limit of running function bodies checks whether any of these finished.
+const HELD_CHECK_INTERVAL: Duration = Duration::from_millis(1);
+
+impl Controller {
+    /// Return `true` if as many function bodies of the given Join Pattern
+    /// are running as it allows at once, see `then_do_with_limit`.
+    pub(in crate::controller) fn is_at_limit(&self, join_pattern_id: JoinPatternId) -> bool {
+        let Some(max_in_flight) = self
+            .join_patterns
+            .get(&join_pattern_id)
+            .and_then(|join_pattern| join_pattern.max_in_flight())
+        else {
+            return false;
+        };
+
+        let in_flight = self
+            .firing_join_patterns
+            .iter()
+            .filter(|(jp_id, handle)| *jp_id == join_pattern_id && handle.is_running())
+            .count();
+
+        in_flight >= max_in_flight
+    }
+
+    /// Return the next instant at which to check whether Join Patterns held
+    /// back by their limit can fire, `None` if no Join Pattern is held back.
+    pub(in crate::controller) fn next_held_check(&self) -> Option<Instant> {
+        if !self
+            .limited_join_patterns
+            .iter()
+            .any(|&jp_id| self.is_held(jp_id))
+        {
+            return None;
+        }
+
+        Some(Instant::now() + HELD_CHECK_INTERVAL)
+    }
+
+    /// Fire the Join Patterns held back by their limit for as long as they
+    /// can fire, now that function bodies may have finished.
+    pub(in crate::controller) fn fire_held_join_patterns(&mut self) {
+        // Join Patterns only fire in the order of the `Trace` being replayed.
+        if self.replay.is_some() {
+            return;
+        }
+
+        while let Some(channel_id) = self
+            .limited_join_patterns
+            .iter()
+            .find(|&&jp_id| self.is_alive(jp_id))
+            .and_then(|jp_id| self.channel_sets[jp_id].as_slice().first().copied())
+        {
+            // Other Join Patterns of the channel may have been waiting for
+            // longer, so the usual selection applies.
+            self.handle_join_pattern_firing(channel_id);
+        }
+    }
+
+    /// Return `true` if the given Join Pattern could fire with the pending
+    /// `Message`s, but is at its limit of running function bodies.
+    fn is_held(&self, join_pattern_id: JoinPatternId) -> bool {
+        self.join_patterns
+            .get(&join_pattern_id)
+            .is_some_and(|join_pattern| !join_pattern.is_cancelled())
+            && self.has_messages(join_pattern_id)
+            && self.is_at_limit(join_pattern_id)
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    sync::{mpsc::RecvTimeoutError, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    controller::Controller,
+    junction::{AuditEvent, LatencyStats},
+    queue::{self, PacketReceiver},
+    types::{ids::ChannelId, Packet},
+};
+
+/// Interval at which `ManualController::wait_idle` polls for `Packet`s.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// `Controller` driven by explicit calls rather than a control thread.
+///
+/// Used by `Junction`s created in manual mode, where the user decides when
+/// and for how long `Packet`s are handled, e.g. once per frame of a game loop.
+pub(crate) struct ManualController {
+    state: Mutex<ManualState>,
+}
+
+struct ManualState {
+    controller: Controller,
+    receiver: PacketReceiver,
+    /// `true` once a `Packet::ShutDownRequest` has been handled.
+    stopped: bool,
+}
+
+impl ManualController {
+    pub(crate) fn new(controller: Controller, receiver: PacketReceiver) -> ManualController {
+        ManualController {
+            state: Mutex::new(ManualState {
+                controller,
+                receiver,
+                stopped: false,
+            }),
+        }
+    }
+
+    /// Generate new, *unique* `ChannelId` without a round trip through the
+    /// `Packet` queue, which nobody might be handling at this point.
+    pub(crate) fn new_channel_id(&self) -> ChannelId {
+        self.state.lock().unwrap().controller.new_channel_id()
+    }
+
+    /// Return the statements of a DOT graph of the `Controller`, see
+    /// `Junction::export_dot`.
+    pub(crate) fn export_dot(&self) -> String {
+        self.state.lock().unwrap().controller.export_dot("")
+    }
+
+    /// Return the latencies of the Join Patterns that have fired, see
+    /// `Junction::latency_stats`.
+    pub(crate) fn latency_stats(&self) -> Vec<LatencyStats> {
+        self.state.lock().unwrap().controller.latency_stats()
+    }
+
+    /// Return the latest Join Patterns registered and removed, see
+    /// `Junction::audit_log`.
+    pub(crate) fn audit_log(&self) -> Vec<AuditEvent> {
+        self.state.lock().unwrap().controller.audit_log()
+    }
+
+    /// Return the number of times the Join Patterns of the given name have
+    /// been fired.
+    #[cfg(feature = "testing")]
+    pub(crate) fn fire_count(&self, name: &str) -> u64 {
+        self.state.lock().unwrap().controller.fire_count(name)
+    }
+
+    /// Handle all `Packet`s that are currently queued without blocking.
+    ///
+    /// Return the number of `Packet`s handled.
+    pub(crate) fn poll(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let mut handled = 0;
+        state.controller.handle_deadlines();
+
+        while !state.stopped {
+            match state.receiver.try_recv() {
+                Ok(packet) => {
+                    state.handle(packet);
+                    handled += 1;
+                }
+                Err(_) => break,
+            }
+        }
+
+        handled
+    }
+
+    /// Handle `Packet`s until the `Controller` is idle, see
+    /// `Junction::wait_idle`.
+    pub(crate) fn wait_idle(&self) {
+        while !self.poll_idle() {
+            thread::sleep(IDLE_POLL_INTERVAL);
+        }
+    }
+
+    /// Handle all `Packet`s that are currently queued and return `true` if
+    /// the `Controller` is idle afterwards.
+    pub(crate) fn poll_idle(&self) -> bool {
+        // Checked before polling, since function bodies may send further
+        // `Message`s right until they finish.
+        let done_firing = self.state.lock().unwrap().controller.is_done_firing();
+
+        self.poll() == 0 && done_firing
+    }
+
+    /// Handle `Packet`s as they arrive until the given `Duration` has passed.
+    ///
+    /// Return the number of `Packet`s handled.
+    pub(crate) fn pump_for(&self, duration: Duration) -> usize {
+        let deadline = Instant::now() + duration;
+        let mut state = self.state.lock().unwrap();
+        let mut handled = 0;
+
+        while !state.stopped {
+            state.controller.handle_deadlines();
+
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match state.receiver.recv_timeout(timeout) {
+                Ok(packet) => {
+                    state.handle(packet);
+                    handled += 1;
+                }
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        handled
+    }
+
+    /// Handle all remaining `Packet`s, then join all firing threads.
+    pub(crate) fn stop(self) {
+        self.poll();
+
+        let state = self.state.into_inner().unwrap();
+        state.controller.join_firing_join_patterns();
+    }
+}
+
+impl ManualState {
+    fn handle(&mut self, packet: Packet) {
+        if queue::handling(|| self.controller.handle_packet(packet)).is_break() {
+            self.stopped = true;
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::mpsc::Sender;
+
+use crate::{
+    controller::Controller,
+    join_pattern::RemappedJoinPattern,
+    junction::Registration,
+    queue::PacketSender,
+    types::{ids::ChannelId, Packet},
+};
+
+impl Controller {
+    /// Move all channels, along with their `Message`s, names and Join
+    /// Patterns, to another `Junction`, then acknowledge the request.
+    ///
+    /// Channels are identified by `to_channel_ids[n]` on the other
+    /// `Junction` for the channel with ID `n` here. Join Patterns are
+    /// registered there before any `Message`s are passed on, since
+    /// registrations are handled first, and `Message`s still arriving for
+    /// the channels are passed on from here.
+    pub(in crate::controller) fn handle_merge_request(
+        &mut self,
+        to: PacketSender,
+        to_channel_ids: Vec<ChannelId>,
+        ack: Sender<()>,
+    ) {
+        let remap = |channel_id: ChannelId| to_channel_ids[channel_id.value()];
+
+        let mut jp_ids: Vec<_> = self.join_patterns.keys().copied().collect();
+        jp_ids.sort_unstable();
+        let control_sender = to.control_sender();
+        for jp_id in jp_ids {
+            let join_pattern = self.join_patterns.remove(&jp_id).unwrap();
+
+            control_sender
+                .send(Packet::AddJoinPatternRequest {
+                    join_pattern: Box::new(RemappedJoinPattern::new(join_pattern, remap)),
+                    registration: Registration::now(),
+                })
+                .unwrap_or_else(|e| log::error!("Failed to merge Join Pattern: {e:?}"));
+        }
+        self.join_pattern_last_fired.clear();
+        self.fire_counts.clear();
+        self.latencies.clear();
+        self.channel_sets.clear();
+        self.messages.unregister_all();
+
+        for (channel_id, broadcast) in self.broadcasts.drain() {
+            for subscriber in broadcast.subscribers {
+                to.send(Packet::Subscribe {
+                    channel_id: remap(channel_id),
+                    subscriber: remap(subscriber),
+                    copy: broadcast.copy,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to merge subscriber: {e:?}"));
+            }
+        }
+
+        let mut channel_id = ChannelId::default();
+        for &to_channel_id in to_channel_ids.iter() {
+            self.join_pattern_index.remove(&channel_id);
+
+            if let Some(name) = self.channel_names.remove(&channel_id) {
+                to.send(Packet::NameChannel {
+                    channel_id: to_channel_id,
+                    name,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to merge channel name: {e:?}"));
+            }
+            if let Some(message_type) = self.channel_types.remove(&channel_id) {
+                to.send(Packet::TypeChannel {
+                    channel_id: to_channel_id,
+                    message_type,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to merge channel type: {e:?}"));
+            }
+            if self.closed_channels.remove(&channel_id) {
+                to.send(Packet::CloseChannel {
+                    channel_id: to_channel_id,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to merge closed channel: {e:?}"));
+            }
+            if let Some(opener) = self.gate_openers.remove(&channel_id) {
+                to.send(Packet::GatePatterns {
+                    channel_id: to_channel_id,
+                    opener,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to merge channel gate: {e:?}"));
+            }
+            if let Some(copy) = self.state_channels.remove(&channel_id) {
+                to.send(Packet::RetainChannel {
+                    channel_id: to_channel_id,
+                    copy,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to merge state channel: {e:?}"));
+            }
+            for (sequence, copy) in self.persistent.remove(&channel_id).unwrap_or_default() {
+                to.send(Packet::Persist {
+                    channel_id: to_channel_id,
+                    sequence,
+                    copy,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to merge persistent Message: {e:?}"));
+            }
+
+            for msg in self.messages.take_all(&channel_id) {
+                to.send(Packet::Message {
+                    channel_id: to_channel_id,
+                    msg,
+                })
+                .unwrap_or_else(|e| log::error!("Failed to merge Message: {e:?}"));
+            }
+
+            self.forwards
+                .insert(channel_id, (to.clone(), to_channel_id));
+            channel_id.increment();
+        }
+
+        ack.send(())
+            .map_err(|e| log::error!("Failed to acknowledge MergeRequest: {e:?}"))
+            .unwrap();
+    }
+}
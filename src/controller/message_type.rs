@@ -0,0 +1,34 @@
+use crate::{
+    controller::Controller,
+    types::{ids::ChannelId, Message},
+};
+
+impl Controller {
+    /// Check that the value of the given `Message` is of the type recorded
+    /// for its channel, logging an error otherwise.
+    ///
+    /// Only `Message`s whose type is unknown at compile time, such as those
+    /// sent through `Junction::send_dynamic`, are checked, as the types of
+    /// all others are guaranteed by their channels.
+    pub(in crate::controller) fn has_channel_type(
+        &self,
+        channel_id: ChannelId,
+        msg: &Message,
+    ) -> bool {
+        if !msg.is_dynamic() {
+            return true;
+        }
+
+        match self.channel_types.get(&channel_id) {
+            Some(message_type) if message_type.id != msg.type_id() => {
+                log::error!(
+                    "Dropping a Message of the wrong type on {}, expected `{}`",
+                    self.describe_channel(channel_id),
+                    message_type.name
+                );
+                false
+            }
+            _ => true,
+        }
+    }
+}
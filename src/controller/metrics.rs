@@ -0,0 +1,74 @@
+//! Metrics reported by the `Controller` through the `metrics` facade.
+//!
+//! The following metrics are maintained:
+//!
+//! * `rusty_junctions_messages_total`: Counter of `Message`s received, by
+//!   `channel`.
+//! * `rusty_junctions_fires_total`: Counter of fired Join Patterns, by
+//!   `join_pattern`.
+//! * `rusty_junctions_send_to_fire_seconds`: Histogram of the time between
+//!   sending a `Message` and firing the Join Pattern consuming it, by
+//!   `join_pattern`.
+//! * `rusty_junctions_queue_depth`: Gauge of the `Packet`s waiting in the
+//!   queue into the control thread, by `junction`.
+//!
+//! Channels and Join Patterns are labelled with their names if they have
+//! been given one, and with their IDs otherwise. The `junction` label is the
+//! name of the control thread, see `JunctionBuilder::thread_name`.
+
+use std::time::Duration;
+
+use metrics::{counter, gauge, histogram};
+
+use crate::{
+    controller::Controller,
+    types::ids::{ChannelId, JoinPatternId},
+};
+
+impl Controller {
+    /// Count a `Message` received on the given channel.
+    pub(in crate::controller) fn record_message(&self, channel_id: ChannelId) {
+        counter!("rusty_junctions_messages_total", "channel" => self.channel_label(channel_id))
+            .increment(1);
+    }
+
+    /// Count the firing of the given Join Pattern and record the given
+    /// latencies of the `Message`s it consumes.
+    pub(in crate::controller) fn record_fire(
+        &self,
+        join_pattern_id: JoinPatternId,
+        latencies: &[Duration],
+    ) {
+        let label = self.join_pattern_label(join_pattern_id);
+
+        counter!("rusty_junctions_fires_total", "join_pattern" => label.clone()).increment(1);
+
+        let latency = histogram!("rusty_junctions_send_to_fire_seconds", "join_pattern" => label);
+        latencies.iter().for_each(|&waited| latency.record(waited));
+    }
+
+    /// Report the number of `Packet`s waiting in the queue.
+    pub(in crate::controller) fn record_queue_depth(&self, depth: usize) {
+        let junction = self.options.thread_name.clone().unwrap_or_default();
+
+        gauge!("rusty_junctions_queue_depth", "junction" => junction).set(depth as f64);
+    }
+
+    fn channel_label(&self, channel_id: ChannelId) -> String {
+        match self.channel_names.get(&channel_id) {
+            Some(name) => name.clone(),
+            None => channel_id.value().to_string(),
+        }
+    }
+
+    fn join_pattern_label(&self, join_pattern_id: JoinPatternId) -> String {
+        match self
+            .join_patterns
+            .get(&join_pattern_id)
+            .and_then(|join_pattern| join_pattern.name())
+        {
+            Some(name) => name.to_string(),
+            None => join_pattern_id.value().to_string(),
+        }
+    }
+}
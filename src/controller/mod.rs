@@ -1,29 +1,87 @@
 //! Control structure started by any new `Junction`, running in a background thread
 //! to handle the coordination of Join Pattern creation and execution.
 use std::{
-    collections::HashMap,
-    sync::mpsc::{Receiver, Sender},
-    thread::{self, JoinHandle},
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    sync::{mpsc::Sender, Arc, Mutex},
+    thread::{self, JoinHandle, ThreadId},
+    time::{Duration, Instant},
 };
 
 use crate::{
+    builder::{
+        Backlog, DeadLetter, DeadLetterPolicy, Deadlock, DroppedChannelPolicy,
+        DuplicatePatternPolicy, FireExecutor, IdleStrategy, MatchPolicy, MessageOrdering,
+        PanicPolicy, ThreadSpawner,
+    },
+    channels::GateOpener,
+    clock::Clock,
+    intercept::Interceptors,
     join_pattern::JoinPattern,
+    junction::{AuditEvent, Histogram, IdleSignal},
+    queue::{self, PacketReceiver, PacketSender},
+    trace::Trace,
     types::{
         ids::{ChannelId, JoinPatternId},
-        Message, Packet,
+        CopyMessage, MessageType, Tap,
     },
 };
 
-use bag::Bag;
+#[cfg(feature = "snapshot")]
+use crate::types::Encode;
+
+use broadcast::Broadcast;
+use channel_set::ChannelSet;
 use counter::Counter;
 use inverted_index::InvertedIndex;
+use readiness::PendingMessages;
 
 mod alive;
+mod audit;
+mod backlog;
+mod broadcast;
+mod channel_set;
+mod dead_letter;
+mod deadlock;
+mod dot;
+mod drain;
+mod dropped;
+mod expiry;
 mod fire;
+mod gate;
 mod handle;
 mod handlers;
+mod idle;
+mod intercept;
+mod latency;
+mod limit;
+mod manual;
+mod merge;
+mod message_type;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod panic;
+mod persistent;
+mod poison;
+mod rate;
+mod readiness;
+#[cfg(all(feature = "sched", target_os = "linux"))]
+mod sched;
+mod shard;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+mod tap;
+#[cfg(feature = "async")]
+mod task;
+mod trace;
+mod wait;
 
 pub use handle::ControllerHandle;
+pub(crate) use manual::ManualController;
+pub(crate) use poison::Salvage;
+use rate::TokenBucket;
+pub(crate) use shard::{Router, ShardedController};
+use trace::ReplayState;
 
 /// Struct to handle `Packet`s sent from the user in the background.
 ///
@@ -32,20 +90,28 @@ pub use handle::ControllerHandle;
 /// `Junction` in a separate control thread, where it continuously listens
 /// for `Packet`s sent by user code and reacts accordingly.
 pub(crate) struct Controller {
+    options: ControllerOptions,
     latest_channel_id: ChannelId,
     latest_join_pattern_id: JoinPatternId,
     /// Counter for how many messages have arrived since creation.
     message_counter: Counter,
     /// Collection of all currently available messages.
-    messages: Bag<ChannelId, Message>,
+    messages: PendingMessages,
     /// Collection of all available Join Patterns for the `Junction` associated with
     /// this `Controller`.
     join_patterns: HashMap<JoinPatternId, Box<dyn JoinPattern>>,
+    /// Channels of each Join Pattern, cached when it is registered.
+    channel_sets: HashMap<JoinPatternId, ChannelSet>,
     /// Map of `JoinPatternId`s to the message count at which they were last
     /// fired, `None` if the Join Pattern has never been fired. Used to
     /// determine precedence of Join Patterns that have not been fired in a
     /// while when needing to choose which of the alive Join Patterns to fire.
     join_pattern_last_fired: HashMap<JoinPatternId, Option<Counter>>,
+    /// Number of times each Join Pattern has been fired.
+    fire_counts: HashMap<JoinPatternId, u64>,
+    /// Latencies between sending `Message`s and firing the Join Pattern
+    /// consuming them, for each Join Pattern that has fired.
+    latencies: HashMap<JoinPatternId, Histogram>,
     /// `InvertedIndex` matching `ChannelId`s to all Join Patterns they appear in.
     /// Used to easily determine which Join Patterns are relevant any time a new
     /// message comes in.
@@ -54,20 +120,241 @@ pub(crate) struct Controller {
     /// the `stop` directive is given to the controller, we can join all of
     /// the `JoinHandle`s to ensure the computation being performed by each
     /// thread is given time to complete.
-    firing_join_patterns: Vec<JoinHandle<()>>,
+    firing_join_patterns: Vec<(JoinPatternId, JoinHandle<()>)>,
+    /// Channels that `Message`s have been stored for in the current batch,
+    /// kept across batches so that handling them does not allocate.
+    arrived: Vec<ChannelId>,
+    /// Alive Join Patterns of the channel last checked for firing, kept
+    /// across checks so that checking does not allocate.
+    alive: Vec<JoinPatternId>,
+    /// Join Patterns with a limited number of function bodies running at
+    /// once, which may have to be fired once one of them has finished.
+    limited_join_patterns: HashSet<JoinPatternId>,
+    /// Channels that have been handed over to another shard of a sharded
+    /// `Junction` or migrated to another `Junction`, mapped to the queue of
+    /// their new `Controller` and the `ChannelId` they have there.
+    /// `Message`s still arriving for these channels are passed on.
+    forwards: HashMap<ChannelId, (PacketSender, ChannelId)>,
+    /// Channels that `Message`s with a `Priority` other than the default
+    /// have been sent on, whose pending `Message`s are consumed in order of
+    /// priority.
+    prioritized_channels: HashSet<ChannelId>,
+    /// Observers of the `Message`s arriving on each channel.
+    taps: HashMap<ChannelId, Vec<Tap>>,
+    /// Token buckets of rate limited channels, along with the `Message`s
+    /// held back until there are tokens for them.
+    rate_limits: HashMap<ChannelId, TokenBucket>,
+    /// Names given to channels, used to describe them in diagnostics.
+    channel_names: HashMap<ChannelId, String>,
+    /// Types of the values held by the `Message`s of each channel, used to
+    /// check `Message`s of unknown type and to describe channels.
+    channel_types: HashMap<ChannelId, MessageType>,
+    /// Channels that have been closed, see `SendChannel::close`.
+    closed_channels: HashSet<ChannelId>,
+    /// Subscribers of the broadcast channels, see `Junction::subscribe`.
+    broadcasts: HashMap<ChannelId, Broadcast>,
+    /// Channels retaining their latest `Message`, along with the function
+    /// copying it for the Join Patterns reading it, see
+    /// `Junction::state_channel`.
+    state_channels: HashMap<ChannelId, CopyMessage>,
+    /// Functions copying the persistent `Message`s of each channel, by
+    /// sequence number, see `SendChannel::send_persistent`.
+    persistent: HashMap<ChannelId, HashMap<u64, CopyMessage>>,
+    /// Openers of the gates of channels without Join Patterns, see
+    /// `NoPatternPolicy`.
+    gate_openers: HashMap<ChannelId, GateOpener>,
+    /// Latest Join Patterns registered and removed, oldest first, see
+    /// `Junction::audit_log`.
+    audit_log: VecDeque<AuditEvent>,
+    /// Instants at which `Message`s stored on channels without Join Patterns
+    /// become dead letters, in order of arrival.
+    dead_letter_deadlines: VecDeque<(Instant, ChannelId)>,
+    /// Instants at which stored `Message`s sent with a time to live expire,
+    /// earliest first.
+    expiry_deadlines: BinaryHeap<Reverse<(Instant, ChannelId)>>,
+    /// Signals to set once the `Controller` is idle.
+    idle_signals: Vec<Arc<IdleSignal>>,
+    /// State kept to detect deadlocks, if enabled.
+    deadlocks: DeadlockState,
+    /// State kept to report backlogs, if enabled.
+    backlogs: BacklogState,
+    /// State kept to replay a `Trace`, `None` once it has been replayed or
+    /// if there is none to replay.
+    replay: Option<ReplayState>,
+    /// Channels included in snapshots, with the names they are included
+    /// under and the functions serializing their `Message`s.
+    #[cfg(feature = "snapshot")]
+    snapshot_channels: HashMap<ChannelId, (String, Encode)>,
+    /// Serialized `Message`s restored from a snapshot, by the names of
+    /// channels not yet included in snapshots again.
+    #[cfg(feature = "snapshot")]
+    restored: HashMap<String, Vec<serde_json::Value>>,
+}
+
+/// What a `Controller` keeps track of to detect deadlocked function bodies,
+/// see `JunctionBuilder::detect_deadlocks`.
+struct DeadlockState {
+    /// Channels that `Message`s of waiting callers have been stored for.
+    call_channels: HashSet<ChannelId>,
+    /// Thread of the function body that has last consumed a `Message` of
+    /// each channel.
+    last_consumers: HashMap<ChannelId, ThreadId>,
+    /// Threads of the function bodies in the last reported deadlock.
+    reported: HashSet<ThreadId>,
+    last_check: Instant,
+}
+
+/// What a `Controller` keeps track of to report channels with a backlog,
+/// see `JunctionBuilder::high_water_mark`.
+struct BacklogState {
+    /// Largest number of `Message`s pending at once on each channel.
+    high_water_marks: HashMap<ChannelId, usize>,
+    /// Channels reported since they last had no `Message`s pending.
+    reported: HashSet<ChannelId>,
+    last_check: Instant,
+}
+
+/// Configuration of a `Controller`, set through a `JunctionBuilder`.
+#[derive(Debug, Clone)]
+pub(crate) struct ControllerOptions {
+    /// Name of the control thread.
+    pub(crate) thread_name: Option<String>,
+    /// Factory spawning the control thread.
+    pub(crate) thread_spawner: ThreadSpawner,
+    /// Stack size of the control thread in bytes.
+    pub(crate) stack_size: Option<usize>,
+    /// Niceness of the control thread.
+    #[cfg(all(feature = "sched", target_os = "linux"))]
+    pub(crate) thread_priority: Option<i32>,
+    /// CPUs the control thread may run on.
+    #[cfg(all(feature = "sched", target_os = "linux"))]
+    pub(crate) thread_affinity: Option<Vec<usize>>,
+    /// Maximum number of `Packet`s handled in one batch by the control thread.
+    pub(crate) max_batch: usize,
+    pub(crate) fire_executor: FireExecutor,
+    pub(crate) match_policy: MatchPolicy,
+    pub(crate) idle_strategy: IdleStrategy,
+    pub(crate) message_ordering: MessageOrdering,
+    pub(crate) panic_policy: PanicPolicy,
+    pub(crate) duplicate_pattern_policy: DuplicatePatternPolicy,
+    pub(crate) dead_letter_policy: DeadLetterPolicy,
+    pub(crate) dropped_channel_policy: DroppedChannelPolicy,
+    /// Age at which `Message`s on channels without Join Patterns become
+    /// dead letters.
+    pub(crate) dead_letter_age: Duration,
+    /// `Sender` to pass expired `Message`s on to.
+    pub(crate) expiry_sender: Option<Sender<DeadLetter>>,
+    /// `Sender` to report deadlocks to, if they are to be detected.
+    pub(crate) deadlock_sender: Option<Sender<Deadlock>>,
+    /// Number of pending `Message`s above which a channel is reported.
+    pub(crate) high_water_mark: Option<usize>,
+    /// Age of the oldest pending `Message` above which a channel is
+    /// reported.
+    pub(crate) max_message_age: Option<Duration>,
+    /// `Sender` to report channels with a backlog to.
+    pub(crate) backlog_sender: Option<Sender<Backlog>>,
+    /// Clock driving the timers of the `Controller`.
+    pub(crate) clock: Clock,
+    /// `Trace` to record decisions into.
+    pub(crate) record_trace: Option<Trace>,
+    /// `Trace` whose decisions to take again.
+    pub(crate) replay_trace: Option<Trace>,
+    /// Interceptors of arriving `Message`s, in the order they run.
+    pub(crate) interceptors: Interceptors,
+    /// Number of events kept in the audit log.
+    pub(crate) audit_capacity: usize,
+}
+
+impl Default for ControllerOptions {
+    fn default() -> ControllerOptions {
+        ControllerOptions {
+            thread_name: None,
+            thread_spawner: ThreadSpawner::default(),
+            stack_size: None,
+            #[cfg(all(feature = "sched", target_os = "linux"))]
+            thread_priority: None,
+            #[cfg(all(feature = "sched", target_os = "linux"))]
+            thread_affinity: None,
+            max_batch: 64,
+            fire_executor: FireExecutor::default(),
+            match_policy: MatchPolicy::default(),
+            idle_strategy: IdleStrategy::default(),
+            message_ordering: MessageOrdering::default(),
+            panic_policy: PanicPolicy::default(),
+            duplicate_pattern_policy: DuplicatePatternPolicy::default(),
+            dead_letter_policy: DeadLetterPolicy::default(),
+            dropped_channel_policy: DroppedChannelPolicy::default(),
+            dead_letter_age: Duration::ZERO,
+            expiry_sender: None,
+            deadlock_sender: None,
+            high_water_mark: None,
+            max_message_age: None,
+            backlog_sender: None,
+            clock: Clock::default(),
+            record_trace: None,
+            replay_trace: None,
+            interceptors: Interceptors::default(),
+            audit_capacity: 64,
+        }
+    }
 }
 
 impl Controller {
     pub(crate) fn new() -> Controller {
+        Controller::with_options(ControllerOptions::default())
+    }
+
+    pub(crate) fn with_options(options: ControllerOptions) -> Controller {
         Controller {
+            replay: options
+                .replay_trace
+                .as_ref()
+                .map(|trace| ReplayState::new(trace.decisions())),
+            arrived: Vec::with_capacity(options.max_batch),
+            alive: Vec::new(),
+            options,
             latest_channel_id: ChannelId::default(),
             latest_join_pattern_id: JoinPatternId::default(),
             message_counter: Counter::default(),
-            messages: Bag::new(),
+            messages: PendingMessages::new(),
             join_patterns: HashMap::new(),
+            channel_sets: HashMap::new(),
             join_pattern_last_fired: HashMap::new(),
+            fire_counts: HashMap::new(),
+            latencies: HashMap::new(),
             join_pattern_index: InvertedIndex::new(),
             firing_join_patterns: Vec::new(),
+            limited_join_patterns: HashSet::new(),
+            forwards: HashMap::new(),
+            prioritized_channels: HashSet::new(),
+            taps: HashMap::new(),
+            rate_limits: HashMap::new(),
+            channel_names: HashMap::new(),
+            channel_types: HashMap::new(),
+            closed_channels: HashSet::new(),
+            broadcasts: HashMap::new(),
+            state_channels: HashMap::new(),
+            persistent: HashMap::new(),
+            gate_openers: HashMap::new(),
+            audit_log: VecDeque::new(),
+            dead_letter_deadlines: VecDeque::new(),
+            expiry_deadlines: BinaryHeap::new(),
+            idle_signals: Vec::new(),
+            deadlocks: DeadlockState {
+                call_channels: HashSet::new(),
+                last_consumers: HashMap::new(),
+                reported: HashSet::new(),
+                last_check: Instant::now(),
+            },
+            backlogs: BacklogState {
+                high_water_marks: HashMap::new(),
+                reported: HashSet::new(),
+                last_check: Instant::now(),
+            },
+            #[cfg(feature = "snapshot")]
+            snapshot_channels: HashMap::new(),
+            #[cfg(feature = "snapshot")]
+            restored: HashMap::new(),
         }
     }
 
@@ -77,11 +364,67 @@ impl Controller {
     /// the user of the `Junction` that created this `Controller`. Return a
     /// `ControlThreadHandle` so that this control thread can be joint at any future
     /// point.
-    pub(crate) fn start(
-        self,
-        sender: Sender<Packet>,
-        receiver: Receiver<Packet>,
-    ) -> ControllerHandle {
-        ControllerHandle::new(sender, thread::spawn(move || self.handle_packets(receiver)))
+    ///
+    /// # Panics
+    ///
+    /// Panics if the control thread could not be spawned.
+    pub(crate) fn start(self, sender: PacketSender, receiver: PacketReceiver) -> ControllerHandle {
+        let mut builder = thread::Builder::new();
+        if let Some(name) = &self.options.thread_name {
+            builder = builder.name(name.clone());
+        }
+        if let Some(stack_size) = self.options.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+
+        let salvage = Arc::new(Mutex::new(None));
+        let thread_salvage = salvage.clone();
+        let thread_sender = sender.clone();
+        let spawner = self.options.thread_spawner.clone();
+        let handle = spawner
+            .spawn(
+                builder,
+                Box::new(move || {
+                    #[cfg(all(feature = "sched", target_os = "linux"))]
+                    self.tune_thread();
+
+                    let salvaged = queue::handling(|| self.run(thread_sender, receiver));
+                    *thread_salvage.lock().unwrap() = salvaged;
+                }),
+            )
+            .map_err(|e| log::error!("Failed to spawn control thread: {e:?}"))
+            .unwrap();
+
+        ControllerHandle::new(sender, handle, salvage)
+    }
+
+    /// Describe the given channel by its name, if it has been given one,
+    /// along with its `ChannelId` and the type of its `Message`s, if known.
+    pub(in crate::controller) fn describe_channel(&self, channel_id: ChannelId) -> String {
+        let id = match self.channel_types.get(&channel_id) {
+            Some(message_type) => format!("{channel_id:?}: {}", message_type.name),
+            None => format!("{channel_id:?}"),
+        };
+
+        match self.channel_names.get(&channel_id) {
+            Some(name) => format!("`{name}` ({id})"),
+            None => id,
+        }
+    }
+
+    /// Describe the given Join Pattern by its name, if it has been given
+    /// one, along with its `JoinPatternId`.
+    pub(in crate::controller) fn describe_join_pattern(
+        &self,
+        join_pattern_id: JoinPatternId,
+    ) -> String {
+        match self
+            .join_patterns
+            .get(&join_pattern_id)
+            .and_then(|join_pattern| join_pattern.name())
+        {
+            Some(name) => format!("`{name}` ({join_pattern_id:?})"),
+            None => format!("{join_pattern_id:?}"),
+        }
     }
 }
@@ -0,0 +1,80 @@
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    thread,
+};
+
+use crate::{
+    builder::{PanicPolicy, PanicReport},
+    controller::Controller,
+    queue,
+    types::ids::JoinPatternId,
+};
+
+impl Controller {
+    /// Apply the `PanicPolicy` to the result of a finished function body of
+    /// the given Join Pattern.
+    pub(in crate::controller) fn handle_fired(
+        &self,
+        join_pattern_id: JoinPatternId,
+        result: thread::Result<()>,
+    ) {
+        if let Err(payload) = result {
+            if let PanicPolicy::Ignore = self.options.panic_policy {
+                return;
+            }
+
+            let join_pattern = self.describe_join_pattern(join_pattern_id);
+            log::error!("Function body of Join Pattern {join_pattern} panicked");
+            self.report_panic(Some(join_pattern), payload);
+        }
+    }
+
+    /// Run the given step of the control thread, applying the `PanicPolicy`
+    /// if it panics.
+    ///
+    /// Return `None` if the step panicked and the control thread is to carry
+    /// on regardless.
+    ///
+    /// # Panics
+    ///
+    /// Resumes the panic of the step under `PanicPolicy::Ignore`.
+    pub(in crate::controller) fn guard<T>(
+        &mut self,
+        step: impl FnOnce(&mut Controller) -> T,
+    ) -> Option<T> {
+        let payload = match panic::catch_unwind(AssertUnwindSafe(|| queue::handling(|| step(self))))
+        {
+            Ok(value) => return Some(value),
+            Err(payload) => payload,
+        };
+
+        if let PanicPolicy::Ignore = self.options.panic_policy {
+            panic::resume_unwind(payload);
+        }
+        log::error!("Control thread panicked");
+        self.report_panic(None, payload);
+
+        None
+    }
+
+    /// Abort the process or forward the panic, as set by the `PanicPolicy`,
+    /// once the panic has been logged.
+    fn report_panic(&self, join_pattern: Option<String>, payload: Box<dyn Any + Send>) {
+        match &self.options.panic_policy {
+            PanicPolicy::Ignore | PanicPolicy::LogAndContinue => {}
+            PanicPolicy::Abort => {
+                log::error!("Aborting after panic");
+                std::process::abort();
+            }
+            PanicPolicy::ForwardTo(sender) => {
+                if sender
+                    .send(PanicReport::new(join_pattern, payload))
+                    .is_err()
+                {
+                    log::warn!("Dropping panic, as panics are no longer received");
+                }
+            }
+        }
+    }
+}
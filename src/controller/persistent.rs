@@ -0,0 +1,40 @@
+use crate::{
+    controller::Controller,
+    types::{ids::ChannelId, CopyMessage},
+};
+
+impl Controller {
+    /// Retain the `Message` with the given sequence number on the given
+    /// channel once it arrives, until it is revoked.
+    pub(in crate::controller) fn persist(
+        &mut self,
+        channel_id: ChannelId,
+        sequence: u64,
+        copy: CopyMessage,
+    ) {
+        self.persistent
+            .entry(channel_id)
+            .or_default()
+            .insert(sequence, copy);
+    }
+
+    /// Remove the persistent `Message` with the given sequence number from
+    /// the given channel.
+    pub(in crate::controller) fn revoke(&mut self, channel_id: ChannelId, sequence: u64) {
+        let Some(persistent) = self.persistent.get_mut(&channel_id) else {
+            return;
+        };
+        if persistent.remove(&sequence).is_none() {
+            return;
+        }
+        if persistent.is_empty() {
+            self.persistent.remove(&channel_id);
+        }
+
+        for msg in self.messages.take_all(&channel_id) {
+            if msg.sequence() != Some(sequence) {
+                self.messages.add(channel_id, msg);
+            }
+        }
+    }
+}
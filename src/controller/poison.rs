@@ -0,0 +1,106 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    mem,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::{
+    channels::GateOpener,
+    controller::{Broadcast, Controller, ControllerOptions},
+    join_pattern::JoinPattern,
+    junction::AuditEvent,
+    queue::{PacketReceiver, PacketSender},
+    types::{ids::ChannelId, CopyMessage, MessageType},
+};
+
+/// What is left of a `Controller` whose control thread panicked, from which
+/// `Junction::heal` starts a fresh `Controller`.
+pub(crate) struct Salvage {
+    receiver: PacketReceiver,
+    latest_channel_id: ChannelId,
+    /// Join Patterns in the order they have been added.
+    join_patterns: Vec<Box<dyn JoinPattern>>,
+    channel_names: HashMap<ChannelId, String>,
+    channel_types: HashMap<ChannelId, MessageType>,
+    closed_channels: HashSet<ChannelId>,
+    broadcasts: HashMap<ChannelId, Broadcast>,
+    state_channels: HashMap<ChannelId, CopyMessage>,
+    persistent: HashMap<ChannelId, HashMap<u64, CopyMessage>>,
+    gate_openers: HashMap<ChannelId, GateOpener>,
+    audit_log: VecDeque<AuditEvent>,
+}
+
+impl Controller {
+    /// Handle incoming `Packet`s until a `Packet::ShutDownRequest` has been
+    /// sent, see `Controller::handle_packets`, then join the function bodies
+    /// of all fired Join Patterns.
+    ///
+    /// Should the control thread panic, the queue behind `sender` is
+    /// poisoned and all stored `Message`s are dropped, so that callers
+    /// waiting for a reply are woken with an error. Return what is needed
+    /// to heal the `Junction` in that case.
+    pub(in crate::controller) fn run(
+        mut self,
+        sender: PacketSender,
+        receiver: PacketReceiver,
+    ) -> Option<Salvage> {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.handle_packets(&receiver)));
+
+        if result.is_ok() {
+            self.join_firing_join_patterns();
+            return None;
+        }
+
+        log::error!("Control thread panicked, poisoning the Junction");
+        sender.set_poisoned(true);
+
+        let mut join_patterns: Vec<_> = self.join_patterns.drain().collect();
+        join_patterns.sort_by_key(|(join_pattern_id, _)| *join_pattern_id);
+
+        // Running function bodies are detached rather than joined, as they
+        // may be waiting for replies to `Message`s dropped along with `self`.
+        Some(Salvage {
+            receiver,
+            latest_channel_id: self.latest_channel_id,
+            join_patterns: join_patterns
+                .into_iter()
+                .map(|(_, join_pattern)| join_pattern)
+                .collect(),
+            channel_names: mem::take(&mut self.channel_names),
+            channel_types: mem::take(&mut self.channel_types),
+            closed_channels: mem::take(&mut self.closed_channels),
+            broadcasts: mem::take(&mut self.broadcasts),
+            state_channels: mem::take(&mut self.state_channels),
+            persistent: mem::take(&mut self.persistent),
+            gate_openers: mem::take(&mut self.gate_openers),
+            audit_log: mem::take(&mut self.audit_log),
+        })
+    }
+
+    /// Create a fresh `Controller` with the given options from what is left
+    /// of a poisoned one, keeping its channels, their names and its Join
+    /// Patterns, and return it along with the queue it is to handle.
+    pub(crate) fn revive(
+        options: ControllerOptions,
+        salvage: Salvage,
+    ) -> (Controller, PacketReceiver) {
+        let mut controller = Controller::with_options(options);
+        controller.latest_channel_id = salvage.latest_channel_id;
+        controller.channel_names = salvage.channel_names;
+        controller.channel_types = salvage.channel_types;
+        controller.closed_channels = salvage.closed_channels;
+        controller.broadcasts = salvage.broadcasts;
+        controller.state_channels = salvage.state_channels;
+        controller.persistent = salvage.persistent;
+        controller.gate_openers = salvage.gate_openers;
+        controller.audit_log = salvage.audit_log;
+
+        // Not recorded in the audit log again, as the Join Patterns have
+        // been registered before.
+        for join_pattern in salvage.join_patterns {
+            controller.handle_registration(join_pattern, None);
+        }
+
+        (controller, salvage.receiver)
+    }
+}
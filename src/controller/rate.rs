@@ -0,0 +1,163 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    controller::Controller,
+    junction::Rate,
+    types::{ids::ChannelId, Message},
+};
+
+/// Token bucket of a rate limited channel, see
+/// `Junction::rate_limited_channel`.
+pub(in crate::controller) struct TokenBucket {
+    rate: Rate,
+    tokens: u32,
+    /// Instant the tokens have last been counted at.
+    refilled_at: Instant,
+    /// `Message`s waiting for a token, in order of arrival.
+    held: VecDeque<Message>,
+}
+
+impl TokenBucket {
+    /// Create a full `TokenBucket`.
+    fn new(rate: Rate, now: Instant) -> TokenBucket {
+        TokenBucket {
+            rate,
+            tokens: rate.burst(),
+            refilled_at: now,
+            held: VecDeque::new(),
+        }
+    }
+
+    /// Add the tokens gained since the bucket has last been refilled.
+    fn refill(&mut self, now: Instant) {
+        let interval = self.rate.interval();
+        let gained =
+            now.saturating_duration_since(self.refilled_at).as_nanos() / interval.as_nanos().max(1);
+        let missing = self.rate.burst() - self.tokens;
+
+        if gained >= u128::from(missing) {
+            self.tokens = self.rate.burst();
+            self.refilled_at = now;
+        } else {
+            // Less than `missing`, so it fits into a `u32`.
+            let gained = gained as u32;
+            self.tokens += gained;
+            self.refilled_at += interval * gained;
+        }
+    }
+
+    /// Take a token, if there is one.
+    fn take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+
+        if self.tokens == 0 {
+            return false;
+        }
+        self.tokens -= 1;
+
+        true
+    }
+
+    /// Return the held `Message` that may be released next, if there is a
+    /// token for it.
+    fn release(&mut self, now: Instant) -> Option<Message> {
+        if self.held.is_empty() || !self.take(now) {
+            return None;
+        }
+
+        self.held.pop_front()
+    }
+
+    /// Return the instant at which the next held `Message` may be released,
+    /// `None` if there is none.
+    fn next_release(&self) -> Option<Instant> {
+        if self.held.is_empty() {
+            return None;
+        }
+
+        Some(self.refilled_at + self.rate.interval().max(Duration::from_nanos(1)))
+    }
+}
+
+impl Controller {
+    /// Release the `Message`s arriving on the given channel no faster than
+    /// the given `Rate` allows.
+    pub(in crate::controller) fn rate_limit(&mut self, channel_id: ChannelId, rate: Rate) {
+        self.rate_limits
+            .insert(channel_id, TokenBucket::new(rate, self.options.clock.now()));
+    }
+
+    /// Return the given `Message` if it may be stored right away, otherwise
+    /// hold it back until the rate limit of its channel allows to release
+    /// it.
+    ///
+    /// `Message`s of a channel are released in order of arrival, so none
+    /// is stored while others are still held back.
+    pub(in crate::controller) fn throttle(
+        &mut self,
+        channel_id: ChannelId,
+        msg: Message,
+    ) -> Option<Message> {
+        let now = self.options.clock.now();
+        let Some(bucket) = self.rate_limits.get_mut(&channel_id) else {
+            return Some(msg);
+        };
+
+        if bucket.held.is_empty() && bucket.take(now) {
+            return Some(msg);
+        }
+
+        bucket.held.push_back(msg);
+        log::debug!(
+            "Holding back Message to rate limited channel: {}",
+            self.describe_channel(channel_id)
+        );
+
+        None
+    }
+
+    /// Return the next instant at which a held back `Message` may be
+    /// released, if any and if the clock is not virtual, in which case
+    /// `Message`s are only released once the clock has been advanced.
+    pub(in crate::controller) fn next_release(&self) -> Option<Instant> {
+        if self.options.clock.is_virtual() {
+            return None;
+        }
+
+        self.rate_limits
+            .values()
+            .filter_map(TokenBucket::next_release)
+            .min()
+    }
+
+    /// Store all held back `Message`s that the rate limits of their
+    /// channels allow to release by now, then check for Join Patterns to
+    /// fire.
+    pub(in crate::controller) fn release_throttled(&mut self) {
+        let now = self.options.clock.now();
+        let channel_ids: Vec<ChannelId> = self
+            .rate_limits
+            .iter()
+            .filter(|(_, bucket)| !bucket.held.is_empty())
+            .map(|(channel_id, _)| *channel_id)
+            .collect();
+
+        let mut arrived = Vec::new();
+        for channel_id in channel_ids {
+            while let Some(msg) = self
+                .rate_limits
+                .get_mut(&channel_id)
+                .and_then(|bucket| bucket.release(now))
+            {
+                if self.store_released_message(channel_id, msg) {
+                    arrived.push(channel_id);
+                }
+            }
+        }
+
+        self.handle_arrived_messages(&mut arrived);
+    }
+}
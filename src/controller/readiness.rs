@@ -0,0 +1,204 @@
+//! Pending `Message`s along with how close each Join Pattern is to firing.
+//!
+//! Rather than counting the `Message`s on every channel of a Join Pattern
+//! whenever a `Message` arrives, the `Controller` keeps, for each Join
+//! Pattern, the number of its distinct channels holding enough `Message`s
+//! for it to fire. The number is updated whenever the number of `Message`s
+//! on a channel crosses what one of its Join Patterns needs, so a Join
+//! Pattern is known to be ready once it matches the number of its distinct
+//! channels. All updates happen on the control thread, so plain counters
+//! suffice.
+
+use std::collections::{HashMap, VecDeque};
+
+use bag::Bag;
+
+use crate::types::{
+    ids::{ChannelId, JoinPatternId},
+    Message,
+};
+
+/// How many of the distinct channels of a Join Pattern hold enough
+/// `Message`s for it to fire.
+struct Readiness {
+    satisfied: usize,
+    channels: usize,
+}
+
+/// `Message`s pending on each channel, tracking the `Readiness` of the
+/// registered Join Patterns as `Message`s are added and removed.
+pub(in crate::controller) struct PendingMessages {
+    messages: Bag<ChannelId, Message>,
+    /// Join Patterns of each channel, along with the number of `Message`s
+    /// they need on it.
+    thresholds: HashMap<ChannelId, Vec<(JoinPatternId, usize)>>,
+    readiness: HashMap<JoinPatternId, Readiness>,
+}
+
+impl PendingMessages {
+    pub(in crate::controller) fn new() -> PendingMessages {
+        PendingMessages {
+            messages: Bag::new(),
+            thresholds: HashMap::new(),
+            readiness: HashMap::new(),
+        }
+    }
+
+    /// Start tracking the given Join Pattern over the given channels, a
+    /// channel appearing once per `Message` the Join Pattern needs on it.
+    pub(in crate::controller) fn register(
+        &mut self,
+        join_pattern_id: JoinPatternId,
+        channels: &[ChannelId],
+    ) {
+        let mut readiness = Readiness {
+            satisfied: 0,
+            channels: 0,
+        };
+
+        for (i, channel_id) in channels.iter().enumerate() {
+            // Each channel is only counted at its first occurrence.
+            if channels[..i].contains(channel_id) {
+                continue;
+            }
+
+            let needed = channels[i..].iter().filter(|&c| c == channel_id).count();
+            readiness.channels += 1;
+            if self.messages.count_items(channel_id) >= needed {
+                readiness.satisfied += 1;
+            }
+
+            self.thresholds
+                .entry(*channel_id)
+                .or_default()
+                .push((join_pattern_id, needed));
+        }
+
+        self.readiness.insert(join_pattern_id, readiness);
+    }
+
+    /// Stop tracking the given Join Pattern over the given channels.
+    pub(in crate::controller) fn unregister(
+        &mut self,
+        join_pattern_id: JoinPatternId,
+        channels: &[ChannelId],
+    ) {
+        for channel_id in channels {
+            if let Some(thresholds) = self.thresholds.get_mut(channel_id) {
+                thresholds.retain(|(jp_id, _)| *jp_id != join_pattern_id);
+                if thresholds.is_empty() {
+                    self.thresholds.remove(channel_id);
+                }
+            }
+        }
+
+        self.readiness.remove(&join_pattern_id);
+    }
+
+    /// Stop tracking all Join Patterns.
+    pub(in crate::controller) fn unregister_all(&mut self) {
+        self.thresholds.clear();
+        self.readiness.clear();
+    }
+
+    /// Return `true` if there are enough `Message`s pending on each of the
+    /// channels of the given Join Pattern for it to fire.
+    pub(in crate::controller) fn is_ready(&self, join_pattern_id: JoinPatternId) -> bool {
+        self.readiness
+            .get(&join_pattern_id)
+            .is_some_and(|readiness| readiness.satisfied == readiness.channels)
+    }
+
+    pub(in crate::controller) fn add(&mut self, channel_id: ChannelId, msg: Message) {
+        let before = self.messages.count_items(&channel_id);
+        self.messages.add(channel_id, msg);
+        self.count_changed(channel_id, before, before + 1);
+    }
+
+    pub(in crate::controller) fn retrieve(&mut self, channel_id: &ChannelId) -> Option<Message> {
+        let msg = self.messages.retrieve(channel_id);
+        self.removed_one(*channel_id, msg.is_some());
+
+        msg
+    }
+
+    pub(in crate::controller) fn retrieve_last(
+        &mut self,
+        channel_id: &ChannelId,
+    ) -> Option<Message> {
+        let msg = self.messages.retrieve_last(channel_id);
+        self.removed_one(*channel_id, msg.is_some());
+
+        msg
+    }
+
+    pub(in crate::controller) fn retrieve_max_by_key<P: Ord>(
+        &mut self,
+        channel_id: &ChannelId,
+        priority: impl Fn(&Message) -> P,
+    ) -> Option<Message> {
+        let msg = self.messages.retrieve_max_by_key(channel_id, priority);
+        self.removed_one(*channel_id, msg.is_some());
+
+        msg
+    }
+
+    pub(in crate::controller) fn retrieve_last_max_by_key<P: Ord>(
+        &mut self,
+        channel_id: &ChannelId,
+        priority: impl Fn(&Message) -> P,
+    ) -> Option<Message> {
+        let msg = self.messages.retrieve_last_max_by_key(channel_id, priority);
+        self.removed_one(*channel_id, msg.is_some());
+
+        msg
+    }
+
+    pub(in crate::controller) fn take_all(&mut self, channel_id: &ChannelId) -> VecDeque<Message> {
+        let msgs = self.messages.take_all(channel_id);
+        self.count_changed(*channel_id, msgs.len(), 0);
+
+        msgs
+    }
+
+    pub(in crate::controller) fn peek(&self, channel_id: &ChannelId) -> Option<&Message> {
+        self.messages.peek(channel_id)
+    }
+
+    pub(in crate::controller) fn contains_items(&self, channel_id: &ChannelId) -> bool {
+        self.messages.contains_items(channel_id)
+    }
+
+    pub(in crate::controller) fn count_items(&self, channel_id: &ChannelId) -> usize {
+        self.messages.count_items(channel_id)
+    }
+
+    /// Account for a `Message` having been removed from the given channel,
+    /// if one was.
+    fn removed_one(&mut self, channel_id: ChannelId, removed: bool) {
+        if removed {
+            let after = self.messages.count_items(&channel_id);
+            self.count_changed(channel_id, after + 1, after);
+        }
+    }
+
+    /// Update the `Readiness` of the Join Patterns of the given channel,
+    /// whose number of `Message`s has changed from `before` to `after`.
+    fn count_changed(&mut self, channel_id: ChannelId, before: usize, after: usize) {
+        let Some(thresholds) = self.thresholds.get(&channel_id) else {
+            return;
+        };
+
+        for (join_pattern_id, needed) in thresholds {
+            let Some(readiness) = self.readiness.get_mut(join_pattern_id) else {
+                continue;
+            };
+
+            if before < *needed && *needed <= after {
+                readiness.satisfied += 1;
+            } else if after < *needed && *needed <= before {
+                readiness.satisfied -= 1;
+            }
+        }
+    }
+}
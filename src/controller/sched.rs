@@ -0,0 +1,43 @@
+use std::{io, mem};
+
+use crate::controller::Controller;
+
+impl Controller {
+    /// Set the niceness and CPU affinity of the calling control thread, as
+    /// far as they have been configured.
+    pub(in crate::controller) fn tune_thread(&self) {
+        if let Some(niceness) = self.options.thread_priority {
+            // SAFETY: `gettid` takes no arguments and cannot fail.
+            let thread_id = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+
+            // SAFETY: Only the niceness of the calling thread is changed.
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, thread_id, niceness) } != 0 {
+                log::warn!(
+                    "Failed to set priority of control thread: {:?}",
+                    io::Error::last_os_error()
+                );
+            }
+        }
+
+        if let Some(cpus) = &self.options.thread_affinity {
+            // SAFETY: An all-zero `cpu_set_t` is the empty set of CPUs.
+            let mut cpu_set: libc::cpu_set_t = unsafe { mem::zeroed() };
+            for &cpu in cpus {
+                // SAFETY: The index has been checked by
+                // `JunctionBuilder::thread_affinity` to be in range.
+                unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+            }
+
+            // SAFETY: `cpu_set` is a valid `cpu_set_t` of the given size, and
+            // only the affinity of the calling thread is changed.
+            if unsafe { libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &cpu_set) }
+                != 0
+            {
+                log::warn!(
+                    "Failed to set affinity of control thread: {:?}",
+                    io::Error::last_os_error()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Sharded control structure for `Junction`s with many channels.
+//!
+//! The channels of a sharded `Junction` are partitioned across several
+//! `Controller`s, called shards, each running in its own control thread.
+//! Channels start out distributed round-robin and a `Router` sends each
+//! `Message` to the shard currently owning its channel.
+//!
+//! A Join Pattern can only be matched atomically if all of its channels are
+//! owned by the same shard. Registrations of new Join Patterns are therefore
+//! handled by a coordinator thread, which keeps track of the groups of
+//! channels connected through Join Patterns. If a new Join Pattern connects
+//! groups owned by different shards, all but one of these groups are handed
+//! over to the remaining shard, together with their `Message`s and Join
+//! Patterns, before the new Join Pattern is added there. Since `Message`s
+//! can overtake the registration of a Join Pattern this way, the shard checks
+//! the `Message`s it already stores against the new Join Pattern.
+//!
+//! A shard that has handed a group of channels over forwards any `Message`
+//! for them that still reaches it. `Message`s sent during a hand-off may
+//! therefore be matched in a different order than they were sent in.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    sync::{mpsc::channel, mpsc::SendError, Mutex, RwLock},
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    controller::{Controller, ControllerHandle, ControllerOptions},
+    join_pattern::JoinPattern,
+    junction::Registration,
+    queue::{packet_channel, PacketReceiver, PacketSender},
+    types::{ids::ChannelId, Packet},
+};
+
+/// Routing table of a sharded `Junction`, mapping channels to their shards.
+pub(crate) struct Router {
+    /// Queues of the shards, without routing.
+    shards: Vec<PacketSender>,
+    owners: RwLock<HashMap<ChannelId, usize>>,
+    latest_channel_id: Mutex<ChannelId>,
+}
+
+impl Router {
+    /// Generate new, *unique* `ChannelId` and assign it to the next shard.
+    pub(crate) fn new_channel_id(&self) -> ChannelId {
+        let mut latest_channel_id = self.latest_channel_id.lock().unwrap();
+        let channel_id = *latest_channel_id;
+        latest_channel_id.increment();
+
+        self.owners
+            .write()
+            .unwrap()
+            .insert(channel_id, channel_id.value() % self.shards.len());
+
+        channel_id
+    }
+
+    /// Send a `Packet::Message`, `Packet::Messages`, `Packet::CloseChannel`,
+    /// `Packet::Subscribe`, `Packet::ChannelDropped`, `Packet::NameChannel`,
+    /// `Packet::Persist`, `Packet::Revoke`, `Packet::RetainChannel`,
+    /// `Packet::GatePatterns`, `Packet::GateRequest`, `Packet::TypeChannel`,
+    /// `Packet::TapRequest` or `Packet::MigrateRequest` to the shard owning
+    /// its channel.
+    ///
+    /// Any other `Packet` is sent to the first shard.
+    pub(crate) fn send(&self, packet: Packet) -> Result<(), SendError<Packet>> {
+        let shard = match &packet {
+            Packet::Message { channel_id, .. }
+            | Packet::Messages { channel_id, .. }
+            | Packet::CloseChannel { channel_id }
+            | Packet::Subscribe { channel_id, .. }
+            | Packet::ChannelDropped { channel_id }
+            | Packet::NameChannel { channel_id, .. }
+            | Packet::Persist { channel_id, .. }
+            | Packet::Revoke { channel_id, .. }
+            | Packet::RetainChannel { channel_id, .. }
+            | Packet::GatePatterns { channel_id, .. }
+            | Packet::GateRequest { channel_id, .. }
+            | Packet::TypeChannel { channel_id, .. }
+            | Packet::TapRequest { channel_id, .. }
+            | Packet::MigrateRequest { channel_id, .. } => self.owner(*channel_id),
+            _ => 0,
+        };
+
+        self.shards[shard].send(packet)
+    }
+
+    /// Return the index of the shard owning the given channel.
+    fn owner(&self, channel_id: ChannelId) -> usize {
+        self.owners
+            .read()
+            .unwrap()
+            .get(&channel_id)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Make the shard owning the channel `with` the owner of the channel
+    /// `channel_id` as well.
+    pub(crate) fn colocate(&self, channel_id: ChannelId, with: ChannelId) {
+        self.reassign(&[channel_id], self.owner(with));
+    }
+
+    /// Make the given shard the owner of all given channels.
+    fn reassign(&self, channels: &[ChannelId], shard: usize) {
+        let mut owners = self.owners.write().unwrap();
+        channels.iter().for_each(|channel_id| {
+            owners.insert(*channel_id, shard);
+        });
+    }
+}
+
+/// Shards and coordinator of a sharded `Junction`.
+pub(crate) struct ShardedController {
+    router: Arc<Router>,
+    shard_handles: Vec<ControllerHandle>,
+    coordinator_sender: PacketSender,
+    coordinator_handle: JoinHandle<()>,
+}
+
+impl ShardedController {
+    /// Start the given number of shards with the given `ControllerOptions`
+    /// and the coordinator, spawning all of their threads through the
+    /// `ThreadSpawner` of the options.
+    ///
+    /// Return the `ShardedController` along with a `PacketSender` routing
+    /// `Message`s to the shards and registering Join Patterns with the
+    /// coordinator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero or if the coordinator thread could not be
+    /// spawned.
+    pub(crate) fn start(
+        shards: usize,
+        options: &ControllerOptions,
+    ) -> (ShardedController, PacketSender) {
+        assert!(shards > 0, "A sharded Junction needs at least one shard");
+
+        let (shard_senders, shard_handles): (Vec<_>, Vec<_>) = (0..shards)
+            .map(|_| {
+                let (sender, receiver) = packet_channel(None);
+                let sender = sender.with_thread_spawner(options.thread_spawner.clone());
+                let handle =
+                    Controller::with_options(options.clone()).start(sender.clone(), receiver);
+
+                (sender, handle)
+            })
+            .unzip();
+
+        let router = Arc::new(Router {
+            shards: shard_senders,
+            owners: RwLock::new(HashMap::new()),
+            latest_channel_id: Mutex::new(ChannelId::default()),
+        });
+
+        let (coordinator_sender, coordinator_receiver) = packet_channel(None);
+        let coordinator = Coordinator::new(router.clone());
+        let coordinator_handle = options
+            .thread_spawner
+            .spawn(
+                thread::Builder::new(),
+                Box::new(move || coordinator.run(coordinator_receiver)),
+            )
+            .map_err(|e| log::error!("Failed to spawn coordinator thread: {e:?}"))
+            .unwrap();
+
+        let sender = coordinator_sender
+            .clone()
+            .with_router(router.clone())
+            .with_thread_spawner(options.thread_spawner.clone());
+
+        (
+            ShardedController {
+                router,
+                shard_handles,
+                coordinator_sender,
+                coordinator_handle,
+            },
+            sender,
+        )
+    }
+
+    /// Generate new, *unique* `ChannelId`.
+    pub(crate) fn new_channel_id(&self) -> ChannelId {
+        self.router.new_channel_id()
+    }
+
+    /// Move the new channel `channel_id`, which has not been used yet, to the
+    /// shard owning the channel `with`.
+    pub(crate) fn colocate(&self, channel_id: ChannelId, with: ChannelId) {
+        self.router.colocate(channel_id, with);
+    }
+
+    /// Return the queues of all shards, without routing.
+    pub(crate) fn shard_senders(&self) -> Vec<PacketSender> {
+        self.router.shards.clone()
+    }
+
+    /// Stop the coordinator, then all shards, joining their threads.
+    pub(crate) fn stop(self) {
+        self.coordinator_sender
+            .control_sender()
+            .send(Packet::ShutDownRequest)
+            .map_err(|e| log::error!("Failed to send ShutDownRequest: {e:?}"))
+            .unwrap();
+        self.coordinator_handle
+            .join()
+            .map_err(|_| log::error!("Failed to join the coordinator thread"))
+            .unwrap();
+
+        self.shard_handles
+            .into_iter()
+            .for_each(|mut handle| handle.stop());
+    }
+}
+
+/// Places new Join Patterns on shards, handing channels over as needed.
+struct Coordinator {
+    router: Arc<Router>,
+    /// Group that each channel appearing in any Join Pattern belongs to.
+    groups: HashMap<ChannelId, usize>,
+    /// Channels of each group.
+    members: HashMap<usize, Vec<ChannelId>>,
+    latest_group: usize,
+}
+
+impl Coordinator {
+    fn new(router: Arc<Router>) -> Coordinator {
+        Coordinator {
+            router,
+            groups: HashMap::new(),
+            members: HashMap::new(),
+            latest_group: 0,
+        }
+    }
+
+    /// Handle Join Pattern registrations until asked to shut down.
+    ///
+    /// Only the control queue is listened on, so the request to shut down
+    /// has to be sent through it as well.
+    fn run(mut self, receiver: PacketReceiver) {
+        while let Ok(packet) = receiver.recv_control() {
+            match packet {
+                Packet::AddJoinPatternRequest {
+                    join_pattern,
+                    registration,
+                } => self.place(join_pattern, registration),
+                Packet::ShutDownRequest => break,
+                _ => log::error!("Coordinator can only handle Join Pattern registrations"),
+            }
+        }
+    }
+
+    /// Move all channels of the Join Pattern to a single shard and add it there.
+    ///
+    /// The shard owning the largest group among the channels is chosen, so
+    /// that as few `Message`s and Join Patterns as possible are handed over.
+    fn place(&mut self, join_pattern: Box<dyn JoinPattern + Send>, registration: Registration) {
+        let mut groups: Vec<usize> = join_pattern
+            .channels()
+            .into_iter()
+            .map(|channel_id| self.group(channel_id))
+            .collect();
+        groups.sort_unstable();
+        groups.dedup();
+
+        let target_group = *groups
+            .iter()
+            .max_by_key(|group| self.members[group].len())
+            .unwrap();
+        let target = self.router.owner(self.members[&target_group][0]);
+
+        for group in groups.iter().filter(|&&group| group != target_group) {
+            let shard = self.router.owner(self.members[group][0]);
+            if shard != target {
+                self.hand_off(self.members[group].clone(), shard, target);
+            }
+        }
+
+        for group in groups.into_iter().filter(|&group| group != target_group) {
+            let channels = self.members.remove(&group).unwrap();
+            channels.iter().for_each(|channel_id| {
+                self.groups.insert(*channel_id, target_group);
+            });
+            self.members
+                .get_mut(&target_group)
+                .unwrap()
+                .extend(channels);
+        }
+
+        // Sent as an adoption, so that `Message`s which overtook the
+        // registration on their way to the shard can still fire it.
+        self.router.shards[target]
+            .send(Packet::Adopt {
+                channels: Vec::new(),
+                messages: Vec::new(),
+                join_patterns: vec![join_pattern],
+                channel_names: Vec::new(),
+                registration: Some(Box::new(registration)),
+            })
+            .map_err(|e| log::error!("Failed to send Adopt: {e:?}"))
+            .unwrap();
+    }
+
+    /// Return the group of the given channel, creating a new one if needed.
+    fn group(&mut self, channel_id: ChannelId) -> usize {
+        if let Some(group) = self.groups.get(&channel_id) {
+            return *group;
+        }
+
+        let group = self.latest_group;
+        self.latest_group += 1;
+
+        self.groups.insert(channel_id, group);
+        self.members.insert(group, vec![channel_id]);
+
+        group
+    }
+
+    /// Hand the given channels over between shards and update the routing.
+    ///
+    /// The routing is only updated once the old shard has sent everything
+    /// over, so that no `Message` sent directly to the new shard afterwards
+    /// can arrive there before the hand-off.
+    fn hand_off(&self, channels: Vec<ChannelId>, from: usize, to: usize) {
+        log::debug!("Handing {channels:?} over from shard {from} to shard {to}");
+        let (ack_sender, ack_receiver) = channel::<()>();
+
+        self.router.shards[from]
+            .send(Packet::HandOffRequest {
+                channels: channels.clone(),
+                to: Box::new(self.router.shards[to].clone()),
+                ack: ack_sender,
+            })
+            .map_err(|e| log::error!("Failed to send HandOffRequest: {e:?}"))
+            .unwrap();
+
+        ack_receiver
+            .recv()
+            .map_err(|e| log::error!("Failed to receive HandOffRequest acknowledgement: {e:?}"))
+            .unwrap();
+
+        self.router.reassign(&channels, to);
+    }
+}
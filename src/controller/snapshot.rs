@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    controller::Controller,
+    types::{ids::ChannelId, Decode, Encode},
+};
+
+impl Controller {
+    /// Include the `Message`s of the given channel in snapshots under the
+    /// given name, storing the `Message`s restored for it, if any.
+    pub(in crate::controller) fn add_snapshot_channel(
+        &mut self,
+        channel_id: ChannelId,
+        name: String,
+        encode: Encode,
+        decode: Decode,
+    ) {
+        for value in self.restored.remove(&name).unwrap_or_default() {
+            match decode(value) {
+                Some(msg) => self.handle_message(channel_id, msg),
+                None => log::warn!("Dropping restored Message to `{name}` that is not valid"),
+            }
+        }
+
+        self.snapshot_channels.insert(channel_id, (name, encode));
+    }
+
+    /// Return the serialized pending `Message`s of all channels included in
+    /// snapshots, by channel name, in order of arrival.
+    ///
+    /// `Message`s restored for channels that are not included in snapshots
+    /// again are kept, so that they are not lost by taking another snapshot.
+    pub(in crate::controller) fn snapshot(&mut self) -> HashMap<String, Vec<Value>> {
+        let mut snapshot = self.restored.clone();
+
+        for (channel_id, (name, encode)) in &self.snapshot_channels {
+            let msgs = self.messages.take_all(channel_id);
+            let values = msgs
+                .iter()
+                .filter_map(|msg| {
+                    let value = encode(msg);
+                    if value.is_none() {
+                        log::warn!("Leaving Message to `{name}` out of snapshot");
+                    }
+                    value
+                })
+                .collect();
+            msgs.into_iter()
+                .for_each(|msg| self.messages.add(*channel_id, msg));
+
+            snapshot.insert(name.clone(), values);
+        }
+
+        snapshot
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{
+    controller::Controller,
+    types::{ids::ChannelId, Message, Tap},
+};
+
+impl Controller {
+    /// Pass every `Message` arriving on the given channel to the given `Tap`
+    /// from now on.
+    pub(in crate::controller) fn add_tap(&mut self, channel_id: ChannelId, tap: Tap) {
+        self.taps.entry(channel_id).or_default().push(tap);
+    }
+
+    /// Pass the given `Message` to all `Tap`s of the given channel, removing
+    /// those that no longer observe the channel.
+    pub(in crate::controller) fn notify_taps(&mut self, channel_id: ChannelId, msg: &Message) {
+        if let Some(taps) = self.taps.get_mut(&channel_id) {
+            taps.retain_mut(|tap| tap(msg));
+
+            if taps.is_empty() {
+                self.taps.remove(&channel_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::{
+    mpsc::{self, TryRecvError},
+    Arc,
+};
+
+use tokio::{runtime::Handle, sync::Notify, time};
+
+use crate::{
+    controller::{Controller, ControllerHandle},
+    queue::{self, PacketReceiver, PacketSender},
+};
+
+impl Controller {
+    /// Spawn a task handling incoming `Packet`s onto the given runtime.
+    ///
+    /// Like `Controller::start`, but without a dedicated control thread.
+    /// The `PacketSender`s of the `Junction` have to notify the given
+    /// `Notify` whenever they send a `Packet`, since the task cannot block
+    /// on the queue.
+    pub(crate) fn spawn_on(
+        self,
+        handle: &Handle,
+        sender: PacketSender,
+        receiver: PacketReceiver,
+        notify: Arc<Notify>,
+    ) -> ControllerHandle {
+        // Dropped once the task has finished or has been dropped by a
+        // runtime shutting down, which `ControllerHandle::stop` waits for.
+        let (done_sender, done_receiver) = mpsc::channel::<()>();
+
+        handle.spawn(async move {
+            self.handle_packets_async(receiver, notify).await;
+            drop(done_sender);
+        });
+
+        ControllerHandle::for_task(sender, done_receiver)
+    }
+
+    /// Handle incoming `Packet`s until a `Packet::ShutDownRequest` has been
+    /// sent, see `Controller::handle_packets`.
+    ///
+    /// Instead of blocking on the queue, the task waits to be notified of
+    /// new `Packet`s, or for the next deadline to pass.
+    async fn handle_packets_async(mut self, receiver: PacketReceiver, notify: Arc<Notify>) {
+        loop {
+            let packet = match receiver.try_recv() {
+                Ok(packet) => packet,
+                Err(TryRecvError::Disconnected) => break,
+                Err(TryRecvError::Empty) => {
+                    let Some(deadline) = self.next_deadline() else {
+                        notify.notified().await;
+                        continue;
+                    };
+
+                    if time::timeout_at(deadline.into(), notify.notified())
+                        .await
+                        .is_ok()
+                    {
+                        continue;
+                    }
+                    self.guard(Controller::handle_deadlines);
+
+                    if self.idle_signals.is_empty() || !self.is_done_firing() {
+                        continue;
+                    }
+                    match receiver.try_recv() {
+                        Ok(packet) => packet,
+                        Err(_) => {
+                            self.signal_idle();
+                            continue;
+                        }
+                    }
+                }
+            };
+
+            let handled = self.guard(|controller| controller.handle_batch(packet, &receiver));
+            if handled.is_some_and(|flow| flow.is_break()) {
+                break;
+            }
+        }
+
+        // Dropping the `Controller` drops the handles to channels held by
+        // its `Message`s and Join Patterns, see `queue::handling`.
+        queue::handling(move || self.join_firing_join_patterns());
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+
+use crate::{
+    controller::Controller,
+    trace::Decision,
+    types::{
+        ids::{ChannelId, JoinPatternId},
+        Message,
+    },
+};
+
+/// What a `Controller` replaying a `Trace` keeps track of, see
+/// `JunctionBuilder::replay_trace`.
+pub(in crate::controller) struct ReplayState {
+    /// Decisions still to be taken, in order.
+    expected: VecDeque<Decision>,
+    /// `Message`s that have arrived earlier than in the `Trace`, in order of
+    /// arrival.
+    held: VecDeque<(ChannelId, Message)>,
+}
+
+impl ReplayState {
+    pub(in crate::controller) fn new(decisions: Vec<Decision>) -> ReplayState {
+        ReplayState {
+            expected: decisions.into(),
+            held: VecDeque::new(),
+        }
+    }
+}
+
+impl Controller {
+    /// Record the arrival of a `Message` on the given channel, if recording.
+    pub(in crate::controller) fn record_arrival(&self, channel_id: ChannelId) {
+        if let Some(trace) = &self.options.record_trace {
+            trace.record(Decision::Arrived {
+                channel: channel_id,
+            });
+        }
+    }
+
+    /// Record the firing of the given Join Pattern on the given `Message`s,
+    /// if recording.
+    pub(in crate::controller) fn record_fired(
+        &self,
+        join_pattern_id: JoinPatternId,
+        channels: &[ChannelId],
+        messages: &[Message],
+    ) {
+        if let Some(trace) = &self.options.record_trace {
+            trace.record(Decision::Fired {
+                join_pattern: join_pattern_id.value(),
+                consumed: channels
+                    .iter()
+                    .zip(messages)
+                    .map(|(channel_id, msg)| (*channel_id, msg.sequence()))
+                    .collect(),
+            });
+        }
+    }
+
+    /// Return `true` if the `Controller` is still replaying a `Trace`.
+    pub(in crate::controller) fn is_replaying(&self) -> bool {
+        self.replay
+            .as_ref()
+            .is_some_and(|replay| !replay.expected.is_empty())
+    }
+
+    /// Return the given `Message` if it arrives on the given channel when
+    /// the `Trace` being replayed expects it to, holding it back otherwise.
+    pub(in crate::controller) fn replay_arrival(
+        &mut self,
+        channel_id: ChannelId,
+        msg: Message,
+    ) -> Option<Message> {
+        if !self.is_replaying() {
+            return Some(msg);
+        }
+
+        let replay = self.replay.as_mut().unwrap();
+        if replay.expected.front()
+            == Some(&Decision::Arrived {
+                channel: channel_id,
+            })
+        {
+            replay.expected.pop_front();
+            return Some(msg);
+        }
+
+        log::debug!(
+            "Holding back Message to {} arriving earlier than in the Trace",
+            self.describe_channel(channel_id)
+        );
+        self.replay
+            .as_mut()
+            .unwrap()
+            .held
+            .push_back((channel_id, msg));
+        None
+    }
+
+    /// Take the decisions of the `Trace` being replayed for as long as
+    /// possible, storing held back `Message`s and firing Join Patterns in
+    /// the order of the `Trace`.
+    ///
+    /// Once all decisions have been taken, the remaining held back
+    /// `Message`s are handled as if they had just arrived.
+    pub(in crate::controller) fn advance_replay(&mut self) {
+        while let Some(replay) = self.replay.as_mut() {
+            match replay.expected.front().cloned() {
+                Some(Decision::Arrived { channel }) => {
+                    let Some(position) = replay.held.iter().position(|(c, _)| *c == channel) else {
+                        return;
+                    };
+                    let (channel_id, msg) = replay.held.remove(position).unwrap();
+
+                    // Takes the decision off the `Trace` through
+                    // `replay_arrival`.
+                    self.store_message(channel_id, msg);
+                }
+                Some(Decision::Fired { join_pattern, .. }) => {
+                    let jp_id = JoinPatternId::new(join_pattern);
+                    if !self.is_alive(jp_id) {
+                        return;
+                    }
+                    if let Some(replay) = self.replay.as_mut() {
+                        replay.expected.pop_front();
+                    }
+
+                    self.fire_join_pattern(jp_id);
+                    self.reset_last_fired(jp_id);
+                }
+                None => {
+                    log::info!("Finished replaying Trace");
+                    let held = self.replay.take().unwrap().held;
+
+                    for (channel_id, msg) in held {
+                        self.handle_message(channel_id, msg);
+                    }
+                }
+            }
+        }
+    }
+}
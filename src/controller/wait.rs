@@ -0,0 +1,56 @@
+use std::{
+    hint,
+    sync::mpsc::{RecvTimeoutError, TryRecvError},
+    time::Instant,
+};
+
+use crate::{builder::IdleStrategy, queue::PacketReceiver, types::Packet};
+
+impl IdleStrategy {
+    /// Wait for the next `Packet` on the given queue, or until the given
+    /// deadline of the `Controller` has passed.
+    ///
+    /// Return `RecvTimeoutError::Timeout` once the `Controller` is to deal
+    /// with its deadlines, which may be later than the given deadline for
+    /// `IdleStrategy::Tick`.
+    pub(in crate::controller) fn wait(
+        &self,
+        receiver: &PacketReceiver,
+        deadline: Option<Instant>,
+    ) -> Result<Packet, RecvTimeoutError> {
+        match *self {
+            IdleStrategy::Block => block(receiver, deadline),
+            IdleStrategy::SpinThenPark { spins } => {
+                for _ in 0..spins {
+                    match receiver.try_recv() {
+                        Ok(packet) => return Ok(packet),
+                        Err(TryRecvError::Disconnected) => {
+                            return Err(RecvTimeoutError::Disconnected)
+                        }
+                        Err(TryRecvError::Empty) => {}
+                    }
+
+                    if deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                    hint::spin_loop();
+                }
+
+                block(receiver, deadline)
+            }
+            IdleStrategy::Tick(period) => {
+                let next_tick = Instant::now() + period;
+                block(receiver, deadline.map(|deadline| deadline.max(next_tick)))
+            }
+        }
+    }
+}
+
+/// Block on the given queue until the next `Packet` arrives or the given
+/// deadline has passed.
+fn block(receiver: &PacketReceiver, deadline: Option<Instant>) -> Result<Packet, RecvTimeoutError> {
+    match deadline {
+        Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+        None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    }
+}
@@ -0,0 +1,181 @@
+//! Join Patterns declared at runtime rather than through the generic
+//! builder API.
+//!
+//! A `PatternSpec` lists the channels of a Join Pattern by the names they
+//! have been published under in a `Registry`, along with a type-erased
+//! handler receiving one message per channel. This allows plugin systems and
+//! scripting layers to declare Join Patterns on channels whose types they do
+//! not know at compile time.
+//!
+//! The handler receives the messages boxed, in the order of the channels:
+//!
+//! - The message `T` of a `SendChannel<T>`.
+//! - A `std::sync::mpsc::Sender<R>` for the reply of a `RecvChannel<R>`.
+//! - A tuple `(T, Sender<R>)` of the message and the reply of a
+//!   `BidirChannel<T, R>`.
+//!
+//! ```
+//! use rusty_junctions::{
+//!     dynamic::PatternSpec,
+//!     registry::{ChannelRef, Registry},
+//!     Junction,
+//! };
+//! use std::sync::mpsc::Sender;
+//!
+//! let j = Junction::new();
+//! let orders = j.send_channel::<String>();
+//! let stock = j.send_channel::<u32>();
+//! let confirm = j.recv_channel::<String>();
+//!
+//! let registry = Registry::new();
+//! registry.publish("orders", orders.clone());
+//! registry.publish("stock", stock.clone());
+//! registry.publish("confirm", confirm.clone());
+//!
+//! // Read from a plugin's configuration, for instance.
+//! let channels = ["orders", "stock", "confirm"].map(ChannelRef::new).to_vec();
+//! let spec = PatternSpec::new(channels, |mut messages| {
+//!     let reply = messages.pop().unwrap().downcast::<Sender<String>>().unwrap();
+//!     let stock = messages.pop().unwrap().downcast::<u32>().unwrap();
+//!     let order = messages.pop().unwrap().downcast::<String>().unwrap();
+//!     reply.send(format!("{order} of {stock}")).unwrap();
+//! });
+//! j.register_pattern(spec, &registry).unwrap();
+//!
+//! orders.send("apples".to_string()).unwrap();
+//! stock.send(12).unwrap();
+//! assert_eq!(confirm.recv().unwrap(), "apples of 12");
+//! ```
+//!
+//! Values can be sent on published channels the same way, through
+//! `Junction::send_dynamic`. As their type is only known at runtime, the
+//! control thread checks it against the type of the channel, dropping the
+//! value with an error naming the expected type on a mismatch.
+
+use std::{any::Any, error::Error, fmt, sync::Arc, thread::JoinHandle};
+
+use crate::{
+    builder::ThreadSpawner,
+    cancel::JunctionClosed,
+    join_pattern::JoinPattern,
+    registry::{ChannelRef, LookupError},
+    types::{ids::ChannelId, Message},
+};
+
+/// Type-erased function body of a Join Pattern declared at runtime, taking
+/// the boxed messages of its channels in order.
+pub type Handler = Box<dyn Fn(Vec<Box<dyn Any + Send>>) + Send + Sync>;
+
+/// Description of a Join Pattern, to be declared through
+/// `Junction::register_pattern`.
+pub struct PatternSpec {
+    channels: Vec<ChannelRef>,
+    handler: Handler,
+}
+
+impl PatternSpec {
+    /// Describe a Join Pattern on the given channels, firing the given
+    /// handler with one message per channel.
+    pub fn new(
+        channels: Vec<ChannelRef>,
+        handler: impl Fn(Vec<Box<dyn Any + Send>>) + Send + Sync + 'static,
+    ) -> PatternSpec {
+        PatternSpec {
+            channels,
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Return the channels of the Join Pattern.
+    pub fn channels(&self) -> &[ChannelRef] {
+        &self.channels
+    }
+
+    /// Split the description into its channels and handler.
+    pub(crate) fn into_parts(self) -> (Vec<ChannelRef>, Handler) {
+        (self.channels, self.handler)
+    }
+}
+
+impl fmt::Debug for PatternSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PatternSpec")
+            .field("channels", &self.channels)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by `Junction::register_pattern` and
+/// `Junction::send_dynamic`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DynamicError {
+    /// The `PatternSpec` does not list any channels.
+    NoChannels,
+    /// The channel of the given name could not be looked up.
+    Lookup {
+        /// Name of the channel.
+        name: String,
+        /// Reason the lookup failed.
+        error: LookupError,
+    },
+    /// The channel of the given name belongs to another `Junction`.
+    ForeignChannel {
+        /// Name of the channel.
+        name: String,
+    },
+    /// The `Junction` has shut down.
+    Closed(JunctionClosed),
+}
+
+impl fmt::Display for DynamicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicError::NoChannels => write!(f, "Join Pattern has no channels"),
+            DynamicError::Lookup { name, error } => {
+                write!(f, "failed to look up channel `{name}`: {error}")
+            }
+            DynamicError::ForeignChannel { name } => {
+                write!(f, "channel `{name}` belongs to another Junction")
+            }
+            DynamicError::Closed(closed) => write!(f, "{closed}"),
+        }
+    }
+}
+
+impl Error for DynamicError {}
+
+/// Join Pattern declared at runtime through a `PatternSpec`.
+pub(crate) struct DynamicJoinPattern {
+    channels: Vec<ChannelId>,
+    handler: Arc<Handler>,
+}
+
+impl DynamicJoinPattern {
+    pub(crate) fn new(channels: Vec<ChannelId>, handler: Handler) -> DynamicJoinPattern {
+        DynamicJoinPattern {
+            channels,
+            handler: Arc::new(handler),
+        }
+    }
+}
+
+impl JoinPattern for DynamicJoinPattern {
+    fn channels(&self) -> Vec<ChannelId> {
+        self.channels.clone()
+    }
+
+    fn fire(&self, messages: Vec<Message>) -> JoinHandle<()> {
+        self.fire_with(messages, &ThreadSpawner::default())
+    }
+
+    fn fire_with(&self, messages: Vec<Message>, spawner: &ThreadSpawner) -> JoinHandle<()> {
+        let handler = Arc::clone(&self.handler);
+
+        spawner.spawn_body(move || {
+            #[cfg(feature = "otel")]
+            let _context = crate::otel::attach(&messages);
+
+            handler(messages.into_iter().map(Message::into_value).collect())
+        })
+    }
+}
@@ -0,0 +1,67 @@
+//! Channels maintaining a running fold over all messages sent on them.
+
+use std::{
+    any::Any,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::{
+    channels::{RecvChannel, SendChannel},
+    Junction,
+};
+
+impl Junction {
+    /// Create a new fold channel on this `Junction`.
+    ///
+    /// Return a `SendChannel` to send values on and a `RecvChannel` to
+    /// retrieve the current accumulated value. The accumulator starts out as
+    /// `initial` and every message `msg` sent updates it to `f(acc, msg)`.
+    ///
+    /// Updates and retrievals are both run inline on the control thread, see
+    /// `then_do_inline`, in the order their messages have been handled. A
+    /// retrieval therefore sees every value sent before it on the same
+    /// thread. As `f` runs on the control thread, it should return quickly.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let (add, sum) = j.fold_channel(0, |acc, n: i32| acc + n);
+    ///
+    /// add.send(1).unwrap();
+    /// add.send(2).unwrap();
+    /// assert_eq!(sum.recv().unwrap(), 3);
+    ///
+    /// add.send(3).unwrap();
+    /// assert_eq!(sum.recv().unwrap(), 6);
+    /// ```
+    ///
+    /// If `f` panics, the message is dropped and the accumulator is left as
+    /// it was.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Join Patterns could not be registered.
+    pub fn fold_channel<T, A, F>(&self, initial: A, f: F) -> (SendChannel<T>, RecvChannel<A>)
+    where
+        T: Any + Send,
+        A: Any + Send + Clone,
+        F: Fn(A, T) -> A + Send + Sync + 'static,
+    {
+        let input = self.send_channel::<T>();
+        let output = self.recv_channel::<A>();
+
+        let acc = Arc::new(Mutex::new(initial));
+
+        let acc_clone = acc.clone();
+        self.when(&input).then_do_inline(move |msg| {
+            let mut acc = acc_clone.lock().unwrap_or_else(PoisonError::into_inner);
+            *acc = f(acc.clone(), msg);
+        });
+
+        self.when_recv(&output)
+            .then_do_inline(move || acc.lock().unwrap_or_else(PoisonError::into_inner).clone());
+
+        (input, output)
+    }
+}
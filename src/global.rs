@@ -0,0 +1,41 @@
+//! Process-wide `Junction` for small programs and examples.
+//!
+//! Rather than creating a `Junction` and passing it to every part of a
+//! program that needs to create channels or Join Patterns, these parts can
+//! all use the `Junction` returned by `global`, which is started the first
+//! time it is asked for.
+
+use std::sync::OnceLock;
+
+use crate::junction::Junction;
+
+/// Return the process-wide `Junction`, starting it on first use.
+///
+/// The global `Junction` is never dropped, so its control thread keeps
+/// running until the process exits, and function bodies of fired Join
+/// Patterns still running by then are not waited for. Programs that need
+/// to shut down cleanly should create a `Junction` of their own.
+///
+/// ```
+/// let counter = rusty_junctions::global().send_channel::<u32>();
+/// let total = rusty_junctions::global().recv_channel::<u32>();
+///
+/// rusty_junctions::global()
+///     .when(&counter)
+///     .and(&counter)
+///     .and_recv(&total)
+///     .then_do(|a, b| a + b);
+///
+/// counter.send(1).unwrap();
+/// counter.send(2).unwrap();
+/// assert_eq!(total.recv().unwrap(), 3);
+/// ```
+///
+/// # Panics
+///
+/// Panics if the control thread could not be spawned on first use.
+pub fn global() -> &'static Junction {
+    static GLOBAL: OnceLock<Junction> = OnceLock::new();
+
+    GLOBAL.get_or_init(Junction::new)
+}
@@ -0,0 +1,195 @@
+//! Observing and transforming messages as they reach the control thread.
+//!
+//! Interceptors are registered through `JunctionBuilder::intercept` and run
+//! on the control thread, in the order they have been registered, for every
+//! message that arrives before it is stored. Each interceptor returns a
+//! `Verdict`, passing the message on to the next interceptor, accepting it
+//! right away or dropping it. This allows logging, metrics, rewriting
+//! values or metadata, and filtering to be added without changing the
+//! channels or Join Patterns of a `Junction`.
+//!
+//! Messages sent together through `SendChannel::send_all` are intercepted
+//! together. Requests from the `Junction` itself, e.g. to add Join Patterns,
+//! are not intercepted.
+//!
+//! ```
+//! use rusty_junctions::{channels::Priority, intercept::Verdict, Junction};
+//! use std::sync::{
+//!     atomic::{AtomicUsize, Ordering},
+//!     Arc,
+//! };
+//!
+//! let intercepted = Arc::new(AtomicUsize::new(0));
+//! let counter = Arc::clone(&intercepted);
+//! let j = Junction::builder()
+//!     .intercept(move |packet| {
+//!         counter.fetch_add(packet.message_count(), Ordering::Relaxed);
+//!         Verdict::Continue
+//!     })
+//!     // Drop negative readings, skipping the interceptors after this one.
+//!     .intercept(|packet| match packet.values::<i32>().any(|reading| *reading < 0) {
+//!         true => Verdict::Drop,
+//!         false => Verdict::Continue,
+//!     })
+//!     .intercept(|packet| {
+//!         if packet.channel_name() == Some("reading") {
+//!             packet.values_mut::<i32>().for_each(|reading| *reading *= 10);
+//!             packet.set_priority(Priority::High);
+//!         }
+//!         Verdict::Continue
+//!     })
+//!     .build();
+//!
+//! let reading = j.send_channel_named::<i32>("reading");
+//! let latest = j.recv_channel::<i32>();
+//! j.when(&reading).and_recv(&latest).then_do(|reading| reading);
+//!
+//! reading.send(-1).unwrap();
+//! reading.send(4).unwrap();
+//! assert_eq!(latest.recv().unwrap(), 40);
+//! // Both readings and the call of `latest` have been intercepted.
+//! assert_eq!(intercepted.load(Ordering::Relaxed), 3);
+//! ```
+
+use std::{any::Any, fmt, sync::Arc};
+
+use crate::{
+    channels::{ChannelId, Priority},
+    metadata::Metadata,
+    types::Message,
+};
+
+/// What to do with a message once an interceptor has seen it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Pass the message on to the next interceptor, or store it if this
+    /// has been the last one.
+    Continue,
+    /// Store the message right away, skipping the remaining interceptors.
+    Accept,
+    /// Drop the message, skipping the remaining interceptors.
+    ///
+    /// Callers waiting on a reply to a dropped message receive an error, as
+    /// no reply can be sent to them anymore.
+    Drop,
+}
+
+/// Messages arriving on a channel, as seen by an interceptor.
+pub struct Intercepted<'a> {
+    channel_id: ChannelId,
+    channel_name: Option<&'a str>,
+    msgs: &'a mut [Message],
+}
+
+impl<'a> Intercepted<'a> {
+    pub(crate) fn new(
+        channel_id: ChannelId,
+        channel_name: Option<&'a str>,
+        msgs: &'a mut [Message],
+    ) -> Intercepted<'a> {
+        Intercepted {
+            channel_id,
+            channel_name,
+            msgs,
+        }
+    }
+
+    /// Return the ID of the channel the messages have been sent on.
+    pub fn channel_id(&self) -> ChannelId {
+        self.channel_id
+    }
+
+    /// Return the name of the channel the messages have been sent on, if
+    /// it has been given one.
+    pub fn channel_name(&self) -> Option<&str> {
+        self.channel_name
+    }
+
+    /// Return the number of messages, which is more than one for messages
+    /// sent through `SendChannel::send_all`.
+    pub fn message_count(&self) -> usize {
+        self.msgs.len()
+    }
+
+    /// Return the values of the messages that are of type `T`.
+    ///
+    /// For a `RecvChannel<R>`, the value is the `Sender<R>` the reply is
+    /// sent through, for a `BidirChannel<T, R>` it is the tuple of the value
+    /// sent and that `Sender<R>`.
+    pub fn values<T: Any + Send>(&self) -> impl Iterator<Item = &T> {
+        self.msgs.iter().filter_map(Message::downcast_ref::<T>)
+    }
+
+    /// Return the values of the messages that are of type `T` for
+    /// rewriting, see `Intercepted::values`.
+    pub fn values_mut<T: Any + Send>(&mut self) -> impl Iterator<Item = &mut T> {
+        self.msgs.iter_mut().filter_map(Message::downcast_mut::<T>)
+    }
+
+    /// Return the metadata of each message, if it has been sent with any,
+    /// see `SendChannel::send_with_meta`.
+    pub fn metadata(&self) -> impl Iterator<Item = Option<&Metadata>> {
+        self.msgs.iter().map(Message::metadata)
+    }
+
+    /// Give the messages the given priority among those pending on their
+    /// channel, see `SendChannel::send_with_priority`.
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.msgs
+            .iter_mut()
+            .for_each(|msg| msg.set_priority(priority));
+    }
+}
+
+impl fmt::Debug for Intercepted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Intercepted")
+            .field("channel_id", &self.channel_id)
+            .field("channel_name", &self.channel_name)
+            .field("message_count", &self.msgs.len())
+            .finish()
+    }
+}
+
+/// Function deciding what to do with arriving messages.
+type Interceptor = dyn Fn(&mut Intercepted<'_>) -> Verdict + Send + Sync;
+
+/// Interceptors of a `Junction`, in the order they run.
+#[derive(Clone, Default)]
+pub(crate) struct Interceptors(Vec<Arc<Interceptor>>);
+
+impl Interceptors {
+    /// Run the given interceptor after all others.
+    pub(crate) fn push(
+        &mut self,
+        interceptor: impl Fn(&mut Intercepted<'_>) -> Verdict + Send + Sync + 'static,
+    ) {
+        self.0.push(Arc::new(interceptor));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Run the interceptors on the given messages until one of them
+    /// accepts or drops them.
+    ///
+    /// Return `false` if the messages are to be dropped.
+    pub(crate) fn run(&self, packet: &mut Intercepted<'_>) -> bool {
+        for interceptor in &self.0 {
+            match interceptor(packet) {
+                Verdict::Continue => {}
+                Verdict::Accept => return true,
+                Verdict::Drop => return false,
+            }
+        }
+
+        true
+    }
+}
+
+impl fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Interceptors").field(&self.0.len()).finish()
+    }
+}
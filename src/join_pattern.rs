@@ -1,45 +1,115 @@
-use crate::types::{ids::ChannelId, Message, Packet};
+use crate::{
+    builder::ThreadSpawner,
+    cancel::{CancellationToken, JunctionClosed},
+    junction::Registration,
+    types::{ids::ChannelId, Message, Packet},
+};
 use bag::Bag;
 use std::{
+    cell::{Cell, RefCell},
     marker::{Send, Sized},
-    sync::mpsc::Sender,
+    sync::{mpsc::Sender, Arc},
 };
 
+/// Join Pattern registration intercepted by `capture`.
+pub(crate) type Captured = (Box<dyn JoinPattern + Send>, Sender<Packet>);
+
+thread_local! {
+    /// Registrations intercepted on this thread, `None` unless `capture` is
+    /// running.
+    static CAPTURED: RefCell<Option<Vec<Captured>>> = const { RefCell::new(None) };
+
+    /// Whether a function body is being run inline on this thread, see
+    /// `then_do_inline`.
+    static INLINE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Function body of a Join Pattern run inline, taking the `Message`s of its
+/// channels.
+pub(crate) type RunInline = Arc<dyn Fn(Vec<Message>) + Send + Sync>;
+
+/// Run `f` and return the first Join Pattern it registers, without actually
+/// sending it to the `Controller`.
+///
+/// The generated partial Join Patterns build their full Join Patterns and
+/// register them in one go within `then_do`. Capturing the registration
+/// instead allows to decorate the full Join Pattern, e.g. with a name, before
+/// registering it with the returned `Sender`.
+pub(crate) fn capture(f: impl FnOnce()) -> Option<Captured> {
+    let outer = CAPTURED.with(|captured| captured.replace(Some(Vec::new())));
+    f();
+    let captured = CAPTURED.with(|captured| captured.replace(outer));
+
+    captured.and_then(|captured| captured.into_iter().next())
+}
+
+/// Send the given Join Pattern to the `Controller` behind `sender`, unless
+/// `capture` is running, in which case the registration is intercepted.
+///
+/// # Errors
+///
+/// Returns `JunctionClosed` if the `Controller` has shut down.
+pub(crate) fn register(
+    join_pattern: Box<dyn JoinPattern + Send>,
+    sender: Sender<Packet>,
+) -> Result<(), JunctionClosed> {
+    let join_pattern = CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(captured) => {
+            captured.push((join_pattern, sender));
+            None
+        }
+        None => Some((join_pattern, sender)),
+    });
+    let Some((join_pattern, sender)) = join_pattern else {
+        return Ok(());
+    };
+
+    sender
+        .send(Packet::AddJoinPatternRequest {
+            join_pattern,
+            registration: Registration::capture(),
+        })
+        .map_err(|_| JunctionClosed)
+}
+
+/// Return `true` if a function body is being run inline on the calling
+/// thread, which is then the control thread of its `Junction`.
+pub(crate) fn is_inline() -> bool {
+    INLINE.with(Cell::get)
+}
+
+/// Panic if called from a function body run inline on the control thread,
+/// where the given operation would wait for the control thread forever.
+///
+/// # Panics
+///
+/// Panics if a function body is being run inline on the calling thread.
+pub(crate) fn assert_not_inline(operation: &str) {
+    assert!(
+        !is_inline(),
+        "`{operation}` must not be called from a function body run inline, \
+         as it would wait for the control thread running it"
+    );
+}
+
 pub trait JoinPattern: Send {
     /// Return `true` if the Join Pattern with given `JoinPatternId` is alive.
     ///
     /// A Join Pattern is considered alive if there is at least one `Message` for
     /// each of the channels involved in it.
-    // TODO: Ensure this is a valid implementation of `is_valid` for any number of channels
-    // TODO: The hashmap might be able to be precomputed in the macro
+    ///
+    /// The `Controller` does not call this on its hot path, but caches the
+    /// channels of a Join Pattern once it is registered and only asks
+    /// whether it has been cancelled.
     fn is_alive(&self, messages: &Bag<ChannelId, Message>) -> bool {
-        // Create a hashmap associating each `ChannelId` with its number of
-        // occurrences
-        let mut threshold_channels = std::collections::HashMap::new();
-        self.channels().into_iter().for_each(|chan| {
-            let counter = threshold_channels.entry(chan).or_insert(0);
-            *counter += 1;
-        });
-
-        // Check if there is a sufficient number of messages for each channel
-        for (channel, num) in threshold_channels.into_iter() {
-            let messages_for_channel = messages.count_items(&channel);
-            if messages_for_channel < num {
-                return false;
-            }
-        }
-
-        true
+        has_messages(self.channels(), messages)
     }
 
     fn add(self, sender: Sender<Packet>)
     where
         Self: Sized + Send + 'static,
     {
-        sender
-            .send(Packet::AddJoinPatternRequest {
-                join_pattern: Box::new(self),
-            })
+        register(Box::new(self), sender)
             .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
             .unwrap();
     }
@@ -49,4 +119,453 @@ pub trait JoinPattern: Send {
 
     /// Given the `Message` for each of the channels in the pattern - fire.
     fn fire(&self, messages: Vec<Message>) -> std::thread::JoinHandle<()>;
+
+    /// Given the `Message` for each of the channels in the pattern, fire,
+    /// spawning the thread of the function body through `spawner`.
+    ///
+    /// The Join Patterns generated for `then_do` spawn the threads of their
+    /// function bodies themselves, so they fall back to `fire` and never
+    /// pass them through `spawner`.
+    fn fire_with(
+        &self,
+        messages: Vec<Message>,
+        _spawner: &ThreadSpawner,
+    ) -> std::thread::JoinHandle<()> {
+        // The function bodies generated for `then_do` only take the values
+        // of the `Message`s, restoring the trace context while doing so.
+        #[cfg(feature = "otel")]
+        let mut messages = messages;
+        #[cfg(feature = "otel")]
+        if let Some(message) = messages
+            .iter_mut()
+            .find(|message| message.has_trace_context())
+        {
+            message.restore_context();
+        }
+
+        self.fire(messages)
+    }
+
+    /// Return the name given to the Join Pattern for diagnostics, if any.
+    fn name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Cancel the Join Pattern if it is cancellable, see
+    /// `then_do_cancellable`.
+    fn cancel(&self) {}
+
+    /// Return `true` if the Join Pattern has been cancelled.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+
+    /// Return the maximum number of function bodies of the Join Pattern
+    /// running at once, if limited, see `then_do_with_limit`.
+    fn max_in_flight(&self) -> Option<usize> {
+        None
+    }
+
+    /// Return `true` if the function body is to be run on the control
+    /// thread through `run` rather than fired, see `then_do_inline`.
+    fn is_inline(&self) -> bool {
+        false
+    }
+
+    /// Given the `Message` for each of the channels in the pattern, run the
+    /// function body on the calling thread.
+    fn run(&self, messages: Vec<Message>) {
+        if let Err(payload) = self.fire(messages).join() {
+            std::panic::resume_unwind(payload);
+        }
+    }
+}
+
+/// Return `true` if there is at least one `Message` for each of the given
+/// channels, counting channels appearing multiple times accordingly.
+pub(crate) fn has_messages(channels: Vec<ChannelId>, messages: &Bag<ChannelId, Message>) -> bool {
+    // Create a hashmap associating each `ChannelId` with its number of
+    // occurrences
+    let mut threshold_channels = std::collections::HashMap::new();
+    channels.into_iter().for_each(|chan| {
+        let counter = threshold_channels.entry(chan).or_insert(0);
+        *counter += 1;
+    });
+
+    // Check if there is a sufficient number of messages for each channel
+    for (channel, num) in threshold_channels.into_iter() {
+        let messages_for_channel = messages.count_items(&channel);
+        if messages_for_channel < num {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Join Pattern decorated with a name, see `then_do_named`.
+pub(crate) struct NamedJoinPattern {
+    name: String,
+    join_pattern: Box<dyn JoinPattern + Send>,
+}
+
+impl NamedJoinPattern {
+    pub(crate) fn new(name: String, join_pattern: Box<dyn JoinPattern + Send>) -> NamedJoinPattern {
+        NamedJoinPattern { name, join_pattern }
+    }
+}
+
+impl JoinPattern for NamedJoinPattern {
+    fn is_alive(&self, messages: &Bag<ChannelId, Message>) -> bool {
+        self.join_pattern.is_alive(messages)
+    }
+
+    fn channels(&self) -> Vec<ChannelId> {
+        self.join_pattern.channels()
+    }
+
+    fn fire(&self, messages: Vec<Message>) -> std::thread::JoinHandle<()> {
+        self.join_pattern.fire(messages)
+    }
+
+    fn fire_with(
+        &self,
+        messages: Vec<Message>,
+        spawner: &ThreadSpawner,
+    ) -> std::thread::JoinHandle<()> {
+        self.join_pattern.fire_with(messages, spawner)
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+
+    fn cancel(&self) {
+        self.join_pattern.cancel()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.join_pattern.is_cancelled()
+    }
+
+    fn max_in_flight(&self) -> Option<usize> {
+        self.join_pattern.max_in_flight()
+    }
+
+    fn is_inline(&self) -> bool {
+        self.join_pattern.is_inline()
+    }
+
+    fn run(&self, messages: Vec<Message>) {
+        self.join_pattern.run(messages)
+    }
+}
+
+/// Join Pattern that stops firing once cancelled, see `then_do_cancellable`.
+pub(crate) struct CancellableJoinPattern {
+    token: CancellationToken,
+    /// `true` if `Junction::shutdown` cancels the Join Pattern as well, as it
+    /// does for those created through `then_do_cancellable`.
+    on_shutdown: bool,
+    join_pattern: Box<dyn JoinPattern + Send>,
+}
+
+impl CancellableJoinPattern {
+    pub(crate) fn new(
+        token: CancellationToken,
+        join_pattern: Box<dyn JoinPattern + Send>,
+    ) -> CancellableJoinPattern {
+        CancellableJoinPattern {
+            token,
+            on_shutdown: true,
+            join_pattern,
+        }
+    }
+
+    /// Wrap the given Join Pattern to only be cancelled through its
+    /// `PatternHandle`, but not by `Junction::shutdown`.
+    pub(crate) fn with_handle(
+        token: CancellationToken,
+        join_pattern: Box<dyn JoinPattern + Send>,
+    ) -> CancellableJoinPattern {
+        CancellableJoinPattern {
+            token,
+            on_shutdown: false,
+            join_pattern,
+        }
+    }
+}
+
+impl JoinPattern for CancellableJoinPattern {
+    fn is_alive(&self, messages: &Bag<ChannelId, Message>) -> bool {
+        !self.is_cancelled() && self.join_pattern.is_alive(messages)
+    }
+
+    fn channels(&self) -> Vec<ChannelId> {
+        self.join_pattern.channels()
+    }
+
+    fn fire(&self, messages: Vec<Message>) -> std::thread::JoinHandle<()> {
+        self.join_pattern.fire(messages)
+    }
+
+    fn fire_with(
+        &self,
+        messages: Vec<Message>,
+        spawner: &ThreadSpawner,
+    ) -> std::thread::JoinHandle<()> {
+        self.join_pattern.fire_with(messages, spawner)
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.join_pattern.name()
+    }
+
+    fn cancel(&self) {
+        if self.on_shutdown {
+            self.token.cancel();
+        }
+        self.join_pattern.cancel()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled() || self.join_pattern.is_cancelled()
+    }
+
+    fn max_in_flight(&self) -> Option<usize> {
+        self.join_pattern.max_in_flight()
+    }
+
+    fn is_inline(&self) -> bool {
+        self.join_pattern.is_inline()
+    }
+
+    fn run(&self, messages: Vec<Message>) {
+        self.join_pattern.run(messages)
+    }
+}
+
+/// Join Pattern moved to another `Junction`, where its channels go by other
+/// `ChannelId`s, see `Junction::merge`.
+pub(crate) struct RemappedJoinPattern {
+    channels: Vec<ChannelId>,
+    join_pattern: Box<dyn JoinPattern + Send>,
+}
+
+impl RemappedJoinPattern {
+    /// Wrap the given Join Pattern, replacing each of its channels by the
+    /// `ChannelId` returned for it by `remap`.
+    pub(crate) fn new(
+        join_pattern: Box<dyn JoinPattern + Send>,
+        remap: impl Fn(ChannelId) -> ChannelId,
+    ) -> RemappedJoinPattern {
+        RemappedJoinPattern {
+            channels: join_pattern.channels().into_iter().map(remap).collect(),
+            join_pattern,
+        }
+    }
+}
+
+impl JoinPattern for RemappedJoinPattern {
+    fn is_alive(&self, messages: &Bag<ChannelId, Message>) -> bool {
+        !self.is_cancelled() && has_messages(self.channels(), messages)
+    }
+
+    fn channels(&self) -> Vec<ChannelId> {
+        self.channels.clone()
+    }
+
+    fn fire(&self, messages: Vec<Message>) -> std::thread::JoinHandle<()> {
+        self.join_pattern.fire(messages)
+    }
+
+    fn fire_with(
+        &self,
+        messages: Vec<Message>,
+        spawner: &ThreadSpawner,
+    ) -> std::thread::JoinHandle<()> {
+        self.join_pattern.fire_with(messages, spawner)
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.join_pattern.name()
+    }
+
+    fn cancel(&self) {
+        self.join_pattern.cancel()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.join_pattern.is_cancelled()
+    }
+
+    fn max_in_flight(&self) -> Option<usize> {
+        self.join_pattern.max_in_flight()
+    }
+
+    fn is_inline(&self) -> bool {
+        self.join_pattern.is_inline()
+    }
+
+    fn run(&self, messages: Vec<Message>) {
+        self.join_pattern.run(messages)
+    }
+}
+
+/// Join Pattern with a limited number of function bodies running at once,
+/// see `then_do_with_limit`.
+pub(crate) struct LimitedJoinPattern {
+    max_in_flight: usize,
+    join_pattern: Box<dyn JoinPattern + Send>,
+}
+
+impl LimitedJoinPattern {
+    pub(crate) fn new(
+        max_in_flight: usize,
+        join_pattern: Box<dyn JoinPattern + Send>,
+    ) -> LimitedJoinPattern {
+        LimitedJoinPattern {
+            max_in_flight,
+            join_pattern,
+        }
+    }
+}
+
+impl JoinPattern for LimitedJoinPattern {
+    fn is_alive(&self, messages: &Bag<ChannelId, Message>) -> bool {
+        self.join_pattern.is_alive(messages)
+    }
+
+    fn channels(&self) -> Vec<ChannelId> {
+        self.join_pattern.channels()
+    }
+
+    fn fire(&self, messages: Vec<Message>) -> std::thread::JoinHandle<()> {
+        self.join_pattern.fire(messages)
+    }
+
+    fn fire_with(
+        &self,
+        messages: Vec<Message>,
+        spawner: &ThreadSpawner,
+    ) -> std::thread::JoinHandle<()> {
+        self.join_pattern.fire_with(messages, spawner)
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.join_pattern.name()
+    }
+
+    fn cancel(&self) {
+        self.join_pattern.cancel()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.join_pattern.is_cancelled()
+    }
+
+    fn max_in_flight(&self) -> Option<usize> {
+        Some(self.max_in_flight)
+    }
+
+    fn is_inline(&self) -> bool {
+        self.join_pattern.is_inline()
+    }
+
+    fn run(&self, messages: Vec<Message>) {
+        self.join_pattern.run(messages)
+    }
+}
+
+/// Join Pattern whose function body runs on the control thread, see
+/// `then_do_inline`.
+pub(crate) struct InlineJoinPattern {
+    channels: Vec<ChannelId>,
+    run: RunInline,
+}
+
+impl InlineJoinPattern {
+    pub(crate) fn new(channels: Vec<ChannelId>, run: RunInline) -> InlineJoinPattern {
+        InlineJoinPattern { channels, run }
+    }
+}
+
+impl JoinPattern for InlineJoinPattern {
+    fn channels(&self) -> Vec<ChannelId> {
+        self.channels.clone()
+    }
+
+    fn fire(&self, messages: Vec<Message>) -> std::thread::JoinHandle<()> {
+        self.fire_with(messages, &ThreadSpawner::default())
+    }
+
+    fn fire_with(
+        &self,
+        messages: Vec<Message>,
+        spawner: &ThreadSpawner,
+    ) -> std::thread::JoinHandle<()> {
+        let run = self.run.clone();
+
+        spawner.spawn_body(move || run(messages))
+    }
+
+    fn is_inline(&self) -> bool {
+        true
+    }
+
+    fn run(&self, messages: Vec<Message>) {
+        /// Resets `INLINE` once the function body has returned or panicked.
+        struct Reset;
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                INLINE.with(|inline| inline.set(false));
+            }
+        }
+
+        INLINE.with(|inline| inline.set(true));
+        let _reset = Reset;
+        #[cfg(feature = "otel")]
+        let _context = crate::otel::attach(&messages);
+
+        (self.run)(messages)
+    }
+}
+
+/// Join Pattern whose function body takes the `Message`s of its channels
+/// as they are, run in a thread of its own, see `then_do_with_meta`.
+pub(crate) struct MessagesJoinPattern {
+    channels: Vec<ChannelId>,
+    run: RunInline,
+}
+
+impl MessagesJoinPattern {
+    pub(crate) fn new(channels: Vec<ChannelId>, run: RunInline) -> MessagesJoinPattern {
+        MessagesJoinPattern { channels, run }
+    }
+}
+
+impl JoinPattern for MessagesJoinPattern {
+    fn channels(&self) -> Vec<ChannelId> {
+        self.channels.clone()
+    }
+
+    fn fire(&self, messages: Vec<Message>) -> std::thread::JoinHandle<()> {
+        self.fire_with(messages, &ThreadSpawner::default())
+    }
+
+    fn fire_with(
+        &self,
+        messages: Vec<Message>,
+        spawner: &ThreadSpawner,
+    ) -> std::thread::JoinHandle<()> {
+        let run = self.run.clone();
+
+        spawner.spawn_body(move || {
+            #[cfg(feature = "otel")]
+            let _context = crate::otel::attach(&messages);
+
+            run(messages)
+        })
+    }
 }
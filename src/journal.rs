@@ -0,0 +1,320 @@
+//! Append-only journal of the messages sent on channels, to be replayed
+//! later.
+//!
+//! A `Journal` records every message arriving on the channels it has been
+//! asked to record, serialized with `serde`, along with the name of the
+//! channel and the time the message was sent. Messages are recorded in the
+//! order the `Junction` has received them, so that writing the `Journal` out
+//! after a concurrency bug shows which messages led up to it, and replaying
+//! it through `Junction::replay` reproduces them in the same order.
+//!
+//! Journals are written and read as JSON lines, one entry per line holding
+//! the `channel` name, the `timestamp` in milliseconds since the Unix epoch
+//! and the `payload`.
+//!
+//! Requires the `journal` feature.
+//!
+//! ```
+//! use rusty_junctions::{journal::Journal, Junction};
+//!
+//! let journal = Journal::new();
+//!
+//! let j = Junction::new();
+//! let job = j.send_channel::<String>();
+//! journal.record("job", &job);
+//!
+//! job.send("resize".to_string()).unwrap();
+//! job.send("crop".to_string()).unwrap();
+//!
+//! // Wait for the `Junction` to have received both messages.
+//! j.wait_idle();
+//!
+//! let mut written = Vec::new();
+//! journal.write_to(&mut written).unwrap();
+//!
+//! // Later, reproducing the same messages on a fresh `Junction`.
+//! let recorded = Journal::read_from(&written[..]).unwrap();
+//!
+//! let j = Junction::new();
+//! let job = j.send_channel::<String>();
+//! let done = j.recv_channel::<String>();
+//! j.when(&job).and_recv(&done).then_do(|job| job);
+//!
+//! recorded.replay_to("job", job);
+//! assert_eq!(j.replay(&recorded).unwrap(), 2);
+//!
+//! let mut done = vec![done.recv().unwrap(), done.recv().unwrap()];
+//! done.sort();
+//! assert_eq!(done, vec!["crop", "resize"]);
+//! ```
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    error::Error,
+    fmt,
+    io::{self, BufRead, ErrorKind, Write},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    channels::SendChannel,
+    types::{ids::JunctionId, Message},
+};
+
+/// Function sending a recorded payload on the channel it is replayed to.
+type Deliver = Box<dyn Fn(Value) -> Result<(), String> + Send>;
+
+/// Channel recorded messages are replayed to, along with the ID of the
+/// `Junction` it belongs to.
+struct Target {
+    junction_id: JunctionId,
+    deliver: Deliver,
+}
+
+/// Message recorded in a `Journal`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    channel: String,
+    timestamp: SystemTime,
+    payload: Value,
+}
+
+impl JournalEntry {
+    /// Return the name the channel of the message has been recorded under.
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    /// Return when the message has been sent.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Return the message, serialized as JSON.
+    pub fn payload(&self) -> &Value {
+        &self.payload
+    }
+
+    fn to_json(&self) -> Value {
+        let timestamp = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        json!({
+            "channel": self.channel,
+            "timestamp": u64::try_from(timestamp.as_millis()).unwrap_or(u64::MAX),
+            "payload": self.payload,
+        })
+    }
+
+    fn from_json(mut value: Value) -> Option<JournalEntry> {
+        let channel = value.get("channel")?.as_str()?.to_string();
+        let timestamp = UNIX_EPOCH + Duration::from_millis(value.get("timestamp")?.as_u64()?);
+        let payload = value.get_mut("payload")?.take();
+
+        Some(JournalEntry {
+            channel,
+            timestamp,
+            payload,
+        })
+    }
+}
+
+/// Append-only log of the messages sent on the channels being recorded.
+///
+/// Clones share the same entries and replay targets.
+#[derive(Clone, Default)]
+pub struct Journal {
+    entries: Arc<Mutex<Vec<JournalEntry>>>,
+    targets: Arc<Mutex<HashMap<String, Target>>>,
+}
+
+impl Journal {
+    /// Create a new, empty `Journal`.
+    pub fn new() -> Journal {
+        Journal::default()
+    }
+
+    /// Record every message arriving on the given channel from now on under
+    /// the given name, which identifies the channel when replaying.
+    ///
+    /// Messages that cannot be serialized are logged and not recorded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request to observe the channel could not be sent to the
+    /// `Junction`.
+    pub fn record<T>(&self, name: impl Into<String>, channel: &SendChannel<T>)
+    where
+        T: Any + Send + Serialize,
+    {
+        let name = name.into();
+        let entries = Arc::downgrade(&self.entries);
+
+        channel.add_tap(Box::new(move |msg: &Message| {
+            // Stop observing the channel once the `Journal` is dropped.
+            let Some(entries) = entries.upgrade() else {
+                return false;
+            };
+
+            if let Some(value) = msg.downcast_ref::<T>() {
+                match serde_json::to_value(value) {
+                    Ok(payload) => entries.lock().unwrap().push(JournalEntry {
+                        channel: name.clone(),
+                        timestamp: SystemTime::now() - msg.sent_at().elapsed(),
+                        payload,
+                    }),
+                    Err(e) => log::warn!("Failed to record message to `{name}`: {e:?}"),
+                }
+            }
+
+            true
+        }));
+    }
+
+    /// Replay the messages recorded under the given name to the given
+    /// channel, see `Junction::replay`.
+    pub fn replay_to<T>(&self, name: impl Into<String>, channel: SendChannel<T>)
+    where
+        T: Any + Send + DeserializeOwned,
+    {
+        let junction_id = channel.junction_id();
+        let deliver: Deliver = Box::new(move |payload| {
+            let value = serde_json::from_value::<T>(payload).map_err(|e| e.to_string())?;
+
+            channel.send(value).map(|_| ()).map_err(|e| e.to_string())
+        });
+
+        self.targets.lock().unwrap().insert(
+            name.into(),
+            Target {
+                junction_id,
+                deliver,
+            },
+        );
+    }
+
+    /// Return a copy of all entries recorded so far, in order.
+    pub fn entries(&self) -> Vec<JournalEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Return the number of entries recorded so far.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Return `true` if no entries have been recorded so far.
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().unwrap().is_empty()
+    }
+
+    /// Write all entries recorded so far to the given writer, one JSON
+    /// object per line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        for entry in self.entries.lock().unwrap().iter() {
+            serde_json::to_writer(&mut writer, &entry.to_json())?;
+            writer.write_all(b"\n")?;
+        }
+
+        writer.flush()
+    }
+
+    /// Read a `Journal` written by `write_to` from the given reader.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, or with `ErrorKind::InvalidData`
+    /// if a line does not hold a valid entry.
+    pub fn read_from(reader: impl BufRead) -> io::Result<Journal> {
+        let mut entries = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_str(&line)
+                .ok()
+                .and_then(JournalEntry::from_json)
+                .ok_or_else(|| {
+                    io::Error::new(ErrorKind::InvalidData, format!("invalid entry: {line}"))
+                })?;
+            entries.push(entry);
+        }
+
+        Ok(Journal {
+            entries: Arc::new(Mutex::new(entries)),
+            targets: Arc::default(),
+        })
+    }
+
+    /// Send all recorded messages again on their channels, which must belong
+    /// to the `Junction` of the given ID, see `Junction::replay`.
+    pub(crate) fn replay(&self, junction_id: JunctionId) -> Result<usize, ReplayError> {
+        let entries = self.entries();
+        let targets = self.targets.lock().unwrap();
+
+        for entry in &entries {
+            let target = targets
+                .get(&entry.channel)
+                .ok_or_else(|| ReplayError::UnknownChannel(entry.channel.clone()))?;
+
+            if target.junction_id != junction_id {
+                return Err(ReplayError::ForeignChannel(entry.channel.clone()));
+            }
+
+            (target.deliver)(entry.payload.clone()).map_err(|error| ReplayError::Deliver {
+                channel: entry.channel.clone(),
+                error,
+            })?;
+        }
+
+        Ok(entries.len())
+    }
+}
+
+/// Error returned by `Junction::replay`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// No channel has been given to replay the messages recorded under the
+    /// contained name to.
+    UnknownChannel(String),
+    /// The channel given to replay the messages recorded under the contained
+    /// name to belongs to another `Junction`.
+    ForeignChannel(String),
+    /// The message could not be deserialized or sent on its channel.
+    Deliver { channel: String, error: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::UnknownChannel(name) => {
+                write!(f, "no channel to replay `{name}` to")
+            }
+            ReplayError::ForeignChannel(name) => {
+                write!(
+                    f,
+                    "channel to replay `{name}` to belongs to another Junction"
+                )
+            }
+            ReplayError::Deliver { channel, error } => {
+                write!(f, "failed to replay message to `{channel}`: {error}")
+            }
+        }
+    }
+}
+
+impl Error for ReplayError {}
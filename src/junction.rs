@@ -5,17 +5,62 @@
 use std::{
     any::Any,
     ops::Drop,
-    sync::mpsc::{channel, RecvError, Sender},
+    sync::{
+        mpsc::{channel, RecvError, Sender},
+        Arc,
+    },
+    time::Duration,
 };
 
 use crate::{
+    builder::{JunctionBuilder, ThreadSpawner},
     channels::{BidirChannel, RecvChannel, SendChannel},
-    controller::{Controller, ControllerHandle},
+    controller::{
+        Controller, ControllerHandle, ControllerOptions, ManualController, ShardedController,
+    },
+    join_pattern,
     // join_pattern::JoinPattern,
     patterns::unary::{BidirPartialPattern, RecvPartialPattern, SendPartialPattern},
-    types::{ids, Packet},
+    queue::{packet_channel, PacketSender},
+    types::{ids, MessageType, Packet},
 };
 
+mod adopt;
+mod audit;
+mod broadcast;
+mod child;
+mod clock;
+mod close;
+mod dot;
+mod dynamic;
+#[cfg(feature = "testing")]
+mod fire_count;
+mod gate;
+mod idle;
+#[cfg(feature = "journal")]
+mod journal;
+mod latency;
+mod merge;
+mod poison;
+mod rate;
+mod scope;
+mod select;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+mod state;
+#[cfg(feature = "async")]
+mod task;
+
+pub(crate) use audit::Registration;
+pub use audit::{AuditAction, AuditEvent};
+use child::Family;
+pub use idle::Idle;
+pub(crate) use idle::IdleSignal;
+pub(crate) use latency::Histogram;
+pub use latency::LatencyStats;
+pub use rate::Rate;
+pub use scope::Scope;
+
 /// Struct managing the creation of new channels and Join Patterns.
 ///
 /// This struct is used to group channels, such as `SendChannel`, which can
@@ -26,8 +71,20 @@ use crate::{
 /// in fact, only consist of channels associated with this struct.
 pub struct Junction {
     id: ids::JunctionId,
-    controller_handle: Option<ControllerHandle>,
-    sender: Sender<Packet>,
+    /// Handle to the control thread, if there is one that has not been
+    /// taken, along with the child `Junction`s to stop with it.
+    family: Arc<Family>,
+    /// Configuration the `Junction` has been started with, inherited by its
+    /// child `Junction`s.
+    options: ControllerOptions,
+    queue_capacity: Option<usize>,
+    /// `Controller` of a `Junction` in manual mode, `None` if the
+    /// `Controller` is running in its own control thread.
+    manual_controller: Option<ManualController>,
+    /// Shards of a sharded `Junction`, `None` if there is a single
+    /// `Controller`.
+    sharded_controller: Option<ShardedController>,
+    sender: PacketSender,
 }
 
 #[allow(clippy::new_without_default)]
@@ -37,18 +94,190 @@ impl Junction {
     /// Create a new `Junction` and spawn a control thread in the background
     /// that will handle all incoming `Packet`s for this `Junction`. A
     /// `JoinHandle` to this control thread is stored alongside the `Junction`.
+    ///
+    /// Not available on `wasm32` targets, where no threads can be spawned,
+    /// and neither are the other constructors of `Junction`. Use a
+    /// `local::LocalJunction` there instead, which runs on the thread driving
+    /// it.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn new() -> Junction {
-        let (sender, receiver) = channel::<Packet>();
+        Junction::start(ControllerOptions::default(), None)
+    }
+
+    /// Return a `JunctionBuilder` to configure a new `Junction` with.
+    pub fn builder() -> JunctionBuilder {
+        JunctionBuilder::new()
+    }
+
+    /// Create a new `Junction` whose control thread handles up to `max_batch`
+    /// queued `Packet`s at a time.
+    ///
+    /// The control thread stores all messages of a batch before checking
+    /// which Join Patterns can fire, which raises throughput for senders
+    /// sending messages in bursts. `Junction::new` uses batches of up to 64
+    /// `Packet`s, while a `max_batch` of 1 handles every `Packet` on its own.
+    ///
+    /// Not available on `wasm32` targets, see `Junction::new`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_batch` is zero.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_max_batch(max_batch: usize) -> Junction {
+        Junction::builder().max_batch(max_batch).build()
+    }
+
+    /// Create a new `Junction` with a control thread running a `Controller`
+    /// with the given options, optionally bounding its queue.
+    pub(crate) fn start(options: ControllerOptions, queue_capacity: Option<usize>) -> Junction {
+        let (sender, receiver) = packet_channel(queue_capacity);
+        let sender = sender.with_thread_spawner(options.thread_spawner.clone());
+        let controller = Controller::with_options(options.clone());
+
+        Junction {
+            id: ids::JunctionId::new(),
+            family: Family::new(Some(controller.start(sender.clone(), receiver))),
+            options,
+            queue_capacity,
+            manual_controller: None,
+            sharded_controller: None,
+            sender,
+        }
+    }
+
+    /// Create a new `Junction` in manual mode, without a control thread.
+    ///
+    /// Incoming `Packet`s are queued up until they are handled by a call to
+    /// `poll` or `pump_for` on the calling thread. This bounds the time
+    /// spent on matching messages against Join Patterns, e.g. to a frame
+    /// budget in a game loop. Note that function bodies of fired Join
+    /// Patterns still run in their own threads.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    /// use std::time::Duration;
+    ///
+    /// let j = Junction::manual();
+    /// let frame = j.send_channel::<u32>();
+    /// j.when(&frame).then_do(|n| println!("Frame {}", n));
+    ///
+    /// for n in 0..3 {
+    ///     frame.send(n).unwrap();
+    ///     j.pump_for(Duration::from_millis(16));
+    /// }
+    /// ```
+    ///
+    /// Not available on `wasm32` targets, see `Junction::new`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn manual() -> Junction {
+        let (sender, receiver) = packet_channel(None);
 
         let controller = Controller::new();
 
         Junction {
             id: ids::JunctionId::new(),
-            controller_handle: Some(controller.start(sender.clone(), receiver)),
+            family: Family::new(None),
+            options: ControllerOptions::default(),
+            queue_capacity: None,
+            manual_controller: Some(ManualController::new(controller, receiver)),
+            sharded_controller: None,
             sender,
         }
     }
 
+    /// Create a new `Junction` with its channels partitioned across the given
+    /// number of control threads.
+    ///
+    /// With thousands of channels, a single control thread matching all
+    /// messages becomes the bottleneck. A sharded `Junction` instead spreads
+    /// its channels across several `Controller`s, each in its own thread.
+    /// Channels that are part of a common Join Pattern are moved onto the
+    /// same `Controller` when the Join Pattern is added, so sharding pays
+    /// off for many independent groups of channels and Join Patterns.
+    ///
+    /// Messages sent while their channel is being moved may be matched in
+    /// a different order than they were sent in. There is no
+    /// `ControllerHandle` for a sharded `Junction`.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::sharded(4);
+    /// let value = j.send_channel::<i32>();
+    /// let get = j.recv_channel::<i32>();
+    /// j.when(&value).and_recv(&get).then_do(|v| v);
+    ///
+    /// value.send(42).unwrap();
+    /// assert_eq!(get.recv().unwrap(), 42);
+    /// ```
+    ///
+    /// Moving channels does not make their messages arrive again, e.g. at a
+    /// `SendChannel::tap`:
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::sharded(2);
+    /// let a = j.send_channel::<i32>();
+    /// let b = j.send_channel::<i32>();
+    /// let (tap_a, tap_b) = (a.tap(), b.tap());
+    ///
+    /// a.send(1).unwrap();
+    /// b.send(2).unwrap();
+    /// j.wait_idle();
+    ///
+    /// // Brings `a` and `b` onto the same shard, along with their messages.
+    /// let sum = j.recv_channel::<i32>();
+    /// j.when(&a).and(&b).and_recv(&sum).then_do(|x, y| x + y);
+    /// assert_eq!(sum.recv().unwrap(), 3);
+    ///
+    /// j.wait_idle();
+    /// assert_eq!(tap_a.try_iter().collect::<Vec<_>>(), vec![1]);
+    /// assert_eq!(tap_b.try_iter().collect::<Vec<_>>(), vec![2]);
+    /// ```
+    ///
+    /// Not available on `wasm32` targets, see `Junction::new`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is zero.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn sharded(shards: usize) -> Junction {
+        let options = ControllerOptions::default();
+        let (sharded_controller, sender) = ShardedController::start(shards, &options);
+
+        Junction {
+            id: ids::JunctionId::new(),
+            family: Family::new(None),
+            options,
+            queue_capacity: None,
+            manual_controller: None,
+            sharded_controller: Some(sharded_controller),
+            sender,
+        }
+    }
+
+    /// Handle all currently queued `Packet`s of a `Junction` in manual mode.
+    ///
+    /// Does not block and returns the number of `Packet`s handled. For a
+    /// `Junction` with a control thread, this is a no-op returning 0.
+    pub fn poll(&self) -> usize {
+        self.manual_controller
+            .as_ref()
+            .map_or(0, |controller| controller.poll())
+    }
+
+    /// Handle `Packet`s of a `Junction` in manual mode for the given `Duration`.
+    ///
+    /// Blocks until `duration` has passed, handling `Packet`s as they arrive,
+    /// and returns the number of `Packet`s handled. For a `Junction` with a
+    /// control thread, this is a no-op returning 0.
+    pub fn pump_for(&self, duration: Duration) -> usize {
+        self.manual_controller
+            .as_ref()
+            .map_or(0, |controller| controller.pump_for(duration))
+    }
+
     /// Return handle to internal `Controller` if available.
     ///
     /// Each `Junction` has an associated control thread with a `Controller`
@@ -61,9 +290,10 @@ impl Junction {
     /// long automatically stop its `Controller` and join the control thread
     /// upon going out of scope.
     ///
-    /// Note that this handle can only be retrieved once.
+    /// Note that this handle can only be retrieved once, and that there is
+    /// no handle for a `Junction` in manual mode.
     pub fn controller_handle(&mut self) -> Option<ControllerHandle> {
-        self.controller_handle.take()
+        self.family.take_controller_handle()
     }
 
     /// Create and return a new `SendChannel` on this `Junction`.
@@ -79,7 +309,10 @@ impl Junction {
     where
         T: Any + Send,
     {
-        SendChannel::new(self.new_channel_id().unwrap(), self.id, self.sender.clone())
+        let channel_id = self.new_channel_id().unwrap();
+        self.type_channel(channel_id, MessageType::of::<T>());
+
+        SendChannel::new(channel_id, self.id, self.sender.clone())
     }
 
     /// Create and return a new `RecvChannel` on this `Junction`.
@@ -95,7 +328,10 @@ impl Junction {
     where
         R: Any + Send,
     {
-        RecvChannel::new(self.new_channel_id().unwrap(), self.id, self.sender.clone())
+        let channel_id = self.new_channel_id().unwrap();
+        self.type_channel(channel_id, MessageType::of::<Sender<R>>());
+
+        RecvChannel::new(channel_id, self.id, self.sender.clone())
     }
 
     /// Create and return a new `BidirChannel` on this `Junction`.
@@ -113,7 +349,177 @@ impl Junction {
         T: Any + Send,
         R: Any + Send,
     {
-        BidirChannel::new(self.new_channel_id().unwrap(), self.id, self.sender.clone())
+        let channel_id = self.new_channel_id().unwrap();
+        self.type_channel(channel_id, MessageType::of::<(T, Sender<R>)>());
+
+        BidirChannel::new(channel_id, self.id, self.sender.clone())
+    }
+
+    /// Create and return a new `SendChannel` with the given name.
+    ///
+    /// The name is used instead of the bare channel ID wherever the control
+    /// thread describes the channel, such as in its log messages, and can be
+    /// read back through `SendChannel::name`.
+    ///
+    /// ```
+    /// let j = rusty_junctions::Junction::new();
+    /// let work = j.send_channel_named::<u32>("work");
+    /// let done = j.recv_channel_named::<u32>("done");
+    /// j.when(&work).and_recv(&done).then_do_named("worker", |n| n * 2);
+    ///
+    /// work.send(21).unwrap();
+    /// assert_eq!(done.recv().unwrap(), 42);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new
+    /// channel ID from the control thread.
+    pub fn send_channel_named<T>(&self, name: impl Into<String>) -> SendChannel<T>
+    where
+        T: Any + Send,
+    {
+        let name = name.into();
+        let channel = self.send_channel();
+        self.name_channel(channel.strip().id(), name.clone());
+
+        channel.with_name(name.into())
+    }
+
+    /// Create and return a new `RecvChannel` with the given name, see
+    /// `Junction::send_channel_named`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new
+    /// channel ID from the control thread.
+    pub fn recv_channel_named<R>(&self, name: impl Into<String>) -> RecvChannel<R>
+    where
+        R: Any + Send,
+    {
+        let name = name.into();
+        let channel = self.recv_channel();
+        self.name_channel(channel.strip().id(), name.clone());
+
+        channel.with_name(name.into())
+    }
+
+    /// Create and return a new `BidirChannel` with the given name, see
+    /// `Junction::send_channel_named`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive the new
+    /// channel IDs from the control thread.
+    pub fn bidir_channel_named<T, R>(&self, name: impl Into<String>) -> BidirChannel<T, R>
+    where
+        T: Any + Send,
+        R: Any + Send,
+    {
+        let name = name.into();
+        let channel = self.bidir_channel();
+        self.name_channel(channel.strip().id(), name.clone());
+
+        channel.with_name(name.into())
+    }
+
+    /// Create and return a linked `SendChannel` and `RecvChannel`, turning
+    /// this `Junction` into a queue: every value sent on the `SendChannel`
+    /// is received once on the `RecvChannel`, in the order they were sent
+    /// unless the `Junction` has been built with `MessageOrdering::Lifo`.
+    ///
+    /// The Join Pattern passing values from one to the other is run on the
+    /// control thread, see `then_do_inline`. Further Join Patterns may be
+    /// declared on either channel, competing with it for their messages.
+    ///
+    /// ```
+    /// let j = rusty_junctions::Junction::new();
+    /// let (jobs, next_job) = j.pipe::<&'static str>();
+    ///
+    /// jobs.send("build").unwrap();
+    /// jobs.send("test").unwrap();
+    /// assert_eq!(next_job.recv().unwrap(), "build");
+    /// assert_eq!(next_job.recv().unwrap(), "test");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive the new
+    /// channel IDs from the control thread, or if the Join Pattern could
+    /// not be registered.
+    pub fn pipe<T>(&self) -> (SendChannel<T>, RecvChannel<T>)
+    where
+        T: Any + Send,
+    {
+        let send_channel = self.send_channel();
+        let recv_channel = self.recv_channel();
+        self.when(&send_channel)
+            .and_recv(&recv_channel)
+            .then_do_inline(|t| t);
+
+        (send_channel, recv_channel)
+    }
+
+    /// Tell the control thread the name of the channel with given ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name could not be sent to the control thread.
+    fn name_channel(&self, channel_id: ids::ChannelId, name: String) {
+        self.sender
+            .send(Packet::NameChannel { channel_id, name })
+            .map_err(|e| log::error!("Failed to send NameChannel: {e:?}"))
+            .unwrap();
+    }
+
+    /// Tell the control thread the type of the values held by the
+    /// `Message`s of the channel with given ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the type could not be sent to the control thread.
+    fn type_channel(&self, channel_id: ids::ChannelId, message_type: MessageType) {
+        self.sender
+            .send(Packet::TypeChannel {
+                channel_id,
+                message_type,
+            })
+            .map_err(|e| log::error!("Failed to send TypeChannel: {e:?}"))
+            .unwrap();
+    }
+
+    /// Cancel all Join Patterns created through `then_do_cancellable`, then
+    /// stop the `Junction` like dropping it would.
+    ///
+    /// Cancelled Join Patterns do not fire anymore and their function bodies
+    /// still running are signalled through their `CancellationToken`s. This
+    /// returns once they have finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request could not be sent to the control thread.
+    pub fn shutdown(self) {
+        for sender in self.controller_senders() {
+            sender
+                .send(Packet::CancelRequest)
+                .map_err(|e| log::error!("Failed to send CancelRequest: {e:?}"))
+                .unwrap();
+        }
+    }
+
+    /// Return the `ThreadSpawner` the threads of this `Junction` are spawned
+    /// through, see `JunctionBuilder::thread_spawner`.
+    pub(crate) fn thread_spawner(&self) -> &ThreadSpawner {
+        &self.options.thread_spawner
+    }
+
+    /// Return the queues of all `Controller`s of the `Junction`, which for a
+    /// sharded `Junction` are those of its shards.
+    fn controller_senders(&self) -> Vec<PacketSender> {
+        match &self.sharded_controller {
+            Some(controller) => controller.shard_senders(),
+            None => vec![self.sender.clone()],
+        }
     }
 
     /// Request ID for a new channel from control thread.
@@ -121,8 +527,18 @@ impl Junction {
     /// # Panics
     ///
     /// Panics if request for new channel id could not be sent to
-    /// control thread.
+    /// control thread, or if called from a function body run inline.
     fn new_channel_id(&self) -> Result<ids::ChannelId, RecvError> {
+        if let Some(controller) = &self.manual_controller {
+            return Ok(controller.new_channel_id());
+        }
+
+        if let Some(controller) = &self.sharded_controller {
+            return Ok(controller.new_channel_id());
+        }
+
+        join_pattern::assert_not_inline("Junction::new_channel_id");
+
         let (id_sender, id_receiver) = channel::<ids::ChannelId>();
 
         self.sender
@@ -143,13 +559,14 @@ impl Junction {
     ///
     /// Panics if the supplied `SendChannel` does not carry the same
     /// `JunctionID` as this `Junction`, i.e. has not been created by and is
-    /// associated with this `Junction`.
+    /// associated with this `Junction`. See `Junction::adopt` to migrate a
+    /// `SendChannel` of another `Junction` over.
     pub fn when<T>(&self, send_channel: &SendChannel<T>) -> SendPartialPattern<T>
     where
         T: Any + Send,
     {
         if send_channel.junction_id() == self.id {
-            SendPartialPattern::new(self.id, send_channel.strip(), self.sender.clone())
+            SendPartialPattern::new(self.id, send_channel.strip(), self.sender.control_sender())
         } else {
             panic!(
                 "SendChannel is not associated with Junction! Please use \
@@ -171,7 +588,7 @@ impl Junction {
         R: Any + Send,
     {
         if recv_channel.junction_id() == self.id {
-            RecvPartialPattern::new(recv_channel.strip(), self.sender.clone())
+            RecvPartialPattern::new(recv_channel.strip(), self.sender.control_sender())
         } else {
             panic!(
                 "RecvChannel is not associated with Junction! Please use \
@@ -194,7 +611,7 @@ impl Junction {
         R: Any + Send,
     {
         if bidir_channel.junction_id() == self.id {
-            BidirPartialPattern::new(bidir_channel.strip(), self.sender.clone())
+            BidirPartialPattern::new(bidir_channel.strip(), self.sender.control_sender())
         } else {
             panic!(
                 "BidirChannel is not associated with Junction! Please use \
@@ -208,14 +625,24 @@ impl Junction {
 impl Drop for Junction {
     /// Drop the `Junction` and free its resources.
     ///
-    /// If there is a `ControllerHandle` still available, use it to stop the
-    /// associated `Controller` and join the control thread. Otherwise, no
-    /// action is needed.
+    /// Child `Junction`s are stopped first. Then, if there is a
+    /// `ControllerHandle` still available, use it to stop the associated
+    /// `Controller` and join the control thread. In manual mode, handle all
+    /// remaining `Packet`s and join the firing threads instead. A sharded
+    /// `Junction` stops all of its shards. Otherwise, no action is needed.
     fn drop(&mut self) {
         log::debug!("Dropping Junction - Attempting to shutdown Controller");
-        if self.controller_handle.is_some() {
+        self.family.stop_children();
+
+        if let Some(controller) = self.manual_controller.take() {
+            log::debug!("Controller is in manual mode");
+            controller.stop();
+        } else if let Some(controller) = self.sharded_controller.take() {
+            log::debug!("Controller is sharded");
+            controller.stop();
+        } else if let Some(mut controller_handle) = self.family.take_controller_handle() {
             log::debug!("Controller has a ControllerHandle");
-            self.controller_handle.take().unwrap().stop();
+            controller_handle.stop();
         } else {
             log::debug!("Controller didn't have a ControllerHandle");
         }
@@ -0,0 +1,66 @@
+//! Migrating channels between `Junction`s.
+//!
+//! Join Patterns can only be built from channels of a single `Junction`. A
+//! `SendChannel` of another `Junction` can be migrated over, after which
+//! all messages sent on it, through any of its handles, arrive at the
+//! adopting `Junction` instead.
+
+use std::any::Any;
+
+use crate::{channels::SendChannel, junction::Junction};
+
+impl Junction {
+    /// Migrate the given `SendChannel` of another `Junction` over to this
+    /// one, returning a handle to the migrated channel.
+    ///
+    /// All messages pending on the channel, as well as those sent later
+    /// through any of its old handles, are passed on to the new channel in
+    /// the order they have been sent. The Join Patterns of the channel on
+    /// the other `Junction` never fire again, and messages are only passed
+    /// on for as long as the other `Junction` is running.
+    ///
+    /// Returns a clone of the given handle if the channel already belongs
+    /// to this `Junction`.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let producers = Junction::new();
+    /// let value = producers.send_channel::<u32>();
+    /// value.send(1).unwrap();
+    ///
+    /// let consumers = Junction::new();
+    /// let adopted = consumers.adopt(&value);
+    /// let get = consumers.recv_channel::<u32>();
+    /// consumers.when(&adopted).and_recv(&get).then_do(|v| v);
+    ///
+    /// value.send(2).unwrap();
+    /// let mut values = vec![get.recv().unwrap(), get.recv().unwrap()];
+    /// values.sort();
+    /// assert_eq!(values, vec![1, 2]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a new channel could not be created on this `Junction`, or
+    /// if the other `Junction` could not be asked to migrate the channel or
+    /// has shut down before doing so. A `Junction` in manual mode only
+    /// migrates the channel once it is polled.
+    pub fn adopt<T: Any + Send>(&self, channel: &SendChannel<T>) -> SendChannel<T> {
+        if channel.junction_id() == self.id {
+            return channel.clone();
+        }
+
+        let adopted = match channel.name() {
+            Some(name) => self.send_channel_named::<T>(name),
+            None => self.send_channel::<T>(),
+        };
+
+        channel
+            .migrate(self.sender.clone(), adopted.id())
+            .map_err(|e| log::error!("Failed to migrate channel: {e:?}"))
+            .unwrap();
+
+        adopted
+    }
+}
@@ -0,0 +1,176 @@
+//! History of Join Patterns being registered with and removed from a
+//! `Junction`.
+//!
+//! Every control thread keeps the latest events in a ring buffer of the
+//! size set through `JunctionBuilder::audit_capacity`, so that it can be
+//! found out which Join Pattern keeps consuming the messages of a channel
+//! and where it has been registered. The stack of the thread registering a
+//! Join Pattern is captured if enabled through the `RUST_LIB_BACKTRACE` or
+//! `RUST_BACKTRACE` environment variables, see `std::backtrace::Backtrace`.
+
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    sync::{mpsc::channel, Arc},
+    time::SystemTime,
+};
+
+use crate::{junction::Junction, types::Packet};
+
+/// When and from where a Join Pattern has been registered, sent along with
+/// it to the control thread.
+#[derive(Debug, Clone)]
+pub struct Registration {
+    at: SystemTime,
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+impl Registration {
+    /// Record a registration on the calling thread, capturing its stack if
+    /// enabled.
+    pub(crate) fn capture() -> Registration {
+        let backtrace = Backtrace::capture();
+
+        Registration {
+            at: SystemTime::now(),
+            backtrace: (backtrace.status() == BacktraceStatus::Captured)
+                .then(|| Arc::new(backtrace)),
+        }
+    }
+
+    /// Record a registration by the control thread itself, whose stack is
+    /// of no interest.
+    pub(crate) fn now() -> Registration {
+        Registration {
+            at: SystemTime::now(),
+            backtrace: None,
+        }
+    }
+}
+
+/// What has happened to a Join Pattern, see `AuditEvent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    /// The Join Pattern has been added.
+    Registered,
+    /// The Join Pattern has not been added, as it duplicates another one,
+    /// see `DuplicatePatternPolicy::Reject`.
+    Rejected,
+    /// The Join Pattern has been removed, e.g. as all handles of one of its
+    /// channels have been dropped, see `DroppedChannelPolicy::Disable`.
+    Removed,
+}
+
+/// Join Pattern registered with or removed from a `Junction`, returned by
+/// `Junction::audit_log`.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    action: AuditAction,
+    join_pattern: String,
+    channels: Vec<String>,
+    at: SystemTime,
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+impl AuditEvent {
+    pub(crate) fn new(
+        action: AuditAction,
+        join_pattern: String,
+        channels: Vec<String>,
+        registration: Option<Registration>,
+    ) -> AuditEvent {
+        let registration = registration.unwrap_or_else(Registration::now);
+
+        AuditEvent {
+            action,
+            join_pattern,
+            channels,
+            at: registration.at,
+            backtrace: registration.backtrace,
+        }
+    }
+
+    /// Return what has happened to the Join Pattern.
+    pub fn action(&self) -> AuditAction {
+        self.action
+    }
+
+    /// Return the description of the Join Pattern, i.e. its name, if it has
+    /// been given one, along with its ID, unless it has been rejected.
+    pub fn join_pattern(&self) -> &str {
+        &self.join_pattern
+    }
+
+    /// Return the descriptions of the channels of the Join Pattern, in the
+    /// order they have been added to it.
+    pub fn channels(&self) -> &[String] {
+        &self.channels
+    }
+
+    /// Return the time at which the Join Pattern has been registered or
+    /// removed.
+    pub fn at(&self) -> SystemTime {
+        self.at
+    }
+
+    /// Return the stack of the thread registering the Join Pattern, if it
+    /// has been captured.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_deref()
+    }
+}
+
+impl Junction {
+    /// Return the latest Join Patterns registered with or removed from this
+    /// `Junction`, oldest first, including those of all shards of a sharded
+    /// `Junction`.
+    ///
+    /// Only as many events as set through `JunctionBuilder::audit_capacity`
+    /// are kept. The stacks of the threads registering Join Patterns are
+    /// only captured if enabled through the `RUST_LIB_BACKTRACE` or
+    /// `RUST_BACKTRACE` environment variables.
+    ///
+    /// ```
+    /// use rusty_junctions::{AuditAction, Junction};
+    ///
+    /// let j = Junction::new();
+    /// let job = j.send_channel_named::<u32>("job");
+    /// let done = j.recv_channel::<u32>();
+    /// j.when(&job).and_recv(&done).then_do_named("worker", |n| n);
+    ///
+    /// let log = j.audit_log();
+    /// assert_eq!(log[0].action(), AuditAction::Registered);
+    /// assert!(log[0].join_pattern().starts_with("`worker`"));
+    /// assert!(log[0].channels()[0].starts_with("`job`"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the events could not be requested from or received from
+    /// the control thread.
+    pub fn audit_log(&self) -> Vec<AuditEvent> {
+        if let Some(controller) = &self.manual_controller {
+            return controller.audit_log();
+        }
+
+        let mut events: Vec<AuditEvent> = self
+            .controller_senders()
+            .into_iter()
+            .flat_map(|sender| {
+                let (return_sender, return_receiver) = channel();
+
+                sender
+                    .send(Packet::AuditLogRequest { return_sender })
+                    .map_err(|e| log::error!("Failed to send AuditLogRequest: {e:?}"))
+                    .unwrap();
+
+                return_receiver
+                    .recv()
+                    .map_err(|e| log::error!("Failed to receive audit log: {e:?}"))
+                    .unwrap()
+            })
+            .collect();
+        events.sort_by_key(AuditEvent::at);
+
+        events
+    }
+}
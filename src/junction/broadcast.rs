@@ -0,0 +1,83 @@
+use std::any::Any;
+
+use crate::{
+    channels::{BroadcastChannel, SendChannel},
+    junction::Junction,
+    types::{Message, MessageType, Packet},
+};
+
+impl Junction {
+    /// Create and return a new `BroadcastChannel` on this `Junction`.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let release = j.broadcast_channel::<String>();
+    ///
+    /// let logged = j.recv_channel::<String>();
+    /// j.when(&j.subscribe(&release)).and_recv(&logged).then_do(|r| r);
+    /// let announced = j.recv_channel::<String>();
+    /// j.when(&j.subscribe(&release))
+    ///     .and_recv(&announced)
+    ///     .then_do(|r| r.to_uppercase());
+    ///
+    /// release.send("v1.0".to_string()).unwrap();
+    /// assert_eq!(logged.recv().unwrap(), "v1.0");
+    /// assert_eq!(announced.recv().unwrap(), "V1.0");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new
+    /// channel ID from the control thread.
+    pub fn broadcast_channel<T>(&self) -> BroadcastChannel<T>
+    where
+        T: Any + Send + Clone,
+    {
+        BroadcastChannel::new(self.send_channel())
+    }
+
+    /// Create and return a new `SendChannel` on which a copy of every
+    /// message sent on the given `BroadcastChannel` is stored from now on.
+    ///
+    /// Each subscriber receives its own copy, which is consumed by the Join
+    /// Patterns of the subscriber as usual. Messages can also be sent on a
+    /// subscriber directly, reaching only its own Join Patterns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given channel is not associated with this `Junction`,
+    /// or if it received an error while trying to receive a new channel ID
+    /// from the control thread.
+    pub fn subscribe<T>(&self, broadcast: &BroadcastChannel<T>) -> SendChannel<T>
+    where
+        T: Any + Send + Clone,
+    {
+        if broadcast.junction_id() != self.id {
+            panic!(
+                "BroadcastChannel is not associated with Junction! Please use \
+                 a BroadcastChannel created using the same Junction calling \
+                 this function!"
+            );
+        }
+
+        let channel_id = self.new_channel_id().unwrap();
+        // Copies are stored by the shard owning the broadcast channel.
+        if let Some(controller) = &self.sharded_controller {
+            controller.colocate(channel_id, broadcast.id());
+        }
+        self.type_channel(channel_id, MessageType::of::<T>());
+
+        self.sender
+            .send(Packet::Subscribe {
+                channel_id: broadcast.id(),
+                subscriber: channel_id,
+                copy: Message::copy::<T>,
+            })
+            .map_err(|e| log::error!("Failed to send Subscribe: {e:?}"))
+            .unwrap();
+
+        SendChannel::new(channel_id, self.id, self.sender.clone())
+    }
+}
@@ -0,0 +1,116 @@
+//! Child `Junction`s, stopped along with their parent.
+//!
+//! Every `Junction` keeps track of the child `Junction`s created from it
+//! through `Junction::child`. When a `Junction` is stopped, its children
+//! are stopped first, which in turn stop their own children, so that the
+//! parts of an application can be structured into nested scopes.
+
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::{controller::ControllerHandle, junction::Junction};
+
+/// Handle to the control thread of a `Junction`, shared with its parent,
+/// along with its children.
+pub(crate) struct Family {
+    controller_handle: Mutex<Option<ControllerHandle>>,
+    /// Children of the `Junction`, which are no longer tracked once they
+    /// have been dropped.
+    children: Mutex<Vec<Weak<Family>>>,
+}
+
+impl Family {
+    pub(crate) fn new(controller_handle: Option<ControllerHandle>) -> Arc<Family> {
+        Arc::new(Family {
+            controller_handle: Mutex::new(controller_handle),
+            children: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Take the `ControllerHandle`, if it has not been taken yet.
+    pub(crate) fn take_controller_handle(&self) -> Option<ControllerHandle> {
+        self.controller_handle.lock().unwrap().take()
+    }
+
+    /// Replace the `ControllerHandle`, e.g. after healing the `Junction`.
+    pub(crate) fn set_controller_handle(&self, controller_handle: ControllerHandle) {
+        *self.controller_handle.lock().unwrap() = Some(controller_handle);
+    }
+
+    /// Track the given `Family` as a child of this one.
+    fn add_child(&self, child: &Arc<Family>) {
+        let mut children = self.children.lock().unwrap();
+
+        children.retain(|child| child.strong_count() > 0);
+        children.push(Arc::downgrade(child));
+    }
+
+    /// Track all children of the given `Family` as children of this one.
+    pub(crate) fn adopt_children(&self, other: &Family) {
+        let adopted = std::mem::take(&mut *other.children.lock().unwrap());
+
+        self.children.lock().unwrap().extend(adopted);
+    }
+
+    /// Stop all children, along with their own children.
+    pub(crate) fn stop_children(&self) {
+        let children = std::mem::take(&mut *self.children.lock().unwrap());
+
+        for child in children.iter().filter_map(Weak::upgrade) {
+            child.stop_children();
+
+            if let Some(mut controller_handle) = child.take_controller_handle() {
+                controller_handle.stop();
+            }
+        }
+    }
+}
+
+impl Junction {
+    /// Create a child `Junction` with the same configuration as this one.
+    ///
+    /// The child `Junction` is stopped along with this one, right before its
+    /// own `Controller` is stopped, whether by dropping it or through
+    /// `Junction::shutdown`. Channels of the child `Junction` can no longer
+    /// be used afterwards. Dropping the child `Junction` first stops it
+    /// right away, as with any other `Junction`.
+    ///
+    /// The child `Junction` always runs its own control thread, even if
+    /// this `Junction` is in manual mode or sharded, in which case it is
+    /// started with the default configuration.
+    ///
+    /// ```
+    /// use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+    /// use rusty_junctions::Junction;
+    ///
+    /// let handled = Arc::new(AtomicUsize::new(0));
+    ///
+    /// let app = Junction::new();
+    /// let module = app.child();
+    ///
+    /// let request = module.send_channel::<u32>();
+    /// let handled_clone = handled.clone();
+    /// module.when(&request).then_do(move |_| {
+    ///     handled_clone.fetch_add(1, Ordering::SeqCst);
+    /// });
+    ///
+    /// request.send(1).unwrap();
+    ///
+    /// // Stopping the parent stops the child, waiting for its function
+    /// // bodies to finish.
+    /// drop(app);
+    /// assert_eq!(handled.load(Ordering::SeqCst), 1);
+    /// ```
+    ///
+    /// Not available on `wasm32` targets, see `Junction::new`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the control thread could not be spawned.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn child(&self) -> Junction {
+        let child = Junction::start(self.options.clone(), self.queue_capacity);
+        self.family.add_child(&child.family);
+
+        child
+    }
+}
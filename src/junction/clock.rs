@@ -0,0 +1,37 @@
+use std::{sync::mpsc::channel, time::Duration};
+
+use crate::{junction::Junction, types::Packet};
+
+impl Junction {
+    /// Move the virtual clock of this `Junction` forward by the given
+    /// `Duration`, see `ClockSource::Virtual`.
+    ///
+    /// Messages sent before the call arrive before the clock is advanced.
+    /// Returns once all messages that have expired or become dead letters
+    /// in the meantime have been dealt with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Junction` has not been built with a virtual clock, or
+    /// if the control thread could not be asked to advance it.
+    pub fn advance(&self, duration: Duration) {
+        let to = self
+            .options
+            .clock
+            .elapsed()
+            .expect("Only Junctions with a virtual clock can be advanced")
+            + duration;
+
+        let (ack, ack_receiver) = channel();
+
+        self.sender
+            .send(Packet::AdvanceClock { to, ack })
+            .map_err(|e| log::error!("Failed to send AdvanceClock: {e:?}"))
+            .unwrap();
+
+        ack_receiver
+            .recv()
+            .map_err(|e| log::error!("Failed to wait for AdvanceClock: {e:?}"))
+            .unwrap();
+    }
+}
@@ -0,0 +1,34 @@
+use std::any::Any;
+
+use crate::{channels::SendChannel, junction::Junction};
+
+impl Junction {
+    /// Create and return a new `SendChannel` receiving a single message
+    /// once the given channel is closed, see `SendChannel::close`.
+    ///
+    /// The message is sent right away if the channel has been closed
+    /// already.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given channel is not associated with this `Junction`,
+    /// or if it received an error while trying to receive a new channel ID
+    /// from the control thread.
+    pub fn on_close<T>(&self, channel: &SendChannel<T>) -> SendChannel<()>
+    where
+        T: Any + Send,
+    {
+        if channel.junction_id() != self.id {
+            panic!(
+                "SendChannel is not associated with Junction! Please use \
+                 a SendChannel created using the same Junction calling \
+                 this function!"
+            );
+        }
+
+        let close_signal = self.send_channel();
+        channel.signal_on_close(close_signal.clone());
+
+        close_signal
+    }
+}
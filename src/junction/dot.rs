@@ -0,0 +1,74 @@
+use std::sync::mpsc::channel;
+
+use crate::{junction::Junction, types::Packet};
+
+impl Junction {
+    /// Return the topology of this `Junction` as a graph in the DOT language
+    /// of Graphviz.
+    ///
+    /// Every channel that has been given a name or is part of a Join Pattern
+    /// is a node labelled with its name, the type of its messages and the
+    /// number of its pending messages. Every Join Pattern is a box labelled
+    /// with its name and the number of times it has fired, with an edge from
+    /// each of its channels.
+    /// Render the graph with e.g. `dot -Tsvg` to review the coordination
+    /// structure of a large `Junction`.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let put = j.send_channel_named::<u32>("put");
+    /// let get = j.recv_channel_named::<u32>("get");
+    /// j.when(&put).and_recv(&get).then_do_named("storage", |v| v);
+    ///
+    /// put.send(1).unwrap();
+    /// get.recv().unwrap();
+    ///
+    /// let dot = j.export_dot();
+    /// assert!(dot.starts_with("digraph junction {"));
+    /// assert!(dot.contains("label=\"put\\nu32\\n0 pending\""));
+    /// assert!(dot.contains("label=\"storage\\nfired 1 times\""));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the graph could not be requested from or received from the
+    /// control thread.
+    pub fn export_dot(&self) -> String {
+        let mut dot = String::from("digraph junction {\n");
+
+        if let Some(controller) = &self.manual_controller {
+            dot.push_str(&controller.export_dot());
+        } else {
+            let senders = self.controller_senders();
+            let sharded = senders.len() > 1;
+
+            for (shard, sender) in senders.into_iter().enumerate() {
+                let (return_sender, return_receiver) = channel();
+                let prefix = if sharded {
+                    format!("{shard}_")
+                } else {
+                    String::new()
+                };
+
+                sender
+                    .send(Packet::ExportDotRequest {
+                        prefix,
+                        return_sender,
+                    })
+                    .map_err(|e| log::error!("Failed to send ExportDotRequest: {e:?}"))
+                    .unwrap();
+
+                let statements = return_receiver
+                    .recv()
+                    .map_err(|e| log::error!("Failed to receive DOT graph: {e:?}"))
+                    .unwrap();
+                dot.push_str(&statements);
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
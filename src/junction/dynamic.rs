@@ -0,0 +1,121 @@
+use std::any::Any;
+
+use crate::{
+    cancel::JunctionClosed,
+    dynamic::{DynamicError, DynamicJoinPattern, PatternSpec},
+    join_pattern,
+    junction::Junction,
+    registry::{ChannelRef, Registry},
+    types::{ids::ChannelId, Message, Packet},
+};
+
+impl Junction {
+    /// Declare the Join Pattern described by the given `PatternSpec`,
+    /// resolving its channels against the given `Registry`.
+    ///
+    /// See the `dynamic` module for an example.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DynamicError::NoChannels` if the `PatternSpec` lists no
+    /// channels, `DynamicError::Lookup` if a channel has not been published
+    /// in the `Registry`, `DynamicError::ForeignChannel` if a channel
+    /// belongs to another `Junction`, and `DynamicError::Closed` if this
+    /// `Junction` has shut down.
+    pub fn register_pattern(
+        &self,
+        spec: PatternSpec,
+        registry: &Registry,
+    ) -> Result<(), DynamicError> {
+        let (channel_refs, handler) = spec.into_parts();
+        if channel_refs.is_empty() {
+            return Err(DynamicError::NoChannels);
+        }
+
+        let channels = channel_refs
+            .iter()
+            .map(|channel_ref| self.identify(channel_ref, registry))
+            .collect::<Result<Vec<ChannelId>, DynamicError>>()?;
+
+        join_pattern::register(
+            Box::new(DynamicJoinPattern::new(channels, handler)),
+            self.sender.control_sender(),
+        )
+        .map_err(DynamicError::Closed)
+    }
+
+    /// Send the given value on the channel published in the given
+    /// `Registry` under the name of the given `ChannelRef`.
+    ///
+    /// The value has to be of the type of the `Message`s of the channel,
+    /// e.g. `(T, Sender<R>)` for a `BidirChannel<T, R>`, see the `dynamic`
+    /// module. As this can only be checked once it has arrived at the
+    /// control thread, a value of another type is dropped there, logging an
+    /// error naming the type of the channel.
+    ///
+    /// ```
+    /// use rusty_junctions::{
+    ///     registry::{ChannelRef, Registry},
+    ///     Junction,
+    /// };
+    ///
+    /// let j = Junction::new();
+    /// let name = j.send_channel::<String>();
+    /// let greeting = j.recv_channel::<String>();
+    /// j.when(&name).and_recv(&greeting).then_do(|name| format!("Hello, {name}!"));
+    ///
+    /// let registry = Registry::new();
+    /// registry.publish("name", name);
+    ///
+    /// let channel = ChannelRef::new("name");
+    /// j.send_dynamic(&channel, &registry, Box::new(42_u32)).unwrap();
+    /// j.send_dynamic(&channel, &registry, Box::new("Ada".to_string())).unwrap();
+    /// assert_eq!(greeting.recv().unwrap(), "Hello, Ada!");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `DynamicError::Lookup` if the channel has not been published
+    /// in the `Registry`, `DynamicError::ForeignChannel` if it belongs to
+    /// another `Junction`, and `DynamicError::Closed` if this `Junction` has
+    /// shut down.
+    pub fn send_dynamic(
+        &self,
+        channel: &ChannelRef,
+        registry: &Registry,
+        value: Box<dyn Any + Send>,
+    ) -> Result<(), DynamicError> {
+        let channel_id = self.identify(channel, registry)?;
+        let msg = Message::dynamic(value);
+        #[cfg(feature = "otel")]
+        let msg = crate::otel::inject(msg);
+
+        self.sender
+            .send(Packet::Message { channel_id, msg })
+            .map_err(|_| DynamicError::Closed(JunctionClosed))
+    }
+
+    /// Look up the channel of the given `ChannelRef` in the given
+    /// `Registry`, checking that it belongs to this `Junction`.
+    fn identify(
+        &self,
+        channel: &ChannelRef,
+        registry: &Registry,
+    ) -> Result<ChannelId, DynamicError> {
+        let name = channel.name();
+        let (junction_id, channel_id) =
+            registry
+                .identify(name)
+                .map_err(|error| DynamicError::Lookup {
+                    name: name.to_string(),
+                    error,
+                })?;
+        if junction_id != self.id {
+            return Err(DynamicError::ForeignChannel {
+                name: name.to_string(),
+            });
+        }
+
+        Ok(channel_id)
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::mpsc::channel;
+
+use crate::{junction::Junction, types::Packet};
+
+impl Junction {
+    /// Return the number of times the Join Patterns of the given name have
+    /// fired on this `Junction`, counting all shards of a sharded
+    /// `Junction`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number could not be requested from or received from
+    /// the control thread.
+    pub(crate) fn fire_count(&self, name: &str) -> u64 {
+        if let Some(controller) = &self.manual_controller {
+            return controller.fire_count(name);
+        }
+
+        self.controller_senders()
+            .into_iter()
+            .map(|sender| {
+                let (return_sender, return_receiver) = channel();
+
+                sender
+                    .send(Packet::FireCountRequest {
+                        name: name.to_string(),
+                        return_sender,
+                    })
+                    .map_err(|e| log::error!("Failed to send FireCountRequest: {e:?}"))
+                    .unwrap();
+
+                return_receiver
+                    .recv()
+                    .map_err(|e| log::error!("Failed to receive fire count: {e:?}"))
+                    .unwrap()
+            })
+            .sum()
+    }
+}
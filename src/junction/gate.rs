@@ -0,0 +1,59 @@
+use std::any::Any;
+
+use crate::{
+    channels::{pattern_gate, NoPatternPolicy, SendChannel},
+    junction::Junction,
+    types::Packet,
+};
+
+impl Junction {
+    /// Create a new `SendChannel` whose messages are handled according to
+    /// the given `NoPatternPolicy` for as long as no Join Pattern of the
+    /// channel has been registered.
+    ///
+    /// Once the first Join Pattern of the channel has been handled by the
+    /// control thread, the channel behaves like any other, even if its Join
+    /// Patterns are removed later on. Join Patterns registered by the thread
+    /// sending on the channel are always taken into account, whereas those
+    /// registered concurrently by other threads may not be yet.
+    ///
+    /// ```
+    /// use rusty_junctions::{channels::NoPatternPolicy, Junction};
+    ///
+    /// let j = Junction::new();
+    /// let job = j.send_channel_with_policy::<u32>(NoPatternPolicy::Reject);
+    /// let done = j.recv_channel::<u32>();
+    ///
+    /// // Rejected rather than left waiting for a worker that never comes.
+    /// assert!(job.send(1).is_err());
+    ///
+    /// j.when(&job).and_recv(&done).then_do(|n| n);
+    /// job.send(2).unwrap();
+    /// assert_eq!(done.recv().unwrap(), 2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel could not be created or the policy could not
+    /// be sent to the control thread.
+    pub fn send_channel_with_policy<T>(&self, policy: NoPatternPolicy) -> SendChannel<T>
+    where
+        T: Any + Send,
+    {
+        let channel = self.send_channel::<T>();
+        if policy == NoPatternPolicy::Queue {
+            return channel;
+        }
+
+        let (gate, opener) = pattern_gate(policy);
+        self.sender
+            .send(Packet::GatePatterns {
+                channel_id: channel.id(),
+                opener,
+            })
+            .map_err(|e| log::error!("Failed to send GatePatterns: {e:?}"))
+            .unwrap();
+
+        channel.with_gate(gate)
+    }
+}
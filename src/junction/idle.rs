@@ -0,0 +1,266 @@
+//! Waiting for a `Junction` to become idle.
+//!
+//! A `Junction` is idle once its `Controller` has handled all queued
+//! `Packet`s, fired all Join Patterns that could fire and all function bodies
+//! of fired Join Patterns have finished. This is mostly of interest for tests
+//! of code built on `Junction`s, which would otherwise have to sleep for an
+//! arbitrary amount of time before checking the outcome.
+//!
+//! The `Controller` is asked through a `Packet::IdleRequest` carrying an
+//! `IdleSignal`, which it sets once it is idle. A sharded `Junction` is idle
+//! once all of its shards have been found idle twice in a row without
+//! having received any `Message`s in between, since function bodies fired on
+//! one shard may keep sending `Message`s to the others.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+use counter::Counter;
+
+use crate::{
+    join_pattern,
+    junction::Junction,
+    primitives::{Condvar, Mutex},
+    queue::PacketSender,
+    types::Packet,
+};
+
+/// Signal set by a `Controller` once it is idle.
+pub struct IdleSignal {
+    state: Mutex<IdleState>,
+    condvar: Condvar,
+}
+
+struct IdleState {
+    /// Number of `Message`s the `Controller` had received when it was found
+    /// idle, `None` until then.
+    messages: Option<Counter>,
+    waker: Option<Waker>,
+}
+
+impl IdleSignal {
+    fn new() -> IdleSignal {
+        IdleSignal {
+            state: Mutex::new(IdleState {
+                messages: None,
+                waker: None,
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Signal that the `Controller` is idle, having received the given
+    /// number of `Message`s so far.
+    pub(crate) fn set(&self, messages: Counter) {
+        let mut state = self.state.lock().unwrap();
+        state.messages = Some(messages);
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        self.condvar.notify_all();
+    }
+
+    /// Block until the signal is set.
+    fn wait(&self) -> Counter {
+        let mut state = self.state.lock().unwrap();
+        while state.messages.is_none() {
+            state = self.condvar.wait(state).unwrap();
+        }
+
+        state.messages.clone().unwrap()
+    }
+
+    /// Return the number of `Message`s if the signal is set, otherwise
+    /// register the given `Waker` to be woken once it is.
+    fn poll(&self, waker: &Waker) -> Option<Counter> {
+        let mut state = self.state.lock().unwrap();
+        if state.messages.is_none() {
+            state.waker = Some(waker.clone());
+        }
+
+        state.messages.clone()
+    }
+}
+
+/// Ask each of the given `Controller`s to signal once it is idle.
+///
+/// # Panics
+///
+/// Panics if a request could not be sent to a `Controller`.
+fn request_idle(senders: &[PacketSender]) -> Vec<Arc<IdleSignal>> {
+    senders
+        .iter()
+        .map(|sender| {
+            let signal = Arc::new(IdleSignal::new());
+            sender
+                .send(Packet::IdleRequest {
+                    signal: signal.clone(),
+                })
+                .map_err(|e| log::error!("Failed to send IdleRequest: {e:?}"))
+                .unwrap();
+
+            signal
+        })
+        .collect()
+}
+
+/// Return `true` if a round of idle `Controller`s that received the given
+/// numbers of `Message`s completes the wait, given those of the last round.
+fn is_settled(messages: &[Counter], last: &Option<Vec<Counter>>) -> bool {
+    messages.len() == 1 || last.as_deref() == Some(messages)
+}
+
+impl Junction {
+    /// Block until the `Junction` is idle.
+    ///
+    /// The `Junction` is idle once no `Packet`s are left to handle, no Join
+    /// Pattern can fire and the function bodies of all fired Join Patterns
+    /// have finished. Messages sent by other threads while waiting can
+    /// prolong the wait indefinitely.
+    ///
+    /// In manual mode, `Packet`s are handled by the calling thread while
+    /// waiting.
+    ///
+    /// ```
+    /// use std::sync::{
+    ///     atomic::{AtomicUsize, Ordering},
+    ///     Arc,
+    /// };
+    ///
+    /// let j = rusty_junctions::Junction::new();
+    /// let count = j.send_channel::<()>();
+    ///
+    /// let counted = Arc::new(AtomicUsize::new(0));
+    /// let counted_clone = counted.clone();
+    /// j.when(&count).then_do(move |_| {
+    ///     counted_clone.fetch_add(1, Ordering::SeqCst);
+    /// });
+    ///
+    /// for _ in 0..10 {
+    ///     count.send(()).unwrap();
+    /// }
+    /// j.wait_idle();
+    ///
+    /// assert_eq!(counted.load(Ordering::SeqCst), 10);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request could not be sent to the control thread.
+    ///
+    /// Panics if called from a function body run inline, see `then_do_inline`.
+    pub fn wait_idle(&self) {
+        join_pattern::assert_not_inline("Junction::wait_idle");
+
+        if let Some(controller) = &self.manual_controller {
+            controller.wait_idle();
+            return;
+        }
+
+        let senders = self.controller_senders();
+        let mut last = None;
+
+        loop {
+            let messages: Vec<Counter> = request_idle(&senders)
+                .iter()
+                .map(|signal| signal.wait())
+                .collect();
+
+            if is_settled(&messages, &last) {
+                return;
+            }
+            last = Some(messages);
+        }
+    }
+
+    /// Return a `Future` resolving once the `Junction` is idle, see
+    /// `Junction::wait_idle`.
+    ///
+    /// In manual mode, `Packet`s are handled whenever the `Future` is polled.
+    pub fn idle(&self) -> Idle<'_> {
+        Idle {
+            junction: self,
+            signals: Vec::new(),
+            last: None,
+        }
+    }
+}
+
+/// `Future` resolving once a `Junction` is idle, created by `Junction::idle`.
+pub struct Idle<'a> {
+    junction: &'a Junction,
+    /// Signals of the current round of requests, empty before the first.
+    signals: Vec<Arc<IdleSignal>>,
+    /// Numbers of `Message`s received by the `Controller`s in the last round.
+    last: Option<Vec<Counter>>,
+}
+
+impl Future for Idle<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some(controller) = &self.junction.manual_controller {
+            if controller.poll_idle() {
+                return Poll::Ready(());
+            }
+
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        loop {
+            if self.signals.is_empty() {
+                self.signals = request_idle(&self.junction.controller_senders());
+            }
+
+            let mut messages = Vec::new();
+            for signal in self.signals.iter() {
+                match signal.poll(cx.waker()) {
+                    Some(count) => messages.push(count),
+                    None => return Poll::Pending,
+                }
+            }
+
+            if is_settled(&messages, &self.last) {
+                return Poll::Ready(());
+            }
+            self.last = Some(messages);
+            self.signals.clear();
+        }
+    }
+}
+
+#[cfg(all(test, loom))]
+mod tests {
+    use std::sync::Arc;
+
+    use counter::Counter;
+    use loom::thread;
+
+    use super::IdleSignal;
+
+    #[test]
+    fn wait_returns_once_set() {
+        loom::model(|| {
+            let signal = Arc::new(IdleSignal::new());
+            let signal_clone = signal.clone();
+
+            let controller = thread::spawn(move || {
+                let mut messages = Counter::default();
+                messages.increment();
+                signal_clone.set(messages);
+            });
+
+            let mut expected = Counter::default();
+            expected.increment();
+            assert_eq!(signal.wait(), expected);
+
+            controller.join().unwrap();
+        });
+    }
+}
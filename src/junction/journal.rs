@@ -0,0 +1,22 @@
+use crate::{
+    journal::{Journal, ReplayError},
+    junction::Junction,
+};
+
+impl Junction {
+    /// Send all messages recorded in the given `Journal` again, in the order
+    /// they were recorded, on the channels given through
+    /// `Journal::replay_to`.
+    ///
+    /// Messages are sent as fast as possible rather than with the delays
+    /// between their timestamps. Return the number of messages sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ReplayError`, having sent all messages before the failing
+    /// one, if a recorded message has no channel of this `Junction` to be
+    /// replayed to or could not be sent on it.
+    pub fn replay(&self, journal: &Journal) -> Result<usize, ReplayError> {
+        journal.replay(self.id)
+    }
+}
@@ -0,0 +1,233 @@
+//! Latencies between sending messages and firing the Join Patterns
+//! consuming them.
+//!
+//! The control thread records, for every message consumed by a fired Join
+//! Pattern, how long ago it has been sent. Latencies are kept in a histogram
+//! per Join Pattern with buckets of exponentially growing width, so that
+//! percentiles are exact to within an eighth of their value at any scale
+//! while recording stays cheap. Growing latencies show that the control
+//! thread or the executor running the function bodies is falling behind.
+
+use std::{fmt, sync::mpsc::channel, time::Duration};
+
+use crate::{junction::Junction, types::Packet};
+
+/// Bits of a latency in nanoseconds below its most significant one that
+/// determine its bucket.
+const SUB_BUCKET_BITS: u32 = 3;
+
+/// Number of buckets per power of two.
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+
+/// Number of buckets covering all latencies up to `u64::MAX` nanoseconds.
+const BUCKETS: usize = (u64::BITS - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS;
+
+/// Histogram of latencies in nanoseconds.
+#[derive(Clone)]
+pub(crate) struct Histogram {
+    counts: Box<[u64; BUCKETS]>,
+    samples: u64,
+}
+
+impl Histogram {
+    pub(crate) fn new() -> Histogram {
+        Histogram {
+            counts: Box::new([0; BUCKETS]),
+            samples: 0,
+        }
+    }
+
+    /// Record the given latency.
+    pub(crate) fn record(&mut self, latency: Duration) {
+        let nanos = u64::try_from(latency.as_nanos()).unwrap_or(u64::MAX);
+
+        self.counts[bucket(nanos)] += 1;
+        self.samples += 1;
+    }
+
+    /// Return the latency below which the given fraction of the recorded
+    /// latencies lie, rounded up to the upper bound of its bucket.
+    fn quantile(&self, quantile: f64) -> Duration {
+        if self.samples == 0 {
+            return Duration::ZERO;
+        }
+
+        let rank = ((quantile * self.samples as f64).ceil() as u64).clamp(1, self.samples);
+
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Duration::from_nanos(upper_bound(bucket));
+            }
+        }
+
+        Duration::ZERO
+    }
+}
+
+/// Return the bucket of the given latency in nanoseconds.
+fn bucket(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+
+    let exponent = u64::BITS - 1 - nanos.leading_zeros();
+    let shift = exponent - SUB_BUCKET_BITS;
+    let sub_bucket = (nanos >> shift) as usize & (SUB_BUCKETS - 1);
+
+    (shift as usize + 1) * SUB_BUCKETS + sub_bucket
+}
+
+/// Return the largest latency in nanoseconds falling into the given bucket.
+fn upper_bound(bucket: usize) -> u64 {
+    if bucket < SUB_BUCKETS {
+        return bucket as u64;
+    }
+
+    let shift = (bucket / SUB_BUCKETS - 1) as u32;
+    let lower_bound = ((SUB_BUCKETS + bucket % SUB_BUCKETS) as u64) << shift;
+
+    lower_bound + ((1 << shift) - 1)
+}
+
+/// Latencies between sending messages and firing a Join Pattern consuming
+/// them, returned by `Junction::latency_stats`.
+#[derive(Clone)]
+pub struct LatencyStats {
+    join_pattern: String,
+    histogram: Histogram,
+}
+
+impl LatencyStats {
+    pub(crate) fn new(join_pattern: String, histogram: Histogram) -> LatencyStats {
+        LatencyStats {
+            join_pattern,
+            histogram,
+        }
+    }
+
+    /// Return the description of the Join Pattern, i.e. its name, if it has
+    /// been given one, along with its ID.
+    pub fn join_pattern(&self) -> &str {
+        &self.join_pattern
+    }
+
+    /// Return the number of messages the Join Pattern has consumed.
+    pub fn samples(&self) -> u64 {
+        self.histogram.samples
+    }
+
+    /// Return the latency that the given percentage of messages has not
+    /// exceeded, e.g. `percentile(99.9)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not between 0 and 100.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        assert!(
+            (0.0..=100.0).contains(&percentile),
+            "`percentile` must be between 0 and 100"
+        );
+
+        self.histogram.quantile(percentile / 100.0)
+    }
+
+    /// Return the median latency.
+    pub fn p50(&self) -> Duration {
+        self.percentile(50.0)
+    }
+
+    /// Return the latency that 95% of messages have not exceeded.
+    pub fn p95(&self) -> Duration {
+        self.percentile(95.0)
+    }
+
+    /// Return the latency that 99% of messages have not exceeded.
+    pub fn p99(&self) -> Duration {
+        self.percentile(99.0)
+    }
+}
+
+impl fmt::Debug for LatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LatencyStats")
+            .field("join_pattern", &self.join_pattern)
+            .field("samples", &self.samples())
+            .field("p50", &self.p50())
+            .field("p95", &self.p95())
+            .field("p99", &self.p99())
+            .finish()
+    }
+}
+
+impl Junction {
+    /// Return the latencies between sending messages and firing the Join
+    /// Patterns consuming them, for every Join Pattern that has fired,
+    /// including those of all shards of a sharded `Junction`.
+    ///
+    /// Only messages waiting since they have been sent are counted. The
+    /// values of state channels, which are read without being consumed, and
+    /// persistent messages consumed again are not.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let job = j.send_channel::<u32>();
+    /// let done = j.recv_channel::<u32>();
+    /// j.when(&job).and_recv(&done).then_do_named("worker", |n| n);
+    ///
+    /// let rate = j.state_channel(2_u32);
+    /// let scale = j.bidir_channel::<u32, u32>();
+    /// j.when(&rate).and_bidir(&scale).then_do_named("scale", |rate, n| rate * n);
+    ///
+    /// for n in 0..10 {
+    ///     job.send(n).unwrap();
+    ///     done.recv().unwrap();
+    ///     scale.send_recv(n).unwrap();
+    /// }
+    ///
+    /// let stats = j.latency_stats();
+    /// let worker = stats
+    ///     .iter()
+    ///     .find(|stats| stats.join_pattern().starts_with("`worker`"))
+    ///     .unwrap();
+    /// assert_eq!(worker.samples(), 20);
+    /// assert!(worker.p50() <= worker.p99());
+    ///
+    /// // Only the messages sent on `scale`, not the value of `rate`.
+    /// let scaled = stats
+    ///     .iter()
+    ///     .find(|stats| stats.join_pattern().starts_with("`scale`"))
+    ///     .unwrap();
+    /// assert_eq!(scaled.samples(), 10);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the latencies could not be requested from or received
+    /// from the control thread.
+    pub fn latency_stats(&self) -> Vec<LatencyStats> {
+        if let Some(controller) = &self.manual_controller {
+            return controller.latency_stats();
+        }
+
+        self.controller_senders()
+            .into_iter()
+            .flat_map(|sender| {
+                let (return_sender, return_receiver) = channel();
+
+                sender
+                    .send(Packet::LatencyStatsRequest { return_sender })
+                    .map_err(|e| log::error!("Failed to send LatencyStatsRequest: {e:?}"))
+                    .unwrap();
+
+                return_receiver
+                    .recv()
+                    .map_err(|e| log::error!("Failed to receive latency stats: {e:?}"))
+                    .unwrap()
+            })
+            .collect()
+    }
+}
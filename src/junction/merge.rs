@@ -0,0 +1,77 @@
+//! Merging `Junction`s into one.
+
+use std::sync::mpsc::channel;
+
+use crate::{junction::Junction, types::Packet};
+
+impl Junction {
+    /// Move all channels of the given `Junction`, along with their pending
+    /// messages and Join Patterns, over to this one.
+    ///
+    /// The Join Patterns of both `Junction`s then match messages together,
+    /// composing independently built parts of an application into one. The
+    /// channel handles of the other `Junction` keep working: messages sent
+    /// through them are passed on by its control thread, which no longer
+    /// matches any messages itself and stops once all of its channel
+    /// handles have been dropped. New Join Patterns can only be built from
+    /// channels of this `Junction`, which the handles of the other
+    /// `Junction` do not belong to.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let orders = Junction::new();
+    /// let order = orders.send_channel::<u32>();
+    /// let next = orders.recv_channel::<u32>();
+    /// orders.when(&order).and_recv(&next).then_do(|id| id);
+    /// order.send(1).unwrap();
+    ///
+    /// let app = Junction::new();
+    /// app.merge(orders);
+    ///
+    /// order.send(2).unwrap();
+    /// let mut ids = vec![next.recv().unwrap(), next.recv().unwrap()];
+    /// ids.sort();
+    /// assert_eq!(ids, vec![1, 2]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the other `Junction` is in manual mode or sharded, or if
+    /// new channels could not be created on this `Junction` or the other
+    /// `Junction` could not be asked to move its channels.
+    pub fn merge(&self, other: Junction) {
+        assert!(
+            other.manual_controller.is_none() && other.sharded_controller.is_none(),
+            "Only Junctions running a control thread of their own can be merged"
+        );
+
+        // Channels are numbered from zero, so the next ID to be handed out
+        // is the number of channels created so far.
+        let channels = other.new_channel_id().unwrap().value();
+        let to_channel_ids = (0..channels)
+            .map(|_| self.new_channel_id().unwrap())
+            .collect();
+
+        let (ack, ack_receiver) = channel();
+        other
+            .sender
+            .send(Packet::MergeRequest {
+                to: Box::new(self.sender.clone()),
+                to_channel_ids,
+                ack,
+            })
+            .map_err(|e| log::error!("Failed to send MergeRequest: {e:?}"))
+            .unwrap();
+        ack_receiver
+            .recv()
+            .map_err(|e| log::error!("Failed to merge Junction: {e:?}"))
+            .unwrap();
+
+        // Leave the control thread of the other `Junction` running to pass
+        // on messages, rather than stopping it on drop, and stop its child
+        // `Junction`s along with this one instead.
+        other.family.take_controller_handle();
+        self.family.adopt_children(&other.family);
+    }
+}
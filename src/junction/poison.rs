@@ -0,0 +1,87 @@
+//! Recovering a `Junction` whose control thread has panicked.
+//!
+//! Under `PanicPolicy::Ignore`, a panic of the control thread, e.g. in the
+//! `Drop` implementation of a message dropped by the `Controller`, stops the
+//! `Controller` for good. The `Junction` is then poisoned: sending on any of
+//! its channels returns an error, and callers waiting for a reply are woken
+//! with an error, as all stored messages have been dropped along with the
+//! `Controller`. `Junction::heal` starts a fresh `Controller` in its place.
+
+use crate::{controller::Controller, junction::Junction};
+
+impl Junction {
+    /// Return `true` if the control thread of this `Junction` has panicked,
+    /// leaving the `Junction` unable to handle any further messages until it
+    /// is healed.
+    pub fn is_poisoned(&self) -> bool {
+        self.sender.is_poisoned()
+    }
+
+    /// Start a fresh control thread in place of the panicked one of a
+    /// poisoned `Junction`.
+    ///
+    /// All channels and Join Patterns of the `Junction` keep working, but
+    /// the messages stored before the panic are lost, as are observers of
+    /// channels and any other state of the panicked `Controller`. Function
+    /// bodies that were still running are no longer waited for when the
+    /// `Junction` is stopped.
+    ///
+    /// Return `false` if the `Junction` is not poisoned, or if it is in
+    /// manual mode or sharded, neither of which can be healed. A `Junction`
+    /// running its `Controller` as a task is never poisoned.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    /// use std::{thread, time::Duration};
+    ///
+    /// /// Message panicking when the `Controller` drops it.
+    /// struct Fuse;
+    ///
+    /// impl Drop for Fuse {
+    ///     fn drop(&mut self) {
+    ///         panic!("Fuse blown");
+    ///     }
+    /// }
+    ///
+    /// let j = Junction::new();
+    /// let job = j.send_channel::<u32>();
+    /// let done = j.recv_channel::<u32>();
+    /// j.when(&job).and_recv(&done).then_do(|n| n);
+    ///
+    /// // Expired messages are dropped by the `Controller`.
+    /// let fuse = j.send_channel::<Fuse>();
+    /// fuse.send_with_ttl(Fuse, Duration::ZERO).unwrap();
+    /// while !j.is_poisoned() {
+    ///     thread::sleep(Duration::from_millis(1));
+    /// }
+    /// assert!(job.send(1).is_err());
+    /// assert!(done.recv().is_err());
+    ///
+    /// assert!(j.heal());
+    /// job.send(2).unwrap();
+    /// assert_eq!(done.recv().unwrap(), 2);
+    /// ```
+    pub fn heal(&self) -> bool {
+        if self.manual_controller.is_some()
+            || self.sharded_controller.is_some()
+            || !self.is_poisoned()
+        {
+            return false;
+        }
+
+        let Some(controller_handle) = self.family.take_controller_handle() else {
+            return false;
+        };
+        let Some(salvage) = controller_handle.into_salvage() else {
+            return false;
+        };
+
+        log::debug!("Healing poisoned Junction");
+        let (controller, receiver) = Controller::revive(self.options.clone(), salvage);
+        self.sender.set_poisoned(false);
+        self.family
+            .set_controller_handle(controller.start(self.sender.clone(), receiver));
+
+        true
+    }
+}
@@ -0,0 +1,105 @@
+//! Channels whose messages are released at a limited rate.
+//!
+//! Messages sent on a channel created through `Junction::rate_limited_channel`
+//! are only passed on to its Join Patterns as tokens of a token bucket kept
+//! by the control thread become available. The bucket holds up to as many
+//! tokens as messages are released per period and is refilled evenly over
+//! the period, so that bursts of messages are smoothed out without any
+//! pacing in the code sending them. Messages held back wait in order of
+//! arrival and are released on the timers of the control thread, which
+//! follow the virtual clock of the `Junction`, if any.
+
+use std::{any::Any, time::Duration};
+
+use crate::{channels::SendChannel, junction::Junction, types::Packet};
+
+/// Rate at which a channel created through `Junction::rate_limited_channel`
+/// releases its messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    messages: u32,
+    per: Duration,
+}
+
+impl Rate {
+    /// Release up to `messages` messages per period of the given length,
+    /// which may all be released at once after a quiet period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `messages` is zero or `per` is `Duration::ZERO`.
+    pub fn new(messages: u32, per: Duration) -> Rate {
+        assert!(messages > 0, "`messages` must be at least 1");
+        assert!(!per.is_zero(), "`per` must not be zero");
+
+        Rate { messages, per }
+    }
+
+    /// Release up to `messages` messages per second, see `Rate::new`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `messages` is zero.
+    pub fn per_second(messages: u32) -> Rate {
+        Rate::new(messages, Duration::from_secs(1))
+    }
+
+    /// Return the number of messages that may be released at once.
+    pub(crate) fn burst(&self) -> u32 {
+        self.messages
+    }
+
+    /// Return the time it takes to gain the token for another message.
+    pub(crate) fn interval(&self) -> Duration {
+        self.per / self.messages
+    }
+}
+
+impl Junction {
+    /// Create and return a new `SendChannel` whose messages are released to
+    /// its Join Patterns no faster than the given `Rate` allows.
+    ///
+    /// Sending on the channel never blocks; messages exceeding the rate are
+    /// held back by the control thread until they may be released, in order
+    /// of arrival.
+    ///
+    /// ```
+    /// use rusty_junctions::{Junction, Rate};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let j = Junction::new();
+    /// let request = j.rate_limited_channel::<u32>(Rate::new(2, Duration::from_millis(100)));
+    /// let handled = j.recv_channel::<u32>();
+    /// j.when(&request).and_recv(&handled).then_do(|n| n);
+    ///
+    /// let start = Instant::now();
+    /// request.send_all(0..4).unwrap();
+    /// for _ in 0..4 {
+    ///     handled.recv().unwrap();
+    /// }
+    ///
+    /// // Two messages are released right away, the others 50ms apart.
+    /// assert!(start.elapsed() >= Duration::from_millis(100));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel could not be created or the rate limit could
+    /// not be sent to the control thread.
+    pub fn rate_limited_channel<T>(&self, rate: Rate) -> SendChannel<T>
+    where
+        T: Any + Send,
+    {
+        let channel = self.send_channel::<T>();
+
+        self.sender
+            .send(Packet::RateLimit {
+                channel_id: channel.id(),
+                rate,
+            })
+            .map_err(|e| log::error!("Failed to send RateLimit: {e:?}"))
+            .unwrap();
+
+        channel
+    }
+}
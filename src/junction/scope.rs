@@ -0,0 +1,145 @@
+//! Junctions whose Join Patterns may borrow from their environment.
+//!
+//! A `Junction` created through `Junction::scope` only lives for the
+//! duration of the closure it is passed to. Once the closure returns, the
+//! `Junction` is stopped, which handles all `Packet`s still queued and waits
+//! for the function bodies of all fired Join Patterns to finish, just like
+//! dropping any other `Junction`. Join Patterns completed through
+//! `then_do_scoped` can therefore capture non-`'static` references, much
+//! like threads spawned through `std::thread::scope`.
+
+use std::{
+    marker::PhantomData,
+    ops::Deref,
+    sync::mpsc::{channel, Sender},
+};
+
+use crate::{
+    cancel::JunctionClosed,
+    join_pattern::{self, JoinPattern},
+    junction::{Junction, Registration},
+    types::Packet,
+};
+
+/// `Junction` living for the duration of a call to `Junction::scope`.
+///
+/// Dereferences to the underlying `Junction`, which is used to create
+/// channels and Join Patterns as usual.
+pub struct Scope<'scope, 'env: 'scope> {
+    junction: Junction,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl Junction {
+    /// Create a `Junction` for the duration of the given closure.
+    ///
+    /// Join Patterns completed through `then_do_scoped` may borrow from the
+    /// environment of the call, as the function bodies of all Join Patterns
+    /// fired on the `Junction` are guaranteed to have finished before this
+    /// function returns, even if the closure panics.
+    ///
+    /// ```
+    /// use std::sync::Mutex;
+    /// use rusty_junctions::Junction;
+    ///
+    /// let received = Mutex::new(Vec::new());
+    ///
+    /// Junction::scope(|j| {
+    ///     let value = j.send_channel::<u32>();
+    ///     j.when(&value)
+    ///         .then_do_scoped(j, |v| received.lock().unwrap().push(v));
+    ///
+    ///     value.send(1).unwrap();
+    ///     value.send(2).unwrap();
+    /// });
+    ///
+    /// let mut received = received.into_inner().unwrap();
+    /// received.sort();
+    /// assert_eq!(received, vec![1, 2]);
+    /// ```
+    ///
+    /// Not available on `wasm32` targets, see `Junction::new`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn scope<'env, F, T>(f: F) -> T
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+    {
+        // Dropped on return as well as on unwinding, stopping the `Junction`.
+        let scope = Scope {
+            junction: Junction::new(),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+
+        f(&scope)
+    }
+}
+
+impl Scope<'_, '_> {
+    /// Return `true` if the given `Sender` leads into the control queue of
+    /// the scoped `Junction`, i.e. if a partial Join Pattern registering
+    /// through it has been created on this `Junction`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a function body is being run inline on the calling thread,
+    /// as it would wait for the control thread of the scoped `Junction`.
+    pub(crate) fn owns(&self, sender: &Sender<Packet>) -> bool {
+        join_pattern::assert_not_inline("Scope::owns");
+
+        let (probe_sender, probe_receiver) = channel();
+        if sender
+            .send(Packet::ProbeRequest {
+                return_sender: probe_sender,
+            })
+            .is_err()
+        {
+            return false;
+        }
+
+        // Control `Packet`s are handled in the order they have been sent in,
+        // so if `sender` leads to this `Junction`, its control thread has
+        // answered the first probe by the time it answers the second.
+        let (return_sender, return_receiver) = channel();
+        let control_thread = self
+            .junction
+            .sender
+            .send(Packet::ProbeRequest { return_sender })
+            .ok()
+            .and_then(|()| return_receiver.recv().ok());
+
+        control_thread.is_some_and(|control_thread| probe_receiver.try_recv() == Ok(control_thread))
+    }
+
+    /// Register the given Join Pattern with the scoped `Junction`.
+    ///
+    /// The partial Join Pattern of the given Join Pattern has to have been
+    /// created on this `Junction`, see `Scope::owns`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JunctionClosed` if the Join Pattern could not be sent to the
+    /// control thread.
+    pub(crate) fn add(
+        &self,
+        join_pattern: Box<dyn JoinPattern + Send>,
+    ) -> Result<(), JunctionClosed> {
+        self.junction
+            .sender
+            .control_sender()
+            .send(Packet::AddJoinPatternRequest {
+                join_pattern,
+                registration: Registration::capture(),
+            })
+            .map_err(|_| JunctionClosed)
+    }
+}
+
+impl Deref for Scope<'_, '_> {
+    type Target = Junction;
+
+    fn deref(&self) -> &Junction {
+        &self.junction
+    }
+}
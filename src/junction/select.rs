@@ -0,0 +1,83 @@
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    channels::{Either, SendChannel},
+    junction::Junction,
+};
+
+impl Junction {
+    /// Pass every message sent on `data` as `Either::Left` and every message
+    /// sent on `shutdown` as `Either::Right` to the given function, one at a
+    /// time and in the order the messages reached the control thread.
+    ///
+    /// The shutdown message wins ties: once it has reached the control
+    /// thread, messages on `data` that have not been passed to the function
+    /// yet are dropped, including those sent concurrently with it and those
+    /// sent afterwards.
+    ///
+    /// Messages sent on `data` and `shutdown` are consumed by the Join
+    /// Patterns added to pass them on, so they should not be part of any
+    /// other Join Pattern.
+    ///
+    /// ```
+    /// use rusty_junctions::{channels::Either, Junction};
+    /// use std::sync::mpsc::channel;
+    ///
+    /// let j = Junction::new();
+    /// let readings = j.send_channel::<u32>();
+    /// let shutdown = j.send_channel::<()>();
+    ///
+    /// let (sender, receiver) = channel();
+    /// j.select(&readings, &shutdown, move |next| sender.send(next).unwrap());
+    ///
+    /// readings.send(1).unwrap();
+    /// assert_eq!(receiver.recv().unwrap(), Either::Left(1));
+    ///
+    /// shutdown.send(()).unwrap();
+    /// readings.send(2).unwrap();
+    /// assert_eq!(receiver.recv().unwrap(), Either::Right(()));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` or `shutdown` is not associated with this
+    /// `Junction`, or if any of the Join Patterns could not be registered.
+    pub fn select<T, S, F>(&self, data: &SendChannel<T>, shutdown: &SendChannel<S>, f: F)
+    where
+        T: Any + Send,
+        S: Any + Send,
+        F: Fn(Either<T, S>) + Send + Sync + 'static,
+    {
+        let selected = self.send_channel::<Either<T, S>>();
+        let shut_down = Arc::new(AtomicBool::new(false));
+
+        // Both messages are passed on from the control thread, so that the
+        // flag is set before any message on `data` arriving later is seen.
+        let left = selected.clone();
+        let data_shut_down = Arc::clone(&shut_down);
+        self.when(data).then_do_inline(move |t| {
+            if !data_shut_down.load(Ordering::Acquire) {
+                let _ = left.send(Either::Left(t));
+            }
+        });
+
+        let right = selected.clone();
+        let shutdown_shut_down = Arc::clone(&shut_down);
+        self.when(shutdown).then_do_inline(move |s| {
+            shutdown_shut_down.store(true, Ordering::Release);
+            let _ = right.send(Either::Right(s));
+        });
+
+        self.when(&selected)
+            .then_do_sequential(move |next| match next {
+                Either::Left(_) if shut_down.load(Ordering::Acquire) => {}
+                next => f(next),
+            });
+    }
+}
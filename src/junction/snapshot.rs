@@ -0,0 +1,144 @@
+use std::{any::Any, collections::HashMap, sync::mpsc::channel};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{self, ErrorKind};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    channels::SendChannel,
+    junction::Junction,
+    types::{Decode, Encode, Message, Packet},
+};
+
+impl Junction {
+    /// Create and return a new `SendChannel` with the given name, whose
+    /// pending messages are included in snapshots of this `Junction`.
+    ///
+    /// If this `Junction` has been restored from a snapshot holding messages
+    /// of a channel of the same name, these messages are sent on the new
+    /// channel, ahead of any messages sent on it afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel could not be created or the request to include
+    /// it in snapshots could not be sent to the control thread.
+    pub fn snapshot_channel<T>(&self, name: impl Into<String>) -> SendChannel<T>
+    where
+        T: Any + Send + Serialize + DeserializeOwned,
+    {
+        let name = name.into();
+        let channel = self.send_channel_named::<T>(name.clone());
+
+        let encode: Encode = Box::new(|msg| {
+            msg.downcast_ref::<T>()
+                .and_then(|value| serde_json::to_value(value).ok())
+        });
+        let decode: Decode =
+            Box::new(|value| serde_json::from_value::<T>(value).ok().map(Message::new));
+
+        self.sender
+            .send(Packet::SnapshotChannel {
+                channel_id: channel.id(),
+                name,
+                encode,
+                decode,
+            })
+            .map_err(|e| log::error!("Failed to send SnapshotChannel: {e:?}"))
+            .unwrap();
+
+        channel
+    }
+
+    /// Return a snapshot of the messages pending on all channels created
+    /// through `snapshot_channel`, serialized as JSON.
+    ///
+    /// All messages sent before the call are included, unless they have been
+    /// consumed by a Join Pattern by the time the control thread takes the
+    /// snapshot. Messages are not removed from the `Junction`.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let job = j.snapshot_channel::<String>("job");
+    /// job.send("resize".to_string()).unwrap();
+    ///
+    /// let snapshot = j.snapshot();
+    /// drop(j);
+    ///
+    /// // Join Patterns are not part of the snapshot and are added again.
+    /// let j = Junction::restore(&snapshot).unwrap();
+    /// let job = j.snapshot_channel::<String>("job");
+    /// let done = j.recv_channel::<String>();
+    /// j.when(&job).and_recv(&done).then_do(|job| job);
+    ///
+    /// assert_eq!(done.recv().unwrap(), "resize");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Junction` is in manual mode, or if the snapshot could
+    /// not be requested from or received from the control thread.
+    pub fn snapshot(&self) -> Vec<u8> {
+        assert!(
+            self.manual_controller.is_none(),
+            "Junctions in manual mode cannot take snapshots"
+        );
+
+        let mut snapshot = HashMap::new();
+
+        for sender in self.controller_senders() {
+            let (return_sender, return_receiver) = channel();
+
+            sender
+                .send(Packet::SnapshotRequest { return_sender })
+                .map_err(|e| log::error!("Failed to send SnapshotRequest: {e:?}"))
+                .unwrap();
+
+            let channels: HashMap<String, Vec<Value>> = return_receiver
+                .recv()
+                .map_err(|e| log::error!("Failed to receive snapshot: {e:?}"))
+                .unwrap();
+            snapshot.extend(channels);
+        }
+
+        serde_json::to_vec(&snapshot)
+            .map_err(|e| log::error!("Failed to serialize snapshot: {e:?}"))
+            .unwrap()
+    }
+
+    /// Create a new `Junction` holding the messages of the given snapshot,
+    /// taken through `Junction::snapshot`.
+    ///
+    /// The messages of each channel are sent once a channel of the same name
+    /// is created through `snapshot_channel`, while Join Patterns have to be
+    /// added again like on any new `Junction`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `ErrorKind::InvalidData` if the snapshot is
+    /// not valid.
+    ///
+    /// Not available on `wasm32` targets, see `Junction::new`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the restored messages could not be sent to the control
+    /// thread.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn restore(snapshot: &[u8]) -> io::Result<Junction> {
+        let channels: HashMap<String, Vec<Value>> = serde_json::from_slice(snapshot)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+        let junction = Junction::new();
+        junction
+            .sender
+            .send(Packet::RestoreRequest { channels })
+            .map_err(|e| log::error!("Failed to send RestoreRequest: {e:?}"))
+            .unwrap();
+
+        Ok(junction)
+    }
+}
@@ -0,0 +1,60 @@
+use std::any::Any;
+
+use crate::{
+    channels::StateChannel,
+    junction::Junction,
+    types::{Message, Packet},
+};
+
+impl Junction {
+    /// Create and return a new `StateChannel` on this `Junction`, holding
+    /// the given initial value.
+    ///
+    /// Join Patterns including the channel read its latest value without
+    /// consuming it, so they fire whenever their other channels are ready.
+    /// Sending a new value replaces the previous one, and fires the Join
+    /// Patterns that become ready as usual.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let tax_percent = j.state_channel(20_u32);
+    /// let price = j.bidir_channel::<u32, u32>();
+    /// j.when(&tax_percent)
+    ///     .and_bidir(&price)
+    ///     .then_do(|tax, net| net * (100 + tax) / 100);
+    ///
+    /// assert_eq!(price.send_recv(100).unwrap(), 120);
+    /// assert_eq!(price.send_recv(50).unwrap(), 60);
+    ///
+    /// tax_percent.send(10).unwrap();
+    /// assert_eq!(price.send_recv(100).unwrap(), 110);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new
+    /// channel ID from the control thread, or if the initial value could
+    /// not be sent.
+    pub fn state_channel<T>(&self, initial: T) -> StateChannel<T>
+    where
+        T: Any + Send + Clone,
+    {
+        let channel = self.send_channel();
+
+        self.sender
+            .send(Packet::RetainChannel {
+                channel_id: channel.id(),
+                copy: Message::copy::<T>,
+            })
+            .map_err(|e| log::error!("Failed to send RetainChannel: {e:?}"))
+            .unwrap();
+        channel
+            .send(initial)
+            .map_err(|e| log::error!("Failed to send initial value: {e:?}"))
+            .unwrap();
+
+        StateChannel::new(channel)
+    }
+}
@@ -0,0 +1,86 @@
+//! Junctions whose `Controller` runs as a task on a Tokio runtime.
+//!
+//! Every `Junction` created through `Junction::new` or a `JunctionBuilder`
+//! occupies a control thread of its own. In deployments capping the number
+//! of threads, the `Controller` can instead be spawned as a task onto an
+//! existing runtime through `Junction::spawn_on`, where it waits for
+//! `Packet`s without blocking a worker thread. Function bodies of fired
+//! Join Patterns still run according to the `FireExecutor`.
+//!
+//! Requires the `async` feature.
+
+use std::sync::Arc;
+
+use tokio::{runtime::Handle, sync::Notify};
+
+use crate::{
+    controller::{Controller, ControllerOptions},
+    junction::{Family, Junction},
+    queue::packet_channel,
+    types::ids,
+};
+
+impl Junction {
+    /// Create a new `Junction` whose `Controller` runs as a task on the
+    /// runtime of the given `Handle` instead of in a control thread.
+    ///
+    /// The runtime needs its time driver enabled if the `Junction` uses
+    /// timers, e.g. for dead letters or `Junction::wait_idle`. Dropping the
+    /// `Junction` blocks until the task has finished, so it must not be
+    /// dropped on a current-thread runtime driving the task.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    ///
+    /// let runtime = tokio::runtime::Builder::new_multi_thread()
+    ///     .worker_threads(1)
+    ///     .enable_all()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let j = Junction::spawn_on(runtime.handle());
+    /// let value = j.send_channel::<i32>();
+    /// let get = j.recv_channel::<i32>();
+    /// j.when(&value).and_recv(&get).then_do(|v| v * 2);
+    ///
+    /// value.send(21).unwrap();
+    /// assert_eq!(get.recv().unwrap(), 42);
+    /// ```
+    ///
+    /// Not available on `wasm32` targets, where the function bodies of fired
+    /// Join Patterns could not be run, see `Junction::new`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn_on(handle: &Handle) -> Junction {
+        Junction::builder().spawn_on(handle)
+    }
+
+    /// Create a new `Junction` with a `Controller` running as a task on the
+    /// given runtime with the given options, optionally bounding its queue.
+    pub(crate) fn start_on(
+        handle: &Handle,
+        options: ControllerOptions,
+        queue_capacity: Option<usize>,
+    ) -> Junction {
+        let notify = Arc::new(Notify::new());
+        let (sender, receiver) = packet_channel(queue_capacity);
+        let sender = sender
+            .with_notify(notify.clone())
+            .with_thread_spawner(options.thread_spawner.clone());
+        let controller = Controller::with_options(options.clone());
+
+        Junction {
+            id: ids::JunctionId::new(),
+            family: Family::new(Some(controller.spawn_on(
+                handle,
+                sender.clone(),
+                receiver,
+                notify,
+            ))),
+            options,
+            queue_capacity,
+            manual_controller: None,
+            sharded_controller: None,
+            sender,
+        }
+    }
+}
@@ -1,4 +1,7 @@
 #![feature(thread_is_running)]
+// No `Junction` can be created on `wasm32` targets, leaving its internals
+// unused there.
+#![cfg_attr(target_arch = "wasm32", allow(dead_code))]
 //! Crate implementing Join Patterns from the [Join Calculus](https://www.microsoft.com/en-us/research/wp-content/uploads/2017/01/join-tutorial.pdf) developed by
 //! Cédric Fournet and Georges Gonthier.
 //!
@@ -133,14 +136,54 @@
 //! For more examples, visit the [`examples`](https://github.com/smueksch/rusty_junctions/tree/master/examples) folder in the [Rusty Junctions GitHub
 //! repository](https://github.com/smueksch/rusty_junctions).
 
+pub mod actor;
+#[cfg(feature = "attr")]
+pub mod attr;
+mod bridge;
+pub mod builder;
+pub mod cancel;
 pub mod channels;
+mod clock;
 mod controller;
+pub mod dynamic;
+mod fold;
+#[cfg(all(feature = "global", not(target_arch = "wasm32")))]
+mod global;
+pub mod intercept;
 mod join_pattern;
+#[cfg(feature = "journal")]
+pub mod journal;
 mod junction;
+pub mod local;
+pub mod metadata;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(feature = "otel")]
+pub mod otel;
+mod primitives;
+mod queue;
+pub mod registry;
+#[cfg(feature = "remote")]
+pub mod remote;
+mod shared;
+#[cfg(all(feature = "signals", unix))]
+pub mod signal;
+#[cfg(feature = "futures")]
+pub mod stream;
+pub mod supervisor;
+pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod then_do;
+pub mod trace;
+pub mod typed;
 mod types;
 
+pub use builder::JunctionBuilder;
 pub use controller::ControllerHandle;
-pub use junction::Junction;
+#[cfg(all(feature = "global", not(target_arch = "wasm32")))]
+pub use global::global;
+pub use junction::{AuditAction, AuditEvent, Idle, Junction, LatencyStats, Rate, Scope};
 pub use rusty_junctions_macro::client::junction;
 
 // Generate the library, upto an order of 32.
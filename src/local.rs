@@ -0,0 +1,798 @@
+//! Single-threaded variant of a `Junction` for message types that are not
+//! `Send`.
+//!
+//! A `LocalJunction` does not start a control thread. Instead, messages are
+//! queued up until `LocalJunction::pump` is called, which matches them
+//! against the declared Join Patterns and runs the function bodies of the
+//! fired Join Patterns on the calling thread. This allows messages to carry
+//! handles that must not leave their thread, such as `Rc`s, as is commonly
+//! the case in GUI and WebAssembly applications.
+//!
+//! As neither matching nor firing Join Patterns requires spawning threads,
+//! `LocalJunction` is also the way to use Join Patterns on targets without
+//! thread support, such as `wasm32-unknown-unknown`.
+//!
+//! Receiving on a `LocalRecvChannel` or `LocalBidirChannel` cannot wait for
+//! another thread to fire the Join Pattern replying. It pumps the
+//! `LocalJunction` itself instead and fails if no reply has been produced
+//! once all queued messages have been processed.
+//!
+//! ```
+//! use rusty_junctions::local::LocalJunction;
+//! use std::{cell::RefCell, rc::Rc};
+//!
+//! let j = LocalJunction::new();
+//! let log = j.send_channel::<Rc<RefCell<Vec<String>>>>();
+//! let line = j.send_channel::<String>();
+//!
+//! let log_clone = log.clone();
+//! j.when(&log).and(&line).then_do(move |l, s| {
+//!     l.borrow_mut().push(s);
+//!     log_clone.send(l).unwrap();
+//! });
+//!
+//! let lines = Rc::new(RefCell::new(Vec::new()));
+//! log.send(lines.clone()).unwrap();
+//! line.send(String::from("Hello")).unwrap();
+//! line.send(String::from("World")).unwrap();
+//!
+//! j.pump();
+//! assert_eq!(*lines.borrow(), vec!["Hello", "World"]);
+//! ```
+//!
+//! Replies are handed back on the same thread, so they need not be `Send`
+//! either:
+//!
+//! ```
+//! use rusty_junctions::local::LocalJunction;
+//! use std::rc::Rc;
+//!
+//! let j = LocalJunction::new();
+//! let name = j.send_channel::<Rc<str>>();
+//! let get = j.recv_channel::<Rc<str>>();
+//! let greet = j.bidir_channel::<Rc<str>, String>();
+//!
+//! let name_clone = name.clone();
+//! j.when(&name).and_recv(&get).then_do(move |n| {
+//!     name_clone.send(n.clone()).unwrap();
+//!     n
+//! });
+//! let name_clone = name.clone();
+//! j.when(&name).and_bidir(&greet).then_do(move |n, greeting| {
+//!     name_clone.send(n.clone()).unwrap();
+//!     format!("{greeting}, {n}!")
+//! });
+//!
+//! // No Join Pattern can reply before a name has been sent.
+//! assert!(get.recv().is_err());
+//!
+//! name.send(Rc::from("World")).unwrap();
+//! assert_eq!(&*get.recv().unwrap(), "World");
+//! assert_eq!(greet.send_recv(Rc::from("Hello")).unwrap(), "Hello, World!");
+//! ```
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    marker::PhantomData,
+    rc::{Rc, Weak},
+    sync::mpsc::{RecvError, SendError},
+};
+
+use bag::Bag;
+use counter::Counter;
+use inverted_index::InvertedIndex;
+
+use crate::types::ids::{ChannelId, JoinPatternId, JunctionId};
+
+/// Type-erased message stored by a `LocalJunction`.
+type LocalMessage = Box<dyn Any>;
+
+/// Type-erased function body of a Join Pattern declared on a `LocalJunction`.
+type LocalFunction = Rc<dyn Fn(Vec<LocalMessage>)>;
+
+/// Slot a Join Pattern declared on a `LocalJunction` puts its reply into,
+/// sent along with each message on a `LocalRecvChannel` or
+/// `LocalBidirChannel`.
+type LocalReply<R> = Rc<RefCell<Option<R>>>;
+
+/// Single-threaded counterpart to `Junction`.
+///
+/// Channels and Join Patterns are created the same way as with a `Junction`,
+/// but payloads and function bodies do not need to be `Send`. Nothing
+/// happens until `pump` is called on the thread owning the `LocalJunction`.
+pub struct LocalJunction {
+    id: JunctionId,
+    state: Rc<RefCell<LocalState>>,
+}
+
+#[allow(clippy::new_without_default)]
+impl LocalJunction {
+    /// Create a new `LocalJunction`.
+    pub fn new() -> LocalJunction {
+        LocalJunction {
+            id: JunctionId::new(),
+            state: Rc::new(RefCell::new(LocalState::new())),
+        }
+    }
+
+    /// Create and return a new `LocalSendChannel` on this `LocalJunction`.
+    pub fn send_channel<T: Any>(&self) -> LocalSendChannel<T> {
+        LocalSendChannel {
+            id: self.state.borrow_mut().new_channel_id(),
+            junction_id: self.id,
+            state: Rc::downgrade(&self.state),
+            send_type: PhantomData,
+        }
+    }
+
+    /// Create and return a new `LocalRecvChannel` on this `LocalJunction`.
+    pub fn recv_channel<R: Any>(&self) -> LocalRecvChannel<R> {
+        LocalRecvChannel {
+            id: self.state.borrow_mut().new_channel_id(),
+            junction_id: self.id,
+            state: Rc::downgrade(&self.state),
+            recv_type: PhantomData,
+        }
+    }
+
+    /// Create and return a new `LocalBidirChannel` on this `LocalJunction`.
+    pub fn bidir_channel<T: Any, R: Any>(&self) -> LocalBidirChannel<T, R> {
+        LocalBidirChannel {
+            id: self.state.borrow_mut().new_channel_id(),
+            junction_id: self.id,
+            state: Rc::downgrade(&self.state),
+            send_type: PhantomData,
+            recv_type: PhantomData,
+        }
+    }
+
+    /// Create new partial Join Pattern starting with a `LocalSendChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied `LocalSendChannel` has not been created by this
+    /// `LocalJunction`.
+    pub fn when<T: Any>(&self, send_channel: &LocalSendChannel<T>) -> UnaryPartialPattern<T> {
+        UnaryPartialPattern {
+            junction_id: self.id,
+            state: self.state.clone(),
+            channel: own_channel_id(self.id, send_channel.junction_id, send_channel.id),
+            types: PhantomData,
+        }
+    }
+
+    /// Create new partial Join Pattern starting with a `LocalRecvChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied `LocalRecvChannel` has not been created by this
+    /// `LocalJunction`.
+    pub fn when_recv<R: Any>(
+        &self,
+        recv_channel: &LocalRecvChannel<R>,
+    ) -> UnaryRecvPartialPattern<R> {
+        UnaryRecvPartialPattern {
+            state: self.state.clone(),
+            channels: vec![own_channel_id(
+                self.id,
+                recv_channel.junction_id,
+                recv_channel.id,
+            )],
+            types: PhantomData,
+        }
+    }
+
+    /// Create new partial Join Pattern starting with a `LocalBidirChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied `LocalBidirChannel` has not been created by
+    /// this `LocalJunction`.
+    pub fn when_bidir<T: Any, R: Any>(
+        &self,
+        bidir_channel: &LocalBidirChannel<T, R>,
+    ) -> UnaryBidirPartialPattern<T, R> {
+        UnaryBidirPartialPattern {
+            state: self.state.clone(),
+            channels: vec![own_channel_id(
+                self.id,
+                bidir_channel.junction_id,
+                bidir_channel.id,
+            )],
+            types: PhantomData,
+        }
+    }
+
+    /// Process all queued messages, firing Join Patterns as they become alive.
+    ///
+    /// Function bodies of fired Join Patterns run on the calling thread before
+    /// this function returns. Messages sent by these function bodies are
+    /// processed as well, so that on return no messages remain queued.
+    ///
+    /// Return the number of Join Patterns that have been fired.
+    pub fn pump(&self) -> usize {
+        pump(&self.state)
+    }
+}
+
+/// Process all queued messages of the `LocalJunction` with the given state,
+/// see `LocalJunction::pump`.
+fn pump(state: &RefCell<LocalState>) -> usize {
+    let mut fired = 0;
+
+    loop {
+        // Release the borrow of the state before running the function
+        // body, which may well send new messages on this junction.
+        let firing = {
+            let mut state = state.borrow_mut();
+            match state.queue.pop_front() {
+                Some((channel_id, msg)) => state.handle_message(channel_id, msg),
+                None => break,
+            }
+        };
+
+        if let Some((function, messages)) = firing {
+            function(messages);
+            fired += 1;
+        }
+    }
+
+    fired
+}
+
+/// Return the given `ChannelId` if the channel it identifies belongs to the
+/// `LocalJunction` with the given `JunctionId`.
+///
+/// # Panics
+///
+/// Panics if the channel was created by a different `LocalJunction`.
+fn own_channel_id(
+    junction_id: JunctionId,
+    channel_junction_id: JunctionId,
+    channel_id: ChannelId,
+) -> ChannelId {
+    if channel_junction_id == junction_id {
+        channel_id
+    } else {
+        panic!(
+            "Channel is not associated with LocalJunction! Please use a \
+             channel created using the same LocalJunction calling this \
+             function!"
+        );
+    }
+}
+
+/// Queue the request carrying the given reply slot on the channel with the
+/// given `ChannelId` and pump the `LocalJunction` until it is idle.
+///
+/// # Errors
+///
+/// Returns `RecvError` if the `LocalJunction` has been dropped or no Join
+/// Pattern has replied. The request then stays pending, and any reply to it
+/// later on is dropped.
+fn request<R: Any>(
+    state: &Weak<RefCell<LocalState>>,
+    channel_id: ChannelId,
+    request: LocalMessage,
+    reply: LocalReply<R>,
+) -> Result<R, RecvError> {
+    let state = state.upgrade().ok_or(RecvError)?;
+    state.borrow_mut().queue.push_back((channel_id, request));
+    pump(&state);
+
+    reply.take().ok_or(RecvError)
+}
+
+/// State of a `LocalJunction`, taking the role of the `Controller`.
+struct LocalState {
+    latest_channel_id: ChannelId,
+    latest_join_pattern_id: JoinPatternId,
+    message_counter: Counter,
+    /// Messages that have been sent but not yet handled by `pump`.
+    queue: VecDeque<(ChannelId, LocalMessage)>,
+    messages: Bag<ChannelId, LocalMessage>,
+    join_patterns: HashMap<JoinPatternId, LocalJoinPattern>,
+    join_pattern_last_fired: HashMap<JoinPatternId, Option<Counter>>,
+    join_pattern_index: InvertedIndex<ChannelId, JoinPatternId>,
+}
+
+impl LocalState {
+    fn new() -> LocalState {
+        LocalState {
+            latest_channel_id: ChannelId::default(),
+            latest_join_pattern_id: JoinPatternId::default(),
+            message_counter: Counter::default(),
+            queue: VecDeque::new(),
+            messages: Bag::new(),
+            join_patterns: HashMap::new(),
+            join_pattern_last_fired: HashMap::new(),
+            join_pattern_index: InvertedIndex::new(),
+        }
+    }
+
+    /// Generate new, *unique* `ChannelId`.
+    fn new_channel_id(&mut self) -> ChannelId {
+        let ch_id = self.latest_channel_id;
+        self.latest_channel_id.increment();
+
+        ch_id
+    }
+
+    /// Store a new Join Pattern.
+    fn add_join_pattern(&mut self, join_pattern: LocalJoinPattern) {
+        let jp_id = self.latest_join_pattern_id;
+        self.latest_join_pattern_id.increment();
+
+        join_pattern
+            .channels
+            .iter()
+            .for_each(|chan| self.join_pattern_index.insert_single(*chan, jp_id));
+        self.join_pattern_last_fired.insert(jp_id, None);
+        self.join_patterns.insert(jp_id, join_pattern);
+    }
+
+    /// Store the given message and select a Join Pattern to fire, if any.
+    ///
+    /// Return the function body of the Join Pattern to fire together with
+    /// the messages it consumes. Like the `Controller`, the alive Join
+    /// Pattern that has not been fired for the longest time is selected.
+    fn handle_message(
+        &mut self,
+        channel_id: ChannelId,
+        msg: LocalMessage,
+    ) -> Option<(LocalFunction, Vec<LocalMessage>)> {
+        self.messages.add(channel_id, msg);
+        self.message_counter.increment();
+
+        let jp_id = self
+            .join_pattern_index
+            .peek_all(&channel_id)?
+            .iter()
+            .filter(|jp_id| self.join_patterns[jp_id].is_alive(&self.messages))
+            .min_by(|jp_id_1, jp_id_2| {
+                // `None` orders before `Some`, so Join Patterns that have
+                // never been fired take precedence.
+                self.join_pattern_last_fired[jp_id_1].cmp(&self.join_pattern_last_fired[jp_id_2])
+            })
+            .copied()?;
+
+        let join_pattern = &self.join_patterns[&jp_id];
+        let messages = join_pattern
+            .channels
+            .iter()
+            .map(|chan| self.messages.retrieve(chan).unwrap())
+            .collect();
+        let function = join_pattern.function.clone();
+
+        self.join_pattern_last_fired
+            .insert(jp_id, Some(self.message_counter.clone()));
+
+        Some((function, messages))
+    }
+}
+
+/// Join Pattern declared on a `LocalJunction`.
+struct LocalJoinPattern {
+    channels: Vec<ChannelId>,
+    function: LocalFunction,
+}
+
+impl LocalJoinPattern {
+    /// Return `true` if there is a message available for every channel.
+    fn is_alive(&self, messages: &Bag<ChannelId, LocalMessage>) -> bool {
+        self.channels.iter().all(|chan| {
+            let required = self.channels.iter().filter(|c| *c == chan).count();
+            messages.count_items(chan) >= required
+        })
+    }
+}
+
+/// Asynchronous, message sending channel of a `LocalJunction`.
+///
+/// Sent messages are queued and only matched against Join Patterns once
+/// `LocalJunction::pump` is called.
+pub struct LocalSendChannel<T> {
+    id: ChannelId,
+    junction_id: JunctionId,
+    state: Weak<RefCell<LocalState>>,
+    send_type: PhantomData<T>,
+}
+
+impl<T: Any> LocalSendChannel<T> {
+    /// Queue a message on this channel.
+    ///
+    /// Fails, returning the message, if the `LocalJunction` has been dropped.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        match self.state.upgrade() {
+            Some(state) => {
+                state
+                    .borrow_mut()
+                    .queue
+                    .push_back((self.id, Box::new(value)));
+                Ok(())
+            }
+            None => Err(SendError(value)),
+        }
+    }
+}
+
+impl<T> Clone for LocalSendChannel<T> {
+    fn clone(&self) -> LocalSendChannel<T> {
+        LocalSendChannel {
+            id: self.id,
+            junction_id: self.junction_id,
+            state: self.state.clone(),
+            send_type: PhantomData,
+        }
+    }
+}
+
+/// Synchronous, value receiving channel of a `LocalJunction`.
+pub struct LocalRecvChannel<R> {
+    id: ChannelId,
+    junction_id: JunctionId,
+    state: Weak<RefCell<LocalState>>,
+    recv_type: PhantomData<R>,
+}
+
+impl<R: Any> LocalRecvChannel<R> {
+    /// Receive the value returned by a fired Join Pattern, pumping the
+    /// `LocalJunction` until it is idle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `LocalJunction` has been dropped or no Join
+    /// Pattern has replied once all queued messages have been processed.
+    pub fn recv(&self) -> Result<R, RecvError> {
+        let reply: LocalReply<R> = Rc::new(RefCell::new(None));
+
+        request(&self.state, self.id, Box::new(reply.clone()), reply)
+    }
+}
+
+impl<R> Clone for LocalRecvChannel<R> {
+    fn clone(&self) -> LocalRecvChannel<R> {
+        LocalRecvChannel {
+            id: self.id,
+            junction_id: self.junction_id,
+            state: self.state.clone(),
+            recv_type: PhantomData,
+        }
+    }
+}
+
+/// Bidirectional channel of a `LocalJunction`, sending a value and receiving
+/// the value returned by the fired Join Pattern.
+pub struct LocalBidirChannel<T, R> {
+    id: ChannelId,
+    junction_id: JunctionId,
+    state: Weak<RefCell<LocalState>>,
+    send_type: PhantomData<T>,
+    recv_type: PhantomData<R>,
+}
+
+impl<T: Any, R: Any> LocalBidirChannel<T, R> {
+    /// Send a message and receive the value returned by the fired Join
+    /// Pattern, pumping the `LocalJunction` until it is idle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `LocalJunction` has been dropped or no Join
+    /// Pattern has replied once all queued messages have been processed.
+    pub fn send_recv(&self, msg: T) -> Result<R, RecvError> {
+        let reply: LocalReply<R> = Rc::new(RefCell::new(None));
+
+        request(&self.state, self.id, Box::new((msg, reply.clone())), reply)
+    }
+}
+
+impl<T, R> Clone for LocalBidirChannel<T, R> {
+    fn clone(&self) -> LocalBidirChannel<T, R> {
+        LocalBidirChannel {
+            id: self.id,
+            junction_id: self.junction_id,
+            state: self.state.clone(),
+            send_type: PhantomData,
+            recv_type: PhantomData,
+        }
+    }
+}
+
+/// Take the message of the given type off the front of `messages`.
+fn take<T: Any>(messages: &mut VecDeque<LocalMessage>) -> T {
+    *messages.pop_front().unwrap().downcast::<T>().unwrap()
+}
+
+/// Partial Join Pattern on a `LocalJunction` with one channel.
+pub struct UnaryPartialPattern<T> {
+    junction_id: JunctionId,
+    state: Rc<RefCell<LocalState>>,
+    channel: ChannelId,
+    types: PhantomData<T>,
+}
+
+impl<T: Any> UnaryPartialPattern<T> {
+    /// Extend this partial Join Pattern by another `LocalSendChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `LocalJunction`.
+    pub fn and<U: Any>(self, send_channel: &LocalSendChannel<U>) -> BinaryPartialPattern<T, U> {
+        BinaryPartialPattern {
+            channels: [
+                self.channel,
+                own_channel_id(self.junction_id, send_channel.junction_id, send_channel.id),
+            ],
+            junction_id: self.junction_id,
+            state: self.state,
+            types: PhantomData,
+        }
+    }
+
+    /// Extend this partial Join Pattern by a `LocalRecvChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `LocalJunction`.
+    pub fn and_recv<R: Any>(
+        self,
+        recv_channel: &LocalRecvChannel<R>,
+    ) -> BinaryRecvPartialPattern<T, R> {
+        BinaryRecvPartialPattern {
+            channels: vec![
+                self.channel,
+                own_channel_id(self.junction_id, recv_channel.junction_id, recv_channel.id),
+            ],
+            state: self.state,
+            types: PhantomData,
+        }
+    }
+
+    /// Extend this partial Join Pattern by a `LocalBidirChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `LocalJunction`.
+    pub fn and_bidir<U: Any, R: Any>(
+        self,
+        bidir_channel: &LocalBidirChannel<U, R>,
+    ) -> BinaryBidirPartialPattern<T, U, R> {
+        BinaryBidirPartialPattern {
+            channels: vec![
+                self.channel,
+                own_channel_id(
+                    self.junction_id,
+                    bidir_channel.junction_id,
+                    bidir_channel.id,
+                ),
+            ],
+            state: self.state,
+            types: PhantomData,
+        }
+    }
+
+    /// Create a full Join Pattern firing `f` with the message received.
+    pub fn then_do<F>(self, f: F)
+    where
+        F: Fn(T) + 'static,
+    {
+        let function = move |messages: Vec<LocalMessage>| {
+            let mut messages = VecDeque::from(messages);
+            f(take(&mut messages))
+        };
+
+        self.state.borrow_mut().add_join_pattern(LocalJoinPattern {
+            channels: vec![self.channel],
+            function: Rc::new(function),
+        });
+    }
+}
+
+/// Partial Join Pattern on a `LocalJunction` with two channels.
+pub struct BinaryPartialPattern<T, U> {
+    junction_id: JunctionId,
+    state: Rc<RefCell<LocalState>>,
+    channels: [ChannelId; 2],
+    types: PhantomData<(T, U)>,
+}
+
+impl<T: Any, U: Any> BinaryPartialPattern<T, U> {
+    /// Extend this partial Join Pattern by another `LocalSendChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `LocalJunction`.
+    pub fn and<V: Any>(self, send_channel: &LocalSendChannel<V>) -> TernaryPartialPattern<T, U, V> {
+        let [first, second] = self.channels;
+
+        TernaryPartialPattern {
+            channels: [
+                first,
+                second,
+                own_channel_id(self.junction_id, send_channel.junction_id, send_channel.id),
+            ],
+            state: self.state,
+            types: PhantomData,
+        }
+    }
+
+    /// Extend this partial Join Pattern by a `LocalRecvChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `LocalJunction`.
+    pub fn and_recv<R: Any>(
+        self,
+        recv_channel: &LocalRecvChannel<R>,
+    ) -> TernaryRecvPartialPattern<T, U, R> {
+        let [first, second] = self.channels;
+
+        TernaryRecvPartialPattern {
+            channels: vec![
+                first,
+                second,
+                own_channel_id(self.junction_id, recv_channel.junction_id, recv_channel.id),
+            ],
+            state: self.state,
+            types: PhantomData,
+        }
+    }
+
+    /// Extend this partial Join Pattern by a `LocalBidirChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `LocalJunction`.
+    pub fn and_bidir<V: Any, R: Any>(
+        self,
+        bidir_channel: &LocalBidirChannel<V, R>,
+    ) -> TernaryBidirPartialPattern<T, U, V, R> {
+        let [first, second] = self.channels;
+
+        TernaryBidirPartialPattern {
+            channels: vec![
+                first,
+                second,
+                own_channel_id(
+                    self.junction_id,
+                    bidir_channel.junction_id,
+                    bidir_channel.id,
+                ),
+            ],
+            state: self.state,
+            types: PhantomData,
+        }
+    }
+
+    /// Create a full Join Pattern firing `f` with the messages received.
+    pub fn then_do<F>(self, f: F)
+    where
+        F: Fn(T, U) + 'static,
+    {
+        let function = move |messages: Vec<LocalMessage>| {
+            let mut messages = VecDeque::from(messages);
+            let t = take(&mut messages);
+            f(t, take(&mut messages))
+        };
+
+        self.state.borrow_mut().add_join_pattern(LocalJoinPattern {
+            channels: self.channels.to_vec(),
+            function: Rc::new(function),
+        });
+    }
+}
+
+/// Partial Join Pattern on a `LocalJunction` with three channels.
+pub struct TernaryPartialPattern<T, U, V> {
+    state: Rc<RefCell<LocalState>>,
+    channels: [ChannelId; 3],
+    types: PhantomData<(T, U, V)>,
+}
+
+impl<T: Any, U: Any, V: Any> TernaryPartialPattern<T, U, V> {
+    /// Create a full Join Pattern firing `f` with the messages received.
+    pub fn then_do<F>(self, f: F)
+    where
+        F: Fn(T, U, V) + 'static,
+    {
+        let function = move |messages: Vec<LocalMessage>| {
+            let mut messages = VecDeque::from(messages);
+            let t = take(&mut messages);
+            let u = take(&mut messages);
+            f(t, u, take(&mut messages))
+        };
+
+        self.state.borrow_mut().add_join_pattern(LocalJoinPattern {
+            channels: self.channels.to_vec(),
+            function: Rc::new(function),
+        });
+    }
+}
+
+/// Declare a partial Join Pattern on a `LocalJunction` whose last channel
+/// replies to its caller.
+///
+/// The arguments list the kind of the last channel, `recv` or `bidir`, the
+/// name and generic parameters of the partial Join Pattern, the arguments of
+/// the function it fires, with the argument taken from a `LocalBidirChannel`
+/// listed on its own, and the return type of the function.
+macro_rules! reply_partial_pattern {
+    ($(#[$doc:meta])* recv $name:ident<$($generic:ident),*>, ($($arg:ident: $arg_type:ident),*) -> $ret:ident) => {
+        reply_partial_pattern!(@impl $(#[$doc])* $name<$($generic),*>, ($($arg_type),*) -> $ret, f, messages, {
+            $(let $arg = take::<$arg_type>(&mut messages);)*
+            let reply = take::<LocalReply<$ret>>(&mut messages);
+            reply.replace(Some(f($($arg),*)));
+        });
+    };
+    ($(#[$doc:meta])* bidir $name:ident<$($generic:ident),*>, ($($arg:ident: $arg_type:ident),*) ($last:ident: $last_type:ident) -> $ret:ident) => {
+        reply_partial_pattern!(@impl $(#[$doc])* $name<$($generic),*>, ($($arg_type,)* $last_type) -> $ret, f, messages, {
+            $(let $arg = take::<$arg_type>(&mut messages);)*
+            let ($last, reply) = take::<($last_type, LocalReply<$ret>)>(&mut messages);
+            reply.replace(Some(f($($arg,)* $last)));
+        });
+    };
+    (@impl $(#[$doc:meta])* $name:ident<$($generic:ident),*>, ($($arg_type:ident),*) -> $ret:ident, $f:ident, $messages:ident, $run:block) => {
+        $(#[$doc])*
+        pub struct $name<$($generic),*> {
+            state: Rc<RefCell<LocalState>>,
+            channels: Vec<ChannelId>,
+            types: PhantomData<($($generic,)*)>,
+        }
+
+        impl<$($generic: Any),*> $name<$($generic),*> {
+            /// Create a full Join Pattern firing `f` with the messages
+            /// received, replying with the value it returns.
+            pub fn then_do<F>(self, $f: F)
+            where
+                F: Fn($($arg_type),*) -> $ret + 'static,
+            {
+                let function = move |$messages: Vec<LocalMessage>| {
+                    let mut $messages = VecDeque::from($messages);
+                    $run
+                };
+
+                self.state.borrow_mut().add_join_pattern(LocalJoinPattern {
+                    channels: self.channels,
+                    function: Rc::new(function),
+                });
+            }
+        }
+    };
+}
+
+reply_partial_pattern!(
+    /// Partial Join Pattern on a `LocalJunction` with a `LocalRecvChannel`.
+    recv UnaryRecvPartialPattern<R>, () -> R
+);
+reply_partial_pattern!(
+    /// Partial Join Pattern on a `LocalJunction` with a `LocalBidirChannel`.
+    bidir UnaryBidirPartialPattern<T, R>, () (t: T) -> R
+);
+reply_partial_pattern!(
+    /// Partial Join Pattern on a `LocalJunction` with a channel followed by
+    /// a `LocalRecvChannel`.
+    recv BinaryRecvPartialPattern<T, R>, (t: T) -> R
+);
+reply_partial_pattern!(
+    /// Partial Join Pattern on a `LocalJunction` with a channel followed by
+    /// a `LocalBidirChannel`.
+    bidir BinaryBidirPartialPattern<T, U, R>, (t: T) (u: U) -> R
+);
+reply_partial_pattern!(
+    /// Partial Join Pattern on a `LocalJunction` with two channels followed
+    /// by a `LocalRecvChannel`.
+    recv TernaryRecvPartialPattern<T, U, R>, (t: T, u: U) -> R
+);
+reply_partial_pattern!(
+    /// Partial Join Pattern on a `LocalJunction` with two channels followed
+    /// by a `LocalBidirChannel`.
+    bidir TernaryBidirPartialPattern<T, U, V, R>, (t: T, u: U) (v: V) -> R
+);
@@ -0,0 +1,98 @@
+//! Metadata carried by messages alongside their values.
+//!
+//! Request tracing needs a correlation ID, trace context or the identity of
+//! the sender to flow through Join Patterns, which would otherwise have to
+//! be added to the type of every value sent. `Metadata` is instead attached
+//! to a message when sending it, e.g. through `SendChannel::send_with_meta`,
+//! and handed to function bodies declared through `then_do_with_meta`, one
+//! per channel of the Join Pattern.
+//!
+//! ```
+//! use rusty_junctions::{metadata::Metadata, Junction};
+//!
+//! let j = Junction::new();
+//! let order = j.send_channel::<u32>();
+//! let confirm = j.recv_channel::<String>();
+//! j.when(&order).and_recv(&confirm).then_do_with_meta(|meta, order| {
+//!     let id = meta[0].correlation_id().unwrap_or("unknown");
+//!     format!("order {order} ({id})")
+//! });
+//!
+//! let meta = Metadata::new().with_correlation_id("req-42");
+//! order.send_with_meta(7, meta).unwrap();
+//! assert_eq!(confirm.recv().unwrap(), "order 7 (req-42)");
+//! ```
+
+use std::collections::BTreeMap;
+
+/// Key of the ID correlating messages belonging to the same request.
+pub const CORRELATION_ID: &str = "correlation-id";
+
+/// Key of the trace context, e.g. a W3C `traceparent` header.
+pub const TRACE_CONTEXT: &str = "traceparent";
+
+/// Key of the tag identifying the sender of a message.
+pub const SENDER: &str = "sender";
+
+/// Entries of metadata attached to a message, by key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    entries: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Create empty metadata.
+    pub fn new() -> Metadata {
+        Metadata::default()
+    }
+
+    /// Return the metadata with the given entry added, replacing any entry
+    /// of the same key.
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Metadata {
+        self.insert(key, value);
+        self
+    }
+
+    /// Return the metadata with the given correlation ID.
+    pub fn with_correlation_id(self, correlation_id: impl Into<String>) -> Metadata {
+        self.with(CORRELATION_ID, correlation_id)
+    }
+
+    /// Add the given entry, returning the value of any entry of the same key
+    /// it replaces.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.entries.insert(key.into(), value.into())
+    }
+
+    /// Return the value of the entry of the given key, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    /// Return the correlation ID, if any.
+    pub fn correlation_id(&self) -> Option<&str> {
+        self.get(CORRELATION_ID)
+    }
+
+    /// Return the trace context, if any.
+    pub fn trace_context(&self) -> Option<&str> {
+        self.get(TRACE_CONTEXT)
+    }
+
+    /// Return the tag of the sender, if any.
+    pub fn sender(&self) -> Option<&str> {
+        self.get(SENDER)
+    }
+
+    /// Return `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return the entries in order of their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
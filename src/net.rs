@@ -0,0 +1,148 @@
+//! Channels exchanging frames over TCP.
+//!
+//! Frames are byte buffers sent with a length prefix, a 4 byte unsigned
+//! integer in big-endian byte order, so that they arrive as sent rather than
+//! split up or merged as the stream sees fit.
+//!
+//! Requires the `net` feature.
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::TcpStream,
+};
+
+use crate::{channels::SendChannel, Junction};
+
+/// Largest frame accepted by `read_frame`, guarding against allocating
+/// buffers for garbled length prefixes.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Channels of a `Junction` connected to a `TcpStream`, see
+/// `Junction::tcp_channels`.
+pub struct TcpChannels {
+    /// Channel receiving every frame read from the stream.
+    pub incoming: SendChannel<Vec<u8>>,
+    /// Channel whose messages are written to the stream as frames.
+    pub outgoing: SendChannel<Vec<u8>>,
+}
+
+impl Junction {
+    /// Connect the given `TcpStream` to two new channels on this `Junction`.
+    ///
+    /// A thread is spawned that reads frames from the stream and sends them
+    /// on the `incoming` channel until the stream is closed. A Join Pattern
+    /// on the `outgoing` channel writes every message sent on it to the
+    /// stream as a frame, one at a time. Errors reading or writing are
+    /// logged, after which reading stops and writing carries on with the
+    /// next frame.
+    ///
+    /// ```
+    /// use std::{net::{TcpListener, TcpStream}, thread};
+    /// use rusty_junctions::{net, Junction};
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let address = listener.local_addr().unwrap();
+    ///
+    /// // An echo server, sending back every frame it reads.
+    /// thread::spawn(move || {
+    ///     let (mut stream, _) = listener.accept().unwrap();
+    ///     while let Ok(frame) = net::read_frame(&mut stream) {
+    ///         net::write_frame(&mut stream, &frame).unwrap();
+    ///     }
+    /// });
+    ///
+    /// let j = Junction::new();
+    /// let connection = j.tcp_channels(TcpStream::connect(address).unwrap()).unwrap();
+    /// let reply = j.recv_channel::<Vec<u8>>();
+    /// j.when(&connection.incoming).and_recv(&reply).then_do(|frame| frame);
+    ///
+    /// connection.outgoing.send(b"ping".to_vec()).unwrap();
+    /// assert_eq!(reply.recv().unwrap(), b"ping");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream could not be cloned for reading and
+    /// writing at the same time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new channel
+    /// ID from the control thread, or if the thread reading the frames could
+    /// not be spawned.
+    pub fn tcp_channels(&self, stream: TcpStream) -> io::Result<TcpChannels> {
+        let mut reader = stream.try_clone()?;
+
+        let incoming = self.bridge(move |channel| loop {
+            let frame = match read_frame(&mut reader) {
+                Ok(frame) => frame,
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => {
+                    log::error!("Failed to read frame: {e:?}");
+                    break;
+                }
+            };
+            if channel.send(frame).is_err() {
+                break;
+            }
+        });
+
+        let outgoing = self.send_channel::<Vec<u8>>();
+        self.when(&outgoing)
+            .then_do_with_state(stream, |stream, frame: Vec<u8>| {
+                if let Err(e) = write_frame(stream, &frame) {
+                    log::error!("Failed to write frame: {e:?}");
+                }
+            });
+
+        Ok(TcpChannels { incoming, outgoing })
+    }
+}
+
+/// Read a single length-prefixed frame.
+///
+/// # Errors
+///
+/// Returns an error of kind `ErrorKind::UnexpectedEof` if the reader ends
+/// before a whole frame has been read, of kind `ErrorKind::InvalidData` if
+/// the frame is longer than `MAX_FRAME_LEN`, or any other error of the
+/// reader.
+pub fn read_frame(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("frame of {len} bytes exceeds the maximum of {MAX_FRAME_LEN}"),
+        ));
+    }
+
+    let mut frame = vec![0; len];
+    reader.read_exact(&mut frame)?;
+
+    Ok(frame)
+}
+
+/// Write the given frame with its length prefix and flush the writer.
+///
+/// # Errors
+///
+/// Returns an error of kind `ErrorKind::InvalidInput` if the frame is longer
+/// than `MAX_FRAME_LEN`, or any error of the writer.
+pub fn write_frame(writer: &mut impl Write, frame: &[u8]) -> io::Result<()> {
+    if frame.len() > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "frame of {} bytes exceeds the maximum of {MAX_FRAME_LEN}",
+                frame.len()
+            ),
+        ));
+    }
+
+    writer.write_all(&(frame.len() as u32).to_be_bytes())?;
+    writer.write_all(frame)?;
+    writer.flush()
+}
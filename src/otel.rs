@@ -0,0 +1,166 @@
+//! OpenTelemetry context propagation through Join Patterns.
+//!
+//! Messages are matched on the control thread and function bodies run in
+//! threads of their own, so the trace context of the thread sending a
+//! message would otherwise be lost once a Join Pattern fires. With the
+//! `otel` feature enabled, every message sent while a valid span context is
+//! current carries that span context in its `Metadata`, under
+//! `metadata::TRACE_CONTEXT` in the W3C Trace Context format. Messages sent
+//! with a trace context of their own, e.g. through
+//! `SendChannel::send_with_meta`, keep theirs.
+//!
+//! The trace context of the first message carrying one is restored in the
+//! thread running the function body of a fired Join Pattern, so that spans
+//! started there continue the trace. The thread running the function body
+//! of a Join Pattern declared through `then_do`, or any of the variants
+//! building on it, keeps the trace context current until it ends, as it is
+//! restored by the code generated for `then_do` taking the values of the
+//! messages.
+//!
+//! ```
+//! use opentelemetry::{
+//!     trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+//!     Context,
+//! };
+//! use rusty_junctions::Junction;
+//!
+//! let j = Junction::new();
+//! let job = j.send_channel::<u32>();
+//! let traced = j.recv_channel::<TraceId>();
+//! j.when(&job).and_recv(&traced).then_do_with_meta(|_, _| {
+//!     Context::current().span().span_context().trace_id()
+//! });
+//!
+//! // Usually the span context of the current span, e.g. of a request.
+//! let trace_id = TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap();
+//! let span_id = SpanId::from_hex("00f067aa0ba902b7").unwrap();
+//! let span_context =
+//!     SpanContext::new(trace_id, span_id, TraceFlags::SAMPLED, true, TraceState::default());
+//! let _guard = Context::current()
+//!     .with_remote_span_context(span_context)
+//!     .attach();
+//!
+//! job.send(1).unwrap();
+//! assert_eq!(traced.recv().unwrap(), trace_id);
+//!
+//! // Function bodies declared through `then_do` continue the trace as well.
+//! let plain = j.recv_channel::<TraceId>();
+//! j.when(&job).and_recv(&plain).then_do(|_| {
+//!     Context::current().span().span_context().trace_id()
+//! });
+//!
+//! job.send(2).unwrap();
+//! assert_eq!(plain.recv().unwrap(), trace_id);
+//! ```
+
+use std::cell::RefCell;
+
+use opentelemetry::{
+    trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+    Context, ContextGuard,
+};
+
+use crate::{
+    metadata::{Metadata, TRACE_CONTEXT},
+    types::Message,
+};
+
+/// Key of the vendor-specific part of the trace context, i.e. the W3C
+/// `tracestate` header.
+const TRACE_STATE: &str = "tracestate";
+
+/// Version of the W3C Trace Context format written and read.
+const VERSION: &str = "00";
+
+thread_local! {
+    /// Trace context restored on this thread by `restore`, kept current
+    /// until the thread ends.
+    static RESTORED: RefCell<Option<ContextGuard>> = const { RefCell::new(None) };
+}
+
+/// Attach the span context current on the calling thread to the given
+/// `Message`, unless there is none or the `Message` carries a trace context
+/// already.
+pub(crate) fn inject(mut msg: Message) -> Message {
+    let cx = Context::current();
+    let span = cx.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() || msg.metadata().and_then(Metadata::trace_context).is_some() {
+        return msg;
+    }
+
+    let metadata = msg.metadata_mut();
+    metadata.insert(
+        TRACE_CONTEXT,
+        format!(
+            "{VERSION}-{:032x}-{:016x}-{:02x}",
+            span_context.trace_id(),
+            span_context.span_id(),
+            span_context.trace_flags()
+        ),
+    );
+    let trace_state = span_context.trace_state().header();
+    if !trace_state.is_empty() {
+        metadata.insert(TRACE_STATE, trace_state);
+    }
+
+    msg
+}
+
+/// Return the current `Context` with the span context carried by the given
+/// `Metadata` as its remote parent, if it carries a valid one.
+pub fn context(metadata: &Metadata) -> Option<Context> {
+    let mut parts = metadata.trace_context()?.split('-');
+    let (Some(VERSION), Some(trace_id), Some(span_id), Some(flags), None) = (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    ) else {
+        return None;
+    };
+    if trace_id.len() != 32 || span_id.len() != 16 || flags.len() != 2 {
+        return None;
+    }
+
+    let span_context = SpanContext::new(
+        TraceId::from_hex(trace_id).ok()?,
+        SpanId::from_hex(span_id).ok()?,
+        TraceFlags::new(u8::from_str_radix(flags, 16).ok()?),
+        true,
+        metadata
+            .get(TRACE_STATE)
+            .and_then(|trace_state| trace_state.parse::<TraceState>().ok())
+            .unwrap_or_default(),
+    );
+
+    span_context
+        .is_valid()
+        .then(|| Context::current().with_remote_span_context(span_context))
+}
+
+/// Make the trace context of the first of the given `Message`s carrying one
+/// current on the calling thread, until the returned guard is dropped.
+pub(crate) fn attach(messages: &[Message]) -> Option<ContextGuard> {
+    messages
+        .iter()
+        .filter_map(Message::metadata)
+        .find_map(context)
+        .map(Context::attach)
+}
+
+/// Make the trace context carried by the given `Metadata`, if any, current
+/// on the calling thread until it ends, replacing any restored before.
+pub(crate) fn restore(metadata: Option<&Metadata>) {
+    let Some(cx) = metadata.and_then(context) else {
+        return;
+    };
+
+    RESTORED.with(|restored| {
+        // Detach the previous trace context first, as guards have to be
+        // dropped in the reverse order of attaching them.
+        drop(restored.borrow_mut().take());
+        *restored.borrow_mut() = Some(cx.attach());
+    });
+}
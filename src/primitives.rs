@@ -0,0 +1,20 @@
+//! Synchronization primitives used internally, taken from `loom` instead of
+//! the standard library when compiled with `--cfg loom`.
+//!
+//! `loom` runs a test once for every possible interleaving of the threads it
+//! spawns, which only covers the code built on the primitives in this
+//! module. The queues of `Controller`s and the threads fired Join Patterns
+//! run on are handed to and from the Join Patterns generated by
+//! `rusty-junctions-macro` as their `std` types, so they cannot be swapped
+//! and are left out.
+//!
+//! The interleaving tests are run with
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --lib
+//! ```
+
+#[cfg(loom)]
+pub(crate) use loom::sync::{Arc, Condvar, Mutex};
+#[cfg(not(loom))]
+pub(crate) use std::sync::{Arc, Condvar, Mutex};
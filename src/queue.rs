@@ -0,0 +1,508 @@
+//! Queue carrying `Packet`s from a `Junction` and its channels to the
+//! `Controller`.
+//!
+//! By default, the queue is a `std::sync::mpsc` channel. With the `crossbeam`
+//! feature enabled, `crossbeam-channel` is used instead, which performs better
+//! under heavy contention from many sending threads. Either kind of queue can
+//! be bounded, in which case senders block while it is full.
+//!
+//! Control `Packet`s, such as requests for new channel IDs, names for
+//! channels or cancellation, are sent through a separate, unbounded control
+//! queue that `PacketReceiver` drains before handing out any other
+//! `Packet`, so administrative operations are never starved behind a deep
+//! backlog of `Message`s, and never block on a full queue. The partial Join
+//! Patterns hand their finished Join Patterns to the `Controller` through
+//! the control queue as well, using the `std::sync::mpsc::Sender` provided
+//! by `PacketSender::control_sender`, so a Join Pattern is always added
+//! before any `Packet` that was sent after its registration.
+//!
+//! `Packet`s whose effect depends on the `Message`s sent before them, such
+//! as requests to shut down or to move channels elsewhere, or reports of
+//! dropped channels, go through the main queue along with the `Message`s,
+//! see `is_control`.
+//!
+//! For a sharded `Junction`, the queue leads to the coordinator registering
+//! Join Patterns, while `Message`s are routed directly to the shards.
+//!
+//! Function bodies of fired Join Patterns may send on the channels of their
+//! own `Junction` at any time. They usually run in threads of their own,
+//! which the `Controller` never waits for, so a body blocking on a full
+//! bounded queue is unblocked as soon as the `Controller` handles the next
+//! `Packet`. Function bodies run inline on the control thread, see
+//! `then_do_inline`, are the `Controller` themselves and must not block on
+//! its queue. Their `Packet`s are sent through the control queue instead if
+//! the main queue is bounded, which keeps them in the order they have been
+//! sent in, and hands them to the `Controller` once the body has returned.
+//! `Message`s sent from a function body are always handled after the
+//! `Packet` whose handling fired the Join Pattern, like any others sent
+//! at that point.
+//!
+//! Once the control thread has panicked, the queue is poisoned and refuses
+//! all `Packet`s until the `Junction` is healed, see `Junction::heal`.
+//!
+//! With the `metrics` feature enabled, both halves share a count of the
+//! `Packet`s waiting in the main queue, reported by the `Controller`.
+//!
+//! A `Controller` running as a task, see `Junction::spawn_on`, cannot block
+//! on the queue. It is woken through a `tokio::sync::Notify` instead, which
+//! `PacketSender` notifies for every `Packet` sent through the main queue.
+
+#[cfg(feature = "metrics")]
+use std::sync::atomic::AtomicUsize;
+#[cfg(not(feature = "crossbeam"))]
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvError, RecvTimeoutError, SendError, Sender, TryRecvError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "async")]
+use tokio::sync::Notify;
+
+use crate::{builder::ThreadSpawner, controller::Router, join_pattern, types::Packet};
+
+thread_local! {
+    /// Whether the calling thread is handling `Packet`s as a `Controller`,
+    /// see `handling`.
+    static HANDLING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` as the `Controller` handling the `Packet`s of a queue.
+///
+/// `Packet::ChannelDropped`s sent from within `f`, as the `Controller` drops
+/// the last handle to a channel held by a `Message` or Join Pattern, go
+/// through the control queue if the main queue is bounded, as the calling
+/// thread would otherwise wait for itself to make room.
+pub(crate) fn handling<T>(f: impl FnOnce() -> T) -> T {
+    /// Restores `HANDLING` once `f` has returned or panicked.
+    struct Reset(bool);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            HANDLING.with(|handling| handling.set(self.0));
+        }
+    }
+
+    let _reset = Reset(HANDLING.with(|handling| handling.replace(true)));
+
+    f()
+}
+
+/// Return `true` if `packet` has to go through the control queue, as it
+/// would otherwise block on a full bounded queue the calling thread is
+/// meant to drain.
+fn would_block_controller(packet: &Packet) -> bool {
+    join_pattern::is_inline()
+        || (HANDLING.with(Cell::get) && matches!(packet, Packet::ChannelDropped { .. }))
+}
+
+/// Return `true` if the given `Packet` is sent through the control queue,
+/// overtaking any `Message`s waiting in the main queue.
+fn is_control(packet: &Packet) -> bool {
+    match packet {
+        Packet::NewChannelIdRequest { .. }
+        | Packet::NameChannel { .. }
+        | Packet::Persist { .. }
+        | Packet::RetainChannel { .. }
+        | Packet::GatePatterns { .. }
+        | Packet::TypeChannel { .. }
+        | Packet::TapRequest { .. }
+        | Packet::RateLimit { .. }
+        | Packet::AddJoinPatternRequest { .. }
+        | Packet::ProbeRequest { .. }
+        | Packet::CancelRequest
+        | Packet::IdleRequest { .. } => true,
+        #[cfg(feature = "snapshot")]
+        Packet::SnapshotChannel { .. } | Packet::RestoreRequest { .. } => true,
+        #[cfg(feature = "snapshot")]
+        Packet::SnapshotRequest { .. } => false,
+        #[cfg(feature = "testing")]
+        Packet::FireCountRequest { .. } => false,
+        Packet::Message { .. }
+        | Packet::Messages { .. }
+        | Packet::CloseChannel { .. }
+        | Packet::ChannelDropped { .. }
+        | Packet::Subscribe { .. }
+        | Packet::Revoke { .. }
+        | Packet::GateRequest { .. }
+        | Packet::HandOffRequest { .. }
+        | Packet::Adopt { .. }
+        | Packet::MigrateRequest { .. }
+        | Packet::DrainRequest { .. }
+        | Packet::MergeRequest { .. }
+        | Packet::ExportDotRequest { .. }
+        | Packet::LatencyStatsRequest { .. }
+        | Packet::AuditLogRequest { .. }
+        | Packet::AdvanceClock { .. }
+        | Packet::ShutDownRequest
+        | Packet::Wake => false,
+    }
+}
+
+/// Return `true` if the given `Packet` concerns a single channel, and is
+/// therefore routed to the shard owning the channel in a sharded `Junction`.
+fn is_routed(packet: &Packet) -> bool {
+    match packet {
+        Packet::Message { .. }
+        | Packet::Messages { .. }
+        | Packet::CloseChannel { .. }
+        | Packet::Subscribe { .. }
+        | Packet::ChannelDropped { .. }
+        | Packet::NameChannel { .. }
+        | Packet::Persist { .. }
+        | Packet::Revoke { .. }
+        | Packet::RetainChannel { .. }
+        | Packet::GatePatterns { .. }
+        | Packet::GateRequest { .. }
+        | Packet::TypeChannel { .. }
+        | Packet::TapRequest { .. }
+        | Packet::RateLimit { .. }
+        | Packet::MigrateRequest { .. }
+        | Packet::DrainRequest { .. } => true,
+        #[cfg(feature = "snapshot")]
+        Packet::SnapshotChannel { .. } => true,
+        _ => false,
+    }
+}
+
+/// Create a new queue for `Packet`s, bounded to `capacity` if given.
+pub(crate) fn packet_channel(capacity: Option<usize>) -> (PacketSender, PacketReceiver) {
+    let (control_sender, control_receiver) = mpsc::channel::<Packet>();
+    let (sender, receiver) = main_channel(capacity);
+    #[cfg(feature = "metrics")]
+    let depth = Arc::new(AtomicUsize::new(0));
+
+    (
+        PacketSender {
+            sender,
+            control_sender,
+            router: None,
+            thread_spawner: ThreadSpawner::default(),
+            poisoned: Arc::new(AtomicBool::new(false)),
+            #[cfg(feature = "async")]
+            notify: None,
+            #[cfg(feature = "metrics")]
+            depth: depth.clone(),
+        },
+        PacketReceiver {
+            receiver,
+            control_receiver,
+            buffer: RefCell::new(VecDeque::new()),
+            #[cfg(feature = "metrics")]
+            depth,
+        },
+    )
+}
+
+#[cfg(feature = "crossbeam")]
+type MainSender = crossbeam_channel::Sender<Packet>;
+
+#[cfg(feature = "crossbeam")]
+type MainReceiver = crossbeam_channel::Receiver<Packet>;
+
+#[cfg(feature = "crossbeam")]
+fn main_channel(capacity: Option<usize>) -> (MainSender, MainReceiver) {
+    match capacity {
+        Some(capacity) => crossbeam_channel::bounded(capacity),
+        None => crossbeam_channel::unbounded(),
+    }
+}
+
+/// Sending half of the main queue, which is only a `SyncSender` if bounded.
+#[cfg(not(feature = "crossbeam"))]
+#[derive(Clone)]
+enum MainSender {
+    Unbounded(Sender<Packet>),
+    Bounded(SyncSender<Packet>),
+}
+
+#[cfg(not(feature = "crossbeam"))]
+type MainReceiver = mpsc::Receiver<Packet>;
+
+#[cfg(not(feature = "crossbeam"))]
+fn main_channel(capacity: Option<usize>) -> (MainSender, MainReceiver) {
+    match capacity {
+        Some(capacity) => {
+            let (sender, receiver) = mpsc::sync_channel(capacity);
+            (MainSender::Bounded(sender), receiver)
+        }
+        None => {
+            let (sender, receiver) = mpsc::channel();
+            (MainSender::Unbounded(sender), receiver)
+        }
+    }
+}
+
+/// Sending half of a `Packet` queue.
+#[derive(Clone)]
+pub struct PacketSender {
+    sender: MainSender,
+    control_sender: Sender<Packet>,
+    /// `Router` to send `Message`s to the shards of a sharded `Junction`.
+    router: Option<Arc<Router>>,
+    /// `ThreadSpawner` of the `Junction`, for the helper threads spawned on
+    /// behalf of its channels, see `RecvChannel::into_stream`.
+    thread_spawner: ThreadSpawner,
+    /// Set while the control thread has panicked and the `Junction` has not
+    /// been healed yet.
+    poisoned: Arc<AtomicBool>,
+    /// `Notify` waking a `Controller` running as a task.
+    #[cfg(feature = "async")]
+    notify: Option<Arc<Notify>>,
+    /// Number of `Packet`s in the main queue.
+    #[cfg(feature = "metrics")]
+    depth: Arc<AtomicUsize>,
+}
+
+impl PacketSender {
+    /// Route all `Packet`s concerning a single channel, such as
+    /// `Packet::Message`s and `Packet::NameChannel`s, through the given
+    /// `Router` instead.
+    pub(crate) fn with_router(mut self, router: Arc<Router>) -> PacketSender {
+        self.router = Some(router);
+        self
+    }
+
+    /// Spawn the helper threads of the channels of the `Junction` through the
+    /// given `ThreadSpawner`.
+    pub(crate) fn with_thread_spawner(mut self, thread_spawner: ThreadSpawner) -> PacketSender {
+        self.thread_spawner = thread_spawner;
+        self
+    }
+
+    /// Notify the given `Notify` whenever a `Packet` has been sent through
+    /// the main queue.
+    #[cfg(feature = "async")]
+    pub(crate) fn with_notify(mut self, notify: Arc<Notify>) -> PacketSender {
+        self.notify = Some(notify);
+        self
+    }
+
+    /// Send a `Packet` to the `Controller`.
+    ///
+    /// Blocks while a bounded queue is full, unless the `Packet` is sent
+    /// through the control queue, as it is when sent from a function body
+    /// run inline.
+    pub(crate) fn send(&self, packet: Packet) -> Result<(), SendError<Packet>> {
+        if self.is_poisoned() {
+            return Err(SendError(packet));
+        }
+
+        if let Some(router) = &self.router {
+            if is_routed(&packet) {
+                return router.send(packet);
+            }
+        }
+
+        if is_control(&packet) || (self.is_bounded() && would_block_controller(&packet)) {
+            self.control_sender.send(packet)?;
+
+            // Wake up the `Controller` in case it is waiting on the main
+            // queue. If the queue is full, it is about to wake up anyway.
+            let _ = self.send_main(Packet::Wake, false);
+            return Ok(());
+        }
+
+        self.send_main(packet, true)
+    }
+
+    /// Return `true` if the main queue is bounded.
+    fn is_bounded(&self) -> bool {
+        #[cfg(feature = "crossbeam")]
+        return self.sender.capacity().is_some();
+
+        #[cfg(not(feature = "crossbeam"))]
+        matches!(self.sender, MainSender::Bounded(_))
+    }
+
+    /// Send a `Packet` through the main queue, blocking while a bounded queue
+    /// is full only if asked to, and dropping the `Packet` otherwise.
+    fn send_main(&self, packet: Packet, block: bool) -> Result<(), SendError<Packet>> {
+        // Counted before sending, so that the receiving side never sees a
+        // `Packet` that has not been counted yet.
+        #[cfg(feature = "metrics")]
+        self.depth.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "crossbeam")]
+        let result = if block {
+            self.sender
+                .send(packet)
+                .map_err(|e| SendError(e.into_inner()))
+        } else {
+            self.sender
+                .try_send(packet)
+                .map_err(|e| SendError(e.into_inner()))
+        };
+
+        #[cfg(not(feature = "crossbeam"))]
+        let result = match &self.sender {
+            MainSender::Unbounded(sender) => sender.send(packet),
+            MainSender::Bounded(sender) if block => sender.send(packet),
+            MainSender::Bounded(sender) => sender.try_send(packet).map_err(|e| match e {
+                TrySendError::Full(packet) | TrySendError::Disconnected(packet) => {
+                    SendError(packet)
+                }
+            }),
+        };
+
+        #[cfg(feature = "metrics")]
+        if result.is_err() {
+            self.depth.fetch_sub(1, Ordering::Relaxed);
+        }
+
+        #[cfg(feature = "async")]
+        if let (Ok(()), Some(notify)) = (&result, &self.notify) {
+            notify.notify_one();
+        }
+
+        result
+    }
+
+    /// Return `true` if the control thread has panicked and the `Junction`
+    /// has not been healed yet.
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    /// Mark the queue as poisoned or healed.
+    pub(crate) fn set_poisoned(&self, poisoned: bool) {
+        self.poisoned.store(poisoned, Ordering::Release);
+    }
+
+    /// Return the `ThreadSpawner` of the `Junction`.
+    #[cfg(feature = "futures")]
+    pub(crate) fn thread_spawner(&self) -> &ThreadSpawner {
+        &self.thread_spawner
+    }
+
+    /// Return a `Sender` into the control queue, e.g. for partial Join
+    /// Patterns to register their full Join Patterns with.
+    pub(crate) fn control_sender(&self) -> Sender<Packet> {
+        self.control_sender.clone()
+    }
+}
+
+/// Receiving half of a `Packet` queue.
+pub(crate) struct PacketReceiver {
+    receiver: MainReceiver,
+    control_receiver: mpsc::Receiver<Packet>,
+    /// `Packet`s that have been received, but are queued up behind control
+    /// `Packet`s that arrived in the meantime.
+    buffer: RefCell<VecDeque<Packet>>,
+    /// Number of `Packet`s in the main queue.
+    #[cfg(feature = "metrics")]
+    depth: Arc<AtomicUsize>,
+}
+
+impl PacketReceiver {
+    /// Block until the next `Packet` is available.
+    pub(crate) fn recv(&self) -> Result<Packet, RecvError> {
+        loop {
+            if let Some(packet) = self.next_buffered() {
+                return Ok(packet);
+            }
+
+            #[cfg(feature = "crossbeam")]
+            let packet = self.receiver.recv().map_err(|_| RecvError)?;
+
+            #[cfg(not(feature = "crossbeam"))]
+            let packet = self.receiver.recv()?;
+
+            if let Some(packet) = self.behind_control(packet) {
+                return Ok(packet);
+            }
+        }
+    }
+
+    /// Return the next `Packet` if one is available without blocking.
+    pub(crate) fn try_recv(&self) -> Result<Packet, TryRecvError> {
+        loop {
+            if let Some(packet) = self.next_buffered() {
+                return Ok(packet);
+            }
+
+            #[cfg(feature = "crossbeam")]
+            let packet = self.receiver.try_recv().map_err(|e| match e {
+                crossbeam_channel::TryRecvError::Empty => TryRecvError::Empty,
+                crossbeam_channel::TryRecvError::Disconnected => TryRecvError::Disconnected,
+            })?;
+
+            #[cfg(not(feature = "crossbeam"))]
+            let packet = self.receiver.try_recv()?;
+
+            if let Some(packet) = self.behind_control(packet) {
+                return Ok(packet);
+            }
+        }
+    }
+
+    /// Block until the next `Packet` is available or `timeout` has passed.
+    pub(crate) fn recv_timeout(&self, timeout: Duration) -> Result<Packet, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(packet) = self.next_buffered() {
+                return Ok(packet);
+            }
+            let timeout = deadline.saturating_duration_since(Instant::now());
+
+            #[cfg(feature = "crossbeam")]
+            let packet = self.receiver.recv_timeout(timeout).map_err(|e| match e {
+                crossbeam_channel::RecvTimeoutError::Timeout => RecvTimeoutError::Timeout,
+                crossbeam_channel::RecvTimeoutError::Disconnected => RecvTimeoutError::Disconnected,
+            })?;
+
+            #[cfg(not(feature = "crossbeam"))]
+            let packet = self.receiver.recv_timeout(timeout)?;
+
+            if let Some(packet) = self.behind_control(packet) {
+                return Ok(packet);
+            }
+        }
+    }
+
+    /// Return the number of `Packet`s waiting in the main queue.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn depth(&self) -> usize {
+        self.depth.load(Ordering::Relaxed)
+    }
+
+    /// Block until the next `Packet` sent through the control queue is
+    /// available, ignoring all other `Packet`s.
+    pub(crate) fn recv_control(&self) -> Result<Packet, RecvError> {
+        self.control_receiver.recv()
+    }
+
+    /// Return the next buffered `Packet`, after moving all pending control
+    /// `Packet`s into the buffer.
+    fn next_buffered(&self) -> Option<Packet> {
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.extend(self.control_receiver.try_iter());
+
+        buffer.pop_front()
+    }
+
+    /// Return the first of all pending control `Packet`s and the given
+    /// `Packet` received from the main queue, buffering the rest.
+    ///
+    /// `Packet::Wake` is dropped, so there may be no `Packet` to return if
+    /// the control `Packet` it was sent for has already been handed out.
+    fn behind_control(&self, packet: Packet) -> Option<Packet> {
+        #[cfg(feature = "metrics")]
+        self.depth.fetch_sub(1, Ordering::Relaxed);
+
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.extend(self.control_receiver.try_iter());
+        if !matches!(packet, Packet::Wake) {
+            buffer.push_back(packet);
+        }
+
+        buffer.pop_front()
+    }
+}
@@ -0,0 +1,242 @@
+//! Channels published under names, to be looked up by code that did not
+//! create them.
+//!
+//! A `Registry` maps names to channel handles of any type, e.g.
+//! `SendChannel`s, `RecvChannel`s or `BidirChannel`s, which are checked
+//! against the type asked for when they are looked up. Besides creating
+//! `Registry`s of their own, parts of an application can share the registry
+//! returned by `Registry::global`.
+//!
+//! ```
+//! use rusty_junctions::{channels::SendChannel, registry::Registry, Junction};
+//!
+//! let j = Junction::new();
+//! let metrics = j.send_channel::<u64>();
+//! let total = j.recv_channel::<u64>();
+//! j.when(&metrics).and(&metrics).and_recv(&total).then_do(|a, b| a + b);
+//!
+//! let registry = Registry::new();
+//! registry.publish("metrics", metrics);
+//!
+//! // Elsewhere, without access to `metrics` itself.
+//! let metrics = registry.lookup::<SendChannel<u64>>("metrics").unwrap();
+//! metrics.send(1).unwrap();
+//! metrics.send(2).unwrap();
+//! assert_eq!(total.recv().unwrap(), 3);
+//!
+//! assert!(registry.lookup::<SendChannel<u32>>("metrics").is_err());
+//! ```
+//!
+//! A `ChannelRef` names a published channel without knowing its type, e.g.
+//! to declare Join Patterns at runtime through `dynamic::PatternSpec`. With
+//! the `serde` feature, it can be read from configuration files or RPC
+//! messages, to be resolved through `Registry::resolve` by whoever reads
+//! them.
+
+use std::{
+    any::{type_name, Any},
+    collections::HashMap,
+    error::Error,
+    fmt,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::channels::{ChannelId, JunctionChannel, JunctionId};
+
+/// Channel handle published in a `Registry`, along with the name of its type
+/// and the IDs of the channel.
+struct Entry {
+    channel: Box<dyn Any + Send>,
+    type_name: &'static str,
+    junction_id: JunctionId,
+    channel_id: ChannelId,
+}
+
+/// Map from names to channel handles of any type.
+#[derive(Default)]
+pub struct Registry {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl Registry {
+    /// Create a new, empty `Registry`.
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// Return the process-wide `Registry`.
+    pub fn global() -> &'static Registry {
+        static GLOBAL: OnceLock<Registry> = OnceLock::new();
+
+        GLOBAL.get_or_init(Registry::new)
+    }
+
+    /// Publish the given channel handle under the given name, replacing any
+    /// handle published under the same name before.
+    pub fn publish<C>(&self, name: impl Into<String>, channel: C)
+    where
+        C: JunctionChannel + Clone + Any + Send,
+    {
+        let entry = Entry {
+            junction_id: channel.junction_id(),
+            channel_id: channel.channel_id(),
+            channel: Box::new(channel),
+            type_name: type_name::<C>(),
+        };
+
+        self.entries.lock().unwrap().insert(name.into(), entry);
+    }
+
+    /// Return a clone of the channel handle published under the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LookupError::NotFound` if no handle has been published under
+    /// the name, and `LookupError::WrongType` if the handle published is not
+    /// of type `C`.
+    pub fn lookup<C: Clone + Any + Send>(&self, name: &str) -> Result<C, LookupError> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(name).ok_or(LookupError::NotFound)?;
+
+        entry
+            .channel
+            .downcast_ref::<C>()
+            .cloned()
+            .ok_or(LookupError::WrongType {
+                published: entry.type_name,
+                requested: type_name::<C>(),
+            })
+    }
+
+    /// Return a clone of the channel handle the given `ChannelRef` refers to.
+    ///
+    /// ```
+    /// use rusty_junctions::{
+    ///     channels::SendChannel,
+    ///     registry::{ChannelRef, Registry},
+    ///     Junction,
+    /// };
+    ///
+    /// let j = Junction::new();
+    /// let alerts = j.send_channel::<String>();
+    /// let next = j.recv_channel::<String>();
+    /// j.when(&alerts).and_recv(&next).then_do(|alert| alert);
+    ///
+    /// let registry = Registry::new();
+    /// registry.publish("alerts", alerts);
+    ///
+    /// let config = r#"{ "on_failure": "alerts" }"#;
+    /// let config: serde_json::Value = serde_json::from_str(config).unwrap();
+    /// let on_failure: ChannelRef = serde_json::from_value(config["on_failure"].clone()).unwrap();
+    ///
+    /// let alerts = registry.resolve::<SendChannel<String>>(&on_failure).unwrap();
+    /// alerts.send("disk full".to_string()).unwrap();
+    /// assert_eq!(next.recv().unwrap(), "disk full");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `Registry::lookup`.
+    #[cfg(feature = "serde")]
+    pub fn resolve<C: Clone + Any + Send>(
+        &self,
+        channel_ref: &ChannelRef,
+    ) -> Result<C, LookupError> {
+        self.lookup(channel_ref.name())
+    }
+
+    /// Return the IDs of the `Junction` and the channel published under the
+    /// given name, whatever the type of its handle.
+    pub(crate) fn identify(&self, name: &str) -> Result<(JunctionId, ChannelId), LookupError> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(name).ok_or(LookupError::NotFound)?;
+
+        Ok((entry.junction_id, entry.channel_id))
+    }
+
+    /// Remove the channel handle published under the given name, returning
+    /// `true` if there was one.
+    pub fn unpublish(&self, name: &str) -> bool {
+        self.entries.lock().unwrap().remove(name).is_some()
+    }
+
+    /// Return the names of all published channel handles, in no particular
+    /// order.
+    pub fn names(&self) -> Vec<String> {
+        self.entries.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = self.entries.lock().unwrap();
+
+        f.debug_map()
+            .entries(entries.iter().map(|(name, entry)| (name, entry.type_name)))
+            .finish()
+    }
+}
+
+/// Reference to a channel published in a `Registry` under the given name,
+/// serialized as that name with the `serde` feature.
+///
+/// Unlike a `ChannelId`, which is only meaningful to the `Junction` that
+/// handed it out, the name stays the same across processes and restarts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChannelRef {
+    name: String,
+}
+
+impl ChannelRef {
+    /// Refer to the channel published under the given name.
+    pub fn new(name: impl Into<String>) -> ChannelRef {
+        ChannelRef { name: name.into() }
+    }
+
+    /// Return the name of the channel referred to.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ChannelRef {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.name)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ChannelRef {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<ChannelRef, D::Error> {
+        String::deserialize(deserializer).map(ChannelRef::new)
+    }
+}
+
+/// Error returned by `Registry::lookup`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupError {
+    /// No channel handle has been published under the name.
+    NotFound,
+    /// The channel handle published under the name is of a different type.
+    WrongType {
+        /// Type of the channel handle published.
+        published: &'static str,
+        /// Type of the channel handle looked up.
+        requested: &'static str,
+    },
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupError::NotFound => write!(f, "no channel published under this name"),
+            LookupError::WrongType {
+                published,
+                requested,
+            } => write!(f, "channel published as `{published}`, not `{requested}`"),
+        }
+    }
+}
+
+impl Error for LookupError {}
@@ -0,0 +1,274 @@
+//! Experimental sending to channels of a `Junction` in another process.
+//!
+//! A process exports `SendChannel`s under names through `Exports` and serves
+//! them on a `TcpListener`. Other processes connect through a
+//! `RemoteJunction` and send messages to the exported channels by name,
+//! serialized with `serde`, which then arrive at the exporting `Junction`
+//! like any other message.
+//!
+//! Every message is sent as a frame, see the `net` module, holding the
+//! length of the channel name as a 2 byte unsigned integer in big-endian
+//! byte order, followed by the channel name and the message serialized as
+//! JSON.
+//!
+//! Requires the `remote` feature.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    io::{self, ErrorKind},
+    marker::PhantomData,
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex, RwLock},
+    thread,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    builder::ThreadSpawner,
+    channels::SendChannel,
+    net::{read_frame, write_frame},
+};
+
+/// Function sending a serialized message on an exported channel.
+type Deliver = Box<dyn Fn(&[u8]) -> Result<(), String> + Send + Sync>;
+
+/// Channels exported under names to be sent to from other processes.
+///
+/// Clones share the same exported channels, which can be added to while
+/// they are being served.
+///
+/// ```
+/// use std::net::TcpListener;
+/// use rusty_junctions::{remote::{Exports, RemoteJunction}, Junction};
+///
+/// // The exporting process.
+/// let j = Junction::new();
+/// let job = j.send_channel::<String>();
+/// let done = j.recv_channel::<String>();
+/// j.when(&job).and_recv(&done).then_do(|job| job);
+///
+/// let exports = Exports::new();
+/// exports.export("job", job);
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let address = listener.local_addr().unwrap();
+/// exports.serve(listener);
+///
+/// // The sending process.
+/// let remote = RemoteJunction::connect(address).unwrap();
+/// remote.channel::<String>("job").send("resize".to_string()).unwrap();
+///
+/// assert_eq!(done.recv().unwrap(), "resize");
+/// ```
+#[derive(Clone, Default)]
+pub struct Exports {
+    channels: Arc<RwLock<HashMap<String, Deliver>>>,
+    thread_spawner: ThreadSpawner,
+}
+
+impl Exports {
+    /// Create a new, empty set of exported channels.
+    pub fn new() -> Exports {
+        Exports::default()
+    }
+
+    /// Create a new, empty set of exported channels, served by threads
+    /// spawned through the given `ThreadSpawner`, see `serve`.
+    pub fn with_thread_spawner(thread_spawner: ThreadSpawner) -> Exports {
+        Exports {
+            channels: Arc::default(),
+            thread_spawner,
+        }
+    }
+
+    /// Export the given channel under the given name, replacing any channel
+    /// exported under the same name before.
+    pub fn export<T>(&self, name: impl Into<String>, channel: SendChannel<T>)
+    where
+        T: Any + Send + DeserializeOwned,
+    {
+        // `SendChannel<T>` is only `Sync` for `T: Sync`.
+        let channel = Mutex::new(channel);
+        let deliver: Deliver = Box::new(move |payload| {
+            let value = serde_json::from_slice::<T>(payload).map_err(|e| e.to_string())?;
+
+            channel
+                .lock()
+                .unwrap()
+                .send(value)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        });
+
+        self.channels.write().unwrap().insert(name.into(), deliver);
+    }
+
+    /// Serve the exported channels on the given listener.
+    ///
+    /// A thread is spawned that accepts connections, each of which is read
+    /// from in a thread of its own. These threads are spawned through the
+    /// `ThreadSpawner` given to `Exports::with_thread_spawner`. Messages to channels that are not
+    /// exported, or that cannot be deserialized, are logged and dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread accepting connections could not be spawned.
+    pub fn serve(&self, listener: TcpListener) {
+        let exports = self.clone();
+
+        self.thread_spawner
+            .spawn(
+                thread::Builder::new(),
+                Box::new(move || {
+                    for stream in listener.incoming() {
+                        match stream {
+                            Ok(stream) => exports.handle_connection(stream),
+                            Err(e) => log::error!("Failed to accept connection: {e:?}"),
+                        }
+                    }
+                }),
+            )
+            .map_err(|e| log::error!("Failed to spawn remote listener thread: {e:?}"))
+            .unwrap();
+    }
+
+    /// Deliver all messages read from the given connection in a thread of
+    /// its own.
+    fn handle_connection(&self, mut stream: TcpStream) {
+        let exports = self.clone();
+
+        let spawned = self.thread_spawner.spawn(
+            thread::Builder::new(),
+            Box::new(move || loop {
+                let frame = match read_frame(&mut stream) {
+                    Ok(frame) => frame,
+                    Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+                    Err(e) => {
+                        log::error!("Failed to read remote message: {e:?}");
+                        break;
+                    }
+                };
+
+                if let Err(e) = exports.deliver(&frame) {
+                    log::warn!("Dropping remote message: {e}");
+                }
+            }),
+        );
+
+        if let Err(e) = spawned {
+            log::error!("Failed to spawn remote connection thread: {e:?}");
+        }
+    }
+
+    /// Send the message held by the given frame on its exported channel.
+    fn deliver(&self, frame: &[u8]) -> Result<(), String> {
+        let (name, payload) = decode(frame).ok_or("malformed frame")?;
+
+        let channels = self.channels.read().unwrap();
+        let deliver = channels
+            .get(name)
+            .ok_or_else(|| format!("no channel exported as `{name}`"))?;
+
+        deliver(payload).map_err(|e| format!("failed to send on `{name}`: {e}"))
+    }
+}
+
+/// Connection to the `Exports` of another process.
+///
+/// Clones share the same connection.
+#[derive(Clone)]
+pub struct RemoteJunction {
+    stream: Arc<Mutex<TcpStream>>,
+}
+
+impl RemoteJunction {
+    /// Connect to the `Exports` served at the given address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection could not be established.
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<RemoteJunction> {
+        Ok(RemoteJunction {
+            stream: Arc::new(Mutex::new(TcpStream::connect(address)?)),
+        })
+    }
+
+    /// Return a handle to the channel exported under the given name.
+    ///
+    /// Whether such a channel exists, and whether it carries messages of type
+    /// `T`, is only checked by the exporting process, which drops messages
+    /// failing these checks.
+    pub fn channel<T: Serialize>(&self, name: impl Into<String>) -> RemoteChannel<T> {
+        RemoteChannel {
+            name: name.into(),
+            junction: self.clone(),
+            send_type: PhantomData,
+        }
+    }
+}
+
+/// Channel exported by another process, see `RemoteJunction::channel`.
+pub struct RemoteChannel<T> {
+    name: String,
+    junction: RemoteJunction,
+    send_type: PhantomData<fn(T)>,
+}
+
+impl<T: Serialize> RemoteChannel<T> {
+    /// Return the name the channel is exported under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Send a message on the remote channel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message could not be serialized or written to
+    /// the connection.
+    pub fn send(&self, value: T) -> io::Result<()> {
+        let frame = encode(&self.name, &serde_json::to_vec(&value)?)?;
+        let mut stream = self.junction.stream.lock().unwrap();
+
+        write_frame(&mut *stream, &frame)
+    }
+}
+
+impl<T> Clone for RemoteChannel<T> {
+    fn clone(&self) -> RemoteChannel<T> {
+        RemoteChannel {
+            name: self.name.clone(),
+            junction: self.junction.clone(),
+            send_type: PhantomData,
+        }
+    }
+}
+
+/// Build the frame sending the given payload to the channel of the given
+/// name.
+fn encode(name: &str, payload: &[u8]) -> io::Result<Vec<u8>> {
+    let name_len = u16::try_from(name.len())
+        .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "channel name too long"))?;
+
+    let mut frame = Vec::with_capacity(2 + name.len() + payload.len());
+    frame.extend_from_slice(&name_len.to_be_bytes());
+    frame.extend_from_slice(name.as_bytes());
+    frame.extend_from_slice(payload);
+
+    Ok(frame)
+}
+
+/// Split the given frame into channel name and payload.
+fn decode(frame: &[u8]) -> Option<(&str, &[u8])> {
+    let (name_len, rest) = frame.split_first_chunk::<2>()?;
+    let name_len = u16::from_be_bytes(*name_len) as usize;
+
+    if rest.len() < name_len {
+        return None;
+    }
+    let (name, payload) = rest.split_at(name_len);
+
+    Some((std::str::from_utf8(name).ok()?, payload))
+}
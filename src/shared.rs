@@ -0,0 +1,87 @@
+//! Channels carrying large payloads behind shared pointers.
+//!
+//! Messages are moved, not cloned, on their way through a `Junction`.
+//! However, a Join Pattern that passes a payload on to several other
+//! channels has to copy it for each of them. Sending large buffers wrapped
+//! in an `Arc` instead makes each such copy a reference count increment.
+
+use std::{
+    any::Any,
+    sync::{mpsc::SendError, Arc},
+};
+
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+#[cfg(feature = "bytes")]
+use crate::channels::BytesChannel;
+use crate::{
+    channels::{MessageReceipt, SendChannel, SharedChannel},
+    types::Packet,
+    Junction,
+};
+
+impl Junction {
+    /// Create and return a new `SharedChannel` on this `Junction`.
+    ///
+    /// Payloads sent on this channel are shared rather than cloned when a
+    /// Join Pattern passes them on to multiple channels.
+    ///
+    /// ```
+    /// use rusty_junctions::Junction;
+    /// use std::sync::Arc;
+    ///
+    /// let j = Junction::new();
+    /// let frames = j.shared_channel::<Vec<u8>>();
+    /// let left = j.shared_channel::<Vec<u8>>();
+    /// let right = j.shared_channel::<Vec<u8>>();
+    ///
+    /// let (left_clone, right_clone) = (left.clone(), right.clone());
+    /// j.when(&frames).then_do(move |frame: Arc<Vec<u8>>| {
+    ///     left_clone.send(Arc::clone(&frame)).unwrap();
+    ///     right_clone.send(frame).unwrap();
+    /// });
+    ///
+    /// frames.send_shared(vec![0; 1 << 20]).unwrap();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new
+    /// channel ID from the control thread.
+    pub fn shared_channel<T>(&self) -> SharedChannel<T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.send_channel::<Arc<T>>()
+    }
+
+    /// Create and return a new `BytesChannel` on this `Junction`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new
+    /// channel ID from the control thread.
+    #[cfg(feature = "bytes")]
+    pub fn bytes_channel(&self) -> BytesChannel {
+        self.send_channel::<Bytes>()
+    }
+}
+
+impl<T: Any + Send + Sync> SendChannel<Arc<T>> {
+    /// Move `value` behind an `Arc` and send it.
+    pub fn send_shared(&self, value: T) -> Result<MessageReceipt, SendError<Packet>> {
+        self.send(Arc::new(value))
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl SendChannel<Bytes> {
+    /// Convert `value` into `Bytes` and send it.
+    ///
+    /// Conversions from owned buffers such as `Vec<u8>` take over the
+    /// allocation rather than copying it.
+    pub fn send_bytes(&self, value: impl Into<Bytes>) -> Result<MessageReceipt, SendError<Packet>> {
+        self.send(value.into())
+    }
+}
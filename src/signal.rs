@@ -0,0 +1,92 @@
+//! Operating system signals delivered as messages on a channel.
+//!
+//! Requires the `signals` feature and is only available on Unix.
+
+use std::io;
+
+use signal_hook::{consts, iterator::Signals};
+
+use crate::{channels::SendChannel, Junction};
+
+/// Operating system signal that can be received through
+/// `Junction::signal_channel`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignalKind {
+    /// `SIGINT`, e.g. raised by pressing Ctrl-C.
+    Interrupt,
+    /// `SIGTERM`, the polite request to terminate.
+    Terminate,
+    /// `SIGHUP`, raised when the controlling terminal is closed.
+    Hangup,
+    /// `SIGQUIT`.
+    Quit,
+    /// `SIGUSR1`.
+    User1,
+    /// `SIGUSR2`.
+    User2,
+}
+
+impl SignalKind {
+    /// Return the number of the signal.
+    fn number(self) -> i32 {
+        match self {
+            SignalKind::Interrupt => consts::SIGINT,
+            SignalKind::Terminate => consts::SIGTERM,
+            SignalKind::Hangup => consts::SIGHUP,
+            SignalKind::Quit => consts::SIGQUIT,
+            SignalKind::User1 => consts::SIGUSR1,
+            SignalKind::User2 => consts::SIGUSR2,
+        }
+    }
+}
+
+impl Junction {
+    /// Create a new `SendChannel` on this `Junction` that receives a message
+    /// every time the process receives the given signal.
+    ///
+    /// The default action of the signal, such as terminating the process, is
+    /// no longer taken once the channel has been created, so that it can be
+    /// handled by Join Patterns instead. The thread receiving the signals
+    /// only notices that the `Junction` has shut down on the next signal.
+    ///
+    /// ```
+    /// use rusty_junctions::{signal::SignalKind, Junction};
+    ///
+    /// let j = Junction::new();
+    /// let signal = j.signal_channel(SignalKind::User1).unwrap();
+    /// let state = j.send_channel::<u32>();
+    /// let on_shutdown = j.recv_channel::<u32>();
+    ///
+    /// // Hand the state over once the signal arrives.
+    /// j.when(&signal)
+    ///     .and(&state)
+    ///     .and_recv(&on_shutdown)
+    ///     .then_do(|_, state| state);
+    ///
+    /// state.send(7).unwrap();
+    /// signal_hook::low_level::raise(signal_hook::consts::SIGUSR1).unwrap();
+    /// assert_eq!(on_shutdown.recv().unwrap(), 7);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the handler for the signal could not be
+    /// registered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it received an error while trying to receive a new channel
+    /// ID from the control thread, or if the thread receiving the signals
+    /// could not be spawned.
+    pub fn signal_channel(&self, kind: SignalKind) -> io::Result<SendChannel<SignalKind>> {
+        let mut signals = Signals::new([kind.number()])?;
+
+        Ok(self.bridge(move |channel| {
+            for _ in signals.forever() {
+                if channel.send(kind).is_err() {
+                    break;
+                }
+            }
+        }))
+    }
+}
@@ -0,0 +1,138 @@
+//! Channels used as `futures::Stream`s and `futures::Sink`s.
+//!
+//! Requires the `futures` feature.
+
+use std::{
+    any::Any,
+    pin::Pin,
+    sync::mpsc::SendError,
+    task::{Context, Poll},
+    thread,
+};
+
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver},
+    Sink, Stream,
+};
+
+use crate::{
+    channels::{RecvChannel, SendChannel},
+    types::Packet,
+};
+
+impl<R: Any + Send> RecvChannel<R> {
+    /// Turn this channel into a `Stream` of the values generated by the Join
+    /// Patterns it is part of.
+    ///
+    /// A thread is spawned through the `ThreadSpawner` of the `Junction` that
+    /// repeatedly calls `recv` on the channel and passes the replies on to
+    /// the `Stream` as they arrive. The `Stream`
+    /// ends once the `Junction` has shut down. Once the `Stream` is dropped,
+    /// the thread stops after the next reply, which is then lost.
+    ///
+    /// ```
+    /// use futures::{executor::block_on, StreamExt};
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let value = j.send_channel::<u32>();
+    /// let next = j.recv_channel::<u32>();
+    /// j.when(&value).and_recv(&next).then_do(|v| v);
+    ///
+    /// for v in 1..=3 {
+    ///     value.send(v).unwrap();
+    /// }
+    ///
+    /// let mut values: Vec<u32> = block_on(next.into_stream().take(3).collect());
+    /// values.sort();
+    /// assert_eq!(values, vec![1, 2, 3]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the thread could not be spawned.
+    pub fn into_stream(self) -> RecvStream<R> {
+        let (sender, receiver) = unbounded();
+
+        let thread_spawner = self.thread_spawner().clone();
+        thread_spawner
+            .spawn(
+                thread::Builder::new(),
+                Box::new(move || {
+                    while let Ok(reply) = self.recv() {
+                        if sender.unbounded_send(reply).is_err() {
+                            break;
+                        }
+                    }
+                }),
+            )
+            .map_err(|e| log::error!("Failed to spawn stream thread: {e:?}"))
+            .unwrap();
+
+        RecvStream { receiver }
+    }
+}
+
+impl<T: Any + Send> SendChannel<T> {
+    /// Turn this channel into a `Sink` sending every item on the channel.
+    ///
+    /// ```
+    /// use futures::{executor::block_on, stream, SinkExt};
+    /// use rusty_junctions::Junction;
+    ///
+    /// let j = Junction::new();
+    /// let value = j.send_channel::<u32>();
+    /// let total = j.recv_channel::<u32>();
+    /// j.when(&value).and(&value).and_recv(&total).then_do(|a, b| a + b);
+    ///
+    /// let mut sink = value.clone().into_sink();
+    /// block_on(sink.send_all(&mut stream::iter(vec![Ok(1), Ok(2)]))).unwrap();
+    /// assert_eq!(total.recv().unwrap(), 3);
+    /// ```
+    pub fn into_sink(self) -> SendSink<T> {
+        SendSink { channel: self }
+    }
+}
+
+/// `Stream` of the values generated for a `RecvChannel`, see
+/// `RecvChannel::into_stream`.
+pub struct RecvStream<R> {
+    receiver: UnboundedReceiver<R>,
+}
+
+impl<R> Stream for RecvStream<R> {
+    type Item = R;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<R>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// `Sink` sending items on a `SendChannel`, see `SendChannel::into_sink`.
+///
+/// Sending on a `SendChannel` never waits for a Join Pattern to fire, so the
+/// `Sink` is always ready. Only sending on a `Junction` with a bounded queue
+/// may block the current thread while the queue is full.
+pub struct SendSink<T> {
+    channel: SendChannel<T>,
+}
+
+impl<T: Any + Send> Sink<T> for SendSink<T> {
+    type Error = SendError<Packet>;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        self.channel.send(item).map(|_| ())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
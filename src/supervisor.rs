@@ -0,0 +1,206 @@
+//! Restarting the control thread of a `Junction` whenever it panics.
+//!
+//! A `Supervisor` takes ownership of a `Junction` and watches it from a
+//! monitor thread. Once the `Junction` is poisoned by a panic of its control
+//! thread, see `Junction::is_poisoned`, the `Supervisor` heals it, keeping
+//! all of its channels and Join Patterns. Should the control thread panic
+//! more often than the `RestartPolicy` allows, the `Supervisor` gives up and
+//! leaves the `Junction` poisoned. Every restart, and giving up, is reported
+//! to a callback, e.g. to raise an alert in a long-running daemon.
+//!
+//! ```
+//! use rusty_junctions::{
+//!     supervisor::{Incident, RestartPolicy, Supervisor},
+//!     Junction,
+//! };
+//! use std::{sync::mpsc, time::Duration};
+//!
+//! /// Message panicking when the `Controller` drops it.
+//! struct Fuse;
+//!
+//! impl Drop for Fuse {
+//!     fn drop(&mut self) {
+//!         panic!("Fuse blown");
+//!     }
+//! }
+//!
+//! let (incidents, incident_receiver) = mpsc::channel();
+//! let j = Supervisor::new(Junction::new(), RestartPolicy::default(), move |incident| {
+//!     incidents.send(incident).unwrap();
+//! });
+//!
+//! let job = j.send_channel::<u32>();
+//! let done = j.recv_channel::<u32>();
+//! j.when(&job).and_recv(&done).then_do(|n| n);
+//!
+//! // Expired messages are dropped by the `Controller`.
+//! let fuse = j.send_channel::<Fuse>();
+//! fuse.send_with_ttl(Fuse, Duration::ZERO).unwrap();
+//!
+//! assert_eq!(incident_receiver.recv().unwrap(), Incident::Restarted { restarts: 1 });
+//! job.send(1).unwrap();
+//! assert_eq!(done.recv().unwrap(), 1);
+//! ```
+
+use std::{
+    collections::VecDeque,
+    ops::Deref,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::Junction;
+
+/// Interval at which the monitor thread checks whether the `Junction` has
+/// been poisoned.
+const CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How often a `Supervisor` restarts the control thread of its `Junction`
+/// before giving up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    max_restarts: usize,
+    window: Duration,
+}
+
+impl RestartPolicy {
+    /// Allow up to `max_restarts` restarts within any period of the given
+    /// length.
+    pub fn new(max_restarts: usize, window: Duration) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts,
+            window,
+        }
+    }
+
+    /// Never restart the control thread, only reporting its panic.
+    pub fn never() -> RestartPolicy {
+        RestartPolicy::new(0, Duration::ZERO)
+    }
+}
+
+impl Default for RestartPolicy {
+    /// Allow up to 5 restarts within any minute.
+    fn default() -> RestartPolicy {
+        RestartPolicy::new(5, Duration::from_secs(60))
+    }
+}
+
+/// Panic of the control thread of a supervised `Junction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Incident {
+    /// The control thread has been restarted, which makes the given number
+    /// of restarts within the window of the `RestartPolicy`.
+    Restarted { restarts: usize },
+    /// The control thread has not been restarted, as the given number of
+    /// restarts within the window of the `RestartPolicy` is the most
+    /// allowed, or as the `Junction` cannot be healed. The `Junction` stays
+    /// poisoned and is no longer supervised.
+    GaveUp { restarts: usize },
+}
+
+/// `Junction` whose control thread is restarted whenever it panics.
+///
+/// Dereferences to the underlying `Junction`, which is used to create
+/// channels and Join Patterns as usual. Dropping the `Supervisor` stops
+/// its monitor thread, then drops the `Junction`.
+pub struct Supervisor {
+    junction: Arc<Junction>,
+    /// `Sender` whose disconnection stops the monitor thread.
+    stop_sender: Option<Sender<()>>,
+    monitor: Option<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    /// Supervise the given `Junction`, restarting its control thread as
+    /// allowed by the given `RestartPolicy` and reporting each `Incident`
+    /// to the given callback, which is called from the monitor thread. The
+    /// monitor thread is spawned through the `ThreadSpawner` of the
+    /// `Junction`.
+    ///
+    /// Only `Junction`s with a single control thread are restarted, see
+    /// `Junction::heal`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the monitor thread could not be spawned.
+    pub fn new(
+        junction: Junction,
+        policy: RestartPolicy,
+        mut on_incident: impl FnMut(Incident) + Send + 'static,
+    ) -> Supervisor {
+        let junction = Arc::new(junction);
+        let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+
+        let supervised = Arc::clone(&junction);
+        let monitor = junction
+            .thread_spawner()
+            .spawn(
+                thread::Builder::new().name("junction-supervisor".to_string()),
+                Box::new(move || {
+                    let mut restarts = VecDeque::new();
+
+                    while let Err(RecvTimeoutError::Timeout) =
+                        stop_receiver.recv_timeout(CHECK_INTERVAL)
+                    {
+                        if !supervised.is_poisoned() {
+                            continue;
+                        }
+
+                        let now = Instant::now();
+                        restarts.retain(|restarted_at| {
+                            now.duration_since(*restarted_at) < policy.window
+                        });
+
+                        if restarts.len() >= policy.max_restarts || !supervised.heal() {
+                            log::error!("Giving up on restarting the control thread of a Junction");
+                            on_incident(Incident::GaveUp {
+                                restarts: restarts.len(),
+                            });
+                            return;
+                        }
+
+                        restarts.push_back(now);
+                        log::warn!("Restarted the control thread of a Junction");
+                        on_incident(Incident::Restarted {
+                            restarts: restarts.len(),
+                        });
+                    }
+                }),
+            )
+            .map_err(|e| log::error!("Failed to spawn supervisor thread: {e:?}"))
+            .unwrap();
+
+        Supervisor {
+            junction,
+            stop_sender: Some(stop_sender),
+            monitor: Some(monitor),
+        }
+    }
+}
+
+impl Deref for Supervisor {
+    type Target = Junction;
+
+    fn deref(&self) -> &Junction {
+        &self.junction
+    }
+}
+
+impl Drop for Supervisor {
+    /// Stop the monitor thread, so that the `Junction` is dropped along
+    /// with the `Supervisor`.
+    fn drop(&mut self) {
+        drop(self.stop_sender.take());
+
+        if let Some(monitor) = self.monitor.take() {
+            if monitor.join().is_err() {
+                log::error!("Supervisor thread panicked");
+            }
+        }
+    }
+}
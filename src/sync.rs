@@ -0,0 +1,464 @@
+//! Synchronization primitives implemented with Join Patterns.
+//!
+//! Each primitive in this module is built from the channels of a `Junction`
+//! passed in on construction and a handful of Join Patterns declared on it.
+//! Besides being useful in their own right, they double as examples of how
+//! classic concurrency primitives translate into the Join Calculus.
+//!
+//! The `Junction` used to construct a primitive has to outlive it, since all
+//! operations are carried out by the `Controller` of that `Junction`.
+//!
+//! ```
+//! use rusty_junctions::{sync::Semaphore, Junction};
+//! use std::thread;
+//!
+//! let j = Junction::new();
+//! let semaphore = Semaphore::new(&j, 2);
+//!
+//! semaphore.acquire().unwrap();
+//! semaphore.acquire().unwrap();
+//!
+//! let releaser = semaphore.clone();
+//! thread::spawn(move || releaser.release().unwrap());
+//!
+//! // Blocks until the spawned thread has released a permit.
+//! semaphore.acquire().unwrap();
+//! ```
+
+use std::{
+    any::Any,
+    sync::mpsc::{channel, RecvError, SendError, Sender},
+};
+
+use crate::{
+    channels::{BidirChannel, RecvChannel, SendChannel},
+    then_do::ThenDoSpawned,
+    types::Packet,
+    Junction,
+};
+
+/*************
+ * Semaphore *
+ *************/
+
+/// Counting semaphore.
+///
+/// Every available permit is represented by a message on an internal
+/// channel, so acquiring a permit consumes such a message and releasing
+/// one sends a new message.
+#[derive(Clone)]
+pub struct Semaphore {
+    acquire: RecvChannel<()>,
+    release: SendChannel<()>,
+}
+
+impl Semaphore {
+    /// Create a new `Semaphore` on the given `Junction` with `permits`
+    /// initially available permits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initial permits could not be sent to the `Junction`.
+    pub fn new(junction: &Junction, permits: usize) -> Semaphore {
+        let acquire = junction.recv_channel::<()>();
+        let release = junction.send_channel::<()>();
+        let permit = junction.send_channel::<()>();
+
+        // Hand out a permit to anyone trying to acquire one.
+        junction
+            .when(&permit)
+            .and_recv(&acquire)
+            .then_do_spawned(|_| {});
+
+        // Turn every release into a new permit.
+        let permit_clone = permit.clone();
+        junction.when(&release).then_do_spawned(move |_| {
+            permit_clone.send(()).unwrap();
+        });
+
+        for _ in 0..permits {
+            permit.send(()).unwrap();
+        }
+
+        Semaphore { acquire, release }
+    }
+
+    /// Acquire a permit, blocking until one is available.
+    pub fn acquire(&self) -> Result<(), RecvError> {
+        self.acquire.recv()
+    }
+
+    /// Release a permit, potentially unblocking a waiting `acquire`.
+    pub fn release(&self) -> Result<(), SendError<Packet>> {
+        self.release.send(()).map(|_| ())
+    }
+}
+
+/***********
+ * Barrier *
+ ***********/
+
+/// Barrier to let a fixed number of threads wait for each other.
+///
+/// A `Barrier` can be reused, once all threads of a generation have
+/// arrived, the next call to `wait` starts a new generation.
+#[derive(Clone)]
+pub struct Barrier {
+    wait: BidirChannel<(), bool>,
+}
+
+impl Barrier {
+    /// Create a new `Barrier` on the given `Junction` that blocks until
+    /// `n` threads have called `wait`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initial state could not be sent to the `Junction`.
+    pub fn new(junction: &Junction, n: usize) -> Barrier {
+        let wait = junction.bidir_channel::<(), bool>();
+
+        // Asynchronous state channel carrying the wake-up senders of all
+        // threads that have arrived in the current generation.
+        let waiting = junction.send_channel::<Vec<Sender<()>>>();
+
+        let waiting_clone = waiting.clone();
+        junction
+            .when(&waiting)
+            .and_bidir(&wait)
+            .then_do_spawned(move |mut waiters, _| {
+                if waiters.len() + 1 >= n {
+                    // Last thread to arrive, wake up everyone else and start
+                    // a fresh generation.
+                    waiters.drain(..).for_each(|waiter| {
+                        let _ = waiter.send(());
+                    });
+                    waiting_clone.send(waiters).unwrap();
+
+                    true
+                } else {
+                    let (tx, rx) = channel::<()>();
+                    waiters.push(tx);
+                    waiting_clone.send(waiters).unwrap();
+
+                    let _ = rx.recv();
+                    false
+                }
+            });
+
+        waiting.send(Vec::new()).unwrap();
+
+        Barrier { wait }
+    }
+
+    /// Block until all threads have arrived at this `Barrier`.
+    ///
+    /// Exactly one thread per generation receives `true`, which can be used
+    /// to elect a leader like with `std::sync::BarrierWaitResult::is_leader`.
+    pub fn wait(&self) -> Result<bool, RecvError> {
+        self.wait.send_recv(())
+    }
+}
+
+/*********
+ * Latch *
+ *********/
+
+/// Single-use count-down latch.
+///
+/// Threads calling `wait` block until `count_down` has been called as many
+/// times as the latch was initialized with. Once open, the latch stays open.
+#[derive(Clone)]
+pub struct Latch {
+    count_down: SendChannel<()>,
+    wait: RecvChannel<()>,
+}
+
+impl Latch {
+    /// Create a new `Latch` on the given `Junction` that opens after
+    /// `count` calls to `count_down`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initial state could not be sent to the `Junction`.
+    pub fn new(junction: &Junction, count: usize) -> Latch {
+        let count_down = junction.send_channel::<()>();
+        let wait = junction.recv_channel::<()>();
+
+        // Asynchronous state channels for the closed latch with its
+        // remaining count and the opened latch respectively.
+        let remaining = junction.send_channel::<usize>();
+        let open = junction.send_channel::<()>();
+
+        let remaining_clone = remaining.clone();
+        let open_clone = open.clone();
+        junction
+            .when(&remaining)
+            .and(&count_down)
+            .then_do_spawned(move |n, _| {
+                if n <= 1 {
+                    open_clone.send(()).unwrap();
+                } else {
+                    remaining_clone.send(n - 1).unwrap();
+                }
+            });
+
+        // Let waiting threads through while keeping the latch open.
+        let open_clone = open.clone();
+        junction
+            .when(&open)
+            .and_recv(&wait)
+            .then_do_spawned(move |_| {
+                open_clone.send(()).unwrap();
+            });
+
+        // Absorb superfluous count downs once the latch is open.
+        let open_clone = open.clone();
+        junction
+            .when(&open)
+            .and(&count_down)
+            .then_do_spawned(move |_, _| {
+                open_clone.send(()).unwrap();
+            });
+
+        if count == 0 {
+            open.send(()).unwrap();
+        } else {
+            remaining.send(count).unwrap();
+        }
+
+        Latch { count_down, wait }
+    }
+
+    /// Decrement the count of the latch, opening it once it reaches zero.
+    pub fn count_down(&self) -> Result<(), SendError<Packet>> {
+        self.count_down.send(()).map(|_| ())
+    }
+
+    /// Block until the latch is open.
+    pub fn wait(&self) -> Result<(), RecvError> {
+        self.wait.recv()
+    }
+}
+
+/**********
+ * RwLock *
+ **********/
+
+/// Reader-writer lock.
+///
+/// Allows any number of readers or a single writer at a time. Access is
+/// granted through guards that release the lock on drop. Note that readers
+/// are not prevented from repeatedly overtaking a waiting writer.
+#[derive(Clone)]
+pub struct RwLock {
+    acquire_read: RecvChannel<()>,
+    release_read: SendChannel<()>,
+    acquire_write: RecvChannel<()>,
+    release_write: SendChannel<()>,
+}
+
+impl RwLock {
+    /// Create a new, unlocked `RwLock` on the given `Junction`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initial state could not be sent to the `Junction`.
+    pub fn new(junction: &Junction) -> RwLock {
+        let acquire_read = junction.recv_channel::<()>();
+        let release_read = junction.send_channel::<()>();
+        let acquire_write = junction.recv_channel::<()>();
+        let release_write = junction.send_channel::<()>();
+
+        // Asynchronous state channels for the unlocked lock and the lock
+        // shared by the carried number of readers respectively.
+        let idle = junction.send_channel::<()>();
+        let shared = junction.send_channel::<usize>();
+
+        let shared_clone = shared.clone();
+        junction
+            .when(&idle)
+            .and_recv(&acquire_read)
+            .then_do_spawned(move |_| {
+                shared_clone.send(1).unwrap();
+            });
+
+        let shared_clone = shared.clone();
+        junction
+            .when(&shared)
+            .and_recv(&acquire_read)
+            .then_do_spawned(move |n| {
+                shared_clone.send(n + 1).unwrap();
+            });
+
+        let idle_clone = idle.clone();
+        let shared_clone = shared.clone();
+        junction
+            .when(&shared)
+            .and(&release_read)
+            .then_do_spawned(move |n, _| {
+                if n <= 1 {
+                    idle_clone.send(()).unwrap();
+                } else {
+                    shared_clone.send(n - 1).unwrap();
+                }
+            });
+
+        // A writer consumes the idle state and only gives it back on release.
+        junction
+            .when(&idle)
+            .and_recv(&acquire_write)
+            .then_do_spawned(|_| {});
+
+        let idle_clone = idle.clone();
+        junction.when(&release_write).then_do_spawned(move |_| {
+            idle_clone.send(()).unwrap();
+        });
+
+        idle.send(()).unwrap();
+
+        RwLock {
+            acquire_read,
+            release_read,
+            acquire_write,
+            release_write,
+        }
+    }
+
+    /// Acquire shared read access, blocking until no writer holds the lock.
+    pub fn read(&self) -> Result<ReadGuard<'_>, RecvError> {
+        self.acquire_read.recv()?;
+
+        Ok(ReadGuard { lock: self })
+    }
+
+    /// Acquire exclusive write access, blocking until the lock is unlocked.
+    pub fn write(&self) -> Result<WriteGuard<'_>, RecvError> {
+        self.acquire_write.recv()?;
+
+        Ok(WriteGuard { lock: self })
+    }
+}
+
+/// Guard for shared read access to a `RwLock`, released on drop.
+pub struct ReadGuard<'a> {
+    lock: &'a RwLock,
+}
+
+impl Drop for ReadGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.lock.release_read.send(());
+    }
+}
+
+/// Guard for exclusive write access to a `RwLock`, released on drop.
+pub struct WriteGuard<'a> {
+    lock: &'a RwLock,
+}
+
+impl Drop for WriteGuard<'_> {
+    fn drop(&mut self) {
+        let _ = self.lock.release_write.send(());
+    }
+}
+
+/************
+ * OnceCell *
+ ************/
+
+/// Cell that can be written to only once.
+///
+/// The value is never held by any user thread, but carried by a message on
+/// an internal channel that is resent every time the value is read.
+#[derive(Clone)]
+pub struct OnceCell<T> {
+    set: BidirChannel<T, Result<(), T>>,
+    get: RecvChannel<Option<T>>,
+    wait: RecvChannel<T>,
+}
+
+impl<T> OnceCell<T>
+where
+    T: Any + Send + Clone,
+{
+    /// Create a new, empty `OnceCell` on the given `Junction`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the initial state could not be sent to the `Junction`.
+    pub fn new(junction: &Junction) -> OnceCell<T> {
+        let set = junction.bidir_channel::<T, Result<(), T>>();
+        let get = junction.recv_channel::<Option<T>>();
+        let wait = junction.recv_channel::<T>();
+
+        // Asynchronous state channels for the empty cell and the cell
+        // holding the carried value respectively.
+        let empty = junction.send_channel::<()>();
+        let full = junction.send_channel::<T>();
+
+        let full_clone = full.clone();
+        junction
+            .when(&empty)
+            .and_bidir(&set)
+            .then_do_spawned(move |_, v| {
+                full_clone.send(v).unwrap();
+                Ok(())
+            });
+
+        let full_clone = full.clone();
+        junction
+            .when(&full)
+            .and_bidir(&set)
+            .then_do_spawned(move |v, w| {
+                full_clone.send(v).unwrap();
+                Err(w)
+            });
+
+        let empty_clone = empty.clone();
+        junction
+            .when(&empty)
+            .and_recv(&get)
+            .then_do_spawned(move |_| {
+                empty_clone.send(()).unwrap();
+                None
+            });
+
+        let full_clone = full.clone();
+        junction
+            .when(&full)
+            .and_recv(&get)
+            .then_do_spawned(move |v| {
+                full_clone.send(v.clone()).unwrap();
+                Some(v)
+            });
+
+        let full_clone = full.clone();
+        junction
+            .when(&full)
+            .and_recv(&wait)
+            .then_do_spawned(move |v| {
+                full_clone.send(v.clone()).unwrap();
+                v
+            });
+
+        empty.send(()).unwrap();
+
+        OnceCell { set, get, wait }
+    }
+
+    /// Set the value of the cell.
+    ///
+    /// Returns `Err` with the given value if the cell had already been set.
+    pub fn set(&self, value: T) -> Result<Result<(), T>, RecvError> {
+        self.set.send_recv(value)
+    }
+
+    /// Return a copy of the value of the cell, or `None` if it is unset.
+    pub fn get(&self) -> Result<Option<T>, RecvError> {
+        self.get.recv()
+    }
+
+    /// Return a copy of the value of the cell, blocking until it is set.
+    pub fn wait(&self) -> Result<T, RecvError> {
+        self.wait.recv()
+    }
+}
@@ -0,0 +1,165 @@
+//! Helpers for testing code built on Join Patterns.
+//!
+//! Requires the `testing` feature, which is meant to be enabled for
+//! `dev-dependencies` only.
+//!
+//! ```
+//! use std::time::Duration;
+//! use rusty_junctions::{assert_fires_within, testing::ProbeChannel, Junction};
+//!
+//! let j = Junction::new();
+//! let order = j.send_channel::<u32>();
+//! let shipped = ProbeChannel::<u32>::new(&j);
+//!
+//! let ship = shipped.channel().clone();
+//! j.when(&order).then_do_named("ship", move |id| {
+//!     ship.send(id).unwrap();
+//! });
+//!
+//! order.send(7).unwrap();
+//!
+//! assert_fires_within!(j, "ship", Duration::from_secs(1));
+//! assert!(shipped.wait_for(1, Duration::from_secs(1)));
+//! assert_eq!(shipped.take(), vec![7]);
+//! ```
+
+use std::{
+    any::Any,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{channels::SendChannel, then_do::ThenDoSpawned, Junction};
+
+/// Interval at which `fires_within` checks whether a Join Pattern has
+/// fired.
+const FIRE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Assert that the Join Pattern of the given name on the given `Junction`
+/// fires within the given `Duration`, see `testing::fires_within`.
+///
+/// An optional message, formatted like in `assert!`, is added to the panic
+/// message.
+#[macro_export]
+macro_rules! assert_fires_within {
+    ($junction:expr, $name:expr, $timeout:expr $(,)?) => {
+        assert!(
+            $crate::testing::fires_within(&$junction, $name, $timeout),
+            "Join Pattern `{}` did not fire within {:?}",
+            $name,
+            $timeout
+        )
+    };
+    ($junction:expr, $name:expr, $timeout:expr, $($arg:tt)+) => {
+        assert!(
+            $crate::testing::fires_within(&$junction, $name, $timeout),
+            $($arg)+
+        )
+    };
+}
+
+/// Return `true` once a Join Pattern of the given name, see
+/// `then_do_named`, has fired on the given `Junction`, or `false` if none
+/// has fired by the time the given `Duration` has passed.
+///
+/// Firings before the call count, so that the messages a Join Pattern fires
+/// on can be sent before checking.
+///
+/// # Panics
+///
+/// Panics if the number of firings could not be requested from or received
+/// from the control thread.
+pub fn fires_within(junction: &Junction, name: &str, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        if junction.fire_count(name) > 0 {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+
+        thread::sleep(FIRE_POLL_INTERVAL);
+    }
+}
+
+/// `SendChannel` recording every message it receives, to be handed to the
+/// code under test in place of a channel that would lead elsewhere.
+///
+/// Messages are recorded by a Join Pattern on the given `Junction`, in the
+/// order in which its function bodies run. Clones share the same record.
+pub struct ProbeChannel<T> {
+    channel: SendChannel<T>,
+    received: Arc<(Mutex<Vec<T>>, Condvar)>,
+}
+
+impl<T: Any + Send> ProbeChannel<T> {
+    /// Create a new `ProbeChannel` on the given `Junction`.
+    pub fn new(junction: &Junction) -> ProbeChannel<T> {
+        let channel = junction.send_channel::<T>();
+        let received = Arc::new((Mutex::new(Vec::new()), Condvar::new()));
+
+        let record = received.clone();
+        junction.when(&channel).then_do_spawned(move |value| {
+            let (values, arrived) = &*record;
+            values.lock().unwrap().push(value);
+            arrived.notify_all();
+        });
+
+        ProbeChannel { channel, received }
+    }
+
+    /// Return the channel to send the recorded messages on.
+    pub fn channel(&self) -> &SendChannel<T> {
+        &self.channel
+    }
+
+    /// Return the number of messages recorded so far.
+    pub fn len(&self) -> usize {
+        self.received.0.lock().unwrap().len()
+    }
+
+    /// Return `true` if no messages have been recorded so far.
+    pub fn is_empty(&self) -> bool {
+        self.received.0.lock().unwrap().is_empty()
+    }
+
+    /// Remove and return all messages recorded so far.
+    pub fn take(&self) -> Vec<T> {
+        std::mem::take(&mut *self.received.0.lock().unwrap())
+    }
+
+    /// Block until at least `count` messages have been recorded, returning
+    /// `false` if they have not been by the time the given `Duration` has
+    /// passed.
+    pub fn wait_for(&self, count: usize, timeout: Duration) -> bool {
+        let (values, arrived) = &*self.received;
+
+        let (values, _) = arrived
+            .wait_timeout_while(values.lock().unwrap(), timeout, |values| {
+                values.len() < count
+            })
+            .unwrap();
+
+        values.len() >= count
+    }
+}
+
+impl<T: Clone> ProbeChannel<T> {
+    /// Return a copy of all messages recorded so far.
+    pub fn received(&self) -> Vec<T> {
+        self.received.0.lock().unwrap().clone()
+    }
+}
+
+// Implemented manually since deriving would require `T: Clone`.
+impl<T> Clone for ProbeChannel<T> {
+    fn clone(&self) -> ProbeChannel<T> {
+        ProbeChannel {
+            channel: self.channel.clone(),
+            received: self.received.clone(),
+        }
+    }
+}
@@ -0,0 +1,827 @@
+//! Additional ways of completing the partial Join Patterns generated for the
+//! crate, building on top of their `then_do` methods.
+
+use std::{
+    any::{type_name, Any},
+    sync::{mpsc::Sender, Arc, Mutex, OnceLock, PoisonError, RwLock},
+};
+
+use crate::{
+    cancel::{CancellationToken, JunctionClosed, PatternHandle},
+    join_pattern::{
+        self, CancellableJoinPattern, InlineJoinPattern, JoinPattern, LimitedJoinPattern,
+        MessagesJoinPattern, NamedJoinPattern, RunInline,
+    },
+    junction::Scope,
+    metadata::Metadata,
+    patterns::{binary, ternary, unary},
+    types::{Message, Packet},
+};
+
+/// Capture the Join Pattern created by `capture_spawned` of the given partial
+/// Join Pattern for the given function, which can be replaced through the
+/// returned `PatternHandle`.
+///
+/// The Join Pattern is returned wrapped to be cancellable through the
+/// `PatternHandle`, along with the `Sender` to register it with.
+macro_rules! capture_with_handle {
+    ($partial:expr, $f:expr, ($($arg:ident: $arg_type:ty),*) $(-> $ret:ty)?) => {{
+        let function: Arc<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync> = Arc::new($f);
+        let function = Arc::new(RwLock::new(function));
+        let token = CancellationToken::new();
+
+        let current = function.clone();
+        let (join_pattern, sender) = $partial.capture_spawned(move |$($arg),*| {
+            let f = current.read().unwrap().clone();
+            f($($arg),*)
+        });
+
+        let join_pattern: Box<dyn JoinPattern + Send> =
+            Box::new(CancellableJoinPattern::with_handle(token.clone(), join_pattern));
+
+        (join_pattern, sender, PatternHandle::new(token, function))
+    }};
+}
+
+/// Implement `then_do_with_state` for the given partial Join Pattern.
+///
+/// The arguments list the generic parameters of the partial Join Pattern,
+/// followed by the arguments and return type of the function it fires, if any.
+macro_rules! impl_then_do_with_state {
+    ($pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*) $(-> $ret:ty)?) => {
+        impl<$($generic: Any + Send),*> $pattern {
+            /// Create a full Join Pattern whose function has mutable access
+            /// to a piece of state owned by the Join Pattern.
+            ///
+            /// The state starts out as `initial_state` and is handed to `f`
+            /// by mutable reference on every firing. Invocations of `f` are
+            /// serialized by the control thread, which does not fire the
+            /// Join Pattern again before `f` has returned, see
+            /// `then_do_sequential`. No locking is required within `f`
+            /// itself and no thread is ever left waiting for the state.
+            ///
+            /// If `f` panics, later invocations carry on with the state as
+            /// the panicking invocation left it.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the full Join Pattern could not be registered.
+            pub fn then_do_with_state<S, F>(self, initial_state: S, f: F)
+            where
+                S: Send + 'static,
+                F: FnMut(&mut S, $($arg_type),*) $(-> $ret)? + Send + 'static,
+            {
+                self.try_then_do_with_state(initial_state, f)
+                    .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
+                    .unwrap();
+            }
+
+            /// Create a full Join Pattern whose function has mutable access
+            /// to a piece of state owned by the Join Pattern, like
+            /// `then_do_with_state`.
+            ///
+            /// Return a `PatternHandle` to cancel the Join Pattern or replace
+            /// its function with. The state is owned by the function, so a
+            /// replacement does not get to see it.
+            ///
+            /// # Errors
+            ///
+            /// Returns `JunctionClosed` instead of panicking if the
+            /// `Junction` has shut down.
+            pub fn try_then_do_with_state<S, F>(
+                self,
+                initial_state: S,
+                f: F,
+            ) -> Result<PatternHandle<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync>, JunctionClosed>
+            where
+                S: Send + 'static,
+                F: FnMut(&mut S, $($arg_type),*) $(-> $ret)? + Send + 'static,
+            {
+                let state = Mutex::new((initial_state, f));
+                let (join_pattern, sender, handle) = capture_with_handle!(
+                    self,
+                    move |$($arg),*| {
+                        // Never contended, as the Join Pattern is held back
+                        // while its function body is running.
+                        let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+                        let (state, f) = &mut *state;
+
+                        f(state, $($arg),*)
+                    },
+                    ($($arg: $arg_type),*) $(-> $ret)?
+                );
+
+                join_pattern::register(Box::new(LimitedJoinPattern::new(1, join_pattern)), sender)?;
+
+                Ok(handle)
+            }
+        }
+    };
+}
+
+impl_then_do_with_state!(unary::SendPartialPattern<T>, [T], (t: T));
+impl_then_do_with_state!(unary::RecvPartialPattern<R>, [R], () -> R);
+impl_then_do_with_state!(unary::BidirPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_with_state!(binary::SendPartialPattern<T, U>, [T, U], (t: T, u: U));
+impl_then_do_with_state!(binary::RecvPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_with_state!(binary::BidirPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_with_state!(ternary::SendPartialPattern<T, U, V>, [T, U, V], (t: T, u: U, v: V));
+impl_then_do_with_state!(ternary::RecvPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_with_state!(ternary::BidirPartialPattern<T, U, V, R>, [T, U, V, R], (t: T, u: U, v: V) -> R);
+
+/// Implement `then_do_named` for the given partial Join Pattern, taking the
+/// same arguments as `impl_then_do_with_state`.
+macro_rules! impl_then_do_named {
+    ($pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*) $(-> $ret:ty)?) => {
+        impl<$($generic: Any + Send),*> $pattern {
+            /// Create a full Join Pattern with the given name.
+            ///
+            /// The name is used instead of the bare Join Pattern ID wherever
+            /// the control thread describes the Join Pattern, such as in its
+            /// log messages and when reporting a panic of `f`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the full Join Pattern could not be registered.
+            pub fn then_do_named<F>(self, name: impl Into<String>, f: F)
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                self.try_then_do_named(name, f)
+                    .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
+                    .unwrap();
+            }
+
+            /// Create a full Join Pattern with the given name, like
+            /// `then_do_named`.
+            ///
+            /// Return a `PatternHandle` to cancel the Join Pattern or replace
+            /// its function with.
+            ///
+            /// # Errors
+            ///
+            /// Returns `JunctionClosed` instead of panicking if the
+            /// `Junction` has shut down.
+            pub fn try_then_do_named<F>(
+                self,
+                name: impl Into<String>,
+                f: F,
+            ) -> Result<PatternHandle<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync>, JunctionClosed>
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                let (join_pattern, sender, handle) =
+                    capture_with_handle!(self, f, ($($arg: $arg_type),*) $(-> $ret)?);
+
+                join_pattern::register(Box::new(NamedJoinPattern::new(name.into(), join_pattern)), sender)?;
+
+                Ok(handle)
+            }
+        }
+    };
+}
+
+impl_then_do_named!(unary::SendPartialPattern<T>, [T], (t: T));
+impl_then_do_named!(unary::RecvPartialPattern<R>, [R], () -> R);
+impl_then_do_named!(unary::BidirPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_named!(binary::SendPartialPattern<T, U>, [T, U], (t: T, u: U));
+impl_then_do_named!(binary::RecvPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_named!(binary::BidirPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_named!(ternary::SendPartialPattern<T, U, V>, [T, U, V], (t: T, u: U, v: V));
+impl_then_do_named!(ternary::RecvPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_named!(ternary::BidirPartialPattern<T, U, V, R>, [T, U, V, R], (t: T, u: U, v: V) -> R);
+
+/// Implement `then_do_with_limit` and `then_do_sequential` for the given
+/// partial Join Pattern, taking the same arguments as
+/// `impl_then_do_with_state`.
+macro_rules! impl_then_do_with_limit {
+    ($pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*) $(-> $ret:ty)?) => {
+        impl<$($generic: Any + Send),*> $pattern {
+            /// Create a full Join Pattern with at most `max_in_flight` of
+            /// its function bodies running at once.
+            ///
+            /// Once the limit has been reached, the Join Pattern is held back
+            /// even if it could fire, leaving its messages pending until one
+            /// of its function bodies has finished. Other Join Patterns may
+            /// consume these messages in the meantime.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `max_in_flight` is zero or if the full Join Pattern
+            /// could not be registered.
+            pub fn then_do_with_limit<F>(self, f: F, max_in_flight: usize)
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                self.try_then_do_with_limit(f, max_in_flight)
+                    .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
+                    .unwrap();
+            }
+
+            /// Create a full Join Pattern with at most `max_in_flight` of
+            /// its function bodies running at once, like
+            /// `then_do_with_limit`.
+            ///
+            /// Return a `PatternHandle` to cancel the Join Pattern or replace
+            /// its function with.
+            ///
+            /// # Errors
+            ///
+            /// Returns `JunctionClosed` instead of panicking if the
+            /// `Junction` has shut down.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `max_in_flight` is zero.
+            pub fn try_then_do_with_limit<F>(
+                self,
+                f: F,
+                max_in_flight: usize,
+            ) -> Result<PatternHandle<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync>, JunctionClosed>
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                assert!(max_in_flight > 0, "`max_in_flight` must be at least 1");
+
+                let (join_pattern, sender, handle) =
+                    capture_with_handle!(self, f, ($($arg: $arg_type),*) $(-> $ret)?);
+
+                join_pattern::register(
+                    Box::new(LimitedJoinPattern::new(max_in_flight, join_pattern)),
+                    sender,
+                )?;
+
+                Ok(handle)
+            }
+
+            /// Create a full Join Pattern whose function bodies never run at
+            /// the same time, each firing waiting for the previous function
+            /// body to return.
+            ///
+            /// The Join Pattern is not fired at all while its function body
+            /// is running, so its messages stay available to other Join
+            /// Patterns. See `then_do_with_limit`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the full Join Pattern could not be registered.
+            pub fn then_do_sequential<F>(self, f: F)
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                self.then_do_with_limit(f, 1)
+            }
+
+            /// Create a full Join Pattern whose function bodies never run at
+            /// the same time, like `then_do_sequential`.
+            ///
+            /// Return a `PatternHandle` to cancel the Join Pattern or replace
+            /// its function with.
+            ///
+            /// # Errors
+            ///
+            /// Returns `JunctionClosed` instead of panicking if the
+            /// `Junction` has shut down.
+            pub fn try_then_do_sequential<F>(
+                self,
+                f: F,
+            ) -> Result<PatternHandle<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync>, JunctionClosed>
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                self.try_then_do_with_limit(f, 1)
+            }
+        }
+    };
+}
+
+impl_then_do_with_limit!(unary::SendPartialPattern<T>, [T], (t: T));
+impl_then_do_with_limit!(unary::RecvPartialPattern<R>, [R], () -> R);
+impl_then_do_with_limit!(unary::BidirPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_with_limit!(binary::SendPartialPattern<T, U>, [T, U], (t: T, u: U));
+impl_then_do_with_limit!(binary::RecvPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_with_limit!(binary::BidirPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_with_limit!(ternary::SendPartialPattern<T, U, V>, [T, U, V], (t: T, u: U, v: V));
+impl_then_do_with_limit!(ternary::RecvPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_with_limit!(ternary::BidirPartialPattern<T, U, V, R>, [T, U, V, R], (t: T, u: U, v: V) -> R);
+
+/// Completion of the partial Join Patterns used by the crate itself.
+///
+/// Unlike plain `then_do`, whose generated Join Patterns spawn their
+/// function bodies as plain threads, the function bodies are spawned
+/// through the `ThreadSpawner` of the `Junction`.
+pub(crate) trait ThenDoSpawned<F> {
+    /// Create a full Join Pattern like `then_do`, spawning its function
+    /// bodies through the `ThreadSpawner` of the `Junction`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the full Join Pattern could not be registered.
+    fn then_do_spawned(self, f: F);
+}
+
+/// Take the value of the next `Message` of a Join Pattern run inline.
+///
+/// # Panics
+///
+/// Panics if there is no `Message` left or it holds a value of another type.
+fn take<T: Any + Send>(messages: &mut Vec<Message>) -> T {
+    *messages.remove(0).downcast::<T>().unwrap_or_else(|_| {
+        panic!(
+            "Message does not hold a value of type `{}`",
+            type_name::<T>()
+        )
+    })
+}
+
+/// Implement `then_do_inline`, `capture_spawned` and `ThenDoSpawned` for
+/// the given partial Join Pattern.
+///
+/// The arguments list the kind of the last channel of the partial Join
+/// Pattern, `send`, `recv` or `bidir`, and the generic parameters of the
+/// partial Join Pattern. These are followed by the arguments of the function
+/// it fires, with the argument taken from a `BidirChannel` listed on its
+/// own, and the return type of the function, if any.
+macro_rules! impl_then_do_inline {
+    (send $pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*)) => {
+        impl_then_do_inline!(@impl $pattern, [$($generic),*], ($($arg_type),*), f, messages, {
+            $(let $arg = take::<$arg_type>(&mut messages);)*
+            f($($arg),*)
+        });
+    };
+    (recv $pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*) -> $ret:ty) => {
+        impl_then_do_inline!(@impl $pattern, [$($generic),*], ($($arg_type),*) -> $ret, f, messages, {
+            $(let $arg = take::<$arg_type>(&mut messages);)*
+            let reply = take::<Sender<$ret>>(&mut messages);
+            if reply.send(f($($arg),*)).is_err() {
+                log::warn!("Dropping reply, as it is no longer waited for");
+            }
+        });
+    };
+    (bidir $pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*) ($last:ident: $last_type:ty) -> $ret:ty) => {
+        impl_then_do_inline!(@impl $pattern, [$($generic),*], ($($arg_type,)* $last_type) -> $ret, f, messages, {
+            $(let $arg = take::<$arg_type>(&mut messages);)*
+            let ($last, reply) = take::<($last_type, Sender<$ret>)>(&mut messages);
+            if reply.send(f($($arg,)* $last)).is_err() {
+                log::warn!("Dropping reply, as it is no longer waited for");
+            }
+        });
+    };
+    (@impl $pattern:ty, [$($generic:ident),*], ($($arg_type:ty),*) $(-> $ret:ty)?, $f:ident, $messages:ident, $run:block) => {
+        impl<$($generic: Any + Send),*> $pattern {
+            /// Create a full Join Pattern whose function is run directly on
+            /// the control thread instead of in a thread of its own, saving
+            /// the cost of spawning a thread for tiny function bodies.
+            ///
+            /// No other messages are handled while the function runs, so it
+            /// should return quickly. It must not wait for the `Junction` in
+            /// any way, e.g. by receiving on one of its channels or creating
+            /// new channels on it, as the control thread would wait for
+            /// itself. Such calls panic instead of deadlocking. Sending
+            /// messages is fine.
+            ///
+            /// A panic of the function is dealt with according to the
+            /// `PanicPolicy` of the `Junction`, like for any other function
+            /// body.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the full Join Pattern could not be registered.
+            pub fn then_do_inline<F>(self, $f: F)
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                self.try_then_do_inline($f)
+                    .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
+                    .unwrap();
+            }
+
+            /// Create a full Join Pattern whose function is run directly on
+            /// the control thread, like `then_do_inline`.
+            ///
+            /// Return a `PatternHandle` to cancel the Join Pattern or replace
+            /// its function with.
+            ///
+            /// # Errors
+            ///
+            /// Returns `JunctionClosed` instead of panicking if the
+            /// `Junction` has shut down.
+            pub fn try_then_do_inline<F>(
+                self,
+                $f: F,
+            ) -> Result<PatternHandle<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync>, JunctionClosed>
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                // Only the channels of the generated Join Pattern are of
+                // interest, as its function body would be run in a thread of
+                // its own.
+                let (join_pattern, sender) = join_pattern::capture(|| {
+                    self.then_do(|$(_: $arg_type),*| unreachable!("Join Pattern is never fired"))
+                })
+                .expect("Join Pattern was not registered by `then_do`");
+
+                let function: Arc<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync> = Arc::new($f);
+                let function = Arc::new(RwLock::new(function));
+                let token = CancellationToken::new();
+
+                let current = function.clone();
+                let run: RunInline = Arc::new(move |mut $messages: Vec<Message>| {
+                    let $f = current.read().unwrap().clone();
+                    $run
+                });
+
+                join_pattern::register(
+                    Box::new(CancellableJoinPattern::with_handle(
+                        token.clone(),
+                        Box::new(InlineJoinPattern::new(join_pattern.channels(), run)),
+                    )),
+                    sender,
+                )?;
+
+                Ok(PatternHandle::new(token, function))
+            }
+
+            /// Create a full Join Pattern like `then_do`, whose function
+            /// bodies are spawned through the `ThreadSpawner` of the
+            /// `Junction`, and return it along with the `Sender` to register
+            /// it with instead of registering it.
+            pub(crate) fn capture_spawned<F>(self, $f: F) -> (Box<dyn JoinPattern + Send>, Sender<Packet>)
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                // Only the channels of the generated Join Pattern are of
+                // interest, as its function body would be spawned as a plain
+                // thread.
+                let (join_pattern, sender) = join_pattern::capture(|| {
+                    self.then_do(|$(_: $arg_type),*| unreachable!("Join Pattern is never fired"))
+                })
+                .expect("Join Pattern was not registered by `then_do`");
+
+                let run: RunInline = Arc::new(move |mut $messages: Vec<Message>| $run);
+
+                (Box::new(MessagesJoinPattern::new(join_pattern.channels(), run)), sender)
+            }
+        }
+
+        impl<$($generic: Any + Send,)* F> ThenDoSpawned<F> for $pattern
+        where
+            F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+        {
+            fn then_do_spawned(self, $f: F) {
+                let (join_pattern, sender) = self.capture_spawned($f);
+
+                join_pattern::register(join_pattern, sender)
+                    .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
+                    .unwrap();
+            }
+        }
+    };
+}
+
+impl_then_do_inline!(send unary::SendPartialPattern<T>, [T], (t: T));
+impl_then_do_inline!(recv unary::RecvPartialPattern<R>, [R], () -> R);
+impl_then_do_inline!(bidir unary::BidirPartialPattern<T, R>, [T, R], () (t: T) -> R);
+impl_then_do_inline!(send binary::SendPartialPattern<T, U>, [T, U], (t: T, u: U));
+impl_then_do_inline!(recv binary::RecvPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_inline!(bidir binary::BidirPartialPattern<T, U, R>, [T, U, R], (t: T) (u: U) -> R);
+impl_then_do_inline!(send ternary::SendPartialPattern<T, U, V>, [T, U, V], (t: T, u: U, v: V));
+impl_then_do_inline!(recv ternary::RecvPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_inline!(bidir ternary::BidirPartialPattern<T, U, V, R>, [T, U, V, R], (t: T, u: U) (v: V) -> R);
+
+/// Implement `then_do_with_meta` for the given partial Join Pattern, taking
+/// the same arguments as `impl_then_do_inline`.
+macro_rules! impl_then_do_with_meta {
+    (send $pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*)) => {
+        impl_then_do_with_meta!(@impl $pattern, [$($generic),*], ($($arg_type),*), f, meta, messages, {
+            $(let $arg = take::<$arg_type>(&mut messages);)*
+            f(&meta, $($arg),*)
+        });
+    };
+    (recv $pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*) -> $ret:ty) => {
+        impl_then_do_with_meta!(@impl $pattern, [$($generic),*], ($($arg_type),*) -> $ret, f, meta, messages, {
+            $(let $arg = take::<$arg_type>(&mut messages);)*
+            let reply = take::<Sender<$ret>>(&mut messages);
+            if reply.send(f(&meta, $($arg),*)).is_err() {
+                log::warn!("Dropping reply, as it is no longer waited for");
+            }
+        });
+    };
+    (bidir $pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*) ($last:ident: $last_type:ty) -> $ret:ty) => {
+        impl_then_do_with_meta!(@impl $pattern, [$($generic),*], ($($arg_type,)* $last_type) -> $ret, f, meta, messages, {
+            $(let $arg = take::<$arg_type>(&mut messages);)*
+            let ($last, reply) = take::<($last_type, Sender<$ret>)>(&mut messages);
+            if reply.send(f(&meta, $($arg,)* $last)).is_err() {
+                log::warn!("Dropping reply, as it is no longer waited for");
+            }
+        });
+    };
+    (@impl $pattern:ty, [$($generic:ident),*], ($($arg_type:ty),*) $(-> $ret:ty)?, $f:ident, $meta:ident, $messages:ident, $run:block) => {
+        impl<$($generic: Any + Send),*> $pattern {
+            /// Create a full Join Pattern whose function additionally
+            /// receives the `Metadata` of the messages it fired on, one per
+            /// channel in the order the channels have been added to the
+            /// Join Pattern, see `SendChannel::send_with_meta`.
+            ///
+            /// Messages sent without metadata, including those sent on a
+            /// `RecvChannel`, have empty `Metadata`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the full Join Pattern could not be registered.
+            pub fn then_do_with_meta<F>(self, $f: F)
+            where
+                F: Fn(&[Metadata], $($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                self.try_then_do_with_meta($f)
+                    .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
+                    .unwrap();
+            }
+
+            /// Create a full Join Pattern whose function additionally
+            /// receives the `Metadata` of the messages it fired on, like
+            /// `then_do_with_meta`.
+            ///
+            /// Return a `PatternHandle` to cancel the Join Pattern with.
+            ///
+            /// # Errors
+            ///
+            /// Returns `JunctionClosed` instead of panicking if the
+            /// `Junction` has shut down.
+            pub fn try_then_do_with_meta<F>(
+                self,
+                $f: F,
+            ) -> Result<
+                PatternHandle<dyn Fn(&[Metadata], $($arg_type),*) $(-> $ret)? + Send + Sync>,
+                JunctionClosed,
+            >
+            where
+                F: Fn(&[Metadata], $($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                // Only the channels of the generated Join Pattern are of
+                // interest, as its function body does not get to see the
+                // `Message`s.
+                let (join_pattern, sender) = join_pattern::capture(|| {
+                    self.then_do(|$(_: $arg_type),*| unreachable!("Join Pattern is never fired"))
+                })
+                .expect("Join Pattern was not registered by `then_do`");
+
+                let function: Arc<dyn Fn(&[Metadata], $($arg_type),*) $(-> $ret)? + Send + Sync> =
+                    Arc::new($f);
+                let function = Arc::new(RwLock::new(function));
+                let token = CancellationToken::new();
+
+                let current = function.clone();
+                let run: RunInline = Arc::new(move |mut $messages: Vec<Message>| {
+                    let $meta: Vec<Metadata> =
+                        $messages.iter_mut().map(Message::take_metadata).collect();
+                    let $f = current.read().unwrap().clone();
+                    $run
+                });
+
+                join_pattern::register(
+                    Box::new(CancellableJoinPattern::with_handle(
+                        token.clone(),
+                        Box::new(MessagesJoinPattern::new(join_pattern.channels(), run)),
+                    )),
+                    sender,
+                )?;
+
+                Ok(PatternHandle::new(token, function))
+            }
+        }
+    };
+}
+
+impl_then_do_with_meta!(send unary::SendPartialPattern<T>, [T], (t: T));
+impl_then_do_with_meta!(recv unary::RecvPartialPattern<R>, [R], () -> R);
+impl_then_do_with_meta!(bidir unary::BidirPartialPattern<T, R>, [T, R], () (t: T) -> R);
+impl_then_do_with_meta!(send binary::SendPartialPattern<T, U>, [T, U], (t: T, u: U));
+impl_then_do_with_meta!(recv binary::RecvPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_with_meta!(bidir binary::BidirPartialPattern<T, U, R>, [T, U, R], (t: T) (u: U) -> R);
+impl_then_do_with_meta!(send ternary::SendPartialPattern<T, U, V>, [T, U, V], (t: T, u: U, v: V));
+impl_then_do_with_meta!(recv ternary::RecvPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_with_meta!(bidir ternary::BidirPartialPattern<T, U, V, R>, [T, U, V, R], (t: T, u: U) (v: V) -> R);
+
+/// Implement `then_do_scoped` for the given partial Join Pattern, taking the
+/// same arguments as `impl_then_do_with_state`.
+macro_rules! impl_then_do_scoped {
+    ($pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*) $(-> $ret:ty)?) => {
+        impl<$($generic: Any + Send),*> $pattern {
+            /// Create a full Join Pattern on the given `Scope`, whose function
+            /// may borrow from the environment of the scope.
+            ///
+            /// The partial Join Pattern has to have been created on the
+            /// `Junction` of the scope. See `Junction::scope`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the partial Join Pattern has been created on another
+            /// `Junction`, or if the full Join Pattern could not be
+            /// registered.
+            pub fn then_do_scoped<'scope, 'env, F>(self, scope: &'scope Scope<'scope, 'env>, f: F)
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'scope,
+            {
+                self.try_then_do_scoped(scope, f)
+                    .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
+                    .unwrap();
+            }
+
+            /// Create a full Join Pattern on the given `Scope`, whose function
+            /// may borrow from the environment of the scope, like
+            /// `then_do_scoped`.
+            ///
+            /// Return a `PatternHandle` to cancel the Join Pattern with,
+            /// which cannot outlive the scope either.
+            ///
+            /// # Errors
+            ///
+            /// Returns `JunctionClosed` instead of panicking if the
+            /// `Junction` has shut down.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the partial Join Pattern has been created on another
+            /// `Junction` than the one of the scope.
+            pub fn try_then_do_scoped<'scope, 'env, F>(
+                self,
+                scope: &'scope Scope<'scope, 'env>,
+                f: F,
+            ) -> Result<
+                PatternHandle<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'scope>,
+                JunctionClosed,
+            >
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'scope,
+            {
+                let shared: Arc<OnceLock<Arc<RwLock<Arc<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync>>>>> =
+                    Arc::new(OnceLock::new());
+
+                let current = shared.clone();
+                let (join_pattern, sender) = self.capture_spawned(move |$($arg),*| {
+                    let function = current.get().expect("Join Pattern fired before its function was set");
+                    let f = function.read().unwrap().clone();
+                    f($($arg),*)
+                });
+
+                assert!(
+                    scope.owns(&sender),
+                    "Partial Join Pattern is not associated with the Junction of \
+                     the Scope! Please create it using the Scope passed to the \
+                     closure of `Junction::scope`!"
+                );
+
+                let function: Arc<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'scope> = Arc::new(f);
+                let function = Arc::new(RwLock::new(function));
+                // SAFETY: The partial Join Pattern has been created on the
+                // `Junction` of `scope`, as asserted above, so the Join
+                // Pattern holding `function` only ever fires on, and is only
+                // ever added to, that `Junction`, which is stopped before the
+                // scope ends. Stopping drops all of its Join Patterns and
+                // joins the threads of all fired function bodies, while the
+                // returned `PatternHandle` cannot outlive `'scope` either, so
+                // `function` is neither called nor dropped after `'scope` has
+                // ended.
+                let erased: Arc<RwLock<Arc<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static>>> =
+                    unsafe { std::mem::transmute(function.clone()) };
+                let _ = shared.set(erased);
+
+                let token = CancellationToken::new();
+                scope.add(Box::new(CancellableJoinPattern::with_handle(token.clone(), join_pattern)))?;
+
+                Ok(PatternHandle::new(token, function))
+            }
+        }
+    };
+}
+
+impl_then_do_scoped!(unary::SendPartialPattern<T>, [T], (t: T));
+impl_then_do_scoped!(unary::RecvPartialPattern<R>, [R], () -> R);
+impl_then_do_scoped!(unary::BidirPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_scoped!(binary::SendPartialPattern<T, U>, [T, U], (t: T, u: U));
+impl_then_do_scoped!(binary::RecvPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_scoped!(binary::BidirPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_scoped!(ternary::SendPartialPattern<T, U, V>, [T, U, V], (t: T, u: U, v: V));
+impl_then_do_scoped!(ternary::RecvPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_scoped!(ternary::BidirPartialPattern<T, U, V, R>, [T, U, V, R], (t: T, u: U, v: V) -> R);
+
+/// Implement `then_do_with_handle` and `then_do_cancellable` for the given
+/// partial Join Pattern, taking the same arguments as
+/// `impl_then_do_with_state`.
+macro_rules! impl_then_do_with_handle {
+    ($pattern:ty, [$($generic:ident),*], ($($arg:ident: $arg_type:ty),*) $(-> $ret:ty)?) => {
+        impl<$($generic: Any + Send),*> $pattern {
+            /// Create a full Join Pattern and return a `PatternHandle` to
+            /// cancel it or replace its function with.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the full Join Pattern could not be registered.
+            pub fn then_do_with_handle<F>(
+                self,
+                f: F,
+            ) -> PatternHandle<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync>
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                self.try_then_do(f)
+                    .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
+                    .unwrap()
+            }
+
+            /// Create a full Join Pattern and return a `PatternHandle` to
+            /// cancel it or replace its function with, like
+            /// `then_do_with_handle`.
+            ///
+            /// # Errors
+            ///
+            /// Returns `JunctionClosed` instead of panicking if the
+            /// `Junction` has shut down.
+            pub fn try_then_do<F>(
+                self,
+                f: F,
+            ) -> Result<PatternHandle<dyn Fn($($arg_type),*) $(-> $ret)? + Send + Sync>, JunctionClosed>
+            where
+                F: Fn($($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                let (join_pattern, sender, handle) =
+                    capture_with_handle!(self, f, ($($arg: $arg_type),*) $(-> $ret)?);
+
+                join_pattern::register(join_pattern, sender)?;
+
+                Ok(handle)
+            }
+
+            /// Create a full Join Pattern whose function is handed a
+            /// `CancellationToken` on every firing, see `cancel`.
+            ///
+            /// Return a `PatternHandle` to cancel the Join Pattern or replace
+            /// its function with.
+            ///
+            /// # Panics
+            ///
+            /// Panics if the full Join Pattern could not be registered.
+            pub fn then_do_cancellable<F>(
+                self,
+                f: F,
+            ) -> PatternHandle<dyn Fn(CancellationToken, $($arg_type),*) $(-> $ret)? + Send + Sync>
+            where
+                F: Fn(CancellationToken, $($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                self.try_then_do_cancellable(f)
+                    .map_err(|e| log::error!("Failed to send AddJoinPatternRequest: {e:?}"))
+                    .unwrap()
+            }
+
+            /// Create a full Join Pattern whose function is handed a
+            /// `CancellationToken` on every firing, like
+            /// `then_do_cancellable`.
+            ///
+            /// # Errors
+            ///
+            /// Returns `JunctionClosed` instead of panicking if the
+            /// `Junction` has shut down.
+            pub fn try_then_do_cancellable<F>(
+                self,
+                f: F,
+            ) -> Result<
+                PatternHandle<dyn Fn(CancellationToken, $($arg_type),*) $(-> $ret)? + Send + Sync>,
+                JunctionClosed,
+            >
+            where
+                F: Fn(CancellationToken, $($arg_type),*) $(-> $ret)? + Send + Sync + 'static,
+            {
+                let function: Arc<
+                    dyn Fn(CancellationToken, $($arg_type),*) $(-> $ret)? + Send + Sync,
+                > = Arc::new(f);
+                let function = Arc::new(RwLock::new(function));
+                let token = CancellationToken::new();
+
+                let current = function.clone();
+                let body_token = token.clone();
+                let (join_pattern, sender) = self.capture_spawned(move |$($arg),*| {
+                    let f = current.read().unwrap().clone();
+                    f(body_token.clone(), $($arg),*)
+                });
+
+                join_pattern::register(
+                    Box::new(CancellableJoinPattern::new(token.clone(), join_pattern)),
+                    sender,
+                )?;
+
+                Ok(PatternHandle::new(token, function))
+            }
+        }
+    };
+}
+
+impl_then_do_with_handle!(unary::SendPartialPattern<T>, [T], (t: T));
+impl_then_do_with_handle!(unary::RecvPartialPattern<R>, [R], () -> R);
+impl_then_do_with_handle!(unary::BidirPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_with_handle!(binary::SendPartialPattern<T, U>, [T, U], (t: T, u: U));
+impl_then_do_with_handle!(binary::RecvPartialPattern<T, R>, [T, R], (t: T) -> R);
+impl_then_do_with_handle!(binary::BidirPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_with_handle!(ternary::SendPartialPattern<T, U, V>, [T, U, V], (t: T, u: U, v: V));
+impl_then_do_with_handle!(ternary::RecvPartialPattern<T, U, R>, [T, U, R], (t: T, u: U) -> R);
+impl_then_do_with_handle!(ternary::BidirPartialPattern<T, U, V, R>, [T, U, V, R], (t: T, u: U, v: V) -> R);
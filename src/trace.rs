@@ -0,0 +1,205 @@
+//! Recording and replaying the decisions of a `Junction` for deterministic
+//! debugging.
+//!
+//! A `Trace` set through `JunctionBuilder::record_trace` records every
+//! decision the control thread takes: the order in which messages arrive,
+//! which Join Pattern is fired and which messages it consumes. A `Junction`
+//! built with `JunctionBuilder::replay_trace` takes the same decisions
+//! again, holding back messages that arrive earlier than they did in the
+//! trace and firing only the Join Patterns that fired in the trace, so that
+//! a rare interleaving can be reproduced while stepping through user code.
+//! Once all decisions of the trace have been taken, the `Junction` carries
+//! on as usual.
+//!
+//! Channels and Join Patterns are identified by the order in which they
+//! were created on the `Junction`, so a `Junction` replaying a trace has to
+//! create them in the same order as the one recording it. Traces of sharded
+//! `Junction`s, whose shards all record into the same `Trace`, cannot be
+//! replayed.
+//!
+//! Traces are written and read in a compact text format with one decision
+//! per line: `a <channel>` for an arriving message and `f <join pattern>`
+//! followed by `<channel>:<sequence>` for each message consumed, where the
+//! sequence number is `-` for messages without one.
+//!
+//! ```
+//! use rusty_junctions::{trace::Trace, Junction};
+//!
+//! let trace = Trace::new();
+//! let j = Junction::builder().record_trace(trace.clone()).build();
+//! let job = j.send_channel::<u32>();
+//! let done = j.recv_channel::<u32>();
+//! j.when(&job).and_recv(&done).then_do(|job| job);
+//!
+//! job.send(1).unwrap();
+//! assert_eq!(done.recv().unwrap(), 1);
+//! drop(j);
+//!
+//! let mut written = Vec::new();
+//! trace.write_to(&mut written).unwrap();
+//! assert_eq!(String::from_utf8(written.clone()).unwrap(), "a 0\na 1\nf 0 0:0 1:-\n");
+//!
+//! // Replaying, the `Junction` waits for the message on `job` to arrive
+//! // before the call to `done`, just like when recording.
+//! let trace = Trace::read_from(&written[..]).unwrap();
+//! let j = Junction::builder().replay_trace(trace).build();
+//! let job = j.send_channel::<u32>();
+//! let done = j.recv_channel::<u32>();
+//! j.when(&job).and_recv(&done).then_do(|job| job);
+//!
+//! let waiting = std::thread::spawn(move || done.recv().unwrap());
+//! job.send(2).unwrap();
+//! assert_eq!(waiting.join().unwrap(), 2);
+//! ```
+
+use std::{
+    fmt,
+    io::{self, BufRead, ErrorKind, Write},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use crate::types::ids::ChannelId;
+
+/// Decision taken by the control thread of a `Junction`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// A message arrived on the given channel.
+    Arrived { channel: ChannelId },
+    /// The Join Pattern with the given number, counting Join Patterns in
+    /// order of creation, fired, consuming a message of each of the given
+    /// channels with the given sequence number, if any.
+    Fired {
+        join_pattern: usize,
+        consumed: Vec<(ChannelId, Option<u64>)>,
+    },
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Decision::Arrived { channel } => write!(f, "a {}", channel.value()),
+            Decision::Fired {
+                join_pattern,
+                consumed,
+            } => {
+                write!(f, "f {join_pattern}")?;
+                for (channel, sequence) in consumed {
+                    match sequence {
+                        Some(sequence) => write!(f, " {}:{sequence}", channel.value())?,
+                        None => write!(f, " {}:-", channel.value())?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Decision {
+    type Err = io::Error;
+
+    fn from_str(line: &str) -> Result<Decision, io::Error> {
+        let invalid =
+            || io::Error::new(ErrorKind::InvalidData, format!("invalid decision: {line}"));
+        let number = |s: &str| s.parse::<usize>().map_err(|_| invalid());
+
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("a") => {
+                let channel = ChannelId::new(number(parts.next().ok_or_else(invalid)?)?);
+                Ok(Decision::Arrived { channel })
+            }
+            Some("f") => {
+                let join_pattern = number(parts.next().ok_or_else(invalid)?)?;
+                let consumed = parts
+                    .map(|part| {
+                        let (channel, sequence) = part.split_once(':').ok_or_else(invalid)?;
+                        let sequence = match sequence {
+                            "-" => None,
+                            sequence => Some(sequence.parse::<u64>().map_err(|_| invalid())?),
+                        };
+                        Ok((ChannelId::new(number(channel)?), sequence))
+                    })
+                    .collect::<Result<_, io::Error>>()?;
+
+                Ok(Decision::Fired {
+                    join_pattern,
+                    consumed,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Decisions recorded from or to be replayed by a `Junction`.
+///
+/// Clones share the same decisions, so a clone handed to a
+/// `JunctionBuilder` records into the original.
+#[derive(Debug, Clone, Default)]
+pub struct Trace {
+    decisions: Arc<Mutex<Vec<Decision>>>,
+}
+
+impl Trace {
+    /// Create a new, empty `Trace`.
+    pub fn new() -> Trace {
+        Trace::default()
+    }
+
+    /// Return a copy of all decisions recorded so far, in order.
+    pub fn decisions(&self) -> Vec<Decision> {
+        self.decisions.lock().unwrap().clone()
+    }
+
+    /// Return the number of decisions recorded so far.
+    pub fn len(&self) -> usize {
+        self.decisions.lock().unwrap().len()
+    }
+
+    /// Return `true` if no decisions have been recorded so far.
+    pub fn is_empty(&self) -> bool {
+        self.decisions.lock().unwrap().is_empty()
+    }
+
+    /// Write all decisions recorded so far to the given writer, one per
+    /// line.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        for decision in self.decisions.lock().unwrap().iter() {
+            writeln!(writer, "{decision}")?;
+        }
+
+        writer.flush()
+    }
+
+    /// Read a `Trace` written by `write_to` from the given reader.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, or with `ErrorKind::InvalidData`
+    /// if a line does not hold a valid decision.
+    pub fn read_from(reader: impl BufRead) -> io::Result<Trace> {
+        let mut decisions = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                decisions.push(line.parse()?);
+            }
+        }
+
+        Ok(Trace {
+            decisions: Arc::new(Mutex::new(decisions)),
+        })
+    }
+
+    /// Record the given decision.
+    pub(crate) fn record(&self, decision: Decision) {
+        self.decisions.lock().unwrap().push(decision);
+    }
+}
@@ -0,0 +1,707 @@
+//! Strongly typed alternative to `Junction` without type-erased messages.
+//!
+//! A `TypedJunction` stores the messages of each channel in a queue of the
+//! channel's own type instead of sending `Box<dyn Any>` messages to a
+//! `Controller`. Join Patterns take their messages straight out of these
+//! queues, so no message is ever downcast and type mismatches are ruled out
+//! at compile time.
+//!
+//! There is no control thread either. Instead, sending a message checks the
+//! Join Patterns of its channel right away on the sending thread. Taking the
+//! messages for a Join Pattern happens under a lock shared by all channels
+//! of the `TypedJunction`, so that Join Patterns fire atomically. Function
+//! bodies of fired Join Patterns run in their own threads, same as with a
+//! `Junction`, spawned through the `ThreadSpawner` given to
+//! `TypedJunction::with_thread_spawner`.
+//!
+//! ```
+//! use rusty_junctions::typed::TypedJunction;
+//!
+//! let j = TypedJunction::new();
+//! let value = j.send_channel::<i32>();
+//! let add = j.bidir_channel::<i32, i32>();
+//!
+//! let value_clone = value.clone();
+//! j.when(&value).and_bidir(&add).then_do(move |v, n| {
+//!     value_clone.send(v + n);
+//!     v + n
+//! });
+//!
+//! value.send(40);
+//! assert_eq!(add.send_recv(2).unwrap(), 42);
+//! ```
+//!
+//! Join Patterns of a shape a `TypedJunction` cannot fire fail to compile,
+//! such as Join Patterns of more than 3 channels:
+//!
+//! ```compile_fail
+//! use rusty_junctions::typed::TypedJunction;
+//!
+//! let j = TypedJunction::new();
+//! let value = j.send_channel::<i32>();
+//! j.when(&value)
+//!     .and(&value)
+//!     .and(&value)
+//!     .and(&value)
+//!     .then_do(|a, b, c, d| println!("{}", a + b + c + d));
+//! ```
+//!
+//! As well as channels following the `TypedBidirChannel`, which has to end
+//! a Join Pattern:
+//!
+//! ```compile_fail
+//! use rusty_junctions::typed::TypedJunction;
+//!
+//! let j = TypedJunction::new();
+//! let value = j.send_channel::<i32>();
+//! let add = j.bidir_channel::<i32, i32>();
+//! j.when(&value).and_bidir(&add).and(&value);
+//! ```
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{channel, RecvError, Sender},
+        Arc, Mutex, Weak,
+    },
+};
+
+use crate::{builder::ThreadSpawner, types::ids::JunctionId};
+
+/// Function body of a fired Join Pattern, ready to be run.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Strongly typed counterpart to `Junction`.
+pub struct TypedJunction {
+    id: JunctionId,
+    /// Lock held while checking and taking the messages for a Join Pattern.
+    lock: Arc<Mutex<()>>,
+    /// All Join Patterns declared on this `TypedJunction`. Channels only
+    /// hold weak references to them, so they are dropped alongside it.
+    join_patterns: Mutex<Vec<Arc<TypedJoinPattern>>>,
+    latest_channel_id: AtomicUsize,
+    thread_spawner: ThreadSpawner,
+}
+
+#[allow(clippy::new_without_default)]
+impl TypedJunction {
+    /// Create a new `TypedJunction`.
+    pub fn new() -> TypedJunction {
+        TypedJunction::with_thread_spawner(ThreadSpawner::default())
+    }
+
+    /// Create a new `TypedJunction` spawning the threads of fired function
+    /// bodies through the given `ThreadSpawner`.
+    ///
+    /// ```
+    /// use rusty_junctions::{builder::ThreadSpawner, typed::TypedJunction};
+    ///
+    /// let j = TypedJunction::with_thread_spawner(ThreadSpawner::new(|builder, f| {
+    ///     builder.name("typed-body".to_string()).spawn(f)
+    /// }));
+    /// let value = j.send_channel::<i32>();
+    /// let name = j.bidir_channel::<(), String>();
+    /// j.when(&value).and_bidir(&name).then_do(|_, ()| {
+    ///     std::thread::current().name().unwrap().to_string()
+    /// });
+    ///
+    /// value.send(1);
+    /// assert_eq!(name.send_recv(()).unwrap(), "typed-body");
+    /// ```
+    pub fn with_thread_spawner(thread_spawner: ThreadSpawner) -> TypedJunction {
+        TypedJunction {
+            id: JunctionId::new(),
+            lock: Arc::new(Mutex::new(())),
+            join_patterns: Mutex::new(Vec::new()),
+            latest_channel_id: AtomicUsize::new(0),
+            thread_spawner,
+        }
+    }
+
+    /// Create and return a new `TypedSendChannel` on this `TypedJunction`.
+    pub fn send_channel<T: Send + 'static>(&self) -> TypedSendChannel<T> {
+        TypedSendChannel {
+            junction_id: self.id,
+            queue: Arc::new(Queue::new(self.new_channel_id())),
+        }
+    }
+
+    /// Create and return a new `TypedBidirChannel` on this `TypedJunction`.
+    pub fn bidir_channel<T, R>(&self) -> TypedBidirChannel<T, R>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+    {
+        TypedBidirChannel {
+            junction_id: self.id,
+            queue: Arc::new(Queue::new(self.new_channel_id())),
+        }
+    }
+
+    /// Create new partial Join Pattern starting with a `TypedSendChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by this
+    /// `TypedJunction`.
+    pub fn when<T: Send + 'static>(
+        &self,
+        send_channel: &TypedSendChannel<T>,
+    ) -> TypedPartialPattern<'_, (T,)> {
+        self.assert_own(send_channel.junction_id);
+
+        let queue = send_channel.queue.clone();
+        TypedPartialPattern {
+            junction: self,
+            channels: vec![send_channel.queue.clone()],
+            take: Arc::new(move || (queue.pop(),)),
+        }
+    }
+
+    /// Create new partial Join Pattern starting with a `TypedBidirChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by this
+    /// `TypedJunction`.
+    pub fn when_bidir<T, R>(
+        &self,
+        bidir_channel: &TypedBidirChannel<T, R>,
+    ) -> TypedBidirPartialPattern<'_, (T,), R>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+    {
+        self.assert_own(bidir_channel.junction_id);
+
+        let queue = bidir_channel.queue.clone();
+        TypedBidirPartialPattern {
+            junction: self,
+            channels: vec![bidir_channel.queue.clone()],
+            take: Arc::new(move || {
+                let (t, reply) = queue.pop();
+                ((t,), reply)
+            }),
+        }
+    }
+
+    fn new_channel_id(&self) -> usize {
+        self.latest_channel_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the given `JunctionId` is not the one of this `TypedJunction`.
+    fn assert_own(&self, junction_id: JunctionId) {
+        if junction_id != self.id {
+            panic!(
+                "Channel is not associated with TypedJunction! Please use a \
+                 channel created using the same TypedJunction calling this \
+                 function!"
+            );
+        }
+    }
+
+    /// Store a new Join Pattern and fire it for messages already queued.
+    fn add_join_pattern(
+        &self,
+        channels: Vec<Arc<dyn PendingQueue>>,
+        take: Box<dyn Fn() -> Job + Send + Sync>,
+    ) {
+        let join_pattern = Arc::new(TypedJoinPattern {
+            lock: self.lock.clone(),
+            channels,
+            take,
+            thread_spawner: self.thread_spawner.clone(),
+        });
+
+        join_pattern
+            .channels
+            .iter()
+            .for_each(|queue| queue.link(Arc::downgrade(&join_pattern)));
+
+        while join_pattern.try_fire() {}
+
+        self.join_patterns.lock().unwrap().push(join_pattern);
+    }
+}
+
+/// Queue of messages of a single channel.
+struct Queue<T> {
+    id: usize,
+    items: Mutex<VecDeque<T>>,
+    join_patterns: Mutex<Vec<Weak<TypedJoinPattern>>>,
+    /// Index of the Join Pattern to check first on the next message, rotated
+    /// so that no Join Pattern is always checked last.
+    next_join_pattern: AtomicUsize,
+}
+
+impl<T: Send> Queue<T> {
+    fn new(id: usize) -> Queue<T> {
+        Queue {
+            id,
+            items: Mutex::new(VecDeque::new()),
+            join_patterns: Mutex::new(Vec::new()),
+            next_join_pattern: AtomicUsize::new(0),
+        }
+    }
+
+    /// Add a message and fire a Join Pattern of this channel, if possible.
+    fn push(&self, item: T) {
+        self.items.lock().unwrap().push_back(item);
+
+        let join_patterns: Vec<_> = self
+            .join_patterns
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect();
+
+        if join_patterns.is_empty() {
+            return;
+        }
+
+        let start = self.next_join_pattern.fetch_add(1, Ordering::Relaxed);
+        for i in 0..join_patterns.len() {
+            if join_patterns[(start + i) % join_patterns.len()].try_fire() {
+                return;
+            }
+        }
+    }
+
+    /// Take the oldest message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no message, must only be called from a Join
+    /// Pattern that has checked for readiness.
+    fn pop(&self) -> T {
+        self.items.lock().unwrap().pop_front().unwrap()
+    }
+}
+
+/// Type-erased view of a `Queue` used to check Join Patterns for readiness.
+trait PendingQueue: Send + Sync {
+    fn id(&self) -> usize;
+    fn len(&self) -> usize;
+    fn link(&self, join_pattern: Weak<TypedJoinPattern>);
+}
+
+impl<T: Send> PendingQueue for Queue<T> {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    fn link(&self, join_pattern: Weak<TypedJoinPattern>) {
+        self.join_patterns.lock().unwrap().push(join_pattern);
+    }
+}
+
+/// Join Pattern declared on a `TypedJunction`.
+struct TypedJoinPattern {
+    lock: Arc<Mutex<()>>,
+    channels: Vec<Arc<dyn PendingQueue>>,
+    /// Take one message off every channel and return the function body to
+    /// run on them.
+    take: Box<dyn Fn() -> Job + Send + Sync>,
+    thread_spawner: ThreadSpawner,
+}
+
+impl TypedJoinPattern {
+    /// Fire this Join Pattern if there are messages for all its channels.
+    ///
+    /// Return `true` if the Join Pattern has been fired.
+    fn try_fire(&self) -> bool {
+        let job = {
+            // Only ever taking messages while holding the lock guarantees
+            // that no message disappears between checking and taking.
+            let _guard = self.lock.lock().unwrap();
+            if !self.is_alive() {
+                return false;
+            }
+
+            (self.take)()
+        };
+
+        self.thread_spawner.spawn_body(job);
+        true
+    }
+
+    /// Return `true` if there are enough messages for all channels, taking
+    /// into account channels appearing multiple times.
+    fn is_alive(&self) -> bool {
+        self.channels.iter().all(|queue| {
+            let required = self
+                .channels
+                .iter()
+                .filter(|other| other.id() == queue.id())
+                .count();
+            queue.len() >= required
+        })
+    }
+}
+
+/// Asynchronous, message sending channel of a `TypedJunction`.
+pub struct TypedSendChannel<T> {
+    junction_id: JunctionId,
+    queue: Arc<Queue<T>>,
+}
+
+impl<T: Send + 'static> TypedSendChannel<T> {
+    /// Send a message, potentially firing a Join Pattern on this thread.
+    pub fn send(&self, value: T) {
+        self.queue.push(value);
+    }
+}
+
+impl<T> Clone for TypedSendChannel<T> {
+    fn clone(&self) -> TypedSendChannel<T> {
+        TypedSendChannel {
+            junction_id: self.junction_id,
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+/// Synchronous, bidirectional message channel of a `TypedJunction`.
+pub struct TypedBidirChannel<T, R> {
+    junction_id: JunctionId,
+    queue: Arc<Queue<(T, Sender<R>)>>,
+}
+
+impl<T: Send + 'static, R: Send + 'static> TypedBidirChannel<T, R> {
+    /// Send a message and block until a Join Pattern has replied to it.
+    pub fn send_recv(&self, value: T) -> Result<R, RecvError> {
+        let (tx, rx) = channel::<R>();
+        self.queue.push((value, tx));
+
+        rx.recv()
+    }
+}
+
+impl<T, R> Clone for TypedBidirChannel<T, R> {
+    fn clone(&self) -> TypedBidirChannel<T, R> {
+        TypedBidirChannel {
+            junction_id: self.junction_id,
+            queue: self.queue.clone(),
+        }
+    }
+}
+
+/// Partial Join Pattern on a `TypedJunction` consisting of `TypedSendChannel`s
+/// whose message types are given by the tuple `A`.
+pub struct TypedPartialPattern<'a, A> {
+    junction: &'a TypedJunction,
+    channels: Vec<Arc<dyn PendingQueue>>,
+    take: Arc<dyn Fn() -> A + Send + Sync>,
+}
+
+/// Partial Join Pattern on a `TypedJunction` consisting of `TypedSendChannel`s
+/// and a final `TypedBidirChannel`, with message types given by the tuple `A`
+/// and reply type `R`.
+pub struct TypedBidirPartialPattern<'a, A, R> {
+    junction: &'a TypedJunction,
+    channels: Vec<Arc<dyn PendingQueue>>,
+    take: Arc<dyn Fn() -> (A, Sender<R>) + Send + Sync>,
+}
+
+impl<'a, A: Send + 'static> TypedPartialPattern<'a, A> {
+    /// Extend this partial Join Pattern by the given channel.
+    fn extend<U: Send + 'static, B: 'static>(
+        mut self,
+        junction_id: JunctionId,
+        queue: &Arc<Queue<U>>,
+        combine: fn(A, U) -> B,
+    ) -> (Self, Arc<dyn Fn() -> B + Send + Sync>) {
+        self.junction.assert_own(junction_id);
+        self.channels.push(queue.clone());
+
+        let take = self.take.clone();
+        let queue = queue.clone();
+        let extended = Arc::new(move || combine(take(), queue.pop()));
+
+        (self, extended)
+    }
+
+    /// Extend this partial Join Pattern by a final `TypedBidirChannel`.
+    fn extend_bidir<U, R, B: 'static>(
+        self,
+        bidir_channel: &TypedBidirChannel<U, R>,
+        combine: fn(A, U) -> B,
+    ) -> TypedBidirPartialPattern<'a, B, R>
+    where
+        U: Send + 'static,
+        R: Send + 'static,
+    {
+        let (partial, take) =
+            self.extend(bidir_channel.junction_id, &bidir_channel.queue, |a, u| {
+                (a, u)
+            });
+
+        TypedBidirPartialPattern {
+            junction: partial.junction,
+            channels: partial.channels,
+            take: Arc::new(move || {
+                let (a, (u, reply)) = take();
+                (combine(a, u), reply)
+            }),
+        }
+    }
+
+    /// Register the Join Pattern, running `f` on the taken messages.
+    fn register(self, f: impl Fn(A) + Send + Sync + 'static) {
+        let take = self.take;
+        let f = Arc::new(f);
+
+        self.junction.add_join_pattern(
+            self.channels,
+            Box::new(move || {
+                let args = take();
+                let f = f.clone();
+                Box::new(move || f(args))
+            }),
+        );
+    }
+}
+
+impl<A: Send + 'static, R: Send + 'static> TypedBidirPartialPattern<'_, A, R> {
+    /// Register the Join Pattern, replying with the result of `f` on the
+    /// taken messages.
+    fn register(self, f: impl Fn(A) -> R + Send + Sync + 'static) {
+        let take = self.take;
+        let f = Arc::new(f);
+
+        self.junction.add_join_pattern(
+            self.channels,
+            Box::new(move || {
+                let (args, reply) = take();
+                let f = f.clone();
+                Box::new(move || {
+                    let _ = reply.send(f(args));
+                })
+            }),
+        );
+    }
+}
+
+impl<'a, T: Send + 'static> TypedPartialPattern<'a, (T,)> {
+    /// Extend this partial Join Pattern by another `TypedSendChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `TypedJunction`.
+    pub fn and<U: Send + 'static>(
+        self,
+        send_channel: &TypedSendChannel<U>,
+    ) -> TypedPartialPattern<'a, (T, U)> {
+        let (partial, take) =
+            self.extend(send_channel.junction_id, &send_channel.queue, |(t,), u| {
+                (t, u)
+            });
+
+        TypedPartialPattern {
+            junction: partial.junction,
+            channels: partial.channels,
+            take,
+        }
+    }
+
+    /// Extend this partial Join Pattern by a final `TypedBidirChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `TypedJunction`.
+    pub fn and_bidir<U, R>(
+        self,
+        bidir_channel: &TypedBidirChannel<U, R>,
+    ) -> TypedBidirPartialPattern<'a, (T, U), R>
+    where
+        U: Send + 'static,
+        R: Send + 'static,
+    {
+        self.extend_bidir(bidir_channel, |(t,), u| (t, u))
+    }
+
+    /// Create a full Join Pattern firing `f` with the message received.
+    pub fn then_do<F>(self, f: F)
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        self.register(move |(t,)| f(t));
+    }
+}
+
+impl<'a, T: Send + 'static, U: Send + 'static> TypedPartialPattern<'a, (T, U)> {
+    /// Extend this partial Join Pattern by another `TypedSendChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `TypedJunction`.
+    pub fn and<V: Send + 'static>(
+        self,
+        send_channel: &TypedSendChannel<V>,
+    ) -> TypedPartialPattern<'a, (T, U, V)> {
+        let (partial, take) = self.extend(
+            send_channel.junction_id,
+            &send_channel.queue,
+            |(t, u), v| (t, u, v),
+        );
+
+        TypedPartialPattern {
+            junction: partial.junction,
+            channels: partial.channels,
+            take,
+        }
+    }
+
+    /// Extend this partial Join Pattern by a final `TypedBidirChannel`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the supplied channel has not been created by the same
+    /// `TypedJunction`.
+    pub fn and_bidir<V, R>(
+        self,
+        bidir_channel: &TypedBidirChannel<V, R>,
+    ) -> TypedBidirPartialPattern<'a, (T, U, V), R>
+    where
+        V: Send + 'static,
+        R: Send + 'static,
+    {
+        self.extend_bidir(bidir_channel, |(t, u), v| (t, u, v))
+    }
+
+    /// Create a full Join Pattern firing `f` with the messages received.
+    pub fn then_do<F>(self, f: F)
+    where
+        F: Fn(T, U) + Send + Sync + 'static,
+    {
+        self.register(move |(t, u)| f(t, u));
+    }
+}
+
+impl<T: Send + 'static, U: Send + 'static, V: Send + 'static> TypedPartialPattern<'_, (T, U, V)> {
+    /// Never compiles, as Join Patterns of a `TypedJunction` take at most
+    /// 3 channels.
+    pub fn and<W>(self, _send_channel: &TypedSendChannel<W>) -> !
+    where
+        Self: Extendable,
+    {
+        unreachable!("`Extendable` is not implemented for any type")
+    }
+
+    /// Never compiles, as Join Patterns of a `TypedJunction` take at most
+    /// 3 channels.
+    pub fn and_bidir<W, R>(self, _bidir_channel: &TypedBidirChannel<W, R>) -> !
+    where
+        Self: Extendable,
+    {
+        unreachable!("`Extendable` is not implemented for any type")
+    }
+
+    /// Create a full Join Pattern firing `f` with the messages received.
+    pub fn then_do<F>(self, f: F)
+    where
+        F: Fn(T, U, V) + Send + Sync + 'static,
+    {
+        self.register(move |(t, u, v)| f(t, u, v));
+    }
+}
+
+impl<A, R> TypedBidirPartialPattern<'_, A, R> {
+    /// Never compiles, as no channel can follow the `TypedBidirChannel`
+    /// ending a Join Pattern.
+    pub fn and<U>(self, _send_channel: &TypedSendChannel<U>) -> !
+    where
+        Self: Unterminated,
+    {
+        unreachable!("`Unterminated` is not implemented for any type")
+    }
+
+    /// Never compiles, as a Join Pattern has at most one
+    /// `TypedBidirChannel`, which ends it.
+    pub fn and_bidir<U, S>(self, _bidir_channel: &TypedBidirChannel<U, S>) -> !
+    where
+        Self: Unterminated,
+    {
+        unreachable!("`Unterminated` is not implemented for any type")
+    }
+}
+
+impl<T: Send + 'static, R: Send + 'static> TypedBidirPartialPattern<'_, (T,), R> {
+    /// Create a full Join Pattern replying with the result of `f`.
+    pub fn then_do<F>(self, f: F)
+    where
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        self.register(move |(t,)| f(t));
+    }
+}
+
+impl<T: Send + 'static, U: Send + 'static, R: Send + 'static>
+    TypedBidirPartialPattern<'_, (T, U), R>
+{
+    /// Create a full Join Pattern replying with the result of `f`.
+    pub fn then_do<F>(self, f: F)
+    where
+        F: Fn(T, U) -> R + Send + Sync + 'static,
+    {
+        self.register(move |(t, u)| f(t, u));
+    }
+}
+
+impl<T, U, V, R> TypedBidirPartialPattern<'_, (T, U, V), R>
+where
+    T: Send + 'static,
+    U: Send + 'static,
+    V: Send + 'static,
+    R: Send + 'static,
+{
+    /// Create a full Join Pattern replying with the result of `f`.
+    pub fn then_do<F>(self, f: F)
+    where
+        F: Fn(T, U, V) -> R + Send + Sync + 'static,
+    {
+        self.register(move |(t, u, v)| f(t, u, v));
+    }
+}
+
+mod sealed {
+    /// Keeps `Extendable` and `Unterminated` from being implemented outside
+    /// of this module.
+    pub trait Sealed {}
+}
+
+/// Partial Join Pattern that can be extended by another channel, which is
+/// never the case once it has 3 channels, the most Join Patterns of a
+/// `TypedJunction` can take.
+///
+/// Not implemented for any type, it only turns appending too many channels
+/// into a clear compile error.
+#[diagnostic::on_unimplemented(
+    message = "Join Patterns of a `TypedJunction` take at most 3 channels",
+    label = "cannot append a fourth channel",
+    note = "combine some of the channels into one carrying a tuple instead"
+)]
+pub trait Extendable: sealed::Sealed {}
+
+/// Partial Join Pattern that can be extended by another channel, which is
+/// never the case once it ends in a `TypedBidirChannel`.
+///
+/// Not implemented for any type, it only turns appending channels after a
+/// `TypedBidirChannel` into a clear compile error.
+#[diagnostic::on_unimplemented(
+    message = "no channel can be appended after the `TypedBidirChannel` ending a Join Pattern",
+    label = "already ends in a `TypedBidirChannel`",
+    note = "append the `TypedBidirChannel` last, through `and_bidir`"
+)]
+pub trait Unterminated: sealed::Sealed {}
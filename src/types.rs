@@ -1,30 +1,336 @@
 //! Collection of types to increase readability and maintainability of the
 //! crate.
 
-use crate::join_pattern::JoinPattern;
-use std::{any::Any, marker::Send, sync::mpsc::Sender};
+use crate::{
+    channels::{GateOpener, Priority},
+    join_pattern::JoinPattern,
+    junction::{AuditEvent, IdleSignal, LatencyStats, Rate, Registration},
+    metadata::Metadata,
+    queue::PacketSender,
+};
+#[cfg(feature = "snapshot")]
+use std::collections::HashMap;
+use std::{
+    any::{type_name, Any, TypeId},
+    marker::Send,
+    num::NonZeroU64,
+    sync::{mpsc::Sender, Arc},
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
 
 /// Shallow wrapper for a trait object using `Box` that can pass through thread
 /// boundaries.
-pub struct Message(Box<dyn Any + Send>);
+pub struct Message {
+    value: Box<dyn Any + Send>,
+    /// Whether the type of the value has been unknown at compile time, as
+    /// for `Message`s sent through `Junction::send_dynamic`, so that the
+    /// `Controller` has to check it against the type of the channel.
+    dynamic: bool,
+    /// Time to live the `Message` has been sent with, if any.
+    ttl: Option<Duration>,
+    /// When the `Message` expires, if it has been sent with a time to live.
+    expires_at: Option<Instant>,
+    /// Thread waiting for a reply to the `Message`, if it has been sent on a
+    /// `RecvChannel` or `BidirChannel`.
+    caller: Option<ThreadId>,
+    /// Sequence number of the `Message` on its channel plus one, if it has
+    /// been sent on a `SendChannel`, keeping the `Message` small.
+    sequence: Option<NonZeroU64>,
+    /// Metadata the `Message` has been sent with, if any.
+    metadata: Option<Box<Metadata>>,
+    /// Whether the trace context carried by the metadata is to be restored
+    /// on the thread taking the value, see `Message::downcast`.
+    #[cfg(feature = "otel")]
+    restore_context: bool,
+    /// Priority of the `Message` among those pending on its channel.
+    priority: Priority,
+    /// Whether the `Message` has been consumed before, as persistent
+    /// `Message`s are stored again once consumed, see
+    /// `SendChannel::send_persistent`.
+    consumed: bool,
+    /// `Sender` to acknowledge the `Message` through once it has been
+    /// consumed by a fired Join Pattern, if it has been sent with
+    /// `SendChannel::send_sync`.
+    ack: Option<Sender<()>>,
+    /// When the `Message` has been created, i.e. sent on its channel.
+    sent_at: Instant,
+}
 
 impl Message {
     pub(crate) fn new<T>(raw_value: T) -> Message
     where
         T: Any + Send,
     {
-        Message(Box::new(raw_value))
+        Message {
+            dynamic: false,
+            ..Message::dynamic(Box::new(raw_value))
+        }
+    }
+
+    /// Create a `Message` holding a value of a type unknown at compile time.
+    pub(crate) fn dynamic(value: Box<dyn Any + Send>) -> Message {
+        Message {
+            value,
+            dynamic: true,
+            ttl: None,
+            expires_at: None,
+            caller: None,
+            sequence: None,
+            metadata: None,
+            #[cfg(feature = "otel")]
+            restore_context: false,
+            priority: Priority::Normal,
+            consumed: false,
+            ack: None,
+            sent_at: Instant::now(),
+        }
+    }
+
+    /// Create a `Message` that expires once the given time to live has
+    /// passed. A time to live too long to be represented never expires.
+    pub(crate) fn with_ttl<T>(raw_value: T, ttl: Duration) -> Message
+    where
+        T: Any + Send,
+    {
+        Message {
+            ttl: Some(ttl),
+            expires_at: Instant::now().checked_add(ttl),
+            ..Message::new(raw_value)
+        }
+    }
+
+    /// Count the time to live of the `Message`, if any, from the given
+    /// instant rather than from when it has been sent.
+    pub(crate) fn restart_ttl(&mut self, now: Instant) {
+        self.expires_at = self.ttl.and_then(|ttl| now.checked_add(ttl));
+    }
+
+    /// Record the current thread as waiting for a reply to the `Message`.
+    pub(crate) fn with_caller(mut self) -> Message {
+        self.caller = Some(thread::current().id());
+        self
+    }
+
+    /// Record the sequence number of the `Message` on its channel.
+    pub(crate) fn with_sequence(mut self, sequence: u64) -> Message {
+        self.sequence = NonZeroU64::new(sequence.wrapping_add(1));
+        self
+    }
+
+    /// Return the sequence number of the `Message` on its channel, if any.
+    pub(crate) fn sequence(&self) -> Option<u64> {
+        self.sequence.map(|sequence| sequence.get() - 1)
+    }
+
+    /// Attach the given metadata to the `Message`.
+    pub(crate) fn with_metadata(mut self, metadata: Metadata) -> Message {
+        self.metadata = Some(Box::new(metadata));
+        self
+    }
+
+    /// Return the metadata of the `Message`, if any.
+    pub(crate) fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_deref()
+    }
+
+    /// Return the metadata of the `Message` for adding entries, attaching
+    /// empty metadata if there is none.
+    #[cfg(feature = "otel")]
+    pub(crate) fn metadata_mut(&mut self) -> &mut Metadata {
+        self.metadata.get_or_insert_with(Box::default)
+    }
+
+    /// Return `true` if the `Message` carries a trace context.
+    #[cfg(feature = "otel")]
+    pub(crate) fn has_trace_context(&self) -> bool {
+        self.metadata().and_then(Metadata::trace_context).is_some()
+    }
+
+    /// Restore the trace context of the `Message` on the thread taking its
+    /// value through `downcast`, i.e. the thread running the function body
+    /// of a Join Pattern generated for `then_do`.
+    #[cfg(feature = "otel")]
+    pub(crate) fn restore_context(&mut self) {
+        self.restore_context = true;
+    }
+
+    /// Take the metadata of the `Message`, empty if there is none.
+    pub(crate) fn take_metadata(&mut self) -> Metadata {
+        self.metadata
+            .take()
+            .map(|metadata| *metadata)
+            .unwrap_or_default()
+    }
+
+    /// Give the `Message` the given priority among those pending on its
+    /// channel.
+    pub(crate) fn with_priority(mut self, priority: Priority) -> Message {
+        self.priority = priority;
+        self
+    }
+
+    /// Change the priority of the `Message` among those pending on its
+    /// channel.
+    pub(crate) fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
+
+    /// Return the priority of the `Message` among those pending on its
+    /// channel.
+    pub(crate) fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Create a `Message` holding a copy of the value of this `Message`,
+    /// with the same priority and metadata, `None` if the value is not of
+    /// type `T`.
+    pub(crate) fn copy<T>(&self) -> Option<Message>
+    where
+        T: Any + Send + Clone,
+    {
+        self.downcast_ref::<T>().map(|value| Message {
+            metadata: self.metadata.clone(),
+            ..Message::new(value.clone()).with_priority(self.priority)
+        })
+    }
+
+    /// Record that the `Message` has been consumed by a fired Join Pattern
+    /// and stored again.
+    pub(crate) fn set_consumed(&mut self) {
+        self.consumed = true;
+    }
+
+    /// Return `true` if the `Message` has been consumed before and stored
+    /// again, so that it has not been waiting since it has been sent.
+    pub(crate) fn was_consumed(&self) -> bool {
+        self.consumed
+    }
+
+    /// Acknowledge the `Message` through the given `Sender` once it has been
+    /// consumed by a fired Join Pattern.
+    pub(crate) fn with_ack(mut self, ack: Sender<()>) -> Message {
+        self.ack = Some(ack);
+        self
+    }
+
+    /// Acknowledge that the `Message` has been consumed, if requested.
+    pub(crate) fn acknowledge(&mut self) {
+        if let Some(ack) = self.ack.take() {
+            // The sender may have stopped waiting, which is fine.
+            let _ = ack.send(());
+        }
+    }
+
+    /// Return the thread waiting for a reply to the `Message`, if any.
+    pub(crate) fn caller(&self) -> Option<ThreadId> {
+        self.caller
+    }
+
+    /// Return when the `Message` expires, if ever.
+    pub(crate) fn expires_at(&self) -> Option<Instant> {
+        self.expires_at
+    }
+
+    /// Return `true` if the `Message` has expired by the given instant.
+    pub(crate) fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Return whether the type of the value has been unknown at compile
+    /// time.
+    pub(crate) fn is_dynamic(&self) -> bool {
+        self.dynamic
+    }
+
+    /// Return the `TypeId` of the value.
+    pub(crate) fn type_id(&self) -> TypeId {
+        (*self.value).type_id()
+    }
+
+    /// Return the internal trait object.
+    pub(crate) fn into_value(self) -> Box<dyn Any + Send> {
+        self.value
     }
 
     /// Cast internal trait object to `Box<T>`.
+    ///
+    /// With the `otel` feature enabled, the trace context of the `Message` is
+    /// made current on the calling thread first, if it is to be restored.
     pub(crate) fn downcast<T>(self) -> Result<Box<T>, Box<dyn Any + Send>>
     where
         T: Any + Send,
     {
-        self.0.downcast::<T>()
+        #[cfg(feature = "otel")]
+        if self.restore_context {
+            crate::otel::restore(self.metadata());
+        }
+
+        self.value.downcast::<T>()
+    }
+
+    /// Return a reference to the internal value if it is of type `T`.
+    pub(crate) fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: Any + Send,
+    {
+        self.value.downcast_ref::<T>()
+    }
+
+    /// Return a mutable reference to the internal value if it is of type
+    /// `T`.
+    pub(crate) fn downcast_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: Any + Send,
+    {
+        self.value.downcast_mut::<T>()
+    }
+
+    /// Return when the `Message` has been sent on its channel.
+    pub(crate) fn sent_at(&self) -> Instant {
+        self.sent_at
     }
 }
 
+/// Type of the values held by the `Message`s of a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageType {
+    pub(crate) id: TypeId,
+    pub(crate) name: &'static str,
+}
+
+impl MessageType {
+    /// Return the `MessageType` of values of type `T`.
+    pub(crate) fn of<T: Any>() -> MessageType {
+        MessageType {
+            id: TypeId::of::<T>(),
+            name: type_name::<T>(),
+        }
+    }
+}
+
+/// Observer of the `Message`s arriving on a channel, returning `false` once
+/// it no longer wants to observe any.
+pub(crate) type Tap = Box<dyn FnMut(&Message) -> bool + Send>;
+
+/// Function copying the `Message`s of a broadcast channel for its
+/// subscribers, or the value of a state channel or a persistent `Message`
+/// for the Join Patterns reading it, see `Message::copy`.
+pub(crate) type CopyMessage = fn(&Message) -> Option<Message>;
+
+/// Function creating the `Message`s sent by `RecvChannel::drain_available`.
+pub(crate) type MakeMessage = Box<dyn Fn() -> Message + Send>;
+
+/// Function serializing the value of a `Message` of a channel included in
+/// snapshots, `None` if it could not be serialized.
+#[cfg(feature = "snapshot")]
+pub(crate) type Encode = Box<dyn Fn(&Message) -> Option<serde_json::Value> + Send>;
+
+/// Function creating a `Message` of a channel included in snapshots from a
+/// serialized value, `None` if it could not be deserialized.
+#[cfg(feature = "snapshot")]
+pub(crate) type Decode = Box<dyn Fn(serde_json::Value) -> Option<Message> + Send>;
+
 /// Standardized packet to be used to send messages of various types on the
 /// channels of a Junction.
 pub enum Packet {
@@ -33,18 +339,203 @@ pub enum Packet {
         channel_id: ids::ChannelId,
         msg: Message,
     },
+    /// Messages sent together from the channel identified by `channel_id`,
+    /// in order.
+    Messages {
+        channel_id: ids::ChannelId,
+        msgs: Vec<Message>,
+    },
+    /// Report that the channel identified by `channel_id` has been closed.
+    CloseChannel { channel_id: ids::ChannelId },
+    /// Report that all handles to the channel identified by `channel_id` have
+    /// been dropped.
+    ChannelDropped { channel_id: ids::ChannelId },
+    /// Request the `Message` numbered `sequence` on the channel identified by
+    /// `channel_id` to be retained once it arrives, passing copies made by
+    /// `copy` to the Join Patterns firing instead of consuming it.
+    Persist {
+        channel_id: ids::ChannelId,
+        sequence: u64,
+        copy: CopyMessage,
+    },
+    /// Request the persistent `Message` numbered `sequence` on the channel
+    /// identified by `channel_id` to be removed.
+    Revoke {
+        channel_id: ids::ChannelId,
+        sequence: u64,
+    },
+    /// Request the channel identified by `channel_id` to retain its latest
+    /// `Message`, passing copies made by `copy` to the Join Patterns firing
+    /// instead of consuming it.
+    RetainChannel {
+        channel_id: ids::ChannelId,
+        copy: CopyMessage,
+    },
+    /// Record the type of the values held by the `Message`s of the channel
+    /// identified by `channel_id`.
+    TypeChannel {
+        channel_id: ids::ChannelId,
+        message_type: MessageType,
+    },
     /// Request a new channel ID from the Junction so a new channel can be
     /// constructed. New ID will be sent back through `return_sender`.
     NewChannelIdRequest {
         return_sender: Sender<ids::ChannelId>,
     },
-    /// Request adding a new Join Pattern to the Junction.
+    /// Give the channel identified by `channel_id` a name for diagnostics.
+    NameChannel {
+        channel_id: ids::ChannelId,
+        name: String,
+    },
+    /// Request a copy of every `Message` arriving on the broadcast channel
+    /// identified by `channel_id` to be stored on the channel identified by
+    /// `subscriber` instead, made by `copy`.
+    Subscribe {
+        channel_id: ids::ChannelId,
+        subscriber: ids::ChannelId,
+        copy: CopyMessage,
+    },
+    /// Request every `Message` arriving on the channel identified by
+    /// `channel_id` to be passed to `tap` before being stored.
+    TapRequest {
+        channel_id: ids::ChannelId,
+        tap: Tap,
+    },
+    /// Request the `Message`s arriving on the channel identified by
+    /// `channel_id` to be released to its Join Patterns no faster than
+    /// `rate` allows.
+    RateLimit {
+        channel_id: ids::ChannelId,
+        rate: Rate,
+    },
+    /// Request the `Message`s of the channel identified by `channel_id` to be
+    /// included in snapshots under `name`, storing any `Message`s restored
+    /// for `name`.
+    #[cfg(feature = "snapshot")]
+    SnapshotChannel {
+        channel_id: ids::ChannelId,
+        name: String,
+        encode: Encode,
+        decode: Decode,
+    },
+    /// Serialized `Message`s restored from a snapshot, by channel name, to be
+    /// stored once their channels are included in snapshots again.
+    #[cfg(feature = "snapshot")]
+    RestoreRequest {
+        channels: HashMap<String, Vec<serde_json::Value>>,
+    },
+    /// Request the serialized `Message`s of all channels included in
+    /// snapshots, by channel name, to be sent back through `return_sender`.
+    #[cfg(feature = "snapshot")]
+    SnapshotRequest {
+        return_sender: Sender<HashMap<String, Vec<serde_json::Value>>>,
+    },
+    /// Request adding a new Join Pattern to the Junction, recording when and
+    /// from where it has been registered in its audit log.
     // TODO: Currently dynamic dispatch is being used
     AddJoinPatternRequest {
         join_pattern: Box<dyn JoinPattern + Send>,
+        registration: Registration,
+    },
+    /// Request a shard of a sharded Junction to hand the given channels, with
+    /// all their `Message`s and Join Patterns, over to the shard behind `to`.
+    /// Sends on `ack` once the hand-off has been sent.
+    HandOffRequest {
+        channels: Vec<ids::ChannelId>,
+        to: Box<PacketSender>,
+        ack: Sender<()>,
+    },
+    /// Channels handed over from another shard of a sharded Junction.
+    /// `registration` is set if the Join Patterns are being registered
+    /// rather than handed over.
+    Adopt {
+        channels: Vec<ids::ChannelId>,
+        messages: Vec<(ids::ChannelId, Message)>,
+        join_patterns: Vec<Box<dyn JoinPattern>>,
+        channel_names: Vec<(ids::ChannelId, String)>,
+        registration: Option<Box<Registration>>,
+    },
+    /// Request the channel identified by `channel_id` to be migrated to the
+    /// Junction behind `to`, where it is identified by `to_channel_id`.
+    /// Sends on `ack` once all of its `Message`s have been passed on.
+    MigrateRequest {
+        channel_id: ids::ChannelId,
+        to: Box<PacketSender>,
+        to_channel_id: ids::ChannelId,
+        ack: Sender<()>,
+    },
+    /// Request `Message`s created by `make_message` to be stored on the
+    /// channel identified by `channel_id` for as long as each of them is
+    /// consumed right away by a firing Join Pattern, sending the number of
+    /// `Message`s consumed back through `return_sender`.
+    DrainRequest {
+        channel_id: ids::ChannelId,
+        make_message: MakeMessage,
+        return_sender: Sender<usize>,
+    },
+    /// Request the Junction to move all of its channels, along with their
+    /// `Message`s and Join Patterns, to the Junction behind `to`, where the
+    /// channel with ID `n` is identified by `to_channel_ids[n]`. Sends on
+    /// `ack` once everything has been passed on.
+    MergeRequest {
+        to: Box<PacketSender>,
+        to_channel_ids: Vec<ids::ChannelId>,
+        ack: Sender<()>,
+    },
+    /// Request the channels and Join Patterns of the Junction as statements of
+    /// a DOT graph, identifying Join Patterns with `prefix`, to be sent back
+    /// through `return_sender`.
+    ExportDotRequest {
+        prefix: String,
+        return_sender: Sender<String>,
+    },
+    /// Request the latencies between sending `Message`s and firing the Join
+    /// Patterns consuming them to be sent back through `return_sender`.
+    LatencyStatsRequest {
+        return_sender: Sender<Vec<LatencyStats>>,
+    },
+    /// Request the channel identified by `channel_id` to be gated by the
+    /// given `GateOpener`, which is opened once a Join Pattern of the channel
+    /// has been registered.
+    GatePatterns {
+        channel_id: ids::ChannelId,
+        opener: GateOpener,
+    },
+    /// Request a reply through `return_sender` once the `Packet`s sent
+    /// before have been handled, so that the gate of the channel identified
+    /// by `channel_id` is open if it has any Join Patterns.
+    GateRequest {
+        channel_id: ids::ChannelId,
+        return_sender: Sender<()>,
+    },
+    /// Request the latest Join Patterns registered and removed to be sent
+    /// back through `return_sender`.
+    AuditLogRequest {
+        return_sender: Sender<Vec<AuditEvent>>,
+    },
+    /// Request the number of times the Join Patterns named `name` have fired
+    /// to be sent back through `return_sender`.
+    #[cfg(feature = "testing")]
+    FireCountRequest {
+        name: String,
+        return_sender: Sender<u64>,
     },
+    /// Request the virtual clock of the Junction to be advanced until `to`
+    /// has passed on it, sending on `ack` once all timers due have been dealt
+    /// with.
+    AdvanceClock { to: Duration, ack: Sender<()> },
+    /// Request the thread handling the control `Packet`s of the Junction to
+    /// identify itself through `return_sender`, see `Scope::owns`.
+    ProbeRequest { return_sender: Sender<ThreadId> },
+    /// Request all cancellable Join Patterns of the Junction to be cancelled.
+    CancelRequest,
+    /// Request `signal` to be set once the Junction is idle.
+    IdleRequest { signal: Arc<IdleSignal> },
     /// Request the internal control thread managing the `Message`s to shut down.
     ShutDownRequest,
+    /// Wake up the control thread waiting on the main queue after a control
+    /// `Packet` has been sent, see `queue`. Never handed out by the queue.
+    Wake,
 }
 
 /// Adds specific ID types for the various IDs that are used in the crate.
@@ -56,9 +547,14 @@ pub mod ids {
     pub struct ChannelId(usize);
 
     impl ChannelId {
-        // pub(crate) fn new(value: usize) -> ChannelId {
-        //     ChannelId(value)
-        // }
+        pub(crate) fn new(value: usize) -> ChannelId {
+            ChannelId(value)
+        }
+
+        /// Return the internal value of the channel ID.
+        pub(crate) fn value(&self) -> usize {
+            self.0
+        }
 
         /// Increment the internal value of the channel ID.
         pub(crate) fn increment(&mut self) {
@@ -67,10 +563,22 @@ pub mod ids {
     }
 
     /// ID to identify a Join Pattern within a Junction.
-    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    ///
+    /// IDs are handed out in increasing order, so ordering them orders Join
+    /// Patterns by when they were added.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Ord, PartialOrd)]
     pub struct JoinPatternId(usize);
 
     impl JoinPatternId {
+        pub(crate) fn new(value: usize) -> JoinPatternId {
+            JoinPatternId(value)
+        }
+
+        /// Return the internal value of the Join Pattern ID.
+        pub(crate) fn value(&self) -> usize {
+            self.0
+        }
+
         /// Increment the internal value of the Join Pattern ID.
         pub(crate) fn increment(&mut self) {
             self.0 += 1;
@@ -82,7 +590,11 @@ pub mod ids {
     pub static LATEST_JUNCTION_ID: AtomicUsize = AtomicUsize::new(0);
 
     /// ID for a Junction to identify itself.
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    ///
+    /// IDs are only unique within a process, so a serialized `JunctionId`
+    /// only identifies the same `Junction` when read back by the process
+    /// that wrote it.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct JunctionId(usize);
 
     impl JunctionId {
@@ -90,4 +602,32 @@ pub mod ids {
             JunctionId(LATEST_JUNCTION_ID.fetch_add(1, Ordering::Relaxed))
         }
     }
+
+    /// Implement `Serialize` and `Deserialize` for an ID as its value.
+    #[cfg(feature = "serde")]
+    macro_rules! impl_serde {
+        ($id:ident) => {
+            impl serde::Serialize for $id {
+                fn serialize<S: serde::Serializer>(
+                    &self,
+                    serializer: S,
+                ) -> Result<S::Ok, S::Error> {
+                    serializer.serialize_u64(self.0 as u64)
+                }
+            }
+
+            impl<'de> serde::Deserialize<'de> for $id {
+                fn deserialize<D: serde::Deserializer<'de>>(
+                    deserializer: D,
+                ) -> Result<$id, D::Error> {
+                    <usize as serde::Deserialize>::deserialize(deserializer).map($id)
+                }
+            }
+        };
+    }
+
+    #[cfg(feature = "serde")]
+    impl_serde!(ChannelId);
+    #[cfg(feature = "serde")]
+    impl_serde!(JunctionId);
 }